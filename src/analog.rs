@@ -0,0 +1,98 @@
+use crate::Axis;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    Linear,
+    Quadratic,
+    Cubic,
+    Power(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisConfig {
+    // raw values closer to the center than this are ignored
+    pub deadzone: i16,
+    pub curve: Curve,
+    // multiplier applied after the curve, the result is capped at full deflection
+    pub sensitivity: f32,
+}
+
+impl Default for AxisConfig {
+    fn default() -> Self {
+        Self {
+            deadzone: 8000,
+            curve: Curve::Linear,
+            sensitivity: 1.0,
+        }
+    }
+}
+
+// Turns the left stick into d-pad presses. A direction engages once the stick is pushed
+// past `press` and only lets go once it falls under `release`, so a stick resting near
+// the threshold doesn't flicker. Both thresholds are normalized deflections (0.0 - 1.0).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DpadEmulation {
+    pub press: f32,
+    pub release: f32,
+    pub repeat_delay: Duration,
+    pub repeat_interval: Duration,
+}
+
+impl Default for DpadEmulation {
+    fn default() -> Self {
+        Self {
+            press: 0.6,
+            release: 0.4,
+            repeat_delay: Duration::from_millis(400),
+            repeat_interval: Duration::from_millis(120),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalogConfig {
+    axes: [AxisConfig; 6],
+    pub dpad: Option<DpadEmulation>,
+}
+
+impl Default for AnalogConfig {
+    fn default() -> Self {
+        Self {
+            axes: [AxisConfig::default(); 6],
+            dpad: Some(DpadEmulation::default()),
+        }
+    }
+}
+
+impl AnalogConfig {
+    pub fn axis(mut self, axis: Axis, config: AxisConfig) -> Self {
+        self.axes[axis as usize] = config;
+        self
+    }
+    pub fn dpad(mut self, dpad: Option<DpadEmulation>) -> Self {
+        self.dpad = dpad;
+        self
+    }
+    pub fn axis_config(&self, axis: Axis) -> &AxisConfig {
+        &self.axes[axis as usize]
+    }
+    // signed deflection in -1.0..=1.0 with deadzone, curve and sensitivity applied
+    pub fn normalized(&self, axis: Axis, value: i16) -> f32 {
+        let config = self.axis_config(axis);
+        let raw = (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+        let deadzone = (config.deadzone as f32 / i16::MAX as f32).clamp(0.0, 0.99);
+        if raw.abs() <= deadzone {
+            return 0.0;
+        }
+
+        let x = ((raw.abs() - deadzone) / (1.0 - deadzone)).min(1.0);
+        let curved = match config.curve {
+            Curve::Linear => x,
+            Curve::Quadratic => x * x,
+            Curve::Cubic => x * x * x,
+            Curve::Power(p) => x.powf(p),
+        };
+        (curved * config.sensitivity).min(1.0) * raw.signum()
+    }
+}
@@ -0,0 +1,51 @@
+use crate::layout::Item;
+use std::time::Duration;
+
+// Ties a selector to the screen brightness, e.g. on a quick-settings overlay. The first
+// option is the darkest, the last one full brightness. Moving through the options calls
+// the setter at most once per `debounce` with the latest level (0.0 - 1.0). Without a
+// setter the renderer adjusts the gamma of its own window, if it can.
+pub struct Brightness {
+    pub id: u128,
+    pub debounce: Duration,
+    setter: Option<Box<dyn FnMut(f32) + Send>>,
+}
+
+impl Brightness {
+    pub fn new(id: u128) -> Self {
+        Self {
+            id,
+            debounce: Duration::from_millis(100),
+            setter: None,
+        }
+    }
+    pub fn setter(mut self, setter: impl FnMut(f32) + Send + 'static) -> Self {
+        self.setter = Some(Box::new(setter));
+        self
+    }
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+    // returns false if there's no setter, the caller falls back to Renderer::set_brightness()
+    pub(crate) fn apply(&mut self, level: f32) -> bool {
+        match self.setter.as_mut() {
+            Some(setter) => {
+                setter(level);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+// level picked on a selector, None for anything else
+pub(crate) fn level(item: &Item) -> Option<f32> {
+    match item {
+        Item::Selector(_, options, selected, _, _) if options.len() > 1 => {
+            Some((*selected).min(options.len() - 1) as f32 / (options.len() - 1) as f32)
+        },
+        Item::Selector(..) => Some(1.0),
+        _ => None,
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    sync::{Arc, atomic::{AtomicU64, Ordering}},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// Time source for everything time-based in the gui (timers, key repeat, animations).
+// now() is monotonic time since some fixed origin picked by the clock, wall() is the
+// time of day for the things that show it. The renderer gets to read it too, see
+// Renderer::set_clock().
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { origin: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+// Only moves when told to. Clones share the same time, so keep one around
+// to advance it after handing another one over to GuiBuilder::clock().
+// The wall clock starts at the unix epoch and moves along with now().
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+    // wall() at now() == 0, in nanoseconds since the epoch
+    wall_origin: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+    pub fn set(&self, to: Duration) {
+        self.nanos.store(to.as_nanos() as u64, Ordering::SeqCst);
+    }
+    // makes wall() return `to` right now, later advances move it along
+    pub fn set_wall(&self, to: SystemTime) {
+        let since_epoch = to.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let origin = since_epoch.saturating_sub(self.now());
+        self.wall_origin.store(origin.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+    fn wall(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.wall_origin.load(Ordering::SeqCst)) + self.now()
+    }
+}
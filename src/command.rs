@@ -0,0 +1,96 @@
+use crossbeam_channel::Sender;
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+// what an Item::Command runs, output lines go to the Item::Log with id `log`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    pub log: Option<u128>,
+    // written to the command's stdin, which is closed otherwise
+    pub(crate) input: Option<String>,
+}
+
+impl CommandSpec {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|v| v.to_string()).collect(),
+            log: None,
+            input: None,
+        }
+    }
+    pub fn log_to(mut self, log: u128) -> Self {
+        self.log = Some(log);
+        self
+    }
+    #[cfg(feature = "wifi")]
+    pub(crate) fn stdin(mut self, input: &str) -> Self {
+        self.input = Some(input.to_string());
+        self
+    }
+    // how the command shows up in its log
+    pub(crate) fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+pub(crate) enum CommandOutput {
+    // id of the command item, one line of stdout or stderr
+    Line(u128, String),
+    // exit code, None if killed by a signal or it couldn't be waited for
+    Exited(u128, Option<i32>),
+    // not a command, but nmcli also runs next to the gui
+    #[cfg(feature = "wifi")]
+    Wifi(crate::wifi::Update),
+}
+
+// runs the command in the background, everything it does gets reported through `tx`
+pub(crate) fn spawn(id: u128, spec: &CommandSpec, tx: Sender<CommandOutput>) -> io::Result<()> {
+    let mut child = Command::new(&spec.program)
+        .args(&spec.args)
+        .stdin(if spec.input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // dropping the pipe closes it, so the command sees the end of its input
+    if let (Some(input), Some(mut stdin)) = (spec.input.clone(), child.stdin.take()) {
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+
+    let readers: Vec<Box<dyn Read + Send>> = vec![
+        Box::new(child.stdout.take().expect("Failed to get command stdout")),
+        Box::new(child.stderr.take().expect("Failed to get command stderr")),
+    ];
+    let readers: Vec<_> = readers.into_iter().map(|pipe| {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(pipe).lines() {
+                let Ok(line) = line else {break};
+                if tx.send(CommandOutput::Line(id, line)).is_err() {
+                    break;
+                }
+            }
+        })
+    }).collect();
+
+    thread::spawn(move || {
+        // all output has to be in before the exit is reported
+        for reader in readers {
+            let _ = reader.join();
+        }
+        let code = child.wait().ok().and_then(|v| v.code());
+        let _ = tx.send(CommandOutput::Exited(id, code));
+    });
+    Ok(())
+}
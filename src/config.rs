@@ -0,0 +1,206 @@
+use crate::{HidEvent, input::parse_command, repeat::NavAcceleration};
+use anyhow::{anyhow, bail, Result};
+use std::{path::{Path, PathBuf}, time::Duration};
+
+// Maps key names ("j", "up", "enter", "f1", ... see KEY_NAMES) and controller button names
+// (see BUTTON_NAMES) to events. Whatever isn't bound here keeps the backend's default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bindings {
+    keys: Vec<(String, HidEvent)>,
+    buttons: Vec<(String, HidEvent)>,
+}
+
+// named keys, besides single characters and f1 - f24
+pub const KEY_NAMES: &[&str] = &["space", "up", "down", "left", "right", "enter", "tab", "backtab",
+    "backspace", "esc", "home", "end", "pageup", "pagedown"];
+// face buttons by position, shoulders, triggers and the middle ones
+pub const BUTTON_NAMES: &[&str] = &["south", "east", "north", "west", "l", "r", "l2", "r2", "start",
+    "select", "guide"];
+
+// the name a key goes by in bindings, backends call a few of them differently
+pub(crate) fn key_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.as_str() {
+        "return" => "enter".to_string(),
+        "escape" => "esc".to_string(),
+        _ => name,
+    }
+}
+
+fn valid_key(name: &str) -> bool {
+    name.chars().count() == 1
+        || KEY_NAMES.contains(&name)
+        || name.strip_prefix('f').and_then(|v| v.parse::<u8>().ok()).is_some_and(|v| (1..=24).contains(&v))
+}
+
+impl Bindings {
+    pub fn bind_key(mut self, key: &str, ev: HidEvent) -> Self {
+        self.keys.push((key_name(key), ev));
+        self
+    }
+    pub fn bind_button(mut self, button: &str, ev: HidEvent) -> Self {
+        self.buttons.push((button.to_lowercase(), ev));
+        self
+    }
+    pub fn key(&self, name: &str) -> Option<HidEvent> {
+        let name = key_name(name);
+        self.keys.iter().rev().find(|(k, _)| *k == name).map(|(_, ev)| ev.clone())
+    }
+    pub fn button(&self, name: &str) -> Option<HidEvent> {
+        let name = name.to_lowercase();
+        self.buttons.iter().rev().find(|(k, _)| *k == name).map(|(_, ev)| ev.clone())
+    }
+}
+
+// User preferences shared by every sgui app, read from $XDG_CONFIG_HOME/sgui/config.toml:
+//
+//   theme = "light"
+//
+//   [font]
+//   path = "/usr/share/fonts/TTF/DejaVuSans.ttf"
+//   size = 24
+//
+//   [repeat]
+//   repeat_gap_ms = 200
+//   fast_after_ms = 1500
+//   fast_step = 3
+//
+//   [keys]
+//   k = "up"
+//   j = "down"
+//
+//   [buttons]
+//   west = "grab"
+//
+// Values in [keys] and [buttons] use the same commands as input::parse_command(), the names
+// on the left are checked against KEY_NAMES and BUTTON_NAMES.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub theme: Option<String>,
+    pub font: Option<PathBuf>,
+    pub font_size: Option<u16>,
+    pub repeat: Option<NavAcceleration>,
+    pub bindings: Bindings,
+}
+
+impl Config {
+    pub fn user_path() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("sgui").join("config.toml"))
+    }
+    // a missing config file isn't an error, it just means defaults
+    pub fn load_user() -> Result<Self> {
+        match Self::user_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+    // understands the small part of toml the config needs: [sections], and key = value
+    // pairs with strings and integers. Unknown keys are ignored.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = Self::default();
+        let mut section = String::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected key = value", i + 1))?;
+            let key = unquote(key.trim()).unwrap_or_else(|| key.trim().to_string());
+            let value = parse_value(value.trim())
+                .ok_or_else(|| anyhow!("line {}: bad value for {}", i + 1, key))?;
+
+            match (section.as_str(), key.as_str()) {
+                ("", "theme") => config.theme = Some(value.string(i)?),
+                ("font", "path") => config.font = Some(value.string(i)?.into()),
+                ("font", "size") => {
+                    let size = value.int(i)?;
+                    match u16::try_from(size) {
+                        Ok(size @ 1..=512) => config.font_size = Some(size),
+                        _ => bail!("line {}: font size {} is out of range", i + 1, size),
+                    }
+                },
+                ("repeat", _) => {
+                    let repeat = config.repeat.get_or_insert_with(NavAcceleration::default);
+                    match key.as_str() {
+                        "repeat_gap_ms" => repeat.repeat_gap = Duration::from_millis(value.int(i)?),
+                        "fast_after_ms" => repeat.fast_after = Duration::from_millis(value.int(i)?),
+                        "fast_step" => repeat.fast_step = usize::try_from(value.int(i)?)?,
+                        "page_after_ms" => repeat.page_after = Duration::from_millis(value.int(i)?),
+                        "page_step" => repeat.page_step = usize::try_from(value.int(i)?)?,
+                        _ => (),
+                    }
+                },
+                ("keys", _) if !valid_key(&key_name(&key)) => bail!("line {}: unknown key {}", i + 1, key),
+                ("buttons", _) if !BUTTON_NAMES.contains(&key.to_lowercase().as_str()) => {
+                    bail!("line {}: unknown button {}", i + 1, key);
+                },
+                ("keys", _) | ("buttons", _) => {
+                    let command = value.string(i)?;
+                    let Some(ev) = parse_command(&command) else {
+                        bail!("line {}: unknown action {}", i + 1, command);
+                    };
+                    config.bindings = match section.as_str() {
+                        "keys" => config.bindings.bind_key(&key, ev),
+                        _ => config.bindings.bind_button(&key, ev),
+                    };
+                },
+                _ => (),
+            }
+        }
+        Ok(config)
+    }
+}
+
+enum Value {
+    Str(String),
+    Int(u64),
+}
+
+impl Value {
+    fn string(self, line: usize) -> Result<String> {
+        match self {
+            Value::Str(v) => Ok(v),
+            _ => bail!("line {}: expected a string", line + 1),
+        }
+    }
+    fn int(self, line: usize) -> Result<u64> {
+        match self {
+            Value::Int(v) => Ok(v),
+            _ => bail!("line {}: expected a number", line + 1),
+        }
+    }
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    if let Some(inner) = value.strip_prefix('"') {
+        // whatever follows the closing quote can only be a comment
+        let end = inner.find('"').map(|v| v + 2)?;
+        let rest = value[end..].trim();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return None;
+        }
+        return unquote(&value[..end]).map(Value::Str);
+    }
+    let value = value.split('#').next()?.trim();
+    value.replace('_', "").parse().ok().map(Value::Int)
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    Some(inner.replace("\\\\", "\\"))
+}
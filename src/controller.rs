@@ -0,0 +1,75 @@
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+
+// Family of the controller in use, which decides what its face buttons are called and
+// which of them confirms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControllerStyle {
+    #[default]
+    Xbox,
+    PlayStation,
+    // A on the right confirms, B below it cancels
+    Nintendo,
+}
+
+// where a face button sits, whatever it's called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaceButton {
+    South,
+    East,
+    North,
+    West,
+}
+
+impl ControllerStyle {
+    // guessed from the name the controller reports, anything unknown is taken for Xbox-style
+    pub fn detect(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let any = |words: &[&str]| words.iter().any(|v| name.contains(v));
+        if any(&["playstation", "dualshock", "dualsense", "ps3", "ps4", "ps5", "sony"]) {
+            ControllerStyle::PlayStation
+        } else if any(&["nintendo", "switch", "joy-con", "joycon", "wii u", "gamecube"]) {
+            ControllerStyle::Nintendo
+        } else {
+            ControllerStyle::Xbox
+        }
+    }
+    // what's printed on the button, for hints like "A: select"
+    pub fn label(self, button: FaceButton) -> &'static str {
+        match (self, button) {
+            (ControllerStyle::Xbox, FaceButton::South) | (ControllerStyle::Nintendo, FaceButton::East) => "A",
+            (ControllerStyle::Xbox, FaceButton::East) | (ControllerStyle::Nintendo, FaceButton::South) => "B",
+            (ControllerStyle::Xbox, FaceButton::West) | (ControllerStyle::Nintendo, FaceButton::North) => "X",
+            (ControllerStyle::Xbox, FaceButton::North) | (ControllerStyle::Nintendo, FaceButton::West) => "Y",
+            (ControllerStyle::PlayStation, FaceButton::South) => "✕",
+            (ControllerStyle::PlayStation, FaceButton::East) => "○",
+            (ControllerStyle::PlayStation, FaceButton::North) => "△",
+            (ControllerStyle::PlayStation, FaceButton::West) => "□",
+        }
+    }
+    pub fn confirm_button(self) -> FaceButton {
+        match self {
+            ControllerStyle::Nintendo => FaceButton::East,
+            _ => FaceButton::South,
+        }
+    }
+    pub fn cancel_button(self) -> FaceButton {
+        match self {
+            ControllerStyle::Nintendo => FaceButton::South,
+            _ => FaceButton::East,
+        }
+    }
+}
+
+// Whether East confirms and South cancels instead of the other way around. Shared between
+// the gui and the input sources running on their own threads, see Gui::confirm_swap().
+#[derive(Debug, Clone, Default)]
+pub struct ConfirmSwap(Arc<AtomicBool>);
+
+impl ConfirmSwap {
+    pub fn swapped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+    pub(crate) fn set(&self, swapped: bool) {
+        self.0.store(swapped, Ordering::Relaxed);
+    }
+}
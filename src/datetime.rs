@@ -0,0 +1,113 @@
+use crate::timezone;
+use std::{fmt, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+// Values of date and time items. Editing goes segment by segment: up/down changes the
+// segment, left/right picks another one. Segments wrap around, except for the year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    // 1 - 12
+    pub month: u8,
+    // 1 - 31, depending on the month
+    pub day: u8,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u8, day: u8) -> Self {
+        let month = month.clamp(1, 12);
+        Self {
+            year,
+            month,
+            day: day.clamp(1, days_in_month(year, month)),
+        }
+    }
+    // year, month, day
+    pub(crate) fn adjust(&mut self, segment: usize, steps: i32) {
+        match segment {
+            0 => self.year = (self.year + steps).clamp(1, 9999),
+            1 => self.month = wrap(self.month as i32 - 1 + steps, 12) as u8 + 1,
+            _ => self.day = wrap(self.day as i32 - 1 + steps, days_in_month(self.year, self.month) as i32) as u8 + 1,
+        }
+        self.day = self.day.min(days_in_month(self.year, self.month));
+    }
+    pub(crate) fn parts(&self) -> Vec<String> {
+        vec![format!("{:04}", self.year), format!("{:02}", self.month), format!("{:02}", self.day)]
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_segments(&self.parts(), '-', None))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    // 0 - 23
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl Time {
+    pub fn new(hour: u8, minute: u8) -> Self {
+        Self {
+            hour: hour.min(23),
+            minute: minute.min(59),
+        }
+    }
+    // `at` in the local timezone, from TZ or /etc/localtime, UTC when neither is set
+    pub fn local(at: SystemTime) -> Self {
+        let secs = at.duration_since(UNIX_EPOCH).map(|v| v.as_secs() as i64).unwrap_or_default();
+        let secs = (secs + timezone::local_offset(at)).rem_euclid(86400);
+        Self::new((secs / 3600) as u8, (secs / 60 % 60) as u8)
+    }
+    // hour, minute
+    pub(crate) fn adjust(&mut self, segment: usize, steps: i32) {
+        match segment {
+            0 => self.hour = wrap(self.hour as i32 + steps, 24) as u8,
+            _ => self.minute = wrap(self.minute as i32 + steps, 60) as u8,
+        }
+    }
+    pub(crate) fn parts(&self) -> Vec<String> {
+        vec![format!("{:02}", self.hour), format!("{:02}", self.minute)]
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_segments(&self.parts(), ':', None))
+    }
+}
+
+// text of an Item::Clock, dashes until it's first read
+pub fn clock_label(time: Option<&Time>) -> String {
+    time.map(|v| v.to_string()).unwrap_or_else(|| "--:--".to_string())
+}
+
+// how long Time::local() stays the same after `at`, timezones are whole minutes apart from UTC
+pub(crate) fn until_next_minute(at: SystemTime) -> Duration {
+    let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Duration::from_secs(60) - Duration::new(since_epoch.as_secs() % 60, since_epoch.subsec_nanos())
+}
+
+fn wrap(value: i32, len: i32) -> i32 {
+    value.rem_euclid(len)
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// text of a date or time item, the segment being edited is put in brackets
+pub(crate) fn format_segments(parts: &[String], separator: char, active: Option<usize>) -> String {
+    parts.iter()
+        .enumerate()
+        .map(|(i, v)| if Some(i) == active { format!("[{}]", v) } else { v.clone() })
+        .collect::<Vec<String>>()
+        .join(&separator.to_string())
+}
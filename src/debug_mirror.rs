@@ -0,0 +1,155 @@
+use crate::{layout::{Item, Layout}, event_log::EventRecord, datetime, format, osk, power};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+// Serves a read-only, auto-refreshing html view of the gui state
+pub struct DebugMirror {
+    page: Arc<Mutex<String>>,
+}
+
+impl DebugMirror {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let page = Arc::new(Mutex::new(String::from("<p>waiting for first frame</p>")));
+        let page_srv = page.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {continue};
+                let page = page_srv.clone();
+                thread::spawn(move || -> io::Result<()> {
+                    // we serve the same page no matter what was requested
+                    let mut reader = BufReader::new(stream.try_clone()?);
+                    let mut line = String::new();
+                    loop {
+                        line.clear();
+                        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                            break;
+                        }
+                    }
+
+                    let body = format!("<!DOCTYPE html>\n<html><head><meta http-equiv=\"refresh\" content=\"1\">\
+                        <title>sgui debug mirror</title><style>\
+                        body {{ font-family: monospace; }} td {{ border: 1px solid #888; padding: 0.2em 0.5em; }} \
+                        .focus {{ background: #fcc; }} .tab-active {{ font-weight: bold; color: #c00; }}\
+                        </style></head><body>{}</body></html>", page.lock().unwrap());
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(), body)?;
+                    stream.flush()
+                });
+            }
+        });
+
+        Ok(Self { page })
+    }
+    pub fn update<'a>(&self, layout: &Layout, tab_pos: usize, item_pos: (usize, usize), events: impl DoubleEndedIterator<Item = &'a EventRecord>) {
+        let mut page = render_page(layout, tab_pos, item_pos);
+        page.push_str(&render_events(events));
+        *self.page.lock().unwrap() = page;
+    }
+}
+
+fn escape(input: &str) -> String {
+    input.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn describe_item(item: &Item) -> String {
+    match item {
+        Item::Text(text) => escape(text),
+        Item::Separator => "<hr>".to_string(),
+        Item::Countdown(left, id) => format!("{} <small>(countdown, id {})</small>", format::countdown(*left), id),
+        Item::Sparkline(samples, id) => format!("{} <small>(sparkline, id {})</small>",
+            samples.last().map(|v| format::number(*v)).unwrap_or_default(), id),
+        Item::Table(text, headers, rows, selected, id) => {
+            let cells = |row: &[String], tag: &str| row.iter().map(|v| format!("<{}>{}</{}>", tag, escape(v), tag)).collect::<String>();
+            let body: String = rows.iter().enumerate()
+                .map(|(i, row)| format!("<tr{}>{}</tr>", if i == *selected { " class=\"focus\"" } else { "" }, cells(row, "td")))
+                .collect();
+            format!("{} <small>(id {})</small><table><tr>{}</tr>{}</table>", escape(text), id, cells(headers, "th"), body)
+        },
+        Item::Message(severity, text) => format!("{:?}: {}", severity, escape(text)),
+        Item::Spinner(text, _, spinning, id) => format!("{} <small>({}id {})</small>", escape(text), if *spinning { "busy, " } else { "" }, id),
+        Item::Header(text) => format!("<b>{}</b>", escape(text)),
+        Item::QrCode(data) => format!("QR: {}", escape(data)),
+        Item::Battery(battery) => escape(&power::label(battery.as_ref())),
+        Item::Clock(time) => datetime::clock_label(time.as_ref()),
+        Item::StatefulButton(text, state, id) => format!("[{}] {} <small>(id {})</small>", if *state { "X" } else { " " }, escape(text), id),
+        Item::StatelessButton(text, id) => format!("&lt;{}&gt; <small>(id {})</small>", escape(text), id),
+        Item::Image(source, id) => format!("[{}] <small>(image, id {})</small>", escape(&source.name()), id),
+        Item::Tile(text, _, id) => format!("&lt;{}&gt; <small>(tile, id {})</small>", escape(text), id),
+        Item::TextInput(text, value, masked, id) => format!("{}: [{}] <small>(id {})</small>", escape(text), escape(&osk::mask(value, *masked)), id),
+        Item::Slider(text, min, max, _, value, id) => format!("{}: {} <small>({} - {}, id {})</small>", escape(text), value, min, max, id),
+        Item::Gauge(text, current, max, id) => format!("{}: {}/{} <small>(id {})</small>", escape(text), format::number(*current), format::number(*max), id),
+        Item::ProgressBar(text, value, id) => format!("{}: {} <small>(id {})</small>", escape(text), format::percent(*value), id),
+        Item::Toggle(text, state, id) => format!("{} [{}] <small>(toggle, id {})</small>", escape(text), if *state { "ON" } else { "OFF" }, id),
+        Item::Radio(text, _, picked, id) => format!("({}) {} <small>(id {})</small>", if *picked { "•" } else { "&nbsp;" }, escape(text), id),
+        Item::Stepper(text, value, min, max, _, id) => format!("{}: {} <small>({} - {}, id {})</small>", escape(text), value, min, max, id),
+        Item::Value(text, value, id) => format!("{}: {} <small>(id {})</small>", escape(text), escape(&value.to_string()), id),
+        Item::Command(text, spec, running, id) => format!("&lt;{}&gt; <small>({}{}, id {})</small>",
+            escape(text), escape(&spec.program), if *running { ", running" } else { "" }, id),
+        Item::Log(lines, id) => format!("<pre>{}</pre> <small>(id {})</small>", escape(&lines.join("\n")), id),
+        Item::Link(text, url, id) => format!("<a href=\"{}\">{}</a> <small>(id {})</small>", escape(url), escape(text), id),
+        Item::Date(text, date, id) => format!("{}: {} <small>(id {})</small>", escape(text), date, id),
+        Item::Time(text, time, id) => format!("{}: {} <small>(id {})</small>", escape(text), time, id),
+        Item::Selector(text, options, selected, _, id) => format!("{}: &lt; {} &gt; <small>(id {})</small>",
+            escape(text), escape(options.get(*selected).map(String::as_str).unwrap_or("")), id),
+    }
+}
+
+fn render_page(layout: &Layout, tab_pos: usize, item_pos: (usize, usize)) -> String {
+    let mut out = String::from("<p>");
+    for (i, name) in layout.tab_names().iter().enumerate() {
+        if i == tab_pos {
+            out.push_str(&format!("<span class=\"tab-active\">[{}]</span> ", escape(name)));
+        } else {
+            out.push_str(&format!("[{}] ", escape(name)));
+        }
+    }
+    out.push_str(&format!("</p>\n<p>focus: row {}, column {}</p>\n<table>\n", item_pos.0, item_pos.1));
+
+    if let Some(tab) = layout.tab(tab_pos) {
+        if let Some(list) = tab.list() {
+            out.push_str("<tr><th colspan=\"100\">list</th></tr>\n");
+            for item in list.iter() {
+                out.push_str(&format!("<tr><td>{}</td></tr>\n", describe_item(item)));
+            }
+            out.push_str("<tr><th colspan=\"100\">detail</th></tr>\n");
+        }
+        for (row_idx, row) in tab.items().iter().enumerate() {
+            out.push_str("<tr>");
+            for (col_idx, item) in row.iter().enumerate() {
+                let class = if (row_idx, col_idx) == item_pos { " class=\"focus\"" } else { "" };
+                out.push_str(&format!("<td{}>{}</td>", class, describe_item(item)));
+            }
+            out.push_str("</tr>\n");
+        }
+        if let Some(footer) = tab.footer() {
+            out.push_str("<tr><th colspan=\"100\">footer</th></tr>\n<tr>");
+            for (col_idx, item) in footer.iter().enumerate() {
+                let class = if (tab.footer_row(), col_idx) == item_pos { " class=\"focus\"" } else { "" };
+                out.push_str(&format!("<td{}>{}</td>", class, describe_item(item)));
+            }
+            out.push_str("</tr>\n");
+        }
+    }
+    out.push_str("</table>");
+    out
+}
+
+// newest first, that's usually what you're looking for
+fn render_events<'a>(events: impl DoubleEndedIterator<Item = &'a EventRecord>) -> String {
+    let mut out = String::from("\n<h4>events</h4>\n<table>\n");
+    for record in events.rev() {
+        out.push_str(&format!("<tr><td>{:.3}</td><td>{}</td></tr>\n",
+            record.at.as_secs_f64(), escape(&format!("{:?}", record.event))));
+    }
+    out.push_str("</table>");
+    out
+}
@@ -0,0 +1,121 @@
+use crate::{Color, ColorPalette, item_id, layout::{Item, ItemKind}};
+use anyhow::Result;
+use std::path::Path;
+
+// area of an item in renderer units, pixels for SDL and character cells for the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DrawRect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
+// what a piece of text is drawn as, for Renderer::measure_text()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextStyle {
+    Tab,
+    Item,
+}
+
+// Widths for table columns that want `natural` each, to fit into `available` with `gap`
+// between them. Narrow columns keep their width, wider ones share what's left evenly.
+pub fn column_widths(natural: &[u32], available: u32, gap: u32) -> Vec<u32> {
+    let mut budget = available.saturating_sub(gap * natural.len().saturating_sub(1) as u32);
+    let mut order: Vec<usize> = (0..natural.len()).collect();
+    order.sort_by_key(|i| natural[*i]);
+    let mut widths = vec![0; natural.len()];
+    for (done, i) in order.into_iter().enumerate() {
+        widths[i] = natural[i].min(budget / (natural.len() - done) as u32);
+        budget -= widths[i];
+    }
+    widths
+}
+
+// the few things a draw hook can do, implemented by every renderer that supports hooks
+pub trait DrawContext {
+    fn fill_rect(&mut self, rect: DrawRect, color: Color) -> Result<()>;
+    fn draw_rect(&mut self, rect: DrawRect, color: Color) -> Result<()>;
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, color: Color) -> Result<()>;
+    // only straight lines where the renderer can't do better
+    fn draw_line(&mut self, from: (i32, i32), to: (i32, i32), color: Color) -> Result<()> {
+        if from.0 != to.0 && from.1 != to.1 {
+            return Ok(());
+        }
+        let (x, y) = (from.0.min(to.0), from.1.min(to.1));
+        self.fill_rect(DrawRect::new(x, y, from.0.abs_diff(to.0) + 1, from.1.abs_diff(to.1) + 1), color)
+    }
+    // an outline where the renderer can't show pictures
+    fn draw_image(&mut self, _path: &Path, rect: DrawRect, color: Color) -> Result<()> {
+        self.draw_rect(rect, color)
+    }
+}
+
+// one shape for Renderer::draw_primitives(), in the same units as DrawRect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive<'a> {
+    FillRect(DrawRect, Color),
+    Rect(DrawRect, Color),
+    // from, to
+    Line((i32, i32), (i32, i32), Color),
+    Text(&'a str, i32, i32, Color),
+    // scaled to the rect, the color is for the outline shown instead of it
+    Image(&'a Path, DrawRect, Color),
+}
+
+// draws everything in order, later primitives end up on top
+pub fn draw_primitives(ctx: &mut dyn DrawContext, primitives: &[Primitive]) -> Result<()> {
+    for primitive in primitives {
+        match *primitive {
+            Primitive::FillRect(rect, color) => ctx.fill_rect(rect, color)?,
+            Primitive::Rect(rect, color) => ctx.draw_rect(rect, color)?,
+            Primitive::Line(from, to, color) => ctx.draw_line(from, to, color)?,
+            Primitive::Text(text, x, y, color) => ctx.draw_text(text, x, y, color)?,
+            Primitive::Image(path, rect, color) => ctx.draw_image(path, rect, color)?,
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawTarget {
+    Id(u128),
+    Kind(ItemKind),
+}
+
+// gets the item, whether it's selected, its cell and the palette; returning false
+// leaves the item to the renderer's own drawing
+pub type DrawHook = Box<dyn FnMut(&Item, bool, DrawRect, &ColorPalette, &mut dyn DrawContext) -> bool + Send>;
+
+#[derive(Default)]
+pub struct DrawHooks {
+    hooks: Vec<(DrawTarget, DrawHook)>,
+}
+
+impl DrawHooks {
+    // a later hook for the same target replaces the earlier one
+    pub fn add(&mut self, target: DrawTarget, hook: DrawHook) {
+        self.hooks.retain(|(t, _)| *t != target);
+        self.hooks.push((target, hook));
+    }
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+    // hooks for the item's id win over hooks for its kind
+    pub fn run(&mut self, item: &Item, selected: bool, cell: DrawRect, colors: &ColorPalette, ctx: &mut dyn DrawContext) -> bool {
+        let id = item_id(item).map(DrawTarget::Id);
+        let kind = DrawTarget::Kind(item.kind());
+        let hook = self.hooks.iter().position(|(t, _)| Some(*t) == id)
+            .or_else(|| self.hooks.iter().position(|(t, _)| *t == kind));
+        match hook {
+            Some(i) => (self.hooks[i].1)(item, selected, cell, colors, ctx),
+            None => false,
+        }
+    }
+}
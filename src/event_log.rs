@@ -0,0 +1,64 @@
+use crate::{GuiEvent, HidEvent, RendererEvent, form::FormValue, journal::REDACTED};
+use std::{collections::VecDeque, time::Duration};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoggedEvent {
+    Hid(HidEvent),
+    Renderer(RendererEvent),
+    // what the gui handed back to the application
+    Gui(GuiEvent),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRecord {
+    // Gui::now() at the time the event was processed
+    pub at: Duration,
+    pub event: LoggedEvent,
+}
+
+// keeps the last `capacity` records, oldest first
+pub(crate) struct EventLog {
+    records: VecDeque<EventRecord>,
+    capacity: usize,
+}
+
+impl EventLog {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+    // keeps `event` without the values of the `masked` text inputs, returns what was kept
+    pub(crate) fn push(&mut self, at: Duration, event: LoggedEvent, masked: &[u128]) -> LoggedEvent {
+        let event = match event {
+            LoggedEvent::Gui(ev) => LoggedEvent::Gui(redact(ev, masked)),
+            event => event,
+        };
+        if self.capacity == 0 {
+            return event;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(EventRecord { at, event: event.clone() });
+        event
+    }
+    pub(crate) fn records(&self) -> impl DoubleEndedIterator<Item = &EventRecord> {
+        self.records.iter()
+    }
+}
+
+// wherever an event carries the text of a masked input, it's replaced with REDACTED
+fn redact(ev: GuiEvent, masked: &[u128]) -> GuiEvent {
+    match ev {
+        GuiEvent::FormSubmitted(id, values) => GuiEvent::FormSubmitted(id, values.into_iter()
+            .map(|(item, value)| match value {
+                FormValue::Text(_) if masked.contains(&item) => (item, FormValue::Text(REDACTED.to_string())),
+                value => (item, value),
+            })
+            .collect()),
+        GuiEvent::TextChanged(id, _) if masked.contains(&id) => GuiEvent::TextChanged(id, REDACTED.to_string()),
+        ev => ev,
+    }
+}
@@ -0,0 +1,54 @@
+// Sound or rumble that goes with pressing an item, played by the renderer, see
+// Renderer::feedback(). What each kind sounds or feels like is up to the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feedback {
+    None,
+    Click,
+    Success,
+    Error,
+}
+
+// Feedback for every press, with overrides for single items, e.g. a chime on the apply
+// button of a form. Everything is silent by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedbackTheme {
+    // items that did something when pressed
+    pub press: Feedback,
+    // items that can't be pressed right now, like a command that's still running
+    pub rejected: Feedback,
+    items: Vec<(u128, Feedback)>,
+}
+
+impl Default for FeedbackTheme {
+    fn default() -> Self {
+        Self {
+            press: Feedback::None,
+            rejected: Feedback::None,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl FeedbackTheme {
+    pub fn new(press: Feedback, rejected: Feedback) -> Self {
+        Self {
+            press,
+            rejected,
+            items: Vec::new(),
+        }
+    }
+    // used for every press of the item with `id`, rejected or not
+    pub fn item(mut self, id: u128, feedback: Feedback) -> Self {
+        self.items.retain(|(v, _)| *v != id);
+        self.items.push((id, feedback));
+        self
+    }
+    pub(crate) fn for_press(&self, id: Option<u128>, accepted: bool) -> Feedback {
+        let item = id.and_then(|id| self.items.iter().find(|(v, _)| *v == id));
+        match (item, accepted) {
+            (Some((_, feedback)), _) => *feedback,
+            (None, true) => self.press,
+            (None, false) => self.rejected,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::ops::Range;
+
+// A block of cells on the current tab that focus can't leave while the scope is active,
+// e.g. the buttons of a dialog drawn into the grid. Scopes stack, only the top one applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusScope {
+    pub id: u128,
+    pub rows: Range<usize>,
+    pub columns: Range<usize>,
+    // Back pops the scope and reports GuiEvent::FocusScopeDismissed
+    pub dismissable: bool,
+    // (id of an Item::Countdown, cell of the default choice), see auto_confirm()
+    pub auto_confirm: Option<(u128, (usize, usize))>,
+}
+
+impl FocusScope {
+    pub fn new(id: u128, rows: Range<usize>) -> Self {
+        Self {
+            id,
+            rows,
+            columns: 0..usize::MAX,
+            dismissable: false,
+            auto_confirm: None,
+        }
+    }
+    pub fn columns(mut self, columns: Range<usize>) -> Self {
+        self.columns = columns;
+        self
+    }
+    pub fn dismissable(mut self) -> Self {
+        self.dismissable = true;
+        self
+    }
+    // When the countdown runs out while this is the top scope, the cell at `default` gets
+    // pressed as if it was picked, e.g. "Rebooting in 10s" with Cancel and Reboot buttons.
+    // Its event is reported instead of GuiEvent::TimerExpired.
+    pub fn auto_confirm(mut self, countdown: u128, default: (usize, usize)) -> Self {
+        self.auto_confirm = Some((countdown, default));
+        self
+    }
+    pub fn contains(&self, (row, column): (usize, usize)) -> bool {
+        self.rows.contains(&row) && self.columns.contains(&column)
+    }
+    pub(crate) fn clamp(&self, (row, column): (usize, usize)) -> (usize, usize) {
+        (
+            row.clamp(self.rows.start, self.rows.end.saturating_sub(1).max(self.rows.start)),
+            column.clamp(self.columns.start, self.columns.end.saturating_sub(1).max(self.columns.start)),
+        )
+    }
+}
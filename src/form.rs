@@ -0,0 +1,77 @@
+use crate::{layout::Item, datetime::{Date, Time}};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormValue {
+    Bool(bool),
+    // selected option
+    Index(usize),
+    Date(Date),
+    Time(Time),
+    // slider position
+    Number(i32),
+    // stepper value
+    Integer(i64),
+    Text(String),
+}
+
+impl FormValue {
+    pub(crate) fn of(item: &Item) -> Option<FormValue> {
+        match item {
+            Item::StatefulButton(_, state, _) | Item::Toggle(_, state, _) => Some(FormValue::Bool(*state)),
+            Item::Selector(_, _, selected, _, _) => Some(FormValue::Index(*selected)),
+            Item::Radio(_, index, true, _) => Some(FormValue::Index(*index)),
+            Item::Date(_, date, _) => Some(FormValue::Date(*date)),
+            Item::Time(_, time, _) => Some(FormValue::Time(*time)),
+            Item::Slider(_, _, _, _, value, _) => Some(FormValue::Number(*value)),
+            Item::Stepper(_, value, _, _, _, _) => Some(FormValue::Integer(*value)),
+            Item::TextInput(_, text, _, _) => Some(FormValue::Text(text.clone())),
+            _ => None,
+        }
+    }
+    pub(crate) fn restore(&self, item: &mut Item) {
+        match (self, item) {
+            (FormValue::Bool(value), Item::StatefulButton(_, state, _) | Item::Toggle(_, state, _)) => *state = *value,
+            (FormValue::Index(value), Item::Selector(_, options, selected, _, _)) => {
+                *selected = (*value).min(options.len().saturating_sub(1));
+            },
+            (FormValue::Index(value), Item::Radio(_, index, picked, _)) => *picked = *index == *value,
+            (FormValue::Date(value), Item::Date(_, date, _)) => *date = *value,
+            (FormValue::Time(value), Item::Time(_, time, _)) => *time = *value,
+            (FormValue::Number(number), Item::Slider(_, min, max, _, value, _)) => *value = (*number).clamp(*min, *max),
+            (FormValue::Integer(number), Item::Stepper(_, value, min, max, _, _)) => *value = (*number).clamp(*min, *max),
+            (FormValue::Text(value), Item::TextInput(_, text, _, _)) => *text = value.clone(),
+            _ => (),
+        }
+    }
+}
+
+// A set of items that's applied as a whole. Changes to its items don't get reported one by
+// one, pressing the apply button reports all values in a single GuiEvent::FormSubmitted and
+// the revert button puts back the values from the last apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form {
+    pub id: u128,
+    pub items: Vec<u128>,
+    // ids of stateless buttons
+    pub apply: Option<u128>,
+    pub revert: Option<u128>,
+}
+
+impl Form {
+    pub fn new(id: u128, items: &[u128]) -> Self {
+        Self {
+            id,
+            items: items.to_vec(),
+            apply: None,
+            revert: None,
+        }
+    }
+    pub fn apply_button(mut self, id: u128) -> Self {
+        self.apply = Some(id);
+        self
+    }
+    pub fn revert_button(mut self, id: u128) -> Self {
+        self.revert = Some(id);
+        self
+    }
+}
@@ -0,0 +1,112 @@
+use std::{fmt, time::{Duration, SystemTime}};
+
+// Live value of an Item::Value, shown in a readable form and changed with Gui::set_value().
+// Since values move on by themselves, the gui works out how long ago they were from its
+// Clock every now and then.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bytes(u64),
+    Duration(Duration),
+    // the moment and how long ago it was when last counted, see From<SystemTime>
+    Since(SystemTime, Duration),
+    // 0.0 - 1.0
+    Percent(f32),
+    // shown as is, e.g. an IP address
+    Text(String),
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::Text(text.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::Text(text)
+    }
+}
+
+// zero until the gui first counts it
+impl From<SystemTime> for Value {
+    fn from(time: SystemTime) -> Self {
+        Value::Since(time, Duration::ZERO)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bytes(v) => write!(f, "{}", bytes(*v)),
+            Value::Duration(v) => write!(f, "{}", duration(*v)),
+            Value::Since(_, elapsed) => write!(f, "{}", ago(*elapsed)),
+            Value::Percent(v) => write!(f, "{}", percent(*v)),
+            Value::Text(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+// "512 B", "1.2 GiB"
+pub fn bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    // a decimal only where it still says something
+    match value < 10.0 {
+        true => format!("{:.1} {}", value, UNITS[unit]),
+        false => format!("{:.0} {}", value, UNITS[unit]),
+    }
+}
+
+// the two biggest units that aren't zero, "42 s", "3 min 5 s", "2 h 10 min", "1 d 4 h"
+// whole seconds rounded up, so the last second shows until it's over
+pub fn countdown(left: Duration) -> String {
+    let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+    duration(Duration::from_secs(secs))
+}
+
+pub fn duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, mins, secs) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match (days, hours, mins) {
+        (0, 0, 0) => format!("{} s", secs),
+        (0, 0, _) if secs == 0 => format!("{} min", mins),
+        (0, 0, _) => format!("{} min {} s", mins, secs),
+        (0, _, 0) => format!("{} h", hours),
+        (0, _, _) => format!("{} h {} min", hours, mins),
+        (_, 0, _) => format!("{} d", days),
+        _ => format!("{} d {} h", days, hours),
+    }
+}
+
+// how long ago something happened, in the biggest whole unit, "just now", "3 min ago"
+pub fn ago(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} d ago", secs / 86400),
+    }
+}
+
+// `fraction` goes from 0.0 to 1.0, "42%"
+pub fn percent(fraction: f32) -> String {
+    format!("{}%", (fraction.clamp(0.0, 1.0) * 100.0).round() as u32)
+}
+
+// "45", "37.5", a decimal only when there's one to show
+pub fn number(value: f32) -> String {
+    let rounded = (value * 10.0).round() / 10.0;
+    match rounded.fract() == 0.0 {
+        true => format!("{:.0}", rounded),
+        false => format!("{:.1}", rounded),
+    }
+}
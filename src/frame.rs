@@ -0,0 +1,159 @@
+use crate::{Renderer, ColorPalette, layout::Item};
+use anyhow::Result;
+
+// parts of the screen that changed since the last frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Damage {
+    // tab names and the page indicator
+    Header,
+    // the grid, the list of split-pane tabs and the footer
+    Items,
+    // anything drawn on top of the items, repainted whenever the items are
+    Overlays,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlay<'a> {
+    // options of a SelectorMode::Popup selector
+    Popup { options: &'a [String], selected: usize },
+    // full size code of a focused Item::QrCode
+    QrCode { data: &'a str },
+    // all rows of a focused Item::Table
+    Table { headers: &'a [String], rows: &'a [Vec<String>], selected: usize },
+    // all lines of a focused Item::Log, oldest first
+    Log { lines: &'a [String] },
+    // value of the item in edit mode, shown big so small steps are easy to follow
+    Readout { text: &'a str },
+    // on-screen keyboard of an Item::TextInput, `selected` is (row, key)
+    Keyboard { text: &'a str, keys: &'a [Vec<String>], selected: (usize, usize) },
+    // idle blank screen, hides everything else, see idle::IdleMode::Blank
+    Blank { logo: &'a str, position: (f32, f32) },
+}
+
+// Everything a renderer needs for one frame. Only the parts listed in `damage` have to be
+// drawn, the rest is still on screen from earlier frames.
+#[derive(Debug, Clone)]
+pub struct Frame<'a> {
+    // index of the current tab
+    pub tab: usize,
+    // starting with the current tab
+    pub tab_names: Vec<&'a str>,
+    // names of the pages pushed on the current tab with Gui::push_page(), outermost first
+    pub breadcrumbs: Vec<&'a str>,
+    // (current, total) on paged tabs
+    pub page: Option<(usize, usize)>,
+    // rows of the current page
+    pub items: &'a [Vec<Item>],
+    // relative to `items`, None while the list pane has focus
+    pub focus: Option<(usize, usize)>,
+    // entries and selected entry of split-pane tabs
+    pub list: Option<(&'a [Item], usize)>,
+    // whether each row of `items` is picked, Some only on multi-select tabs
+    pub marked: Option<Vec<bool>>,
+    // rows picked on the whole tab, not just this page
+    pub selected_count: usize,
+    // `items` is a single row of tiles, see layout::TabBuilder::carousel()
+    pub carousel: bool,
+    // items and focused column of the footer
+    pub footer: Option<(&'a [Item], Option<usize>)>,
+    // bottom first
+    pub overlays: Vec<Overlay<'a>>,
+    pub colors: &'a ColorPalette,
+    pub damage: Vec<Damage>,
+}
+
+impl Frame<'_> {
+    pub fn damaged(&self, part: Damage) -> bool {
+        self.damage.contains(&part)
+    }
+}
+
+// shown in place of the items of tabs without any, and of layouts without tabs
+const EMPTY: &str = "Nothing here";
+
+// Default Renderer::render(), splits the frame up into the older per-part draw calls.
+pub fn render_parts<R: Renderer + ?Sized>(renderer: &mut R, frame: &Frame) -> Result<()> {
+    let blank = frame.overlays.iter().find_map(|v| match v {
+        Overlay::Blank { logo, position } => Some((*logo, *position)),
+        _ => None,
+    });
+    if let Some((logo, position)) = blank {
+        if !frame.damage.is_empty() {
+            renderer.draw_blank(logo, position, frame.colors)?;
+        }
+        return Ok(());
+    }
+
+    if frame.damaged(Damage::Header) {
+        // nested pages show up as a trail in place of the current tab's name, followed by
+        // the size of the selection on multi-select tabs
+        let mut current = frame.tab_names.first().map(|v| v.to_string());
+        if let Some(name) = current.as_mut() {
+            if !frame.breadcrumbs.is_empty() {
+                *name = std::iter::once(name.as_str()).chain(frame.breadcrumbs.iter().copied()).collect::<Vec<_>>().join(" ▸ ");
+            }
+            if frame.selected_count > 0 {
+                *name = format!("{} ({} selected)", name, frame.selected_count);
+            }
+        }
+        let mut names = frame.tab_names.clone();
+        if let (Some(name), Some(first)) = (current.as_ref(), names.first_mut()) {
+            *first = name;
+        }
+        renderer.draw_tab_header(&names, frame.colors)?;
+        if let Some((current, total)) = frame.page {
+            renderer.draw_page_indicator(current, total, frame.colors)?;
+        }
+    }
+
+    // picked rows of multi-select tabs get the checked marker in front of their first label
+    let marked_items = frame.marked.as_ref().map(|marked| {
+        frame.items.iter()
+            .zip(marked.iter())
+            .map(|(row, marked)| {
+                let mut row = row.clone();
+                if let Some(label) = row.iter_mut().find_map(Item::label_mut) {
+                    *label = format!("{} {}", frame.colors.marker(*marked), label);
+                }
+                row
+            })
+            .collect::<Vec<_>>()
+    });
+    let items = marked_items.as_deref().unwrap_or(frame.items);
+    let placeholder = [vec![Item::Text(EMPTY.to_string())]];
+    let empty = items.iter().all(Vec::is_empty);
+    let (items, focus) = match empty {
+        true => (&placeholder[..], None),
+        // nothing but separators and headers, so there's nothing to highlight
+        false => (items, frame.focus.filter(|_| items.iter().flatten().any(Item::focusable))),
+    };
+
+    if frame.damaged(Damage::Items) {
+        match frame.list {
+            Some((list, selected)) => renderer.draw_split(list, selected, items, focus, frame.colors)?,
+            None if frame.carousel && !empty => {
+                let tiles = items.first().map(Vec::as_slice).unwrap_or(&[]);
+                renderer.draw_carousel(tiles, focus.map(|v| v.1).unwrap_or(usize::MAX), frame.colors)?
+            },
+            None => renderer.draw_items(items, frame.colors, focus.unwrap_or((usize::MAX, usize::MAX)))?,
+        }
+        if let Some((footer, selected)) = frame.footer {
+            renderer.draw_footer(footer, frame.colors, selected)?;
+        }
+    }
+
+    if frame.damaged(Damage::Overlays) {
+        for overlay in frame.overlays.iter() {
+            match overlay {
+                Overlay::Popup { options, selected } => renderer.draw_popup(options, *selected, frame.colors)?,
+                Overlay::QrCode { data } => renderer.draw_qr(data, frame.colors)?,
+                Overlay::Table { headers, rows, selected } => renderer.draw_table(headers, rows, *selected, frame.colors)?,
+                Overlay::Log { lines } => renderer.draw_log(lines, frame.colors)?,
+                Overlay::Readout { text } => renderer.draw_readout(text, frame.colors)?,
+                Overlay::Keyboard { text, keys, selected } => renderer.draw_keyboard(text, keys, *selected, frame.colors)?,
+                Overlay::Blank { .. } => (),
+            }
+        }
+    }
+    Ok(())
+}
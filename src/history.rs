@@ -0,0 +1,66 @@
+use crate::{layout::Item, Pane};
+use std::collections::VecDeque;
+
+// a single user-made change, kept so it can be undone and redone
+#[derive(Debug, Clone)]
+pub(crate) enum Change {
+    // item at (row, column) of a tab changed from the first to the second value, boxed since
+    // items are much bigger than moves
+    Item { tab: usize, pos: (usize, usize), before: Box<Item>, after: Box<Item> },
+    // a row or list entry was moved from one index to another
+    Moved { tab: usize, pane: Pane, id: u128, from: usize, to: usize },
+    // the pick of a radio group went from one option to another, None if none was picked
+    Radio { tab: usize, id: u128, before: Option<usize>, after: usize },
+}
+
+pub(crate) struct History {
+    undo: VecDeque<Change>,
+    redo: Vec<Change>,
+    limit: usize,
+}
+
+impl History {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+    // `merge` folds the change into the previous one if it touched the same item,
+    // so holding a stick on a value doesn't fill the history with single steps
+    pub(crate) fn record(&mut self, change: Change, merge: bool) {
+        if self.limit == 0 {
+            return;
+        }
+        self.redo.clear();
+
+        if merge {
+            if let (Some(Change::Item { tab, pos, after, .. }), Change::Item { tab: new_tab, pos: new_pos, after: new_after, .. }) = (self.undo.back_mut(), &change) {
+                if tab == new_tab && pos == new_pos {
+                    *after = new_after.clone();
+                    return;
+                }
+            }
+        }
+
+        self.undo.push_back(change);
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+    pub(crate) fn undo(&mut self) -> Option<Change> {
+        let change = self.undo.pop_back()?;
+        self.redo.push(change.clone());
+        Some(change)
+    }
+    pub(crate) fn redo(&mut self) -> Option<Change> {
+        let change = self.redo.pop()?;
+        self.undo.push_back(change.clone());
+        Some(change)
+    }
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
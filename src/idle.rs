@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdleMode {
+    // darker palette, or the dim attribute on terminals
+    Dim,
+    // a black screen with just `logo` on it, moved around so no pixel stays lit
+    Blank(String),
+}
+
+// What happens once there was no input for `after`. Any input wakes the gui up again,
+// that input is swallowed so nothing gets pressed blindly on a dark screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdleConfig {
+    pub after: Duration,
+    pub mode: IdleMode,
+    // how often the blank screen logo jumps to a new place
+    pub logo_interval: Duration,
+}
+
+impl IdleConfig {
+    pub fn dim(after: Duration) -> Self {
+        Self {
+            after,
+            mode: IdleMode::Dim,
+            logo_interval: Duration::from_secs(5),
+        }
+    }
+    pub fn blank(after: Duration, logo: &str) -> Self {
+        Self {
+            mode: IdleMode::Blank(logo.to_string()),
+            ..Self::dim(after)
+        }
+    }
+}
+
+// how far the palette is pulled towards black while dimmed
+pub(crate) const DIM_AMOUNT: f32 = 0.6;
+
+// Logo position for the given move as fractions of the free space, stepping by irrational
+// amounts spreads the positions over the whole screen without obvious repeats.
+pub(crate) fn logo_position(step: u32) -> (f32, f32) {
+    ((step as f32 * 0.618_034).fract(), (step as f32 * 0.414_214).fract())
+}
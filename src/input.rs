@@ -0,0 +1,230 @@
+use crate::{HidEvent, ActionButton, Axis, config::Bindings, controller::ConfirmSwap};
+use std::{
+    io::{self, BufRead},
+    thread,
+};
+use crossbeam_channel::{bounded, Receiver};
+
+pub trait InputSource: Send {
+    // blocks until the next event is available, None means the source is gone for good
+    fn next_event(&mut self) -> Option<HidEvent>;
+}
+
+impl<F> InputSource for F
+where
+    F: FnMut() -> Option<HidEvent> + Send,
+{
+    fn next_event(&mut self) -> Option<HidEvent> {
+        self()
+    }
+}
+
+// what a filter set with Gui::set_input_filter() does with an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    // handled as usual
+    Pass,
+    // dropped without a trace
+    Swallow,
+    // handled as if this event came in instead
+    Remap(HidEvent),
+    // skips navigation and goes straight to the application as GuiEvent::HidCaptured
+    Capture,
+}
+
+// every source gets its own thread, all of them feed a single channel
+pub fn spawn_sources(sources: Vec<Box<dyn InputSource>>) -> Option<Receiver<HidEvent>> {
+    if sources.is_empty() {
+        return None;
+    }
+
+    let (tx, rx) = bounded(1);
+    for mut source in sources.into_iter() {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            while let Some(ev) = source.next_event() {
+                if tx.send(ev).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    Some(rx)
+}
+
+#[cfg(feature = "ez_input")]
+pub struct RinputerSource {
+    handle: ez_input::RinputerHandle,
+    bindings: Bindings,
+    swap: ConfirmSwap,
+}
+
+#[cfg(feature = "ez_input")]
+impl RinputerSource {
+    pub fn open() -> Option<Self> {
+        Some(Self {
+            handle: ez_input::RinputerHandle::open()?,
+            bindings: Bindings::default(),
+            swap: ConfirmSwap::default(),
+        })
+    }
+    // button names are "south", "east", "north", "west", "l", "r", "l2", "r2", "start" and "select"
+    pub fn with_bindings(mut self, bindings: Bindings) -> Self {
+        self.bindings = bindings;
+        self
+    }
+    // follows the gui's controller style, see Gui::confirm_swap()
+    pub fn with_confirm_swap(mut self, swap: ConfirmSwap) -> Self {
+        self.swap = swap;
+        self
+    }
+}
+
+#[cfg(feature = "ez_input")]
+impl InputSource for RinputerSource {
+    fn next_event(&mut self) -> Option<HidEvent> {
+        use ez_input::EzEvent;
+        loop {
+            let Some(event) = self.handle.get_event_blocking() else {continue};
+            let button = match event {
+                EzEvent::South(true) => Some("south"),
+                EzEvent::East(true) => Some("east"),
+                EzEvent::North(true) => Some("north"),
+                EzEvent::West(true) => Some("west"),
+                EzEvent::L(true) => Some("l"),
+                EzEvent::R(true) => Some("r"),
+                EzEvent::L2(true) => Some("l2"),
+                EzEvent::R2(true) => Some("r2"),
+                EzEvent::Start(true) => Some("start"),
+                EzEvent::Select(true) => Some("select"),
+                _ => None,
+            };
+            if let Some(ev) = button.and_then(|v| self.bindings.button(v)) {
+                return Some(ev);
+            }
+            return Some(match event {
+                EzEvent::DirectionUp => HidEvent::Up,
+                EzEvent::DirectionDown => HidEvent::Down,
+                EzEvent::DirectionLeft => HidEvent::Left,
+                EzEvent::DirectionRight => HidEvent::Right,
+                // raw stick positions, next to the Direction* steps made from them
+                EzEvent::LeftX(value) => HidEvent::Analog{axis: Axis::LeftX, value},
+                EzEvent::LeftY(value) => HidEvent::Analog{axis: Axis::LeftY, value},
+                EzEvent::RightX(value) => HidEvent::Analog{axis: Axis::RightX, value},
+                EzEvent::RightY(value) => HidEvent::Analog{axis: Axis::RightY, value},
+                EzEvent::South(true) if self.swap.swapped() => HidEvent::Back,
+                EzEvent::East(true) if self.swap.swapped() => HidEvent::ButtonPress,
+                EzEvent::South(true) => HidEvent::ButtonPress,
+                EzEvent::East(true) => HidEvent::Back,
+                EzEvent::North(true) => HidEvent::Action(ActionButton::North),
+                EzEvent::West(true) => HidEvent::Action(ActionButton::West),
+                EzEvent::Start(true) => HidEvent::Action(ActionButton::Start),
+                EzEvent::Select(true) => HidEvent::Action(ActionButton::Select),
+                EzEvent::R(true) => HidEvent::NextTab,
+                EzEvent::L(true) => HidEvent::PreviousTab,
+                _ => continue,
+            });
+        }
+    }
+}
+
+// reads one command per line, e.g. "up", "select" or "tab 2"
+pub struct LineCommandSource<R> {
+    reader: R,
+}
+
+impl LineCommandSource<io::BufReader<io::Stdin>> {
+    pub fn stdin() -> Self {
+        Self::new(io::BufReader::new(io::stdin()))
+    }
+}
+
+impl<R: BufRead + Send> LineCommandSource<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: BufRead + Send> InputSource for LineCommandSource<R> {
+    fn next_event(&mut self) -> Option<HidEvent> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => if let Some(ev) = parse_command(&line) {
+                    return Some(ev);
+                },
+            }
+        }
+    }
+}
+
+pub fn parse_command(line: &str) -> Option<HidEvent> {
+    let mut words = line.split_whitespace();
+    let ev = match words.next()?.to_lowercase().as_str() {
+        "up" => HidEvent::Up,
+        "down" => HidEvent::Down,
+        "left" => HidEvent::Left,
+        "right" => HidEvent::Right,
+        "select" | "press" | "enter" => HidEvent::ButtonPress,
+        "next" => HidEvent::NextTab,
+        "prev" | "previous" => HidEvent::PreviousTab,
+        "tab" => match words.next() {
+            Some(n) => HidEvent::GoToTab(n.parse().ok()?),
+            None => HidEvent::NextTab,
+        },
+        "first" | "home" => HidEvent::First,
+        "last" | "end" => HidEvent::Last,
+        "pane" => HidEvent::SwitchPane,
+        "grab" => HidEvent::Grab,
+        "action" => HidEvent::Action(match words.next()? {
+            "north" => ActionButton::North,
+            "west" => ActionButton::West,
+            "start" => ActionButton::Start,
+            "select" => ActionButton::Select,
+            _ => return None,
+        }),
+        "guide" => HidEvent::Home,
+        "undo" => HidEvent::Undo,
+        "redo" => HidEvent::Redo,
+        "back" | "cancel" => HidEvent::Back,
+        "axis" => {
+            let axis = match words.next()? {
+                "lx" => Axis::LeftX,
+                "ly" => Axis::LeftY,
+                "rx" => Axis::RightX,
+                "ry" => Axis::RightY,
+                "lt" => Axis::TriggerLeft,
+                "rt" => Axis::TriggerRight,
+                _ => return None,
+            };
+            HidEvent::Analog{axis, value: words.next()?.parse().ok()?}
+        },
+        "quit" | "exit" => HidEvent::Quit,
+        _ => return None,
+    };
+    Some(ev)
+}
+
+pub fn autopick_sources() -> Vec<Box<dyn InputSource>> {
+    autopick_sources_with(&Bindings::default())
+}
+
+pub fn autopick_sources_with(bindings: &Bindings) -> Vec<Box<dyn InputSource>> {
+    autopick_sources_for(bindings, &ConfirmSwap::default())
+}
+
+#[allow(unused_variables)]
+pub fn autopick_sources_for(bindings: &Bindings, swap: &ConfirmSwap) -> Vec<Box<dyn InputSource>> {
+    #[allow(unused_mut)]
+    let mut ret: Vec<Box<dyn InputSource>> = Vec::new();
+
+    #[cfg(feature = "ez_input")]
+    if let Some(source) = RinputerSource::open() {
+        ret.push(Box::new(source.with_bindings(bindings.clone()).with_confirm_swap(swap.clone())));
+    }
+
+    ret
+}
@@ -0,0 +1,153 @@
+use crate::{HidEvent, input::{InputSource, parse_command}};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+const REMOTE_PAGE: &str = r#"<!DOCTYPE html>
+<html><head><meta name="viewport" content="width=device-width, initial-scale=1">
+<title>sgui remote</title>
+<style>
+body { background: #000; color: #fff; font-family: sans-serif; text-align: center; }
+button { width: 30%; height: 4em; margin: 0.3em; font-size: 1.2em; background: #222; color: #fff; border: 1px solid #f00; }
+</style></head><body>
+<div><button onclick="cmd('prev')">L</button><button onclick="cmd('up')">Up</button><button onclick="cmd('next')">R</button></div>
+<div><button onclick="cmd('left')">Left</button><button onclick="cmd('select')">OK</button><button onclick="cmd('right')">Right</button></div>
+<div><button onclick="cmd('down')">Down</button></div>
+<script>
+const secret = new URLSearchParams(location.search).get("secret") || "";
+function cmd(c) { fetch("/cmd?secret=" + encodeURIComponent(secret) + "&c=" + encodeURIComponent(c)); }
+</script></body></html>
+"#;
+
+// Accepts navigation commands over TCP. Plain clients send the secret on the first line and
+// then one command per line, browsers get a small remote control page at /?secret=...
+pub struct RemoteSource {
+    rx: Receiver<HidEvent>,
+}
+
+impl RemoteSource {
+    pub fn bind(addr: impl ToSocketAddrs, secret: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let secret: Arc<str> = Arc::from(secret);
+        let (tx, rx) = unbounded();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {continue};
+                let tx = tx.clone();
+                let secret = secret.clone();
+                thread::spawn(move || {
+                    let _ = handle_client(stream, &secret, tx);
+                });
+            }
+        });
+
+        Ok(Self { rx })
+    }
+}
+
+impl InputSource for RemoteSource {
+    fn next_event(&mut self) -> Option<HidEvent> {
+        self.rx.recv().ok()
+    }
+}
+
+fn secret_matches(given: &str, secret: &str) -> bool {
+    // compare everything so the time taken doesn't leak the matching prefix length
+    given.len() == secret.len() && given.bytes().zip(secret.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn handle_client(stream: TcpStream, secret: &str, tx: Sender<HidEvent>) -> io::Result<()> {
+    let mut out = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut first = String::new();
+    reader.read_line(&mut first)?;
+
+    if first.starts_with("GET ") {
+        return handle_http(&first, reader, out, secret, tx);
+    }
+
+    if !secret_matches(first.trim_end(), secret) {
+        out.write_all(b"denied\n")?;
+        return Ok(());
+    }
+    out.write_all(b"ok\n")?;
+
+    for line in reader.lines() {
+        let line = line?;
+        match parse_command(&line) {
+            Some(ev) => if tx.send(ev).is_err() {
+                break;
+            },
+            None => out.write_all(b"unknown command\n")?,
+        }
+    }
+    Ok(())
+}
+
+fn handle_http(request: &str, mut reader: BufReader<TcpStream>, mut out: TcpStream, secret: &str, tx: Sender<HidEvent>) -> io::Result<()> {
+    // skip headers, we don't need any of them
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut given_secret = String::new();
+    let mut command = String::new();
+    for pair in query.split('&') {
+        match pair.split_once('=') {
+            Some(("secret", v)) => given_secret = url_decode(v),
+            Some(("c", v)) => command = url_decode(v),
+            _ => (),
+        }
+    }
+
+    let (status, body) = if !secret_matches(&given_secret, secret) {
+        ("403 Forbidden", "denied")
+    } else {
+        match path {
+            "/" => ("200 OK", REMOTE_PAGE),
+            "/cmd" => match parse_command(&command) {
+                Some(ev) => {
+                    let _ = tx.send(ev);
+                    ("200 OK", "ok")
+                },
+                None => ("400 Bad Request", "unknown command"),
+            },
+            _ => ("404 Not Found", "not found"),
+        }
+    };
+
+    let content_type = if body == REMOTE_PAGE { "text/html" } else { "text/plain" };
+    write!(out, "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body)?;
+    out.flush()
+}
+
+fn url_decode(input: &str) -> String {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut iter = input.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                match std::str::from_utf8(&hex).ok().and_then(|v| u8::from_str_radix(v, 16).ok()) {
+                    Some(v) => bytes.push(v),
+                    None => bytes.extend_from_slice(&hex),
+                }
+            },
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
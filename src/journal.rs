@@ -0,0 +1,102 @@
+use crate::{GuiEvent, layout::ItemKind};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, LineWriter, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// what `detail` becomes for values that shouldn't end up on disk
+pub const REDACTED: &str = "[redacted]";
+
+// A GuiEvent as it's written to the journal. Redactors get every entry before that
+// happens and can blank out whatever shouldn't leave the device, see Gui::set_journal_redactor().
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    // milliseconds since the unix epoch
+    pub time: u128,
+    // Gui::now() when the event was returned
+    pub uptime: Duration,
+    // variant name, e.g. "SliderChanged"
+    pub event: String,
+    // everything the event carries, the way Debug prints it
+    pub detail: String,
+    pub item: Option<JournalItem>,
+}
+
+// the item an event is about
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalItem {
+    pub id: u128,
+    pub kind: ItemKind,
+    pub label: Option<String>,
+    pub tab: Option<String>,
+    // password fields, their values are redacted before any redactor runs
+    pub masked: bool,
+}
+
+impl JournalEntry {
+    pub(crate) fn new(wall: SystemTime, uptime: Duration, ev: &GuiEvent, item: Option<JournalItem>) -> Self {
+        let debug = format!("{:?}", ev);
+        let (event, detail) = match debug.find(['(', ' ']) {
+            Some(split) => (&debug[..split], debug[split..].trim()),
+            None => (debug.as_str(), ""),
+        };
+        let detail = detail.strip_prefix('(').and_then(|v| v.strip_suffix(')')).unwrap_or(detail);
+        let masked = item.as_ref().is_some_and(|v| v.masked);
+        Self {
+            time: wall.duration_since(UNIX_EPOCH).map(|v| v.as_millis()).unwrap_or_default(),
+            uptime,
+            event: event.to_string(),
+            detail: if masked { REDACTED.to_string() } else { detail.to_string() },
+            item,
+        }
+    }
+    // a single line of JSON, ids are strings since they don't fit a JSON number
+    fn to_json(&self) -> String {
+        let item = match self.item.as_ref() {
+            Some(item) => format!(r#"{{"id":"{}","kind":"{:?}","label":{},"tab":{}}}"#,
+                item.id, item.kind, json_opt(item.label.as_deref()), json_opt(item.tab.as_deref())),
+            None => "null".to_string(),
+        };
+        format!(r#"{{"time":{},"uptime_ms":{},"event":{},"detail":{},"item":{}}}"#,
+            self.time, self.uptime.as_millis(), json_str(&self.event), json_str(&self.detail), item)
+    }
+}
+
+// appends a JSON line per entry, lines are flushed right away so a crash loses nothing
+pub(crate) struct Journal {
+    out: LineWriter<File>,
+}
+
+impl Journal {
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { out: LineWriter::new(file) })
+    }
+    pub(crate) fn write(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        writeln!(self.out, "{}", entry.to_json())
+    }
+}
+
+fn json_str(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len() + 2);
+    ret.push('"');
+    for c in input.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if c.is_control() => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+fn json_opt(input: Option<&str>) -> String {
+    input.map(json_str).unwrap_or_else(|| "null".to_string())
+}
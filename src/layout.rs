@@ -1,16 +1,43 @@
-#[derive(Debug)]
+use crate::{HidEvent, command::CommandSpec, datetime::{Date, Time}, format::Value, input::Filter, power::Battery};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+#[derive(Debug, Clone, Default)]
 pub struct Layout {
     tabs: Vec<Tab>,
 }
 
+// Position of a tab in a Layout. Layouts only hand out ids of tabs they have, except for
+// the id of a layout without tabs, which doesn't point anywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TabId(usize);
+
+impl TabId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
 impl Layout {
     pub fn tab_names(&self) -> Vec<&str> {
         self.tabs.iter()
             .map(|v| v.name())
             .collect()
     }
-    pub fn tab_count(&self) -> i32 {
-        self.tabs.len() as i32 - 1
+    pub fn tab_count(&self) -> usize {
+        self.tabs.len()
+    }
+    // the tab at `index`, or the last one if there aren't that many
+    pub fn clamp_tab(&self, index: usize) -> TabId {
+        TabId(index.min(self.tabs.len().saturating_sub(1)))
+    }
+    // stops at the last tab
+    pub fn next_tab(&self, id: TabId) -> TabId {
+        self.clamp_tab(id.0 + 1)
+    }
+    // stops at the first tab
+    pub fn prev_tab(&self, id: TabId) -> TabId {
+        self.clamp_tab(id.0.saturating_sub(1))
     }
     pub fn tab(&self, number: usize) -> Option<&Tab> {
         self.tabs.get(number)
@@ -21,11 +48,81 @@ impl Layout {
     pub fn builder() -> LayoutBuilder {
         LayoutBuilder::new()
     }
+    // every item of every tab, including lists and footers
+    pub fn all_items(&self) -> impl Iterator<Item = &Item> {
+        self.tabs.iter().flat_map(|tab| {
+            tab.item_grid.iter().flatten()
+                .chain(tab.list.iter().flatten())
+                .chain(tab.footer.iter().flatten())
+        })
+    }
+    pub fn all_items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.tabs.iter_mut().flat_map(Tab::all_items_mut)
+    }
+    // What it takes to turn this layout into `other`. Items that keep their place are
+    // changed one by one, grids that changed shape as a whole, see Gui::apply_layout().
+    pub fn diff(&self, other: &Layout) -> Vec<LayoutChange> {
+        if self.tabs.len() != other.tabs.len() || self.tabs.iter().zip(other.tabs.iter()).any(|(a, b)| !a.same_setup(b)) {
+            return vec![LayoutChange::Replace];
+        }
+        let mut changes = Vec::new();
+        for (tab, (old, new)) in self.tabs.iter().zip(other.tabs.iter()).enumerate() {
+            let same_shape = old.item_grid.len() == new.item_grid.len()
+                && old.item_grid.iter().zip(new.item_grid.iter()).all(|(a, b)| a.len() == b.len());
+            match same_shape {
+                true => {
+                    for (row, (a, b)) in old.item_grid.iter().zip(new.item_grid.iter()).enumerate() {
+                        for (column, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                            if a != b {
+                                changes.push(LayoutChange::Item(tab, row, column, b.clone()));
+                            }
+                        }
+                    }
+                },
+                false => changes.push(LayoutChange::Grid(tab, new.item_grid.clone())),
+            }
+            if let (Some(a), Some(b)) = (old.list.as_ref(), new.list.as_ref()) {
+                if a != b {
+                    changes.push(LayoutChange::List(tab, b.clone()));
+                }
+            }
+            if let (Some(a), Some(b)) = (old.footer.as_ref(), new.footer.as_ref()) {
+                if a != b {
+                    changes.push(LayoutChange::Footer(tab, b.clone()));
+                }
+            }
+        }
+        changes
+    }
 }
-#[derive(Debug)]
+
+// one step of Layout::diff()
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutChange {
+    // tabs were added, removed, renamed or set up differently, nothing short of the whole
+    // layout will do
+    Replace,
+    // tab, row, column and the new item
+    Item(usize, usize, usize, Item),
+    // tab and its new item grid, rows were added, removed or changed length
+    Grid(usize, Vec<Vec<Item>>),
+    List(usize, Vec<Item>),
+    Footer(usize, Vec<Item>),
+}
+#[derive(Debug, Clone)]
 pub struct Tab {
     name: String,
     item_grid: Vec<Vec<Item>>,
+    page_size: Option<usize>,
+    footer: Option<Vec<Item>>,
+    footer_focusable: bool,
+    list: Option<Vec<Item>>,
+    reorderable: bool,
+    carousel: bool,
+    bindings: Vec<(HidEvent, Filter)>,
+    multi_select: Option<u128>,
+    // item id, button and what it does
+    actions: Vec<(u128, HidEvent, ItemAction)>,
 }
 
 impl Tab {
@@ -38,31 +135,395 @@ impl Tab {
     pub fn items_mut(&mut self) -> &mut Vec<Vec<Item>> {
         &mut self.item_grid
     }
+    // the grid, the list and the footer
+    pub fn all_items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.item_grid.iter_mut().flatten()
+            .chain(self.list.iter_mut().flatten())
+            .chain(self.footer.iter_mut().flatten())
+    }
+    // pinned to the bottom of the item area, never scrolls
+    pub fn footer(&self) -> Option<&Vec<Item>> {
+        self.footer.as_ref()
+    }
+    pub fn footer_mut(&mut self) -> Option<&mut Vec<Item>> {
+        self.footer.as_mut()
+    }
+    // a focusable footer is reachable by moving past the last row, it then
+    // acts as row number items().len()
+    pub fn footer_focusable(&self) -> bool {
+        self.footer.is_some() && self.footer_focusable
+    }
+    pub fn footer_row(&self) -> usize {
+        self.item_grid.len()
+    }
+    pub fn row(&self, row: usize) -> Option<&Vec<Item>> {
+        if row == self.footer_row() {
+            return self.footer.as_ref();
+        }
+        self.item_grid.get(row)
+    }
+    pub fn item(&self, row: usize, column: usize) -> Option<&Item> {
+        if row == self.footer_row() {
+            return self.footer.as_ref()?.get(column);
+        }
+        self.item_grid.get(row)?.get(column)
+    }
+    pub fn item_mut(&mut self, row: usize, column: usize) -> Option<&mut Item> {
+        if row == self.footer_row() {
+            return self.footer.as_mut()?.get_mut(column);
+        }
+        self.item_grid.get_mut(row)?.get_mut(column)
+    }
+    // split-pane tabs have a list on the left and the item grid on the right
+    pub fn list(&self) -> Option<&Vec<Item>> {
+        self.list.as_ref()
+    }
+    pub fn list_mut(&mut self) -> Option<&mut Vec<Item>> {
+        self.list.as_mut()
+    }
+    // rows of the grid and entries of the list can be picked up and moved around
+    pub fn reorderable(&self) -> bool {
+        self.reorderable
+    }
+    // items are shown as big tiles in a single row, see TabBuilder::carousel()
+    pub fn carousel(&self) -> bool {
+        self.carousel
+    }
+    // id of the confirm button of multi-select tabs, see TabBuilder::multi_select()
+    pub fn multi_select(&self) -> Option<u128> {
+        self.multi_select
+    }
+    // buttons the item with `id` declared with TabBuilder::item_action()
+    pub fn item_actions(&self, id: u128) -> impl Iterator<Item = (&HidEvent, &ItemAction)> {
+        self.actions.iter().filter(move |(v, _, _)| *v == id).map(|(_, ev, action)| (ev, action))
+    }
+    pub fn item_action(&self, id: u128, ev: &HidEvent) -> Option<&ItemAction> {
+        self.item_actions(id).filter(|(v, _)| *v == ev).last().map(|(_, action)| action)
+    }
+    // what happens to `ev` while this tab is shown, None if the tab doesn't override it
+    pub fn binding(&self, ev: &HidEvent) -> Option<&Filter> {
+        self.bindings.iter().rev().find(|(v, _)| v == ev).map(|(_, filter)| filter)
+    }
+    // rows per page, None if the tab isn't paged
+    pub fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+    pub fn page_count(&self) -> usize {
+        match self.page_size {
+            Some(size) => self.item_grid.len().div_ceil(size).max(1),
+            None => 1,
+        }
+    }
+    // everything but the items, and whether there's a list and a footer at all
+    fn same_setup(&self, other: &Tab) -> bool {
+        self.name == other.name
+            && self.page_size == other.page_size
+            && self.footer.is_some() == other.footer.is_some()
+            && self.footer_focusable == other.footer_focusable
+            && self.list.is_some() == other.list.is_some()
+            && self.reorderable == other.reorderable
+            && self.carousel == other.carousel
+            && self.bindings == other.bindings
+            && self.multi_select == other.multi_select
+            && self.actions == other.actions
+    }
+    // range of rows shown on the given page
+    pub fn page_rows(&self, page: usize) -> std::ops::Range<usize> {
+        match self.page_size {
+            Some(size) => {
+                let start = (page * size).min(self.item_grid.len());
+                start..(start + size).min(self.item_grid.len())
+            },
+            None => 0..self.item_grid.len(),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Item {
     Text(String),
     StatefulButton(String, bool, u128),
+    // on/off switch, same as StatefulButton but drawn as one and reported with GuiEvent::ToggleChanged
+    Toggle(String, bool, u128),
     StatelessButton(String, u128),
+    // label, options, selected option
+    Selector(String, Vec<String>, usize, SelectorMode, u128),
+    // text, url
+    Link(String, String, u128),
+    // label, what to run, whether it's running right now
+    Command(String, CommandSpec, bool, u128),
+    // output lines, newest last
+    Log(Vec<String>, u128),
+    Date(String, Date, u128),
+    Time(String, Time, u128),
+    // data to encode, the code itself is shown over the items while this has focus
+    QrCode(String),
+    // filled in and kept up to date by the gui, None without a battery
+    Battery(Option<Battery>),
+    // caption, picture, pressed like a stateless button
+    Tile(String, Option<PathBuf>, u128),
+    // label and a number shown formatted, see Gui::set_value()
+    Value(String, Value, u128),
+    // label, min, max, step, value, Left and Right move it while focused
+    Slider(String, i32, i32, i32, i32, u128),
+    // label, text, whether it's shown as dots, pressing it opens the on-screen keyboard
+    TextInput(String, String, bool, u128),
+    // label, 0.0 - 1.0, see Gui::set_progress()
+    ProgressBar(String, f32, u128),
+    // label, current and max reading, see Gui::set_gauge()
+    Gauge(String, f32, f32, u128),
+    // recent samples, oldest first, drawn as a small graph, see Gui::push_sample()
+    Sparkline(Vec<f32>, u128),
+    // picture scaled to fit its cell, e.g. cover art
+    Image(ImageSource, u128),
+    // label, value, min, max, step, Left and Right change it while focused
+    Stepper(String, i64, i64, i64, i64, u128),
+    // option, its index and whether it's picked, options of a group share the group's id,
+    // see LineBuilder::radio_group()
+    Radio(String, usize, bool, u128),
+    // caption, column headers, rows and the selected one, the whole table is shown over the
+    // items while it has focus and Up and Down go through its rows
+    Table(String, Vec<String>, Vec<Vec<String>>, usize, u128),
+    // status or validation feedback, icon and color come from the severity
+    Message(Severity, String),
+    // label, animation frame, whether it spins, see Gui::set_spinner_visible()
+    Spinner(String, usize, bool, u128),
+    // local time, the gui keeps it current on every tab, a minute at a time
+    Clock(Option<Time>),
+    // time left, goes down by itself and reports GuiEvent::TimerExpired at zero, see Gui::set_countdown()
+    Countdown(Duration, u128),
+    // horizontal rule and section title, navigation steps over both
+    Separator,
+    Header(String),
+}
+
+// Picture of an Item::Image, a file or one that's already in memory, e.g. cover art pulled
+// out of an archive. Bytes are an encoded picture like a file would hold, their name tells
+// them apart in the renderer's cache and stands in for a file name where pictures can't be
+// shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    Path(PathBuf),
+    Bytes(String, Arc<[u8]>),
+}
+
+impl ImageSource {
+    // the file name without its extension, or the name of the bytes
+    pub fn name(&self) -> std::borrow::Cow<'_, str> {
+        match self {
+            ImageSource::Path(path) => path.file_stem().map(|v| v.to_string_lossy()).unwrap_or_default(),
+            ImageSource::Bytes(name, _) => name.into(),
+        }
+    }
+}
+
+// only the name of bytes, so caches don't go through the whole picture on every lookup
+impl std::hash::Hash for ImageSource {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            ImageSource::Path(path) => path.hash(state),
+            ImageSource::Bytes(name, _) => name.hash(state),
+        }
+    }
 }
 
+// Item variants without their contents, see draw::DrawTarget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Text,
+    StatefulButton,
+    Toggle,
+    StatelessButton,
+    Selector,
+    Link,
+    Command,
+    Log,
+    Date,
+    Time,
+    QrCode,
+    Battery,
+    Tile,
+    Value,
+    Slider,
+    TextInput,
+    ProgressBar,
+    Gauge,
+    Sparkline,
+    Image,
+    Stepper,
+    Radio,
+    Spinner,
+    Countdown,
+    Clock,
+    Table,
+    Message,
+    Separator,
+    Header,
+}
+
+impl Item {
+    // text the item is shown with, None for items without one
+    pub(crate) fn label_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Item::Text(text) | Item::StatefulButton(text, _, _) | Item::Toggle(text, _, _) | Item::StatelessButton(text, _)
+                | Item::Selector(text, _, _, _, _) | Item::Link(text, _, _) | Item::Command(text, _, _, _)
+                | Item::Date(text, _, _) | Item::Time(text, _, _) | Item::Tile(text, _, _) | Item::Value(text, _, _)
+                | Item::Slider(text, _, _, _, _, _) | Item::TextInput(text, _, _, _) | Item::ProgressBar(text, _, _)
+                | Item::Gauge(text, _, _, _) | Item::Stepper(text, _, _, _, _, _) | Item::Radio(text, _, _, _) | Item::Spinner(text, _, _, _)
+                | Item::Table(text, _, _, _, _) | Item::Message(_, text) | Item::Header(text) => Some(text),
+            Item::Log(..) | Item::QrCode(..) | Item::Battery(..) | Item::Image(..) | Item::Sparkline(..)
+                | Item::Countdown(..) | Item::Clock(..) | Item::Separator => None,
+        }
+    }
+    pub(crate) fn label(&self) -> Option<&str> {
+        match self {
+            Item::Text(text) | Item::StatefulButton(text, _, _) | Item::Toggle(text, _, _) | Item::StatelessButton(text, _)
+                | Item::Selector(text, _, _, _, _) | Item::Link(text, _, _) | Item::Command(text, _, _, _)
+                | Item::Date(text, _, _) | Item::Time(text, _, _) | Item::Tile(text, _, _) | Item::Value(text, _, _)
+                | Item::Slider(text, _, _, _, _, _) | Item::TextInput(text, _, _, _) | Item::ProgressBar(text, _, _)
+                | Item::Gauge(text, _, _, _) | Item::Stepper(text, _, _, _, _, _) | Item::Radio(text, _, _, _) | Item::Spinner(text, _, _, _)
+                | Item::Table(text, _, _, _, _) | Item::Message(_, text) | Item::Header(text) => Some(text),
+            Item::Log(..) | Item::QrCode(..) | Item::Battery(..) | Item::Image(..) | Item::Sparkline(..)
+                | Item::Countdown(..) | Item::Clock(..) | Item::Separator => None,
+        }
+    }
+    // false for items that are only there to group others
+    pub fn focusable(&self) -> bool {
+        !matches!(self, Item::Separator | Item::Header(_))
+    }
+    pub fn kind(&self) -> ItemKind {
+        match self {
+            Item::Text(..) => ItemKind::Text,
+            Item::StatefulButton(..) => ItemKind::StatefulButton,
+            Item::Toggle(..) => ItemKind::Toggle,
+            Item::StatelessButton(..) => ItemKind::StatelessButton,
+            Item::Selector(..) => ItemKind::Selector,
+            Item::Link(..) => ItemKind::Link,
+            Item::Command(..) => ItemKind::Command,
+            Item::Log(..) => ItemKind::Log,
+            Item::Date(..) => ItemKind::Date,
+            Item::Time(..) => ItemKind::Time,
+            Item::QrCode(..) => ItemKind::QrCode,
+            Item::Battery(..) => ItemKind::Battery,
+            Item::Tile(..) => ItemKind::Tile,
+            Item::Value(..) => ItemKind::Value,
+            Item::Slider(..) => ItemKind::Slider,
+            Item::ProgressBar(..) => ItemKind::ProgressBar,
+            Item::Gauge(..) => ItemKind::Gauge,
+            Item::Sparkline(..) => ItemKind::Sparkline,
+            Item::Image(..) => ItemKind::Image,
+            Item::Stepper(..) => ItemKind::Stepper,
+            Item::Radio(..) => ItemKind::Radio,
+            Item::Spinner(..) => ItemKind::Spinner,
+            Item::Countdown(..) => ItemKind::Countdown,
+            Item::Clock(..) => ItemKind::Clock,
+            Item::Table(..) => ItemKind::Table,
+            Item::Message(..) => ItemKind::Message,
+            Item::Separator => ItemKind::Separator,
+            Item::Header(..) => ItemKind::Header,
+            Item::TextInput(..) => ItemKind::TextInput,
+        }
+    }
+}
+
+// samples an Item::Sparkline holds on to, older ones are dropped
+pub const SPARKLINE_SAMPLES: usize = 120;
+
+// where every sample of a sparkline sits between the lowest and highest one, 0.0 - 1.0
+#[cfg(any(feature = "crossterm", feature = "sdl2"))]
+pub(crate) fn sparkline_levels(samples: &[f32]) -> Vec<f32> {
+    let min = samples.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = samples.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    samples.iter()
+        .map(|v| if max > min { (v - min) / (max - min) } else { 0.5 })
+        .collect()
+}
+
+// how full a gauge is, 0.0 - 1.0
+#[cfg(any(feature = "crossterm", feature = "sdl2"))]
+pub(crate) fn gauge_fraction(current: f32, max: f32) -> f32 {
+    if max <= 0.0 {
+        return 1.0;
+    }
+    (current / max).clamp(0.0, 1.0)
+}
+
+// how far along its range a slider is, 0.0 - 1.0
+#[cfg(any(feature = "crossterm", feature = "sdl2"))]
+pub(crate) fn slider_fraction(min: i32, max: i32, value: i32) -> f32 {
+    if max <= min {
+        return 1.0;
+    }
+    ((value as f32 - min as f32) / (max as f32 - min as f32)).clamp(0.0, 1.0)
+}
+
+// what a button declared with TabBuilder::item_action() does to its item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemAction {
+    // same as ButtonPress
+    Activate,
+    // GuiEvent::ItemAction with this name
+    Emit(String),
+}
+
+// how bad an Item::Message is, see ColorPalette::severity_color()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorMode {
+    // every press moves on to the next option
+    Cycle,
+    // a press opens a list of all options on top of the tab
+    Popup,
+    // a press captures the d-pad for picking an option in place until confirmed or cancelled
+    Edit,
+}
+
+#[derive(Default)]
 pub struct LayoutBuilder {
     tabs: Vec<Tab>,
 }
 
 impl LayoutBuilder {
     pub fn new() -> LayoutBuilder {
-        LayoutBuilder {
-            tabs: Vec::new(),
-        }
+        LayoutBuilder::default()
     }
     pub fn tab(self, name: &str) -> TabBuilder {
         TabBuilder {
             layout_builder: Some(self),
             lines: Vec::new(),
             name: name.to_string(),
+            page_size: None,
+            footer: None,
+            footer_focusable: false,
+            list: None,
+            reorderable: false,
+            carousel: false,
+            bindings: Vec::new(),
+            multi_select: None,
+            actions: Vec::new(),
+        }
+    }
+    // one plain tab per (name, rows) pair, for tabs generated at runtime
+    pub fn tabs_from<S, L>(mut self, tabs: impl IntoIterator<Item = (S, L)>) -> LayoutBuilder
+    where
+        S: AsRef<str>,
+        L: IntoIterator<Item = Vec<Item>>,
+    {
+        for (name, lines) in tabs {
+            self = self.tab(name.as_ref()).lines_from(lines).end_tab();
         }
+        self
+    }
+    // appends every tab of a layout built elsewhere, e.g. an "About" tab shared by several apps
+    pub fn include(mut self, fragment: Layout) -> LayoutBuilder {
+        self.tabs.extend(fragment.tabs);
+        self
     }
     pub fn build(self) -> Layout {
         Layout { tabs: self.tabs }
@@ -72,41 +533,140 @@ impl LayoutBuilder {
 pub struct TabBuilder {
     lines: Vec<Vec<Item>>,
     name: String,
+    page_size: Option<usize>,
+    footer: Option<Vec<Item>>,
+    footer_focusable: bool,
+    list: Option<Vec<Item>>,
+    reorderable: bool,
+    carousel: bool,
+    bindings: Vec<(HidEvent, Filter)>,
+    multi_select: Option<u128>,
+    actions: Vec<(u128, HidEvent, ItemAction)>,
     layout_builder: Option<LayoutBuilder>,
 }
 
 impl TabBuilder {
+    // split the tab into pages of `rows` rows, flipped with the shoulder buttons
+    pub fn paged(mut self, rows: usize) -> TabBuilder {
+        self.page_size = Some(rows.max(1));
+        self
+    }
+    // let the user grab rows (or list entries) and move them, see GuiEvent::ItemMoved
+    pub fn reorderable(mut self) -> TabBuilder {
+        self.reorderable = true;
+        self
+    }
+    // Shows the items as large tiles in one horizontally scrolling row with the focused one
+    // enlarged, for launcher front pages. All lines get joined into that one row.
+    pub fn carousel(mut self) -> TabBuilder {
+        self.carousel = true;
+        self
+    }
+    // Pressing a row adds it to (or drops it from) a selection instead of activating it, rows
+    // are known by the id of their first item with one. The stateless button `confirm`,
+    // e.g. in the footer, reports the selection as GuiEvent::SelectionConfirmed.
+    pub fn multi_select(mut self, confirm: u128) -> TabBuilder {
+        self.multi_select = Some(confirm);
+        self
+    }
+    // Overrides what `ev` does while this tab is shown, e.g. capturing Up and Down so the
+    // application can scroll a log with them. Other tabs keep the usual behaviour.
+    pub fn bind(mut self, ev: HidEvent, filter: Filter) -> TabBuilder {
+        self.bindings.push((ev, filter));
+        self
+    }
+    // Gives the item with `id` a button of its own, e.g. North opening details of a game
+    // that South launches. Moving ItemAction::Activate to another button, say Start, stops
+    // ButtonPress from activating the item.
+    pub fn item_action(mut self, id: u128, ev: HidEvent, action: ItemAction) -> TabBuilder {
+        self.actions.push((id, ev, action));
+        self
+    }
     pub fn line(self) -> LineBuilder {
         LineBuilder {
             tab_builder: Some(self),
             items: Vec::new(),
+            target: LineTarget::Row,
         }
     }
-    pub fn build(mut self) -> Layout {
-        let mut layout_builder = self.layout_builder.take().unwrap();
-        layout_builder.tabs.push(Tab{ item_grid: self.lines, name: self.name });
-
-        Layout {
-            tabs: layout_builder.tabs,
+    // appends every row, e.g. one per file of a directory listing
+    pub fn lines_from(mut self, lines: impl IntoIterator<Item = Vec<Item>>) -> TabBuilder {
+        self.lines.extend(lines);
+        self
+    }
+    // Appends the rows of every tab of a layout built elsewhere, along with the buttons
+    // its items declared with item_action(). Names, footers, lists and the rest of the
+    // fragment's tab settings are left behind.
+    pub fn include(mut self, fragment: Layout) -> TabBuilder {
+        for tab in fragment.tabs {
+            self.lines.extend(tab.item_grid);
+            self.actions.extend(tab.actions);
         }
+        self
     }
-    pub fn tab(mut self, name: &str) -> TabBuilder {
-        let mut layout_builder = self.layout_builder.take().unwrap();
-        layout_builder.tabs.push(Tab{ item_grid: self.lines, name: self.name });
-
-        layout_builder.tab(name)
+    // starts the footer line, only one footer per tab
+    pub fn footer(self) -> LineBuilder {
+        LineBuilder {
+            tab_builder: Some(self),
+            items: Vec::new(),
+            target: LineTarget::Footer,
+        }
     }
-    pub fn end_tab(mut self) -> LayoutBuilder {
+    // turns the tab into a split-pane one, every item added here becomes
+    // one entry of the list on the left
+    pub fn list(self) -> LineBuilder {
+        LineBuilder {
+            tab_builder: Some(self),
+            items: Vec::new(),
+            target: LineTarget::List,
+        }
+    }
+    pub fn focusable_footer(mut self) -> LineBuilder {
+        self.footer_focusable = true;
+        self.footer()
+    }
+    pub fn build(self) -> Layout {
+        self.finish().build()
+    }
+    pub fn tab(self, name: &str) -> TabBuilder {
+        self.finish().tab(name)
+    }
+    pub fn end_tab(self) -> LayoutBuilder {
+        self.finish()
+    }
+    fn finish(mut self) -> LayoutBuilder {
         let mut layout_builder = self.layout_builder.take().unwrap();
-        layout_builder.tabs.push(Tab{ item_grid: self.lines, name: self.name });
+        if self.carousel {
+            self.lines = vec![self.lines.into_iter().flatten().collect()];
+        }
+        layout_builder.tabs.push(Tab{
+            item_grid: self.lines,
+            name: self.name,
+            page_size: self.page_size,
+            footer: self.footer,
+            footer_focusable: self.footer_focusable,
+            list: self.list,
+            reorderable: self.reorderable,
+            carousel: self.carousel,
+            bindings: self.bindings,
+            multi_select: self.multi_select,
+            actions: self.actions,
+        });
 
         layout_builder
     }
 }
 
+enum LineTarget {
+    Row,
+    Footer,
+    List,
+}
+
 pub struct LineBuilder {
     items: Vec<Item>,
     tab_builder: Option<TabBuilder>,
+    target: LineTarget,
 }
 
 impl LineBuilder {
@@ -118,40 +678,206 @@ impl LineBuilder {
         self.items.push(Item::StatefulButton(text.to_string(), init_state, id));
         self
     }
+    pub fn toggle(mut self, text: &str, init_state: bool, id: u128) -> LineBuilder {
+        self.items.push(Item::Toggle(text.to_string(), init_state, id));
+        self
+    }
     pub fn button_stateless(mut self, text: &str, id: u128) -> LineBuilder {
         self.items.push(Item::StatelessButton(text.to_string(), id));
         self
     }
+    pub fn link(mut self, text: &str, url: &str, id: u128) -> LineBuilder {
+        self.items.push(Item::Link(text.to_string(), url.to_string(), id));
+        self
+    }
+    // activating it runs `command`, it can't be activated again until the command exits
+    pub fn command(mut self, text: &str, command: CommandSpec, id: u128) -> LineBuilder {
+        self.items.push(Item::Command(text.to_string(), command, false, id));
+        self
+    }
+    pub fn log(mut self, id: u128) -> LineBuilder {
+        self.items.push(Item::Log(Vec::new(), id));
+        self
+    }
+    // charge and charging state, see GuiBuilder::battery_refresh()
+    pub fn battery(mut self) -> LineBuilder {
+        self.items.push(Item::Battery(None));
+        self
+    }
+    // hours and minutes of the local time, put it in a footer next to a battery for a status strip
+    pub fn clock(mut self) -> LineBuilder {
+        self.items.push(Item::Clock(None));
+        self
+    }
+    // `value` is clamped to min..=max, a step of 0 counts as 1
+    pub fn slider(mut self, text: &str, min: i32, max: i32, step: i32, value: i32, id: u128) -> LineBuilder {
+        let (min, max) = (min.min(max), min.max(max));
+        self.items.push(Item::Slider(text.to_string(), min, max, step.abs().max(1), value.clamp(min, max), id));
+        self
+    }
+    // one item per option, picking one drops the others, see GuiEvent::RadioChanged
+    pub fn radio_group(mut self, options: &[&str], selected: usize, id: u128) -> LineBuilder {
+        for (i, option) in options.iter().enumerate() {
+            self.items.push(Item::Radio(option.to_string(), i, i == selected, id));
+        }
+        self
+    }
+    // a number without a bar, e.g. a timeout in seconds
+    pub fn stepper(mut self, text: &str, value: i64, min: i64, max: i64, step: i64, id: u128) -> LineBuilder {
+        let (min, max) = (min.min(max), min.max(max));
+        self.items.push(Item::Stepper(text.to_string(), value.clamp(min, max), min, max, step.saturating_abs().max(1), id));
+        self
+    }
+    pub fn progress_bar(mut self, text: &str, value: f32, id: u128) -> LineBuilder {
+        self.items.push(Item::ProgressBar(text.to_string(), value.clamp(0.0, 1.0), id));
+        self
+    }
+    // reading out of `max`, e.g. a temperature, shown as a bar with the numbers next to it
+    pub fn gauge(mut self, text: &str, current: f32, max: f32, id: u128) -> LineBuilder {
+        self.items.push(Item::Gauge(text.to_string(), current, max, id));
+        self
+    }
+    // the newest SPARKLINE_SAMPLES of `samples` are kept
+    pub fn sparkline(mut self, samples: &[f32], id: u128) -> LineBuilder {
+        let first = samples.len().saturating_sub(SPARKLINE_SAMPLES);
+        self.items.push(Item::Sparkline(samples[first..].to_vec(), id));
+        self
+    }
+    pub fn text_input(mut self, text: &str, value: &str, id: u128) -> LineBuilder {
+        self.items.push(Item::TextInput(text.to_string(), value.to_string(), false, id));
+        self
+    }
+    // text input showing dots instead of what's typed, for passwords
+    pub fn password_input(mut self, text: &str, value: &str, id: u128) -> LineBuilder {
+        self.items.push(Item::TextInput(text.to_string(), value.to_string(), true, id));
+        self
+    }
+    // "label: value", with the value formatted to fit its kind, e.g. "Free: 1.2 GiB"
+    pub fn value(mut self, label: &str, value: Value, id: u128) -> LineBuilder {
+        self.items.push(Item::Value(label.to_string(), value, id));
+        self
+    }
+    // "key: value" with a value that's shown as is, changed with Gui::set_value() too
+    pub fn key_value(mut self, key: &str, value: &str, id: u128) -> LineBuilder {
+        self.items.push(Item::Value(key.to_string(), Value::Text(value.to_string()), id));
+        self
+    }
+    // picture and caption, meant for carousel tabs but works anywhere
+    pub fn tile(mut self, text: &str, image: Option<&std::path::Path>, id: u128) -> LineBuilder {
+        self.items.push(Item::Tile(text.to_string(), image.map(|v| v.to_path_buf()), id));
+        self
+    }
+    pub fn image(mut self, path: &std::path::Path, id: u128) -> LineBuilder {
+        self.items.push(Item::Image(ImageSource::Path(path.to_path_buf()), id));
+        self
+    }
+    // `data` is an encoded picture, see ImageSource
+    pub fn image_bytes(mut self, name: &str, data: impl Into<Arc<[u8]>>, id: u128) -> LineBuilder {
+        self.items.push(Item::Image(ImageSource::Bytes(name.to_string(), data.into()), id));
+        self
+    }
+    // running from the start
+    pub fn countdown(mut self, left: Duration, id: u128) -> LineBuilder {
+        self.items.push(Item::Countdown(left, id));
+        self
+    }
+    // spinning from the start
+    pub fn spinner(mut self, text: &str, id: u128) -> LineBuilder {
+        self.items.push(Item::Spinner(text.to_string(), 0, true, id));
+        self
+    }
+    // pressing it reports the selected row with GuiEvent::TableRowSelected
+    pub fn table(mut self, text: &str, headers: &[&str], rows: Vec<Vec<String>>, id: u128) -> LineBuilder {
+        let headers = headers.iter().map(|v| v.to_string()).collect();
+        self.items.push(Item::Table(text.to_string(), headers, rows, 0, id));
+        self
+    }
+    pub fn message(mut self, severity: Severity, text: &str) -> LineBuilder {
+        self.items.push(Item::Message(severity, text.to_string()));
+        self
+    }
+    pub fn separator(mut self) -> LineBuilder {
+        self.items.push(Item::Separator);
+        self
+    }
+    pub fn header(mut self, text: &str) -> LineBuilder {
+        self.items.push(Item::Header(text.to_string()));
+        self
+    }
+    pub fn qr_code(mut self, data: &str) -> LineBuilder {
+        self.items.push(Item::QrCode(data.to_string()));
+        self
+    }
+    // picked segment by segment in edit mode, see GuiEvent::DateChanged
+    pub fn date(mut self, text: &str, date: Date, id: u128) -> LineBuilder {
+        self.items.push(Item::Date(text.to_string(), date, id));
+        self
+    }
+    pub fn time(mut self, text: &str, time: Time, id: u128) -> LineBuilder {
+        self.items.push(Item::Time(text.to_string(), time, id));
+        self
+    }
+    pub fn selector(mut self, text: &str, options: &[&str], selected: usize, id: u128) -> LineBuilder {
+        self.items.push(Item::Selector(text.to_string(), options.iter().map(|v| v.to_string()).collect(), selected, SelectorMode::Cycle, id));
+        self
+    }
+    pub fn selector_popup(mut self, text: &str, options: &[&str], selected: usize, id: u128) -> LineBuilder {
+        self.items.push(Item::Selector(text.to_string(), options.iter().map(|v| v.to_string()).collect(), selected, SelectorMode::Popup, id));
+        self
+    }
+    // Dropdown, the same item as selector_popup(). Pressing it lists `options` over the tab,
+    // picking one reports GuiEvent::SelectorChanged(id, index).
+    pub fn select(self, text: &str, options: &[&str], selected: usize, id: u128) -> LineBuilder {
+        self.selector_popup(text, options, selected, id)
+    }
+    pub fn selector_edit(mut self, text: &str, options: &[&str], selected: usize, id: u128) -> LineBuilder {
+        self.items.push(Item::Selector(text.to_string(), options.iter().map(|v| v.to_string()).collect(), selected, SelectorMode::Edit, id));
+        self
+    }
     pub fn line(mut self) -> LineBuilder {
-        let mut tab_builder = self.tab_builder.take().unwrap();
-        tab_builder.lines.push(self.items);
+        let tab_builder = self.finish();
 
         LineBuilder {
             tab_builder: Some(tab_builder),
             items: Vec::new(),
+            target: LineTarget::Row,
         }
     }
+    pub fn footer(mut self) -> LineBuilder {
+        self.finish().footer()
+    }
+    pub fn list(mut self) -> LineBuilder {
+        self.finish().list()
+    }
+    pub fn focusable_footer(mut self) -> LineBuilder {
+        self.finish().focusable_footer()
+    }
     pub fn tab(mut self, name: &str) -> TabBuilder {
-        let mut tab_builder = self.tab_builder.take().unwrap();
-        tab_builder.lines.push(self.items);
+        let tab_builder = self.finish();
 
         tab_builder.tab(name)
     }
     pub fn endl(mut self) -> TabBuilder {
-        let mut tab_builder = self.tab_builder.take().unwrap();
-        tab_builder.lines.push(self.items);
-
-        tab_builder
+        self.finish()
     }
     pub fn end_tab(mut self) -> LayoutBuilder {
-        let mut tab_builder = self.tab_builder.take().unwrap();
-        tab_builder.lines.push(self.items);
+        let tab_builder = self.finish();
         tab_builder.end_tab()
     }
     pub fn build(mut self) -> Layout {
-        let mut tab_builder = self.tab_builder.take().unwrap();
-        tab_builder.lines.push(self.items);
+        let tab_builder = self.finish();
 
         tab_builder.build()
     }
+    fn finish(&mut self) -> TabBuilder {
+        let mut tab_builder = self.tab_builder.take().unwrap();
+        let items = std::mem::take(&mut self.items);
+        match self.target {
+            LineTarget::Row => tab_builder.lines.push(items),
+            LineTarget::Footer => tab_builder.footer = Some(items),
+            LineTarget::List => tab_builder.list.get_or_insert_with(Vec::new).extend(items),
+        }
+
+        tab_builder
+    }
 }
@@ -1,21 +1,72 @@
 pub mod layout;
+pub mod input;
+pub mod clock;
+pub mod repeat;
+pub mod analog;
+pub mod focus;
+pub mod idle;
+pub mod brightness;
+mod history;
+pub mod event_log;
+pub mod journal;
+pub mod draw;
+pub mod frame;
+pub mod form;
+pub mod feedback;
+pub mod datetime;
+mod timezone;
+pub mod osk;
+mod shortcut;
+pub mod format;
+pub mod osd;
+pub mod power;
+#[cfg(all(feature = "qr", any(feature = "crossterm", feature = "sdl2")))]
+mod qr;
+#[cfg(any(feature = "crossterm", feature = "sdl2"))]
+mod marquee;
+pub mod command;
+pub mod config;
+pub mod controller;
+#[cfg(feature = "wifi")]
+pub mod wifi;
+#[cfg(feature = "remote")]
+pub mod input_remote;
+#[cfg(feature = "debug-mirror")]
+pub mod debug_mirror;
 #[cfg(feature = "sdl2")]
 pub mod renderer_sdl2;
+#[cfg(feature = "shaping")]
+mod shaping;
+#[cfg(feature = "crossterm")]
 pub mod renderer_crossterm;
+#[cfg(feature = "crossterm")]
+mod screen;
+#[cfg(feature = "test-util")]
+pub mod renderer_null;
 
-use layout::Item;
+use layout::{Item, ItemAction, SelectorMode, Severity, TabId};
+use input::{InputSource, Filter};
+use clock::Clock;
+use repeat::{NavAcceleration, RepeatTracker};
+use analog::AnalogConfig;
+use focus::FocusScope;
+use idle::{IdleConfig, IdleMode};
+use brightness::Brightness;
+use feedback::{Feedback, FeedbackTheme};
+use form::{Form, FormValue};
+use history::{History, Change};
+use event_log::{EventLog, EventRecord, LoggedEvent};
+use journal::{Journal, JournalEntry, JournalItem};
+use command::CommandOutput;
+use controller::{ControllerStyle, FaceButton};
 use anyhow::Result;
-use ez_input::RinputerHandle;
-use std::{
-    thread,
-    time::Duration,
-};
 
-use crossbeam_channel::{bounded, select, Receiver, Sender, never};
+use std::{sync::Arc, time::Duration};
+use crossbeam_channel::{select, Receiver, Sender, never, after, unbounded};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color{r: u8, g: u8, b: u8}
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct ColorPalette {
     tab_outline: Color,
@@ -27,18 +78,33 @@ pub struct ColorPalette {
     item_text: Color,
     item_bg: Color,
     item_accent: Color,
+
+    checked_marker: String,
+    unchecked_marker: String,
+    marker_color: Option<Color>,
+    // info, warning, error
+    severity_colors: [Color; 3],
 }
 
 impl Color {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+    #[cfg(feature = "crossterm")]
     fn as_crossterm_color(&self) -> crossterm::style::Color {
         (self.r, self.g, self.b).into()
     }
+    #[cfg(any(feature = "crossterm", feature = "sdl2"))]
     fn as_tuple(&self) -> (u8, u8, u8) {
         (self.r, self.g, self.b)
     }
+    fn blend(&self, other: &Color, t: f32) -> Color {
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+    }
 }
 
-impl ColorPalette {
+impl Default for ColorPalette {
     fn default() -> Self {
         Self {
             tab_outline: Color{r: 255, g: 255, b: 255},
@@ -50,21 +116,227 @@ impl ColorPalette {
             item_text: Color{r: 255, g: 255, b: 255},
             item_bg: Color{r: 0, g: 0, b: 0},
             item_accent: Color{r: 255, g: 0, b: 0},
+
+            checked_marker: "[X]".to_string(),
+            unchecked_marker: "[ ]".to_string(),
+            marker_color: None,
+            severity_colors: [Color{r: 80, g: 160, b: 255}, Color{r: 255, g: 200, b: 0}, Color{r: 255, g: 60, b: 60}],
+        }
+    }
+}
+
+impl ColorPalette {
+    // built-in palettes for Gui::set_theme(), "default" is the same as ColorPalette::default()
+    pub fn named(name: &str) -> Option<Self> {
+        let (tab_text, tab_bg, accent, item_text, item_bg) = match name {
+            "default" | "dark" => return Some(Self::default()),
+            "light" => ((0, 0, 0), (230, 230, 230), (200, 0, 0), (0, 0, 0), (255, 255, 255)),
+            "high-contrast" => ((255, 255, 255), (0, 0, 0), (255, 255, 0), (255, 255, 255), (0, 0, 0)),
+            "amber" => ((255, 176, 0), (0, 0, 0), (255, 220, 120), (255, 176, 0), (0, 0, 0)),
+            _ => return None,
+        };
+        let color = |(r, g, b): (u8, u8, u8)| Color::new(r, g, b);
+        Some(Self {
+            tab_outline: color(tab_text),
+            tab_text: color(tab_text),
+            tab_bg: color(tab_bg),
+            tab_accent: color(accent),
+            item_outline: color(accent),
+            item_text: color(item_text),
+            item_bg: color(item_bg),
+            item_accent: color(accent),
+            ..Self::default()
+        })
+    }
+    // colors part way from self to other, markers switch over at the end
+    fn blend(&self, other: &ColorPalette, t: f32) -> ColorPalette {
+        let t = t.clamp(0.0, 1.0);
+        let base = if t < 1.0 { self } else { other };
+        ColorPalette {
+            tab_outline: self.tab_outline.blend(&other.tab_outline, t),
+            tab_text: self.tab_text.blend(&other.tab_text, t),
+            tab_bg: self.tab_bg.blend(&other.tab_bg, t),
+            tab_accent: self.tab_accent.blend(&other.tab_accent, t),
+            item_outline: self.item_outline.blend(&other.item_outline, t),
+            item_text: self.item_text.blend(&other.item_text, t),
+            item_bg: self.item_bg.blend(&other.item_bg, t),
+            item_accent: self.item_accent.blend(&other.item_accent, t),
+            checked_marker: base.checked_marker.clone(),
+            unchecked_marker: base.unchecked_marker.clone(),
+            marker_color: match (self.marker_color, other.marker_color) {
+                (Some(a), Some(b)) => Some(a.blend(&b, t)),
+                _ => base.marker_color,
+            },
+            severity_colors: std::array::from_fn(|i| self.severity_colors[i].blend(&other.severity_colors[i], t)),
+        }
+    }
+    // every color pulled towards black by `t`, used for idle dimming
+    fn darkened(&self, t: f32) -> ColorPalette {
+        let black = Color::new(0, 0, 0);
+        let dark = ColorPalette {
+            tab_outline: black,
+            tab_text: black,
+            tab_bg: black,
+            tab_accent: black,
+            item_outline: black,
+            item_text: black,
+            item_bg: black,
+            item_accent: black,
+            marker_color: self.marker_color.map(|_| black),
+            severity_colors: [black; 3],
+            ..self.clone()
+        };
+        self.blend(&dark, t)
+    }
+    // markers drawn in front of stateful buttons by text-based renderers
+    pub fn with_markers(mut self, checked: &str, unchecked: &str) -> Self {
+        self.checked_marker = checked.to_string();
+        self.unchecked_marker = unchecked.to_string();
+        self
+    }
+    pub fn with_marker_color(mut self, color: Color) -> Self {
+        self.marker_color = Some(color);
+        self
+    }
+    pub fn with_severity_colors(mut self, info: Color, warning: Color, error: Color) -> Self {
+        self.severity_colors = [info, warning, error];
+        self
+    }
+    // icon and text color of an Item::Message
+    pub fn severity_color(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::Info => self.severity_colors[0],
+            Severity::Warning => self.severity_colors[1],
+            Severity::Error => self.severity_colors[2],
+        }
+    }
+    pub fn marker(&self, state: bool) -> &str {
+        if state {
+            &self.checked_marker
+        } else {
+            &self.unchecked_marker
         }
     }
 }
 
-#[derive(Eq, PartialEq, Debug)]
+// argument of Gui::set_theme(), either the name of a built-in palette or a custom one
+#[derive(Debug, Clone)]
+pub enum Theme {
+    Named(String),
+    Palette(ColorPalette),
+}
+
+impl From<&str> for Theme {
+    fn from(name: &str) -> Self {
+        Theme::Named(name.to_string())
+    }
+}
+
+impl From<ColorPalette> for Theme {
+    fn from(palette: ColorPalette) -> Self {
+        Theme::Palette(palette)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub enum GuiEvent {
     ItemSelected(String),
     StatefulButtonChange(String, bool, u128),
+    ToggleChanged(u128, bool),
     StatelessButtonPress(String, u128),
+    // (id, index of the picked option), for every selector mode and LineBuilder::select()
+    SelectorChanged(u128, usize),
     TabChanged(String),
+    PageChanged(usize),
+    PaneChanged(Pane),
+    ListFocused(usize),
+    ListActivated(usize),
+    LinkActivated(u128, String),
+    CommandStarted(u128),
+    // exit code, None if it was killed by a signal
+    CommandFinished(u128, Option<i32>),
+    // confirm button of a multi-select tab, ids of the picked rows in the order they were picked
+    SelectionConfirmed(Vec<u128>),
+    // let through by an input filter or a tab binding returning Filter::Capture
+    HidCaptured(HidEvent),
+    // item id and the name given to layout::ItemAction::Emit for the button pressed on it
+    ItemAction(u128, String),
+    // no input for this long, see GuiBuilder::idle_events()
+    Idle(Duration),
+    // first input after GuiEvent::Idle
+    Active,
+    // Back left a page pushed with Gui::push_page(), carries its name
+    PagePopped(String),
+    FocusScopeDismissed(u128),
+    // id of the moved item, old and new row (or list entry) index
+    ItemMoved(u128, usize, usize),
+    // form id and the id and value of every item in it
+    FormSubmitted(u128, Vec<(u128, FormValue)>),
+    FormReverted(u128),
+    DateChanged(u128, datetime::Date),
+    TimeChanged(u128, datetime::Time),
+    SliderChanged(u128, i32),
+    StepperChanged(u128, i64),
+    // group id and index of the picked option
+    RadioChanged(u128, usize),
+    // table id and the row that was pressed
+    TableRowSelected(u128, usize),
+    // an Item::Countdown reached zero
+    TimerExpired(u128),
+    // confirmed on the on-screen keyboard
+    TextChanged(u128, String),
+    #[cfg(feature = "wifi")]
+    WifiConnected(String),
+    // ssid and nmcli's last words
+    #[cfg(feature = "wifi")]
+    WifiFailed(String, String),
+    // the controller's Guide button, e.g. to go back to the main menu from anywhere
+    HomePressed,
+    // name given to Gui::reserve_shortcut()
+    Shortcut(String),
+    // the window or terminal changed size, with the new one
+    Resized(ViewportInfo),
     Quit,
     IgnoredHid,
 }
 
-#[derive(Eq, PartialEq, Debug)]
+// Where the item an event is about sits, see Gui::event_origin(). Tells apart items that
+// share an id across tabs made from the same template.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ItemOrigin {
+    pub tab: usize,
+    pub tab_name: String,
+    // the footer is row items().len(), list entries of split-pane tabs are (index, 0)
+    pub row: usize,
+    pub column: usize,
+    pub in_list: bool,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Pane {
+    List,
+    Detail,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Axis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    TriggerLeft,
+    TriggerRight,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum ActionButton {
+    North,
+    West,
+    Start,
+    Select,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub enum HidEvent {
     Up,
     Down,
@@ -72,247 +344,3269 @@ pub enum HidEvent {
     Right,
     NextTab,
     PreviousTab,
+    GoToTab(usize),
+    // first and last row of the current page
+    First,
+    Last,
+    SwitchPane,
     ButtonPress,
+    Back,
+    // picks up or drops the focused row on reorderable tabs
+    Grab,
+    // buttons that only do something on items that ask for them, see
+    // layout::TabBuilder::item_action(), except for North picking rows up like Grab
+    Action(ActionButton),
+    Undo,
+    Redo,
+    // Guide / Home button of the controller, always reported as GuiEvent::HomePressed
+    Home,
+    Analog{axis: Axis, value: i16},
     Quit,
 }
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub enum RendererEvent {
     Refresh,
+    // the window or terminal changed size, redrawn like Refresh
+    Resized,
     WindowClosed,
+    // a controller was plugged in or pulled out, see Renderer::controller_name()
+    ControllersChanged,
     Hid(HidEvent),
 }
 
+// Space the renderer draws into, in the same units as draw::DrawRect: cells in a terminal,
+// pixels in a window. See Gui::viewport_info().
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewportInfo {
+    pub width: u32,
+    pub height: u32,
+    // size of a character of item text
+    pub cell_width: u32,
+    pub cell_height: u32,
+    // lines of items that fit below the tab header, characters that fit across
+    pub rows: u32,
+    pub columns: u32,
+}
+
+// Memory the text and image caches of a renderer may hold on to, in bytes of texture
+// data (width * height * 4). None is unlimited, which is the default. See
+// Renderer::set_cache_limits().
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheLimits {
+    pub texts: Option<usize>,
+    pub images: Option<usize>,
+}
+
+// how long every frame of an Item::Spinner stays on screen
+const SPINNER_FRAME: Duration = Duration::from_millis(100);
+// how often the renderer gets to move a marquee along
+const MARQUEE_FRAME: Duration = Duration::from_millis(50);
+// how long a masked text stays readable after North, long enough to bridge the delay
+// before a held key starts repeating
+const REVEAL_TIME: Duration = Duration::from_millis(1000);
+// command output arriving within this long is drawn in one go
+const LOG_FRAME: Duration = Duration::from_millis(100);
+// see Gui::show_wifi_status()
+#[cfg(feature = "wifi")]
+const WIFI_STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
 pub trait Renderer {
+    // Draws the damaged parts of a frame. The default hands them to the draw_* methods
+    // below, backends that want to see the whole frame at once override this too.
+    fn render(&mut self, frame: &frame::Frame) -> Result<()> {
+        frame::render_parts(self, frame)
+    }
     fn draw_tab_header(&mut self, names: &[&str], colors: &ColorPalette) -> Result<()>;
-    fn draw_items(&mut self, items: &Vec<Vec<layout::Item>>, colors: &ColorPalette, selected_item_idx: (usize, usize)) -> Result<()>;
+    fn draw_items(&mut self, items: &[Vec<layout::Item>], colors: &ColorPalette, selected_item_idx: (usize, usize)) -> Result<()>;
+    // drawn right after the tab header for paged tabs, `current` starts at 0
+    fn draw_page_indicator(&mut self, _current: usize, _total: usize, _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // split-pane tabs, `detail_selected` is None while the list has focus
+    fn draw_split(&mut self, _list: &[layout::Item], _list_selected: usize, detail: &[Vec<layout::Item>], detail_selected: Option<(usize, usize)>, colors: &ColorPalette) -> Result<()> {
+        self.draw_items(detail, colors, detail_selected.unwrap_or((usize::MAX, usize::MAX)))
+    }
+    // carousel tabs, `tiles` is their only row, falls back to a plain row of items
+    fn draw_carousel(&mut self, tiles: &[layout::Item], selected: usize, colors: &ColorPalette) -> Result<()> {
+        self.draw_items(&[tiles.to_vec()], colors, (0, selected))
+    }
+    // list of options drawn on top of everything else, e.g. for popup selectors
+    fn draw_popup(&mut self, _options: &[String], _selected: usize, _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // the selected item captures directional input while this is set, see SelectorMode::Edit
+    fn set_edit_mode(&mut self, _editing: bool) {}
+    // part of a date or time item being edited, 0 is the leftmost one
+    fn set_edit_segment(&mut self, _segment: usize) {}
+    // rows kept visible above and below the focused one when scrolling
+    fn set_scroll_margin(&mut self, _rows: usize) {}
+    // colors are about to change, anything drawn with the old ones and kept around has to go
+    fn palette_changed(&mut self) {}
+    // whether palette changes should be faded in, only worth it if redrawing is cheap
+    fn smooth_transitions(&self) -> bool {
+        false
+    }
+    // window or terminal title
+    fn set_title(&mut self, _title: &str) {}
+    // user key bindings, called before get_event()
+    fn set_bindings(&mut self, _bindings: &config::Bindings) {}
+    // the gui's clock, anything the renderer animates or holds back should go by it
+    fn set_clock(&mut self, _clock: Arc<dyn Clock>) {}
+    // clock time tick() has something to do at, None when it doesn't need waking up
+    fn next_tick(&self) -> Option<Duration> {
+        None
+    }
+    // applies right away, for renderers that cache textures
+    fn set_cache_limits(&mut self, _limits: CacheLimits) {}
+    // no input source reads the controller, renderers that see it should turn its buttons
+    // into events themselves, following the [buttons] bindings
+    fn read_pad_buttons(&mut self) {}
+    // whether East confirms, for renderers that read controller buttons, see
+    // Gui::confirm_swap()
+    fn set_confirm_swap(&mut self, _swap: controller::ConfirmSwap) {}
+    // renderers that let applications override how single items are drawn return their hooks
+    fn draw_hooks(&mut self) -> Option<&mut draw::DrawHooks> {
+        None
+    }
+    // Idle dimming, see GuiBuilder::idle(). Renderers that can't dim on their own return
+    // false and get drawn with a darker palette instead.
+    fn set_dimmed(&mut self, _dimmed: bool) -> bool {
+        false
+    }
+    // (width, height) in the same units as draw::DrawRect, one cell per char by default
+    fn measure_text(&mut self, text: &str, _style: draw::TextStyle) -> (u32, u32) {
+        (text.chars().count() as u32, 1)
+    }
+    // fallback for brightness::Brightness without a setter, `level` goes from 0.0 to 1.0
+    fn set_brightness(&mut self, _level: f32) {}
+    // replaces everything while blanked, `position` places the logo as fractions of the free space
+    fn draw_blank(&mut self, _logo: &str, _position: (f32, f32), _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // QR code of the focused Item::QrCode, centered on top of the items
+    fn draw_qr(&mut self, _data: &str, _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // focused Item::Table on top of the items, columns as wide as their content allows
+    fn draw_table(&mut self, _headers: &[String], _rows: &[Vec<String>], _selected: usize, _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // focused Item::Log on top of the items, as many of the newest lines as fit
+    fn draw_log(&mut self, _lines: &[String], _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // value of the item being edited, e.g. big and centered, or in a status line
+    fn draw_readout(&mut self, _text: &str, _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // on-screen keyboard over the bottom of the items, `text` is what's been typed so far
+    fn draw_keyboard(&mut self, _text: &str, _keys: &[Vec<String>], _selected: (usize, usize), _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // sound or rumble for a press, see feedback::FeedbackTheme
+    fn feedback(&mut self, _feedback: Feedback) {}
+    // Shapes for things the gui draws on its own, drawn over whatever is on screen. Only
+    // renderers with a draw::DrawContext show them.
+    fn draw_primitives(&mut self, _primitives: &[draw::Primitive], _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    // loads pictures ahead of the tabs that show them, see Gui::preload_images()
+    fn preload_images(&mut self, _paths: &[&std::path::Path]) -> Result<()> {
+        Ok(())
+    }
+    // pinned to the bottom of the item area, drawn after the items
+    fn draw_footer(&mut self, _items: &[layout::Item], _colors: &ColorPalette, _selected: Option<usize>) -> Result<()> {
+        Ok(())
+    }
+    // the whole screen in osd::Osd mode, `value` goes from 0.0 to 1.0
+    fn draw_osd(&mut self, label: &str, value: f32, colors: &ColorPalette) -> Result<()> {
+        let row = vec![layout::Item::Text(osd::bar_text(label, value))];
+        self.draw_items(&[row], colors, (usize::MAX, usize::MAX))
+    }
+    // shows or hides the window, for overlays that only come up now and then
+    fn set_visible(&mut self, _visible: bool) {}
+    // scroll selected labels that don't fit their cell from tick()
+    fn set_marquee(&mut self, _enabled: bool) {}
+    // name of the controller in use, for telling its style, see controller::ControllerStyle::detect()
+    fn controller_name(&self) -> Option<String> {
+        None
+    }
+    // None for renderers without a size, e.g. renderer_null
+    fn viewport_info(&mut self) -> Option<ViewportInfo> {
+        None
+    }
     fn get_event(&self) -> Option<Receiver<RendererEvent>>;
     fn tick(&mut self);
 }
 
+type InputFilter = Box<dyn FnMut(&HidEvent) -> Filter + Send>;
+type JournalRedactor = Box<dyn FnMut(&mut JournalEntry) -> bool + Send>;
+
 pub struct Gui {
     renderer: Box<dyn Renderer>,
     layout: layout::Layout,
     colors: ColorPalette,
     hid_rx: Option<Receiver<HidEvent>>,
     renderer_rx: Option<Receiver<RendererEvent>>,
-    tab_pos: i32,
+    tab_pos: TabId,
     item_pos: (usize, usize),
+    page: usize,
+    pane: Pane,
+    list_pos: usize,
+    popup: Option<Popup>,
+    osk: Option<osk::Osk>,
+    // see set_keyboard_layouts() and set_input_keyboard()
+    keyboard_layouts: Vec<osk::KeyboardLayout>,
+    input_keyboards: Vec<(u128, Vec<osk::KeyboardLayout>)>,
+    editing: Option<Editing>,
+    // active focus traps with the position to go back to once each is popped
+    focus_scopes: Vec<(FocusScope, (usize, usize))>,
+    // pages pushed with push_page(), innermost last
+    nav_pages: Vec<NavPage>,
+    // (tab, row id) of rows picked on multi-select tabs, in the order they were picked
+    multi_selected: Vec<(usize, u128)>,
+    // see event_origin()
+    last_origin: Option<ItemOrigin>,
+    // where the event being made comes from, set along with it and taken by note_origin()
+    origin: Option<ItemOrigin>,
+    // running commands and where they were started from, for their GuiEvent::CommandFinished
+    command_origins: Vec<(u128, ItemOrigin)>,
+    // where every tab was left, and where restore_state() wants tabs to be once they're entered
+    tab_scroll: Vec<Option<TabScroll>>,
+    resume_scroll: Vec<Option<TabScroll>>,
+    // see start_journal() and set_journal_redactor()
+    journal: Option<Journal>,
+    journal_redactor: Option<JournalRedactor>,
+    grab: Option<Grab>,
+    history: History,
+    event_log: EventLog,
+    theme_fade: Option<ThemeFade>,
+    open_links: bool,
+    // see set_controller_style(), the style in use is the pinned one or else the detected one
+    controller_style: ControllerStyle,
+    pinned_style: Option<ControllerStyle>,
+    confirm_swap: controller::ConfirmSwap,
+    // presses closer together than this count once, per-id ones override it, see set_activation_cooldown()
+    activation_cooldown: Duration,
+    item_cooldowns: Vec<(u128, Duration)>,
+    // id and Gui::now() of the last press that went through
+    last_activation: Option<(u128, Duration)>,
+    // output of commands started by Item::Command
+    cmd_tx: Sender<CommandOutput>,
+    cmd_rx: Receiver<CommandOutput>,
+    theme_fade_time: Duration,
+    // palettes added with GuiBuilder::register_theme(), looked up before the built-in ones
+    themes: Vec<(String, ColorPalette)>,
+    analog: AnalogConfig,
+    analog_x: i16,
+    analog_next: Option<Duration>,
+    // engaged direction per left stick axis when emulating a d-pad
+    dpad_held: (i32, i32),
+    dpad_next: Option<Duration>,
+    accel: Option<NavAcceleration>,
+    idle: Option<IdleConfig>,
+    last_input: Duration,
+    idle_state: Option<Idle>,
+    idle_events: Option<Duration>,
+    // GuiEvent::Idle went out and GuiEvent::Active didn't yet
+    idle_reported: bool,
+    brightness: Option<Brightness>,
+    feedback: FeedbackTheme,
+    // last level seen on the brightness selector and when it gets applied next
+    brightness_level: Option<f32>,
+    brightness_next: Option<Duration>,
+    battery_interval: Duration,
+    // next refresh of battery items, None while there are none
+    battery_next: Option<Duration>,
+    // next redraw for Item::Value items counting up from a point in time, None while there are none
+    since_next: Option<Duration>,
+    // next minute for Item::Clock, None while there are none
+    clock_next: Option<Duration>,
+    // next frame of spinning Item::Spinner items, None while there are none
+    spinner_next: Option<Duration>,
+    // next second of the running Item::Countdown closest to one, and when they were last counted down
+    countdown_next: Option<Duration>,
+    countdown_last: Option<Duration>,
+    // countdowns that ran out, reported one GuiEvent::TimerExpired at a time
+    expired_timers: std::collections::VecDeque<(u128, ItemOrigin)>,
+    // wakes get_ev up for the renderer's marquee, None while marquee mode is off
+    marquee_next: Option<Duration>,
+    // when the masked text on the keyboard gets hidden again, see osk_event()
+    reveal_next: Option<Duration>,
+    // when log lines that came in since the last frame get drawn
+    log_next: Option<Duration>,
+    // see set_osd()
+    osd: Option<osd::Osd>,
+    // every form with the values it had when last applied
+    forms: Vec<(Form, Vec<(u128, FormValue)>)>,
+    #[cfg(feature = "wifi")]
+    wifi: Option<wifi::WifiPicker>,
+    // Item::Values showing the network status and when to check it again, see show_wifi_status()
+    #[cfg(feature = "wifi")]
+    wifi_status: Vec<u128>,
+    #[cfg(feature = "wifi")]
+    wifi_status_next: Option<Duration>,
+    repeat: RepeatTracker,
     ignore_hid: bool,
+    raw_hid_taps: Vec<Sender<HidEvent>>,
+    input_filter: Option<InputFilter>,
+    // see set_focus_callback(), with the id it was last called with
+    focus_callback: Option<Box<dyn FnMut(u128) + Send>>,
+    last_focused: Option<u128>,
+    shortcuts: shortcut::Shortcuts,
+    clock: Arc<dyn Clock>,
+    redraw_tabs: bool,
+    redraw_items: bool,
+    // see freeze_redraws()
+    frozen: bool,
+    #[cfg(feature = "test-util")]
+    queued: std::collections::VecDeque<HidEvent>,
+    #[cfg(feature = "debug-mirror")]
+    mirror: Option<debug_mirror::DebugMirror>,
+}
+
+// an open popup selector, confirming writes `selected` back into the item at `origin`
+struct Popup {
+    options: Vec<String>,
+    selected: usize,
+    origin: (usize, usize),
+}
+
+// an item in edit mode, cancelling puts `original` back at `origin`
+struct Editing {
+    origin: (usize, usize),
+    original: Item,
+    // of date and time items
+    segment: usize,
+}
+
+// a page pushed over a tab's items, `items` and `pos` are what it covers up
+struct NavPage {
+    tab: usize,
+    name: String,
+    items: Vec<Vec<Item>>,
+    pos: (usize, usize),
+    page: usize,
+}
+
+// palette transition started by set_theme()
+struct ThemeFade {
+    from: ColorPalette,
+    to: ColorPalette,
+    started: Duration,
+}
+
+// set while dimmed or blanked, see GuiBuilder::idle()
+struct Idle {
+    // the real palette while the gui draws with a darkened one
+    undimmed: Option<ColorPalette>,
+    renderer_dimmed: bool,
+    logo_step: u32,
+    next_move: Option<Duration>,
+}
+
+// a picked up row or list entry, `current` follows it around until it's dropped
+struct Grab {
+    id: u128,
+    origin: usize,
+    current: usize,
+    pane: Pane,
+}
+
+// where a tab was scrolled to when it was left, see GuiState
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TabScroll {
+    pub page: usize,
+    pub item_pos: (usize, usize),
+    // entry of the list on split-pane tabs
+    pub list_pos: usize,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub struct GuiState {
+    // the app builds its layout again on the next start, so it's not saved
+    #[cfg_attr(feature = "serde", serde(skip))]
     layout: layout::Layout,
-    tab_pos: i32,
+    tab_pos: TabId,
     item_pos: (usize, usize),
+    // by tab, None for tabs that weren't visited
+    scroll: Vec<Option<TabScroll>>,
 }
 
 impl Gui {
-    pub fn exit_dumping_state(self) -> GuiState {
+    pub fn exit_dumping_state(mut self) -> GuiState {
+        self.remember_scroll();
         GuiState {
             layout: self.layout,
             tab_pos: self.tab_pos,
             item_pos: self.item_pos,
+            scroll: self.tab_scroll,
+        }
+    }
+    // Goes back to the tab of `state` and to where every tab was scrolled to, tabs other
+    // than the current one get there once they're switched to. Positions that don't exist
+    // in the current layout are left alone.
+    pub fn restore_state(&mut self, state: &GuiState) {
+        self.resume_scroll = state.scroll.clone();
+        self.tab_pos = self.layout.clamp_tab(state.tab_pos.index());
+        self.reset_tab_focus();
+        self.redraw_tabs = true;
+        self.redraw_items = true;
+        self.flush_redraws();
+    }
+    fn remember_scroll(&mut self) {
+        let tab = self.tab_pos.index();
+        if self.tab_scroll.len() <= tab {
+            self.tab_scroll.resize(tab + 1, None);
+        }
+        self.tab_scroll[tab] = Some(TabScroll {
+            page: self.page,
+            item_pos: self.item_pos,
+            list_pos: self.list_pos,
+        });
+    }
+    // the saved scroll position of the current tab, if there's one and it still fits
+    fn resume_tab_scroll(&mut self) {
+        let Some(scroll) = self.resume_scroll.get_mut(self.tab_pos.index()).and_then(Option::take) else {return};
+        let Some(tab) = self.layout.tab(self.tab_pos.index()) else {return};
+        let (row, column) = scroll.item_pos;
+        if scroll.page < tab.page_count().max(1) && tab.item(row, column).is_some_and(Item::focusable) {
+            self.page = scroll.page;
+            self.item_pos = scroll.item_pos;
+        }
+        if tab.list().is_some_and(|v| scroll.list_pos < v.len()) {
+            self.list_pos = scroll.list_pos;
         }
     }
     pub fn set_ignore_hid(&mut self, val: bool) {
         self.ignore_hid = val;
     }
-    pub fn get_ev(&mut self) -> GuiEvent {
-        loop {
-            let mut ret = None;
-            let mut redraw_items = false;
-            let mut redraw_tabs = false;
-
-            // handle events made by renderer
-            let mut tab_chg = 0;
-            let mut item_column_chg: i32 = 0;
-            let mut item_row_chg: i32 = 0;
-            let mut activate_selection = false;
-            let mut hid_ev = None;
-            let mut r_ev = None;
-
-            select! {
-                recv(self.hid_rx.as_ref().unwrap_or(&never())) -> msg => hid_ev = Some(msg),
-                recv(self.renderer_rx.as_ref().unwrap_or(&never())) -> msg => r_ev = Some(msg),
+    // Every HidEvent coming from the inputs or the renderer gets copied here before the gui
+    // looks at it, including ones that get ignored or swallowed by a popup. Dropping the
+    // receiver unsubscribes.
+    pub fn subscribe_raw_hid(&mut self) -> Receiver<HidEvent> {
+        let (tx, rx) = unbounded();
+        self.raw_hid_taps.push(tx);
+        rx
+    }
+    // Decides what happens to HidEvents headed for the current tab, e.g. a game tab capturing
+    // all directions. Open popups, keyboards, edits and grabs get their events first, and
+    // set_ignore_hid() beats it too. Replaces the previous filter.
+    pub fn set_input_filter(&mut self, filter: impl FnMut(&HidEvent) -> Filter + Send + 'static) {
+        self.input_filter = Some(Box::new(filter));
+    }
+    pub fn clear_input_filter(&mut self) {
+        self.input_filter = None;
+    }
+    // Scrolling keeps this many rows of context visible above and below the focused item
+    // instead of stopping with it at the edge. 0 by default, capped at half the view.
+    pub fn set_scroll_margin(&mut self, rows: usize) {
+        self.renderer.set_scroll_margin(rows);
+        self.redraw_items = true;
+        self.flush_redraws();
+    }
+    // Selected labels too long for their cell scroll back and forth instead of running over.
+    pub fn set_marquee(&mut self, enabled: bool) {
+        self.renderer.set_marquee(enabled);
+        self.marquee_next = enabled.then(|| self.clock.now() + MARQUEE_FRAME);
+        self.redraw_items = true;
+        self.flush_redraws();
+    }
+    // Layouts of the on-screen keyboard, the first one comes up and the user switches on
+    // from there. QWERTY and symbols by default.
+    pub fn set_keyboard_layouts(&mut self, layouts: Vec<osk::KeyboardLayout>) {
+        self.keyboard_layouts = layouts;
+    }
+    // layouts for one Item::TextInput only, e.g. osk::KeyboardLayout::numeric() for a PIN
+    pub fn set_input_keyboard(&mut self, id: u128, layouts: Vec<osk::KeyboardLayout>) {
+        self.input_keyboards.retain(|(v, _)| *v != id);
+        self.input_keyboards.push((id, layouts));
+    }
+    // Style of the controller in use: what its buttons are called and whether East confirms
+    // and South cancels, like on Nintendo pads. Detected from the name the renderer gets for
+    // the controller, again whenever one is plugged in or out, unless an app pins one here.
+    // None goes back to detecting it.
+    pub fn set_controller_style(&mut self, style: Option<ControllerStyle>) {
+        self.pinned_style = style;
+        self.apply_controller_style();
+    }
+    pub fn controller_style(&self) -> ControllerStyle {
+        self.controller_style
+    }
+    // for input sources added with GuiBuilder::input(), the default ones already follow it
+    pub fn confirm_swap(&self) -> controller::ConfirmSwap {
+        self.confirm_swap.clone()
+    }
+    fn apply_controller_style(&mut self) {
+        self.controller_style = self.pinned_style
+            .or_else(|| self.renderer.controller_name().map(|v| ControllerStyle::detect(&v)))
+            .unwrap_or_default();
+        self.confirm_swap.set(self.controller_style.confirm_button() == FaceButton::East);
+    }
+    // Presses of an item sooner than `cooldown` after the last one that went through are
+    // ignored, for bouncy buttons that would otherwise fire twice. Zero turns it off.
+    pub fn set_activation_cooldown(&mut self, cooldown: Duration) {
+        self.activation_cooldown = cooldown;
+    }
+    // overrides set_activation_cooldown() for one item, None goes back to it
+    pub fn set_item_cooldown(&mut self, id: u128, cooldown: Option<Duration>) {
+        self.item_cooldowns.retain(|(v, _)| *v != id);
+        if let Some(cooldown) = cooldown {
+            self.item_cooldowns.push((id, cooldown));
+        }
+    }
+    // true for a press of the focused item that comes too soon after the last one
+    fn bounced(&mut self) -> bool {
+        let Some(id) = self.focused_item().and_then(item_id) else {return false};
+        let cooldown = self.item_cooldowns.iter().find(|(v, _)| *v == id)
+            .map(|(_, v)| *v)
+            .unwrap_or(self.activation_cooldown);
+        let now = self.clock.now();
+        if let Some((last, at)) = self.last_activation {
+            if last == id && now.saturating_sub(at) < cooldown {
+                return true;
+            }
+        }
+        self.last_activation = Some((id, now));
+        false
+    }
+    // Called with the id of every item that gets focus, right away and before it's drawn, e.g.
+    // to start loading what a split-pane detail view is going to show. Starts with the item
+    // focused right now. Replaces the previous callback.
+    pub fn set_focus_callback(&mut self, callback: impl FnMut(u128) + Send + 'static) {
+        self.focus_callback = Some(Box::new(callback));
+        self.last_focused = None;
+        self.report_focus();
+    }
+    // Size of the window or terminal, e.g. for deciding how many columns a generated layout
+    // gets. GuiEvent::Resized tells when it changes.
+    pub fn viewport_info(&mut self) -> Option<ViewportInfo> {
+        self.renderer.viewport_info()
+    }
+    pub fn clear_focus_callback(&mut self) {
+        self.focus_callback = None;
+    }
+    fn report_focus(&mut self) {
+        if self.focus_callback.is_none() {
+            return;
+        }
+        let focused = self.focused_item().and_then(item_id);
+        if focused == self.last_focused {
+            return;
+        }
+        self.last_focused = focused;
+        if let (Some(callback), Some(id)) = (self.focus_callback.as_mut(), focused) {
+            callback(id);
+        }
+    }
+    // Reports `events` pressed in quick succession as GuiEvent::Shortcut(name), even on tabs
+    // and filters that capture input. Only the last press is kept from the gui, the ones
+    // before it do what they always do. An empty list drops the shortcut.
+    pub fn reserve_shortcut(&mut self, name: &str, events: &[HidEvent]) {
+        self.shortcuts.add(name, events.to_vec());
+    }
+    fn tap_raw_hid(&mut self, ev: &HidEvent) {
+        self.raw_hid_taps.retain(|tx| tx.send(ev.clone()).is_ok());
+    }
+    // the last few events that went in and out of the gui, oldest first, with the values of
+    // masked text inputs redacted
+    pub fn event_history(&self) -> impl DoubleEndedIterator<Item = &EventRecord> {
+        self.event_log.records()
+    }
+    // Appends every GuiEvent returned from now on to `path` as a line of JSON, with the time
+    // and the item it's about. Stops by itself on the first failed write.
+    pub fn start_journal(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.journal = Some(Journal::open(path.as_ref())?);
+        Ok(())
+    }
+    pub fn stop_journal(&mut self) {
+        self.journal = None;
+    }
+    // Sees every journal entry before it's written, returning false leaves it out.
+    // Values of masked text inputs are already redacted by then.
+    pub fn set_journal_redactor(&mut self, redactor: impl FnMut(&mut JournalEntry) -> bool + Send + 'static) {
+        self.journal_redactor = Some(Box::new(redactor));
+    }
+    fn write_journal(&mut self, ev: &GuiEvent) {
+        if self.journal.is_none() {
+            return;
+        }
+        // the one the event came from, ids can repeat across tabs
+        let item = event_item(ev).and_then(|id| {
+            let found = self.last_origin.as_ref().and_then(|origin| Some((self.item_at(origin)?, Some(origin.tab_name.clone()))));
+            let found = found.or_else(|| self.layout.all_items().find(|v| item_id(v) == Some(id)).map(|item| (item, None)));
+            found.map(|(item, tab)| JournalItem {
+                id,
+                kind: item.kind(),
+                label: item.label().map(str::to_string),
+                tab,
+                masked: matches!(item, Item::TextInput(_, _, true, _)),
+            })
+        });
+        let mut entry = JournalEntry::new(self.clock.wall(), self.clock.now(), ev, item);
+        if let Some(redactor) = self.journal_redactor.as_mut() {
+            if !redactor(&mut entry) {
+                return;
+            }
+        }
+        if let Some(journal) = self.journal.as_mut() {
+            if journal.write(&entry).is_err() {
+                self.journal = None;
             }
+        }
+    }
+    // ids of the text inputs that show dots instead of their text
+    fn masked_inputs(&self) -> Vec<u128> {
+        self.layout.all_items()
+            .filter_map(|v| match v {
+                Item::TextInput(_, _, true, id) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+    fn log_event(&mut self, event: LoggedEvent) {
+        // the journal gets what the log kept, so neither shows what was typed into a masked input
+        let masked = self.masked_inputs();
+        let event = self.event_log.push(self.clock.now(), event, &masked);
+        if let LoggedEvent::Gui(ev) = &event {
+            self.write_journal(ev);
+        }
+        self.update_mirror();
+    }
+    #[cfg(feature = "debug-mirror")]
+    pub fn serve_debug_mirror(&mut self, addr: impl std::net::ToSocketAddrs) -> std::io::Result<()> {
+        let mirror = debug_mirror::DebugMirror::bind(addr)?;
+        mirror.update(&self.layout, self.tab_pos.index(), self.item_pos, self.event_log.records());
+        self.mirror = Some(mirror);
+        Ok(())
+    }
+    fn update_mirror(&self) {
+        #[cfg(feature = "debug-mirror")]
+        if let Some(mirror) = self.mirror.as_ref() {
+            mirror.update(&self.layout, self.tab_pos.index(), self.item_pos, self.event_log.records());
+        }
+    }
+    // Draws items with the given id or of the given kind with `hook` instead of the
+    // renderer's own code. Fails if the renderer doesn't support hooks.
+    pub fn set_draw_hook(&mut self, target: draw::DrawTarget, hook: draw::DrawHook) -> Result<()> {
+        let hooks = self.renderer.draw_hooks()
+            .ok_or_else(|| anyhow::anyhow!("Renderer doesn't support draw hooks"))?;
+        hooks.add(target, hook);
+        self.redraw_all();
+        Ok(())
+    }
+    // draws over what's on screen in the gui's palette, until the next redraw covers it up
+    pub fn draw_primitives(&mut self, primitives: &[draw::Primitive]) -> Result<()> {
+        self.renderer.draw_primitives(primitives, &self.colors)
+    }
+    pub fn set_title(&mut self, title: &str) {
+        self.renderer.set_title(title);
+    }
+    // Loads tile pictures before they scroll into view, e.g. the next screenful of covers.
+    // Renderers without images ignore this.
+    pub fn preload_images<P: AsRef<std::path::Path>>(&mut self, paths: &[P]) -> Result<()> {
+        let paths: Vec<&std::path::Path> = paths.iter().map(AsRef::as_ref).collect();
+        self.renderer.preload_images(&paths)
+    }
+    // size the renderer will draw `text` at, for layouts built around the text they hold
+    pub fn measure_text(&mut self, text: &str, style: draw::TextStyle) -> (u32, u32) {
+        self.renderer.measure_text(text, style)
+    }
+    // current time according to the gui's clock, see GuiBuilder::clock()
+    pub fn now(&self) -> std::time::Duration {
+        self.clock.now()
+    }
+    pub fn set_palette(&mut self, colors: ColorPalette) {
+        self.theme_fade = None;
+        // stays dimmed until woken up
+        match self.idle_state.as_mut().and_then(|v| v.undimmed.as_mut()) {
+            Some(undimmed) => {
+                self.colors = colors.darkened(idle::DIM_AMOUNT);
+                *undimmed = colors;
+            },
+            None => self.colors = colors,
+        }
+        self.renderer.palette_changed();
+        self.redraw_all();
+    }
+    // Switches to a built-in palette by name or to a custom one, fading over on renderers
+    // that can afford it. Unknown names leave the current palette alone.
+    pub fn set_theme(&mut self, theme: impl Into<Theme>) -> Result<()> {
+        let palette = match theme.into() {
+            Theme::Named(name) => find_theme(&self.themes, &name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown theme {}", name))?,
+            Theme::Palette(palette) => palette,
+        };
 
-            if let Some(Ok(ev)) = r_ev {
-                match ev {
-                    RendererEvent::Refresh => {
-                        redraw_items = true;
-                        redraw_tabs = true;
-                    },
-                    RendererEvent::WindowClosed => {
-                        ret = Some(GuiEvent::Quit);
+        if self.renderer.smooth_transitions() && !self.theme_fade_time.is_zero() && self.idle_state.is_none() {
+            self.theme_fade = Some(ThemeFade {
+                from: self.colors.clone(),
+                to: palette,
+                started: self.clock.now(),
+            });
+        } else {
+            self.set_palette(palette);
+        }
+        Ok(())
+    }
+    fn fade_step(&mut self) {
+        let Some(fade) = self.theme_fade.as_ref() else {return};
+        let t = (self.clock.now() - fade.started).as_secs_f32() / self.theme_fade_time.as_secs_f32();
+        self.colors = fade.from.blend(&fade.to, t);
+        if t >= 1.0 {
+            self.theme_fade = None;
+        }
+        self.renderer.palette_changed();
+        self.redraw_tabs = true;
+        self.redraw_items = true;
+    }
+    fn enter_idle(&mut self) {
+        let Some(config) = self.idle.clone() else {return};
+        let mut state = Idle {
+            undimmed: None,
+            renderer_dimmed: false,
+            logo_step: 0,
+            next_move: None,
+        };
+        match config.mode {
+            IdleMode::Dim => {
+                if let Some(fade) = self.theme_fade.take() {
+                    self.colors = fade.to;
+                }
+                state.renderer_dimmed = self.renderer.set_dimmed(true);
+                if !state.renderer_dimmed {
+                    let dimmed = self.colors.darkened(idle::DIM_AMOUNT);
+                    state.undimmed = Some(std::mem::replace(&mut self.colors, dimmed));
+                    self.renderer.palette_changed();
+                }
+            },
+            IdleMode::Blank(_) => state.next_move = Some(self.clock.now() + config.logo_interval),
+        }
+        self.idle_state = Some(state);
+        self.redraw_tabs = true;
+        self.redraw_items = true;
+    }
+    // returns whether the gui was idle
+    fn wake(&mut self) -> bool {
+        let Some(state) = self.idle_state.take() else {return false};
+        if state.renderer_dimmed {
+            self.renderer.set_dimmed(false);
+        }
+        if let Some(colors) = state.undimmed {
+            self.colors = colors;
+        }
+        self.renderer.palette_changed();
+        self.redraw_tabs = true;
+        self.redraw_items = true;
+        true
+    }
+    // Values can change through presses, edit mode, undo or set_tab_items(), so the
+    // selector itself is checked instead of the events.
+    fn watch_brightness(&mut self) {
+        let Some(id) = self.brightness.as_ref().map(|v| v.id) else {return};
+        let level = self.layout.all_items()
+            .find(|v| item_id(v) == Some(id))
+            .and_then(brightness::level);
+        if level.is_none() || level == self.brightness_level {
+            return;
+        }
+        self.brightness_level = level;
+        if self.brightness_next.is_none() {
+            let debounce = self.brightness.as_ref().map(|v| v.debounce).unwrap_or_default();
+            self.brightness_next = Some(self.clock.now() + debounce);
+        }
+    }
+    // replaces the feedback set with GuiBuilder::feedback()
+    pub fn set_feedback(&mut self, feedback: FeedbackTheme) {
+        self.feedback = feedback;
+    }
+    pub fn add_form(&mut self, form: Form) {
+        let values = self.current_values(&form);
+        self.forms.retain(|(v, _)| v.id != form.id);
+        self.forms.push((form, values));
+    }
+    // Scans for Wi-Fi networks and shows them as buttons on `tab`, replacing its items. The
+    // buttons get ids starting at `id` + 1, pressing one connects to that network, asking for
+    // the password first for secured ones it doesn't know yet. nmcli scans in the background.
+    #[cfg(feature = "wifi")]
+    pub fn show_wifi_picker(&mut self, tab: usize, id: u128) {
+        let picker = wifi::WifiPicker::new(id, tab);
+        self.set_tab_items(tab, picker.rows());
+        self.wifi = Some(picker);
+        wifi::spawn_scan(self.cmd_tx.clone());
+    }
+    // scans again, e.g. after a connection attempt or to pick up networks that came in range
+    #[cfg(feature = "wifi")]
+    pub fn refresh_wifi_picker(&mut self) {
+        let Some(picker) = self.wifi.as_mut() else {return};
+        if !picker.scanning {
+            picker.scanning = true;
+            wifi::spawn_scan(self.cmd_tx.clone());
+        }
+    }
+    // Keeps the Item::Value with `id` showing the network connected to and its signal, or
+    // "Not connected". It's checked again every 10 seconds and after connecting.
+    #[cfg(feature = "wifi")]
+    pub fn show_wifi_status(&mut self, id: u128) {
+        if !self.wifi_status.contains(&id) {
+            self.wifi_status.push(id);
+        }
+        self.wifi_status_next = Some(self.clock.now());
+    }
+    // Connects to a network of the picker, the result comes back as WifiConnected or WifiFailed.
+    #[cfg(feature = "wifi")]
+    pub fn wifi_connect(&mut self, ssid: &str, password: Option<&str>) {
+        let Some(picker) = self.wifi.as_mut() else {return};
+        let Some(network) = picker.networks.iter().find(|v| v.ssid == ssid) else {return};
+        let spec = wifi::connect_spec(network, password);
+        picker.connecting = Some((ssid.to_string(), None));
+        // the typed password doesn't stay around in the layout
+        picker.asking = None;
+        let (id, tab, rows) = (picker.id, picker.tab, picker.rows());
+        self.set_tab_items(tab, rows);
+        if let Err(e) = command::spawn(id, &spec, self.cmd_tx.clone()) {
+            let _ = self.cmd_tx.send(CommandOutput::Line(id, format!("Failed to start nmcli: {}", e)));
+            let _ = self.cmd_tx.send(CommandOutput::Exited(id, None));
+        }
+    }
+    // turns presses of network buttons into connection attempts, and typed passwords too
+    #[cfg(feature = "wifi")]
+    fn route_wifi(&mut self, ev: GuiEvent) -> Option<GuiEvent> {
+        let Some(picker) = self.wifi.as_mut() else {return Some(ev)};
+        if let GuiEvent::TextChanged(id, password) = &ev {
+            if *id != picker.id {
+                return Some(ev);
+            }
+            if let Some(ssid) = picker.asking.clone() {
+                self.wifi_connect(&ssid, Some(password));
+            }
+            return None;
+        }
+        let GuiEvent::StatelessButtonPress(_, id) = &ev else {return Some(ev)};
+        let Some(network) = picker.network(*id).cloned() else {return Some(ev)};
+        if !network.secured || network.known {
+            self.wifi_connect(&network.ssid, None);
+            return None;
+        }
+        // a password input right below the network, with the keyboard already up
+        picker.asking = Some(network.ssid);
+        let (id, tab, rows, row) = (picker.id, picker.tab, picker.rows(), picker.password_row());
+        self.set_tab_items(tab, rows);
+        if let (Some(row), true) = (row, tab == self.tab_pos.index()) {
+            self.item_pos = (row, 0);
+            let layouts = self.input_keyboards.iter().find(|(v, _)| *v == id)
+                .map(|(_, v)| v)
+                .unwrap_or(&self.keyboard_layouts);
+            self.osk = Some(osk::Osk::new((row, 0), String::new(), true, layouts.clone()));
+            self.redraw_items = true;
+        }
+        None
+    }
+    // output of the nmcli started by wifi_connect() or the scans, None if it belongs to something else
+    #[cfg(feature = "wifi")]
+    fn wifi_output(&mut self, out: &CommandOutput) -> Option<Option<GuiEvent>> {
+        let (id, exited) = match out {
+            CommandOutput::Line(id, _) => (*id, None),
+            CommandOutput::Exited(id, code) => (*id, Some(*code)),
+            CommandOutput::Wifi(wifi::Update::Status(network)) => {
+                self.set_wifi_status(network.as_ref());
+                return Some(None);
+            },
+            CommandOutput::Wifi(wifi::Update::Scanned(networks)) => {
+                let picker = self.wifi.as_mut()?;
+                picker.scanning = false;
+                match networks {
+                    Ok(networks) => {
+                        picker.networks = networks.clone();
+                        picker.error = None;
                     },
-                    RendererEvent::Hid(ev) => {
-                        hid_ev = Some(Ok(ev));
-                    }
+                    Err(e) => picker.error = Some(e.clone()),
+                }
+                // the network being asked about might be gone
+                if picker.password_row().is_none() {
+                    picker.asking = None;
+                }
+                let (tab, rows, row) = (picker.tab, picker.rows(), picker.password_row());
+                // a keyboard open on the password follows it to wherever the networks moved it
+                let osk = self.osk.take_if(|_| tab == self.tab_pos.index());
+                self.set_tab_items(tab, rows);
+                if let (Some(mut osk), Some(row)) = (osk, row) {
+                    osk.origin = (row, 0);
+                    self.osk = Some(osk);
+                    self.item_pos = (row, 0);
+                    self.redraw_items = true;
                 }
+                return Some(None);
+            },
+        };
+        let picker = self.wifi.as_mut()?;
+        if id != picker.id {
+            return None;
+        }
+        let Some((ssid, last)) = picker.connecting.as_mut() else {return Some(None)};
+        match (out, exited) {
+            (CommandOutput::Line(_, line), _) => {
+                *last = Some(line.clone());
+                Some(None)
+            },
+            (_, Some(Some(0))) => {
+                let ssid = ssid.clone();
+                picker.connecting = None;
+                for network in picker.networks.iter_mut() {
+                    network.connected = network.ssid == ssid;
+                }
+                let rows = picker.rows();
+                let tab = picker.tab;
+                self.set_tab_items(tab, rows);
+                if !self.wifi_status.is_empty() {
+                    self.wifi_status_next = Some(self.clock.now());
+                }
+                Some(Some(GuiEvent::WifiConnected(ssid)))
+            },
+            _ => {
+                let (ssid, last) = picker.connecting.take()?;
+                Some(Some(GuiEvent::WifiFailed(ssid, last.unwrap_or_else(|| "nmcli failed".to_string()))))
+            },
+        }
+    }
+    #[cfg(feature = "wifi")]
+    fn set_wifi_status(&mut self, network: Option<&wifi::Network>) {
+        let text = wifi::status_text(network);
+        for item in self.layout.all_items_mut() {
+            let Item::Value(_, value, id) = item else {continue};
+            if self.wifi_status.contains(id) && *value != format::Value::Text(text.clone()) {
+                *value = format::Value::Text(text.clone());
+                self.redraw_items = true;
             }
+        }
+        self.wifi_status_next = Some(self.clock.now() + WIFI_STATUS_INTERVAL);
+    }
+    // values as they are right now, applied or not
+    pub fn form_values(&self, form_id: u128) -> Option<Vec<(u128, FormValue)>> {
+        let (form, _) = self.forms.iter().find(|(v, _)| v.id == form_id)?;
+        Some(self.current_values(form))
+    }
+    fn current_values(&self, form: &Form) -> Vec<(u128, FormValue)> {
+        form.items.iter()
+            .filter_map(|id| {
+                // radio groups are known by their picked option
+                let value = self.layout.all_items().filter(|v| item_id(v) == Some(*id)).find_map(FormValue::of)?;
+                Some((*id, value))
+            })
+            .collect()
+    }
+    // turns apply/revert presses into form events and swallows changes of form items
+    fn route_forms(&mut self, ev: GuiEvent) -> Option<GuiEvent> {
+        let id = match &ev {
+            GuiEvent::StatelessButtonPress(_, id) | GuiEvent::StatefulButtonChange(_, _, id) | GuiEvent::ToggleChanged(id, _)
+                | GuiEvent::SelectorChanged(id, _) | GuiEvent::DateChanged(id, _) | GuiEvent::TimeChanged(id, _) | GuiEvent::SliderChanged(id, _)
+                | GuiEvent::StepperChanged(id, _) | GuiEvent::RadioChanged(id, _) | GuiEvent::TextChanged(id, _) => *id,
+            _ => return Some(ev),
+        };
+        let Some(idx) = self.forms.iter().position(|(form, _)| form.items.contains(&id) || form.apply == Some(id) || form.revert == Some(id)) else {
+            return Some(ev);
+        };
 
-            if let Some(Ok(hid_ev)) = hid_ev {
-                if self.ignore_hid {
-                    return GuiEvent::IgnoredHid;
-                }
-                match hid_ev {
-                    HidEvent::NextTab => tab_chg = 1,
-                    HidEvent::PreviousTab => tab_chg = -1,
-                    HidEvent::Up => item_row_chg = -1,
-                    HidEvent::Down => item_row_chg = 1,
-                    HidEvent::Left => item_column_chg = -1,
-                    HidEvent::Right => item_column_chg = 1,
-                    HidEvent::ButtonPress => activate_selection = true,
-                    HidEvent::Quit => ret = Some(GuiEvent::Quit),
-                }
-            }
-
-            if activate_selection {
-                let (row, col) = self.item_pos;
-                if let Some(tab) = self.layout.tab_mut(self.tab_pos as usize) {
-                    if let Some(row) = tab.items_mut().get_mut(row) {
-                        if let Some(item) = row.get_mut(col) {
-                            match item {
-                                &mut Item::StatefulButton(ref text, ref mut state, ref id) => {
-                                    *state = !*state;
-                                    redraw_items = true;
-                                    ret = Some(GuiEvent::StatefulButtonChange(text.to_string(), *state, *id));
-                                },
-                                Item::StatelessButton(text, id) => {
-                                    ret = Some(GuiEvent::StatelessButtonPress(text.to_string(), *id));
-                                },
-                                _ => (),
-                            }
-                        }
-                    }
+        let form = self.forms[idx].0.clone();
+        if form.apply == Some(id) {
+            let values = self.current_values(&form);
+            self.forms[idx].1 = values.clone();
+            Some(GuiEvent::FormSubmitted(form.id, values))
+        } else if form.revert == Some(id) {
+            for (id, value) in self.forms[idx].1.iter() {
+                for item in self.layout.all_items_mut().filter(|v| item_id(v) == Some(*id)) {
+                    value.restore(item);
                 }
             }
-
-            // change tab if we need to, and refresh everything if we changed a tab
-            if tab_chg != 0 {
-                self.tab_pos = (self.tab_pos + tab_chg).clamp(0, self.layout.tab_count());
-                self.item_pos = (0, 0);
-
-                redraw_tabs = true;
-                redraw_items = true;
-
-                match tab_chg {
-                    1  => ret = Some(GuiEvent::TabChanged("todo".to_string())),
-                    -1 => ret = Some(GuiEvent::TabChanged("todo".to_string())),
-                    _ => (),
+            self.redraw_items = true;
+            Some(GuiEvent::FormReverted(form.id))
+        } else {
+            None
+        }
+    }
+    // reads the battery for every Item::Battery, and keeps doing so while there are any
+    fn refresh_battery(&mut self) {
+        if !self.layout.all_items().any(|v| matches!(v, Item::Battery(_))) {
+            self.battery_next = None;
+            return;
+        }
+        let battery = power::Battery::read();
+        for item in self.layout.all_items_mut() {
+            if let Item::Battery(current) = item {
+                if *current != battery {
+                    *current = battery;
+                    self.redraw_items = true;
                 }
             }
-
-            if item_row_chg != 0 {
-                if let Some(curtab) = self.layout.tab(self.tab_pos as usize) {
-                    let (cur_row, cur_column) = self.item_pos;
-                    
-                    let max_row = (curtab.items().len() as i32 - 1).clamp(0, 10000);
-                    let new_cur_row = (cur_row as i32 + item_row_chg).clamp(0, max_row) as usize;
-
-                    // we have to check because we're moving selection to another row
-                    if let Some(row) = curtab.items().get(new_cur_row) {
-                        if let Some(_item) = row.get(cur_column) {
-                            self.item_pos = (new_cur_row, cur_column);
-                            redraw_items = true;
-                        }
-                    }
+        }
+        self.battery_next = Some(self.clock.now() + self.battery_interval);
+    }
+    // reads the local time for every Item::Clock, then sleeps until the minute changes
+    fn refresh_clocks(&mut self) {
+        if !self.layout.all_items().any(|v| matches!(v, Item::Clock(_))) {
+            self.clock_next = None;
+            return;
+        }
+        let wall = self.clock.wall();
+        let time = Some(datetime::Time::local(wall));
+        for item in self.layout.all_items_mut() {
+            if let Item::Clock(current) = item {
+                if *current != time {
+                    *current = time;
+                    self.redraw_items = true;
                 }
             }
-
-            if item_column_chg != 0 {
-                if let Some(curtab) = self.layout.tab(self.tab_pos as usize) {
-                    let (cur_row, cur_column) = self.item_pos;
-                    let max_column;
-                    let new_cur_column;
-
-                    if let Some(row) = curtab.items().get(cur_row) {
-                        max_column = (row.len() as i32 - 1).clamp(0, 10000);
-                        new_cur_column = (cur_column as i32 + item_column_chg).clamp(0, max_column) as usize;
-                    } else {
-                        new_cur_column = 0;
-                    }
-
-                    self.item_pos = (cur_row, new_cur_column);
-                    redraw_items = true;
+        }
+        self.clock_next = Some(self.clock.now() + datetime::until_next_minute(wall));
+    }
+    // "3 min ago" only changes once a minute, counting every few seconds keeps it close
+    fn refresh_since(&mut self) {
+        let now = self.clock.wall();
+        let mut any = false;
+        for item in self.layout.all_items_mut() {
+            let Item::Value(_, format::Value::Since(at, elapsed), _) = item else {continue};
+            // times in the future count as now
+            let current = now.duration_since(*at).unwrap_or_default();
+            self.redraw_items |= format::ago(current) != format::ago(*elapsed);
+            *elapsed = current;
+            any = true;
+        }
+        self.since_next = any.then(|| self.clock.now() + Duration::from_secs(15));
+    }
+    fn refresh_spinners(&mut self) {
+        let advance = self.spinner_next.is_some();
+        let mut spinning = false;
+        for item in self.layout.all_items_mut() {
+            if let Item::Spinner(_, frame, true, _) = item {
+                if advance {
+                    *frame = frame.wrapping_add(1);
+                }
+                spinning = true;
+            }
+        }
+        self.redraw_items |= advance && spinning;
+        self.spinner_next = spinning.then(|| self.clock.now() + SPINNER_FRAME);
+    }
+    // counts every running Item::Countdown down by the time since the last round
+    fn refresh_countdowns(&mut self) {
+        let now = self.clock.now();
+        let elapsed = self.countdown_last.map(|v| now.saturating_sub(v)).unwrap_or_default();
+        let mut next: Option<Duration> = None;
+        let mut expired = Vec::new();
+        for (n, item) in self.layout.all_items_mut().enumerate() {
+            let Item::Countdown(left, id) = item else {continue};
+            if left.is_zero() {
+                continue;
+            }
+            *left = left.saturating_sub(elapsed);
+            self.redraw_items |= !elapsed.is_zero();
+            if left.is_zero() {
+                expired.push((*id, n));
+                continue;
+            }
+            // wakes up right when the shown seconds change
+            let step = match left.subsec_nanos() {
+                0 => Duration::from_secs(1),
+                nanos => Duration::from_nanos(nanos as u64),
+            };
+            next = Some(next.map_or(step, |v| v.min(step)));
+        }
+        self.countdown_last = next.map(|_| now);
+        self.countdown_next = next.map(|v| now + v);
+        for (id, n) in expired {
+            if let Some(origin) = self.nth_origin(n) {
+                self.expired_timers.push_back((id, origin));
+            }
+        }
+    }
+    // starts an Item::Countdown over with `left`, on every tab, zero stops it without a GuiEvent::TimerExpired
+    pub fn set_countdown(&mut self, id: u128, left: Duration) {
+        self.refresh_countdowns();
+        for item in self.layout.all_items_mut() {
+            if let Item::Countdown(current, item_id) = item {
+                if *item_id == id {
+                    *current = left;
+                    self.redraw_items = true;
                 }
             }
+        }
+        self.expired_timers.retain(|(v, _)| *v != id);
+        self.refresh_countdowns();
+        self.flush_redraws();
+    }
+    // An on-screen display kept next to the gui, e.g. a volume bar in an overlay window. It
+    // goes by the gui's clock and get_ev() hides it when its time is up.
+    pub fn set_osd(&mut self, mut osd: osd::Osd) {
+        osd.set_clock(self.clock.clone());
+        self.osd = Some(osd);
+    }
+    pub fn osd(&mut self) -> Option<&mut osd::Osd> {
+        self.osd.as_mut()
+    }
+    fn apply_brightness(&mut self) {
+        let (Some(brightness), Some(level)) = (self.brightness.as_mut(), self.brightness_level) else {return};
+        if !brightness.apply(level) {
+            self.renderer.set_brightness(level);
+        }
+    }
+    fn redraw_all(&mut self) {
+        self.redraw_tabs = true;
+        self.redraw_items = true;
+        self.flush_redraws();
+    }
+    // repaints everything, e.g. after drawing over the window from outside the gui
+    pub fn request_redraw(&mut self) {
+        self.redraw_all();
+    }
+    // Holds back drawing until thaw(), so a batch of setter calls ends up as one redraw
+    // instead of one per call. Events keep being handled while frozen.
+    pub fn freeze_redraws(&mut self) {
+        self.frozen = true;
+    }
+    // draws whatever changed while frozen
+    pub fn thaw(&mut self) {
+        self.frozen = false;
+        self.flush_redraws();
+    }
+    pub fn get_ev(&mut self) -> GuiEvent {
+        loop {
+            let mut hid_ev = None;
+            let mut r_ev = None;
+            let mut cmd_ev = None;
+            let timer = self.next_timeout().map(after).unwrap_or_else(never);
 
-            if redraw_tabs {
-                self.renderer.draw_tab_header(&self.layout.tab_names().into_iter().skip(self.tab_pos as usize).collect::<Vec<&str>>(), &self.colors)
-                    .expect("Failed to draw tab header");
+            select! {
+                recv(self.hid_rx.as_ref().unwrap_or(&never())) -> msg => hid_ev = Some(msg),
+                recv(self.renderer_rx.as_ref().unwrap_or(&never())) -> msg => r_ev = Some(msg),
+                recv(self.cmd_rx) -> msg => cmd_ev = msg.ok(),
+                recv(timer) -> _ => (),
             }
 
-            if redraw_items {
-                if let Some(curtab) = self.layout.tab(self.tab_pos as usize) {
-                    self.renderer.draw_items(curtab.items(), &self.colors, self.item_pos)
-                        .expect("Failed to draw items");
+            // every input source went away, stop listening instead of spinning on a dead channel
+            if let Some(Err(_)) = hid_ev {
+                self.hid_rx = None;
+            }
+            if let Some(Ok(ev)) = hid_ev.as_ref() {
+                self.tap_raw_hid(ev);
+                self.log_event(LoggedEvent::Hid(ev.clone()));
+            }
+            if let Some(Ok(ev)) = r_ev.as_ref() {
+                if let RendererEvent::Hid(hid) = ev {
+                    self.tap_raw_hid(hid);
                 }
+                self.log_event(LoggedEvent::Renderer(ev.clone()));
             }
 
+            // timers that are due while events keep coming in get handled on the next round
+            let ret = if let Some(out) = cmd_ev {
+                self.command_output(out)
+            } else if hid_ev.is_none() && r_ev.is_none() {
+                self.handle_timers()
+            } else {
+                self.process_event(hid_ev.and_then(Result::ok), r_ev.and_then(Result::ok))
+            };
+            self.watch_brightness();
+            let ret = ret.and_then(|v| self.route_forms(v));
+            #[cfg(feature = "wifi")]
+            let ret = ret.and_then(|v| self.route_wifi(v));
+            self.flush_redraws();
+
+            self.note_origin(ret.as_ref());
             if let Some(return_this) = ret {
+                self.log_event(LoggedEvent::Gui(return_this.clone()));
                 return return_this;
             }
 
             self.renderer.tick();
         }
     }
-    pub fn new(layout: layout::Layout) -> Gui {
-        let colors = ColorPalette::default();
-        let mut renderer = autopick_renderer();
-        renderer.draw_tab_header(&layout.tab_names(), &colors).unwrap();
-        renderer.draw_items(&layout.tab(0).unwrap().items(), &colors, (0, 0)).unwrap();
-        let renderer_rx = renderer.get_event();
-
-        let hid_rx = autopick_input();
-
-        Gui {
-            layout,
-            renderer,
-            colors,
-            hid_rx,
-            renderer_rx,
-            tab_pos: 0,
-            item_pos: (0, 0),
-            ignore_hid: false,
-        }
+    // how long get_ev can block before something time-based needs attention
+    fn next_timeout(&self) -> Option<Duration> {
+        let now = self.clock.now();
+        // a running fade wants a new frame about every 16ms
+        let fade_frame = self.theme_fade.as_ref().map(|_| now + Duration::from_millis(16));
+        let idle = match (self.idle.as_ref(), self.idle_state.as_ref()) {
+            (_, Some(state)) => state.next_move,
+            (Some(config), None) => Some(self.last_input + config.after),
+            _ => None,
+        };
+        let idle_event = self.idle_events.filter(|_| !self.idle_reported).map(|v| self.last_input + v);
+        let expired = (!self.expired_timers.is_empty()).then_some(now);
+        let renderer = self.renderer.next_tick();
+        let osd = self.osd.as_ref().and_then(|v| v.hide_at());
+        #[cfg(feature = "wifi")]
+        let wifi = self.wifi_status_next;
+        #[cfg(not(feature = "wifi"))]
+        let wifi = None;
+        [self.analog_next, self.dpad_next, fade_frame, idle, idle_event, self.brightness_next, self.battery_next, self.since_next, self.clock_next, self.spinner_next, self.marquee_next, self.countdown_next, self.reveal_next, self.log_next, expired, renderer, osd, wifi].into_iter()
+            .flatten()
+            .map(|v| v.saturating_sub(now))
+            .min()
     }
-}
+    fn handle_timers(&mut self) -> Option<GuiEvent> {
+        let now = self.clock.now();
+        self.fade_step();
+        let mut ret = None;
 
-fn autopick_input() -> Option<Receiver<HidEvent>> {
-    let mut handle = RinputerHandle::open()?;
-    let (tx, rx) = bounded(1);
-    thread::spawn(move || {
-        loop {
-            use ez_input::EzEvent;
-            let Some(event) = handle.get_event_blocking() else {continue};
-            let ev = match event {
-                EzEvent::DirectionUp => HidEvent::Up,
-                EzEvent::DirectionDown => HidEvent::Down,
-                EzEvent::DirectionLeft => HidEvent::Left,
-                EzEvent::DirectionRight => HidEvent::Right,
-                EzEvent::South(true) => HidEvent::ButtonPress,
-                EzEvent::R(true) => HidEvent::NextTab,
-                EzEvent::L(true) => HidEvent::PreviousTab,
-                _ => continue,
+        if self.battery_next.map(|v| v <= now).unwrap_or(false) {
+            self.refresh_battery();
+        }
+        if self.since_next.map(|v| v <= now).unwrap_or(false) {
+            self.refresh_since();
+        }
+        if self.clock_next.map(|v| v <= now).unwrap_or(false) {
+            self.refresh_clocks();
+        }
+        if self.spinner_next.map(|v| v <= now).unwrap_or(false) {
+            self.refresh_spinners();
+        }
+        if self.countdown_next.map(|v| v <= now).unwrap_or(false) {
+            self.refresh_countdowns();
+        }
+        if self.log_next.map(|v| v <= now).unwrap_or(false) {
+            self.log_next = None;
+            self.redraw_items = true;
+        }
+        if self.reveal_next.map(|v| v <= now).unwrap_or(false) {
+            self.reveal_next = None;
+            if let Some(osk) = self.osk.as_mut() {
+                osk.reveal(false);
+                self.redraw_items = true;
+            }
+        }
+        // nothing to do but wake up, the renderer moves it along in tick()
+        if self.marquee_next.map(|v| v <= now).unwrap_or(false) {
+            self.marquee_next = Some(now + MARQUEE_FRAME);
+        }
+
+        if self.brightness_next.map(|v| v <= now).unwrap_or(false) {
+            self.brightness_next = None;
+            self.apply_brightness();
+        }
+        if let Some(osd) = self.osd.as_mut() {
+            osd.tick();
+        }
+        // checked again once the answer is in
+        #[cfg(feature = "wifi")]
+        if self.wifi_status_next.map(|v| v <= now).unwrap_or(false) {
+            self.wifi_status_next = None;
+            wifi::spawn_status(self.cmd_tx.clone());
+        }
+
+        if let Some((after, interval)) = self.idle.as_ref().map(|v| (v.after, v.logo_interval)) {
+            match self.idle_state.as_mut() {
+                None if self.last_input + after <= now => self.enter_idle(),
+                Some(state) if state.next_move.map(|v| v <= now).unwrap_or(false) => {
+                    state.logo_step += 1;
+                    state.next_move = Some(now + interval);
+                    self.redraw_items = true;
+                },
+                _ => (),
+            }
+        }
+
+        if let Some(after) = self.idle_events {
+            if !self.idle_reported && self.last_input + after <= now {
+                self.idle_reported = true;
+                ret = Some(GuiEvent::Idle(now - self.last_input));
+            }
+        }
+
+        if self.analog_next.map(|v| v <= now).unwrap_or(false) {
+            ret = self.analog_step().or(ret);
+        }
+
+        if self.dpad_next.map(|v| v <= now).unwrap_or(false) {
+            let ev = match self.dpad_held {
+                (-1, _) => Some(HidEvent::Up),
+                (1, _) => Some(HidEvent::Down),
+                (_, -1) => Some(HidEvent::Left),
+                (_, 1) => Some(HidEvent::Right),
+                _ => None,
             };
-            if tx.send(ev).is_err() {
-                break;
+            self.dpad_next = None;
+            if let Some(ev) = ev {
+                if let Some(dpad) = self.analog.dpad.as_ref() {
+                    self.dpad_next = Some(now + dpad.repeat_interval);
+                }
+                ret = self.process_event(Some(ev), None).or(ret);
+            }
+        }
+
+        if ret.is_none() {
+            ret = self.expired_timers.pop_front().map(|(id, origin)| {
+                self.auto_confirm(id).unwrap_or_else(|| {
+                    self.origin = Some(origin);
+                    GuiEvent::TimerExpired(id)
+                })
+            });
+        }
+        ret
+    }
+    // what pressing the focused item does, for a real press as well as auto_confirm()
+    fn activate_focused(&mut self) -> Option<GuiEvent> {
+        let mut ret = None;
+        let (row, col) = self.item_pos;
+        let before = self.layout.tab(self.tab_pos.index()).and_then(|v| v.item(row, col)).cloned();
+        let mut run_command = false;
+        let mut radio = None;
+        if let Some(tab) = self.layout.tab_mut(self.tab_pos.index()) {
+            if let Some(item) = tab.item_mut(row, col) {
+                match item {
+                    &mut Item::StatefulButton(ref text, ref mut state, ref id) => {
+                        *state = !*state;
+                        self.redraw_items = true;
+                        ret = Some(GuiEvent::StatefulButtonChange(text.to_string(), *state, *id));
+                    },
+                    Item::Toggle(_, state, id) => {
+                        *state = !*state;
+                        self.redraw_items = true;
+                        ret = Some(GuiEvent::ToggleChanged(*id, *state));
+                    },
+                    Item::StatelessButton(text, id) | Item::Tile(text, _, id) => {
+                        ret = Some(GuiEvent::StatelessButtonPress(text.to_string(), *id));
+                    },
+                    Item::Command(_, _, running, _) if !*running => run_command = true,
+                    Item::Link(_, url, id) => {
+                        if self.open_links {
+                            // waited on off the ui thread, it may only exit with the viewer
+                            if let Ok(mut child) = std::process::Command::new("xdg-open").arg(url.as_str()).spawn() {
+                                std::thread::spawn(move || child.wait());
+                            }
+                        }
+                        ret = Some(GuiEvent::LinkActivated(*id, url.to_string()));
+                    },
+                    Item::Selector(_, options, selected, SelectorMode::Cycle, id) if !options.is_empty() => {
+                        *selected = (*selected + 1) % options.len();
+                        self.redraw_items = true;
+                        ret = Some(GuiEvent::SelectorChanged(*id, *selected));
+                    },
+                    Item::Selector(_, options, _, SelectorMode::Edit, _) if !options.is_empty() => {
+                        self.editing = Some(Editing {
+                            origin: (row, col),
+                            original: item.clone(),
+                            segment: 0,
+                        });
+                        self.renderer.set_edit_mode(true);
+                        self.redraw_items = true;
+                    },
+                    Item::Date(..) | Item::Time(..) => {
+                        self.editing = Some(Editing {
+                            origin: (row, col),
+                            original: item.clone(),
+                            segment: 0,
+                        });
+                        self.renderer.set_edit_mode(true);
+                        self.renderer.set_edit_segment(0);
+                        self.redraw_items = true;
+                    },
+                    Item::Radio(_, index, _, id) => radio = Some((*id, *index)),
+                    Item::Table(_, _, rows, selected, id) if !rows.is_empty() => {
+                        ret = Some(GuiEvent::TableRowSelected(*id, *selected));
+                    },
+                    Item::TextInput(_, text, masked, id) => {
+                        let layouts = self.input_keyboards.iter().find(|(v, _)| v == id)
+                            .map(|(_, v)| v)
+                            .unwrap_or(&self.keyboard_layouts);
+                        self.osk = Some(osk::Osk::new((row, col), text.clone(), *masked, layouts.clone()));
+                        self.redraw_items = true;
+                    },
+                    Item::Selector(_, options, selected, SelectorMode::Popup, _) if !options.is_empty() => {
+                        self.popup = Some(Popup {
+                            options: options.clone(),
+                            selected: *selected,
+                            origin: (row, col),
+                        });
+                        self.redraw_items = true;
+                    },
+                    _ => (),
+                }
+            }
+        }
+        // a running command isn't something to undo
+        let rejected = matches!(before, Some(Item::Command(_, _, true, _)));
+        let pressed = before.as_ref().and_then(item_id);
+        if let Some((id, index)) = radio {
+            ret = self.pick_radio(id, index);
+        } else if run_command {
+            ret = self.run_command((row, col)).or(ret);
+        } else if let Some(before) = before {
+            self.record_item((row, col), before, false);
+        }
+        if pressed.is_some() {
+            let feedback = self.feedback.for_press(pressed, !rejected);
+            if feedback != Feedback::None {
+                self.renderer.feedback(feedback);
+            }
+        }
+
+        let confirm = self.layout.tab(self.tab_pos.index()).and_then(|v| v.multi_select());
+        if let (Some(GuiEvent::StatelessButtonPress(_, id)), Some(confirm)) = (ret.as_ref(), confirm) {
+            if *id == confirm {
+                ret = Some(GuiEvent::SelectionConfirmed(self.selection(self.tab_pos.index())));
+            }
+        }
+        ret
+    }
+    // presses the default choice of the top focus scope once its countdown has run out
+    fn auto_confirm(&mut self, countdown: u128) -> Option<GuiEvent> {
+        let (scope, _) = self.focus_scopes.last()?;
+        let cell = scope.auto_confirm
+            .filter(|(id, _)| *id == countdown)
+            .map(|(_, cell)| scope.clamp(cell))?;
+        self.item_pos = cell;
+        self.redraw_items = true;
+        // nobody pressed anything, so it skips what input goes through, e.g. waking up
+        self.origin = Some(self.focused_origin());
+        self.activate_focused()
+    }
+    // Held sticks adjust the focused value repeatedly, faster the further they're pushed.
+    fn analog_step(&mut self) -> Option<GuiEvent> {
+        let deflection = self.analog.normalized(Axis::LeftX, self.analog_x).abs();
+        if deflection <= 0.0 || self.popup.is_some() {
+            self.analog_next = None;
+            return None;
+        }
+
+        let direction = self.analog_x.signum() as i32;
+        let (row, col) = self.item_pos;
+        let before = self.layout.tab(self.tab_pos.index()).and_then(|v| v.item(row, col)).cloned();
+        let ret = self.layout.tab_mut(self.tab_pos.index())
+            .and_then(|tab| tab.item_mut(row, col))
+            .and_then(|item| adjust_item(item, direction));
+
+        if let (Some(_), Some(before), None) = (ret.as_ref(), before, self.editing.as_ref()) {
+            self.record_item((row, col), before, true);
+        }
+        if ret.is_some() {
+            self.origin = Some(self.focused_origin());
+            let interval = Duration::from_millis(400 - (360.0 * deflection.min(1.0)) as u64);
+            self.analog_next = Some(self.clock.now() + interval);
+            self.redraw_items = true;
+        } else {
+            // nothing adjustable in focus
+            self.analog_next = None;
+        }
+        // edited values are only reported once confirmed
+        ret.filter(|_| self.editing.is_none())
+    }
+    fn focused_item(&self) -> Option<&Item> {
+        let tab = self.layout.tab(self.tab_pos.index())?;
+        match self.pane {
+            Pane::List if tab.list().is_some() => tab.list()?.get(self.list_pos),
+            _ => tab.item(self.item_pos.0, self.item_pos.1),
+        }
+    }
+    // What `ev` does to the focused item if it declared buttons of its own. Some(None) is
+    // a ButtonPress on an item that moved activation to another button.
+    fn item_action(&self, ev: &HidEvent) -> Option<(u128, Option<ItemAction>)> {
+        let id = self.focused_item().and_then(item_id)?;
+        let tab = self.layout.tab(self.tab_pos.index())?;
+        if let Some(action) = tab.item_action(id, ev) {
+            return Some((id, Some(action.clone())));
+        }
+        let moved = tab.item_actions(id).any(|(_, v)| *v == ItemAction::Activate);
+        (*ev == HidEvent::ButtonPress && moved).then_some((id, None))
+    }
+    fn focused_adjustable(&self) -> bool {
+        let (row, col) = self.item_pos;
+        matches!(self.layout.tab(self.tab_pos.index()).and_then(|v| v.item(row, col)), Some(Item::Selector(..) | Item::Slider(..) | Item::Stepper(..)))
+    }
+    // Left and Right move the focused slider instead of the focus
+    fn adjust_focused(&mut self, steps: i32) -> Option<GuiEvent> {
+        let (row, col) = self.item_pos;
+        let tab = self.layout.tab_mut(self.tab_pos.index())?;
+        let item = tab.item_mut(row, col)?;
+        let before = item.clone();
+        let ret = adjust_item(item, steps)?;
+        self.record_item((row, col), before, true);
+        self.redraw_items = true;
+        Some(ret)
+    }
+    // Up and Down go through the rows of a focused table, focus only moves on past its ends
+    fn table_row_after(&self, ev: &HidEvent) -> Option<usize> {
+        let steps = match ev {
+            HidEvent::Up => -1,
+            HidEvent::Down => 1,
+            _ => return None,
+        };
+        match (self.pane, self.focused_item()?) {
+            (Pane::Detail, Item::Table(_, _, rows, selected, _)) => {
+                let row = *selected as i32 + steps;
+                (0..rows.len() as i32).contains(&row).then_some(row as usize)
+            },
+            _ => None,
+        }
+    }
+    fn select_table_row(&mut self, row: usize) {
+        let (r, c) = self.item_pos;
+        if let Some(Item::Table(_, _, _, selected, _)) = self.layout.tab_mut(self.tab_pos.index()).and_then(|v| v.item_mut(r, c)) {
+            *selected = row;
+            self.redraw_items = true;
+        }
+    }
+    // picks option `index` of the radio group `id` on the current tab, None if it already was
+    fn pick_radio(&mut self, id: u128, index: usize) -> Option<GuiEvent> {
+        let tab = self.tab_pos.index();
+        let before = self.set_radio(tab, id, Some(index))?;
+        self.history.record(Change::Radio { tab, id, before, after: index }, false);
+        Some(GuiEvent::RadioChanged(id, index))
+    }
+    // picks `index` of the radio group `id` on `tab`, or nothing, returns which option was
+    // picked before and None if nothing changed
+    fn set_radio(&mut self, tab: usize, id: u128, index: Option<usize>) -> Option<Option<usize>> {
+        let current = self.tab_pos.index();
+        let mut before = None;
+        let mut changed = false;
+        for item in self.layout.tab_mut(tab)?.all_items_mut() {
+            if let Item::Radio(_, i, picked, group) = item {
+                if *group != id {
+                    continue;
+                }
+                if *picked {
+                    before = Some(*i);
+                }
+                if *picked != (Some(*i) == index) {
+                    *picked = Some(*i) == index;
+                    changed = true;
+                }
+            }
+        }
+        self.redraw_items |= changed && tab == current;
+        changed.then_some(before)
+    }
+    // returns the direction to move in when a stick axis newly engages a d-pad direction
+    fn dpad_from_axis(&mut self, axis: Axis, value: i16) -> Option<(i32, i32)> {
+        let dpad = self.analog.dpad.as_ref()?;
+        let deflection = self.analog.normalized(axis, value);
+        let held = match axis {
+            Axis::LeftY => &mut self.dpad_held.0,
+            Axis::LeftX => &mut self.dpad_held.1,
+            _ => return None,
+        };
+
+        if *held != 0 && (deflection.abs() < dpad.release || deflection.signum() as i32 != *held) {
+            *held = 0;
+        }
+        if *held == 0 && deflection.abs() >= dpad.press {
+            *held = deflection.signum() as i32;
+            self.dpad_next = Some(self.clock.now() + dpad.repeat_delay);
+            return Some(match axis {
+                Axis::LeftY => (deflection.signum() as i32, 0),
+                _ => (0, deflection.signum() as i32),
+            });
+        }
+
+        if self.dpad_held == (0, 0) {
+            self.dpad_next = None;
+        }
+        None
+    }
+    #[cfg(feature = "test-util")]
+    pub fn tab_index(&self) -> usize {
+        self.tab_pos.index()
+    }
+    #[cfg(feature = "test-util")]
+    pub fn focused_cell(&self) -> (usize, usize) {
+        self.item_pos
+    }
+    // (tab header, items) redraws requested by the last step() that weren't drawn yet
+    #[cfg(feature = "test-util")]
+    pub fn pending_redraws(&self) -> (bool, bool) {
+        (self.redraw_tabs, self.redraw_items)
+    }
+    #[cfg(feature = "test-util")]
+    pub fn push_event(&mut self, ev: HidEvent) {
+        self.queued.push_back(ev);
+    }
+    // Processes exactly one queued event without blocking. Events passed to push_event() come
+    // first, then whatever the input sources and renderer have ready. With nothing queued it
+    // runs the timers that are due by the Clock instead, and ticks the renderer when that
+    // doesn't make an event either. Redraws requested by the event stay pending until the
+    // next step() or get_ev().
+    #[cfg(feature = "test-util")]
+    pub fn step(&mut self) -> Option<GuiEvent> {
+        self.flush_redraws();
+
+        let ret = if let Some(ev) = self.queued.pop_front() {
+            self.tap_raw_hid(&ev);
+            self.log_event(LoggedEvent::Hid(ev.clone()));
+            self.process_event(Some(ev), None)
+        } else if let Some(Ok(ev)) = self.hid_rx.as_ref().map(|rx| rx.try_recv()) {
+            self.tap_raw_hid(&ev);
+            self.log_event(LoggedEvent::Hid(ev.clone()));
+            self.process_event(Some(ev), None)
+        } else if let Some(Ok(ev)) = self.renderer_rx.as_ref().map(|rx| rx.try_recv()) {
+            if let RendererEvent::Hid(hid) = &ev {
+                self.tap_raw_hid(hid);
+            }
+            self.log_event(LoggedEvent::Renderer(ev.clone()));
+            self.process_event(None, Some(ev))
+        } else if let Ok(out) = self.cmd_rx.try_recv() {
+            self.command_output(out)
+        } else {
+            self.handle_timers()
+        };
+        self.watch_brightness();
+        let ret = ret.and_then(|v| self.route_forms(v));
+        #[cfg(feature = "wifi")]
+        let ret = ret.and_then(|v| self.route_wifi(v));
+
+        self.note_origin(ret.as_ref());
+        match ret.as_ref() {
+            Some(ev) => self.log_event(LoggedEvent::Gui(ev.clone())),
+            None => self.renderer.tick(),
+        }
+        ret
+    }
+    // steps until nothing is queued, due or waiting to be drawn, returns what came out on the way
+    #[cfg(feature = "test-util")]
+    pub fn settle(&mut self) -> Vec<GuiEvent> {
+        let mut events = Vec::new();
+        for _ in 0..16 {
+            match self.step() {
+                Some(ev) => events.push(ev),
+                None if self.pending_redraws() == (false, false) && self.queued.is_empty()
+                    && self.next_timeout() != Some(Duration::ZERO) => break,
+                None => (),
+            }
+        }
+        events
+    }
+    // Tab and position of the item behind the last event returned, None if that event wasn't
+    // about an item. Noted down where the event is made, so items sharing an id are told apart.
+    pub fn event_origin(&self) -> Option<&ItemOrigin> {
+        self.last_origin.as_ref()
+    }
+    // keeps the origin of the event about to be returned, unless it points at an item the
+    // event isn't about
+    fn note_origin(&mut self, ev: Option<&GuiEvent>) {
+        let origin = self.origin.take();
+        let Some(ev) = ev else {return};
+        self.last_origin = event_item(ev).and_then(|id| origin.filter(|v| self.item_at(v).and_then(item_id) == Some(id)));
+    }
+    fn origin_at(&self, tab: usize, (row, column): (usize, usize), in_list: bool) -> ItemOrigin {
+        ItemOrigin {
+            tab,
+            tab_name: self.layout.tab(tab).map(|v| v.name().to_string()).unwrap_or_default(),
+            row,
+            column,
+            in_list,
+        }
+    }
+    fn focused_origin(&self) -> ItemOrigin {
+        let tab = self.tab_pos.index();
+        match self.pane {
+            Pane::List if self.layout.tab(tab).is_some_and(|v| v.list().is_some()) => self.origin_at(tab, (self.list_pos, 0), true),
+            _ => self.origin_at(tab, self.item_pos, false),
+        }
+    }
+    // origin of the `n`th item of Layout::all_items()
+    fn nth_origin(&self, mut n: usize) -> Option<ItemOrigin> {
+        for i in 0..self.layout.tab_count() {
+            let tab = self.layout.tab(i)?;
+            for (row, items) in tab.items().iter().enumerate() {
+                if n < items.len() {
+                    return Some(self.origin_at(i, (row, n), false));
+                }
+                n -= items.len();
+            }
+            let list = tab.list().map_or(0, Vec::len);
+            if n < list {
+                return Some(self.origin_at(i, (n, 0), true));
+            }
+            n -= list;
+            let footer = tab.footer().map_or(0, Vec::len);
+            if n < footer {
+                return Some(self.origin_at(i, (tab.footer_row(), n), false));
+            }
+            n -= footer;
+        }
+        None
+    }
+    fn item_at(&self, origin: &ItemOrigin) -> Option<&Item> {
+        let tab = self.layout.tab(origin.tab)?;
+        match origin.in_list {
+            true => tab.list()?.get(origin.row),
+            false => tab.item(origin.row, origin.column),
+        }
+    }
+    // Input is about the item focused when it comes in, unless handling it said otherwise,
+    // e.g. undo.
+    fn process_event(&mut self, hid_ev: Option<HidEvent>, r_ev: Option<RendererEvent>) -> Option<GuiEvent> {
+        let focused = self.focused_origin();
+        let outer = self.origin.take();
+        let ret = self.handle_input(hid_ev, r_ev);
+        self.origin = match ret.as_ref().and_then(event_item) {
+            Some(_) => self.origin.take().or(Some(focused)),
+            None => outer,
+        };
+        ret
+    }
+    fn handle_input(&mut self, mut hid_ev: Option<HidEvent>, r_ev: Option<RendererEvent>) -> Option<GuiEvent> {
+        let mut ret = None;
+
+        // handle events made by renderer
+        let mut tab_chg = 0;
+        let mut tab_jump = None;
+        let mut item_column_chg: i32 = 0;
+        let mut item_row_chg: i32 = 0;
+        let mut activate_selection = false;
+        let mut switch_pane = false;
+
+        if let Some(ev) = r_ev {
+            match ev {
+                RendererEvent::Refresh => {
+                    self.redraw_items = true;
+                    self.redraw_tabs = true;
+                },
+                RendererEvent::Resized => {
+                    self.redraw_items = true;
+                    self.redraw_tabs = true;
+                    ret = self.renderer.viewport_info().map(GuiEvent::Resized);
+                },
+                RendererEvent::WindowClosed => {
+                    ret = Some(GuiEvent::Quit);
+                },
+                // button hints follow the style of the new controller
+                RendererEvent::ControllersChanged => {
+                    self.apply_controller_style();
+                    self.redraw_items = true;
+                    self.redraw_tabs = true;
+                },
+                RendererEvent::Hid(ev) => {
+                    hid_ev = Some(ev);
+                }
+            }
+        }
+
+        // resting sticks don't count as input
+        let is_input = match hid_ev.as_ref() {
+            Some(HidEvent::Analog{axis, value}) => self.analog.normalized(*axis, *value) != 0.0,
+            other => other.is_some(),
+        };
+        if is_input {
+            self.last_input = self.clock.now();
+            let reported = std::mem::take(&mut self.idle_reported);
+            let woke = self.wake();
+            if reported {
+                return ret.or(Some(GuiEvent::Active));
+            }
+            if woke {
+                return ret;
+            }
+        }
+
+        // system buttons go first, nothing on screen gets to keep them
+        if hid_ev == Some(HidEvent::Home) {
+            return Some(GuiEvent::HomePressed);
+        }
+        if let (Some(ev), false) = (hid_ev.as_ref(), self.shortcuts.is_empty()) {
+            if let Some(name) = self.shortcuts.feed(ev, self.clock.now()) {
+                return Some(GuiEvent::Shortcut(name));
+            }
+        }
+
+        if let Some(hid_ev) = hid_ev {
+            if self.ignore_hid {
+                return Some(GuiEvent::IgnoredHid);
+            }
+            if self.popup.is_some() {
+                return self.popup_event(hid_ev).or(ret);
+            }
+            if self.osk.is_some() {
+                return self.osk_event(hid_ev).or(ret);
+            }
+            if self.editing.is_some() && !matches!(hid_ev, HidEvent::Analog{..}) {
+                return self.edit_event(hid_ev).or(ret);
+            }
+            if self.grab.is_some() {
+                return self.grab_event(hid_ev).or(ret);
+            }
+            // filters and bindings only see what would otherwise move around the tab
+            let hid_ev = match self.input_filter.as_mut().map(|filter| filter(&hid_ev)) {
+                None | Some(Filter::Pass) => hid_ev,
+                Some(Filter::Swallow) => return ret,
+                Some(Filter::Remap(other)) => other,
+                Some(Filter::Capture) => return Some(GuiEvent::HidCaptured(hid_ev)),
+            };
+            let hid_ev = match self.layout.tab(self.tab_pos.index()).and_then(|v| v.binding(&hid_ev)).cloned() {
+                None | Some(Filter::Pass) => hid_ev,
+                Some(Filter::Swallow) => return ret,
+                Some(Filter::Remap(other)) => other,
+                Some(Filter::Capture) => return Some(GuiEvent::HidCaptured(hid_ev)),
+            };
+            let hid_ev = match self.item_action(&hid_ev) {
+                Some((_, Some(ItemAction::Activate))) => HidEvent::ButtonPress,
+                Some((id, Some(ItemAction::Emit(name)))) => return Some(GuiEvent::ItemAction(id, name)),
+                Some((_, None)) => return ret,
+                None => hid_ev,
+            };
+            if let Some(row) = self.table_row_after(&hid_ev) {
+                self.select_table_row(row);
+                return ret;
+            }
+            match hid_ev {
+                HidEvent::NextTab => tab_chg = 1,
+                HidEvent::PreviousTab => tab_chg = -1,
+                HidEvent::GoToTab(n) => tab_jump = Some(n),
+                HidEvent::SwitchPane => switch_pane = true,
+                HidEvent::Up => item_row_chg = -1,
+                HidEvent::Down => item_row_chg = 1,
+                // far enough to hit the edge, moving clamps to the page
+                HidEvent::First => item_row_chg = -(u16::MAX as i32),
+                HidEvent::Last => item_row_chg = u16::MAX as i32,
+                HidEvent::Left | HidEvent::Right if self.pane != Pane::List && matches!(self.focused_item(), Some(Item::Slider(..) | Item::Stepper(..))) => {
+                    let steps = if hid_ev == HidEvent::Left { -1 } else { 1 };
+                    ret = self.adjust_focused(steps);
+                    // at either end the focus moves on, so other items on the row stay reachable
+                    if ret.is_none() {
+                        item_column_chg = steps;
+                    }
+                },
+                HidEvent::Left => item_column_chg = -1,
+                HidEvent::Right => item_column_chg = 1,
+                HidEvent::ButtonPress => activate_selection = true,
+                HidEvent::Grab | HidEvent::Action(ActionButton::North) => self.start_grab(),
+                HidEvent::Action(_) => (),
+                HidEvent::Undo => ret = self.apply_history(true),
+                HidEvent::Redo => ret = self.apply_history(false),
+                HidEvent::Back => {
+                    if self.focus_scopes.last().map(|(v, _)| v.dismissable).unwrap_or(false) {
+                        if let Some((scope, prev)) = self.focus_scopes.pop() {
+                            self.item_pos = prev;
+                            self.redraw_items = true;
+                            ret = Some(GuiEvent::FocusScopeDismissed(scope.id));
+                        }
+                    } else if self.focus_scopes.is_empty() {
+                        ret = self.pop_page().map(GuiEvent::PagePopped);
+                    }
+                },
+                HidEvent::Analog{axis: Axis::LeftX, value} if self.focused_adjustable() => {
+                    let was_centered = self.analog.normalized(Axis::LeftX, self.analog_x) == 0.0;
+                    self.analog_x = value;
+                    if was_centered && self.analog.normalized(Axis::LeftX, value) != 0.0 {
+                        ret = self.analog_step();
+                    }
+                },
+                HidEvent::Analog{axis, value} => {
+                    if let Some((row, column)) = self.dpad_from_axis(axis, value) {
+                        item_row_chg = row;
+                        item_column_chg = column;
+                    }
+                },
+                // remapped to by a filter or binding
+                HidEvent::Home => ret = Some(GuiEvent::HomePressed),
+                HidEvent::Quit => ret = Some(GuiEvent::Quit),
+            }
+        }
+
+        // a focus scope keeps everything on the current tab and pane
+        if !self.focus_scopes.is_empty() {
+            tab_chg = 0;
+            tab_jump = None;
+            switch_pane = false;
+        }
+        let prev_pos = self.item_pos;
+
+        // held directions move further the longer they're held
+        if item_row_chg != 0 || item_column_chg != 0 {
+            if let Some(accel) = self.accel.as_ref() {
+                let page = self.layout.tab(self.tab_pos.index()).and_then(|v| v.page_size());
+                let step = self.repeat.step((item_row_chg, item_column_chg), self.clock.now(), accel, page) as i32;
+                item_row_chg *= step;
+                item_column_chg *= step;
+            }
+        }
+
+        if activate_selection && self.bounced() {
+            activate_selection = false;
+        }
+        if let Some(list_len) = self.layout.tab(self.tab_pos.index()).and_then(|v| v.list()).map(|v| v.len()) {
+            // left/right at the pane edges hop between the list and the detail grid
+            let edge_hop = (self.pane == Pane::List && item_column_chg > 0) || (self.pane == Pane::Detail && item_column_chg < 0 && self.item_pos.1 == 0);
+            if edge_hop && self.focus_scopes.is_empty() {
+                switch_pane = true;
+                item_column_chg = 0;
+            }
+            if switch_pane {
+                self.pane = match self.pane {
+                    Pane::List => Pane::Detail,
+                    Pane::Detail => Pane::List,
+                };
+                self.redraw_items = true;
+                ret = Some(GuiEvent::PaneChanged(self.pane));
+            }
+
+            // the list pane takes over vertical movement and activation
+            if self.pane == Pane::List {
+                if item_row_chg != 0 && list_len > 0 {
+                    let new_pos = (self.list_pos as i32 + item_row_chg).clamp(0, list_len as i32 - 1) as usize;
+                    if new_pos != self.list_pos {
+                        self.list_pos = new_pos;
+                        self.redraw_items = true;
+                        ret = Some(GuiEvent::ListFocused(self.list_pos));
+                    }
+                }
+                if activate_selection && list_len > 0 {
+                    ret = Some(GuiEvent::ListActivated(self.list_pos));
+                }
+                item_row_chg = 0;
+                item_column_chg = 0;
+                activate_selection = false;
+            }
+        }
+
+        if activate_selection && self.toggle_row_selection() {
+            activate_selection = false;
+        }
+        if activate_selection {
+            ret = self.activate_focused().or(ret);
+        }
+
+        // paged tabs flip pages with the shoulder buttons before moving on to another tab
+        if tab_chg != 0 {
+            if let Some(curtab) = self.layout.tab(self.tab_pos.index()) {
+                let new_page = self.page as i32 + tab_chg;
+                if curtab.page_size().is_some() && new_page >= 0 && (new_page as usize) < curtab.page_count() {
+                    self.page = new_page as usize;
+                    let rows = curtab.page_rows(self.page);
+                    let row = rows.clone().find(|v| curtab.items()[*v].iter().any(Item::focusable)).unwrap_or(rows.start);
+                    let column = curtab.row(row).map(|v| focusable_column(v, 0)).unwrap_or(0);
+                    self.item_pos = (row, column);
+
+                    self.redraw_tabs = true;
+                    self.redraw_items = true;
+                    ret = Some(GuiEvent::PageChanged(self.page));
+                    tab_chg = 0;
+                }
+            }
+        }
+
+        // change tab if we need to, and refresh everything if we changed a tab
+        if tab_chg != 0 {
+            self.remember_scroll();
+            self.tab_pos = match tab_chg {
+                1 => self.layout.next_tab(self.tab_pos),
+                _ => self.layout.prev_tab(self.tab_pos),
             };
+            self.reset_tab_focus();
+
+            self.redraw_tabs = true;
+            self.redraw_items = true;
+            ret = self.tab_changed();
+        }
+
+        if let Some(n) = tab_jump {
+            self.remember_scroll();
+            self.tab_pos = self.layout.clamp_tab(n);
+            self.reset_tab_focus();
+
+            self.redraw_tabs = true;
+            self.redraw_items = true;
+            ret = self.tab_changed();
         }
-    });
 
-    Some(rx)
+        if item_row_chg != 0 {
+            if let Some(curtab) = self.layout.tab(self.tab_pos.index()) {
+                let (cur_row, cur_column) = self.item_pos;
+                
+                let rows = curtab.page_rows(self.page);
+                let max_row = (rows.end as i32 - 1).clamp(rows.start as i32, 10000);
+                let new_cur_row = (cur_row as i32 + item_row_chg).clamp(rows.start as i32, max_row) as usize;
+                // rows with nothing to focus, empty or just separators and headers, are stepped over
+                let new_cur_row = match item_row_chg > 0 {
+                    true => (new_cur_row..rows.end).find(|v| curtab.items()[*v].iter().any(Item::focusable)),
+                    false => (rows.start..new_cur_row + 1).rev().find(|v| curtab.items()[*v].iter().any(Item::focusable)),
+                }.unwrap_or(usize::MAX);
+                let last_row = rows.clone().rev().find(|v| curtab.items()[*v].iter().any(Item::focusable));
+                let on_footer = curtab.footer_focusable() && cur_row == curtab.footer_row();
+                let footer_len = curtab.footer().map(|v| v.len()).unwrap_or(0);
+
+                if on_footer {
+                    // only way out of the footer is up, into the last row of the page
+                    if let (true, Some(last_row)) = (item_row_chg < 0, last_row) {
+                        let row = &curtab.items()[last_row];
+                        self.item_pos = (last_row, focusable_column(row, cur_column.min(row.len().saturating_sub(1))));
+                        self.redraw_items = true;
+                    }
+                } else if curtab.footer_focusable() && footer_len > 0 && item_row_chg > 0
+                    && (cur_row as i32 + item_row_chg > max_row || last_row.is_none_or(|v| cur_row >= v)) {
+                    self.item_pos = (curtab.footer_row(), cur_column.min(footer_len - 1));
+                    self.redraw_items = true;
+                } else if let Some(row) = curtab.items().get(new_cur_row) {
+                    // we have to check because we're moving selection to another row
+                    if let Some(_item) = row.get(cur_column) {
+                        self.item_pos = (new_cur_row, focusable_column(row, cur_column));
+                        self.redraw_items = true;
+                    }
+                }
+            }
+        }
+
+        if item_column_chg != 0 {
+            if let Some(curtab) = self.layout.tab(self.tab_pos.index()) {
+                let (cur_row, cur_column) = self.item_pos;
+                let max_column;
+                let new_cur_column;
+
+                if let Some(row) = curtab.row(cur_row) {
+                    max_column = (row.len() as i32 - 1).clamp(0, 10000);
+                    let wanted = (cur_column as i32 + item_column_chg).clamp(0, max_column) as usize;
+                    // separators and headers in between are jumped over, at the edge focus stays put
+                    new_cur_column = match item_column_chg > 0 {
+                        true => (wanted..row.len()).find(|v| row[*v].focusable()),
+                        false => (0..wanted + 1).rev().find(|v| row[*v].focusable()),
+                    }.unwrap_or(cur_column);
+                } else {
+                    new_cur_column = 0;
+                }
+
+                self.item_pos = (cur_row, new_cur_column);
+                self.redraw_items = true;
+            }
+        }
+
+        if let Some((scope, _)) = self.focus_scopes.last() {
+            if !scope.contains(self.item_pos) {
+                let pos = scope.clamp(self.item_pos);
+                let exists = self.layout.tab(self.tab_pos.index()).and_then(|v| v.item(pos.0, pos.1)).is_some();
+                self.item_pos = if exists { pos } else { prev_pos };
+            }
+        }
+
+        ret
+    }
+    // while a popup is open it gets all the input
+    fn popup_event(&mut self, hid_ev: HidEvent) -> Option<GuiEvent> {
+        let popup = self.popup.as_mut()?;
+        match hid_ev {
+            HidEvent::Up => {
+                popup.selected = popup.selected.saturating_sub(1);
+                self.redraw_items = true;
+            },
+            HidEvent::Down => {
+                popup.selected = (popup.selected + 1).min(popup.options.len() - 1);
+                self.redraw_items = true;
+            },
+            HidEvent::ButtonPress => {
+                let popup = self.popup.take()?;
+                self.redraw_tabs = true;
+                self.redraw_items = true;
+                let (row, col) = popup.origin;
+                let tab = self.layout.tab_mut(self.tab_pos.index())?;
+                let before = tab.item(row, col)?.clone();
+                if let Some(Item::Selector(_, _, selected, _, id)) = tab.item_mut(row, col) {
+                    *selected = popup.selected;
+                    let ret = Some(GuiEvent::SelectorChanged(*id, *selected));
+                    self.record_item((row, col), before, false);
+                    return ret;
+                }
+            },
+            HidEvent::Back => {
+                self.popup = None;
+                self.redraw_tabs = true;
+                self.redraw_items = true;
+            },
+            HidEvent::Quit => return Some(GuiEvent::Quit),
+            _ => (),
+        }
+        None
+    }
+    fn osk_event(&mut self, hid_ev: HidEvent) -> Option<GuiEvent> {
+        if hid_ev == HidEvent::Quit {
+            return Some(GuiEvent::Quit);
+        }
+        // hold to reveal: every press shows a masked text for a while, so a held key's
+        // auto-repeat keeps it shown until it's let go
+        if hid_ev == HidEvent::Action(ActionButton::North) && self.osk.as_ref()?.masked {
+            self.osk.as_mut()?.reveal(true);
+            self.reveal_next = Some(self.clock.now() + REVEAL_TIME);
+            self.redraw_items = true;
+            return None;
+        }
+        let outcome = self.osk.as_mut()?.handle(&hid_ev);
+        self.redraw_items = true;
+        if outcome == osk::Outcome::Typing {
+            return None;
+        }
+        let osk = self.osk.take()?;
+        self.redraw_tabs = true;
+        let osk::Outcome::Done(new) = outcome else {return None};
+        let (row, col) = osk.origin;
+        let tab = self.layout.tab_mut(self.tab_pos.index())?;
+        let before = tab.item(row, col)?.clone();
+        let Some(Item::TextInput(_, text, _, id)) = tab.item_mut(row, col) else {return None};
+        if *text == new {
+            return None;
+        }
+        *text = new;
+        let ret = Some(GuiEvent::TextChanged(*id, text.clone()));
+        self.record_item((row, col), before, false);
+        ret
+    }
+    // while an item is being edited the d-pad changes its value instead of moving focus
+    fn edit_event(&mut self, hid_ev: HidEvent) -> Option<GuiEvent> {
+        let (row, col) = self.editing.as_ref()?.origin;
+        let segments = match self.layout.tab(self.tab_pos.index())?.item(row, col)? {
+            Item::Date(..) => 3,
+            Item::Time(..) => 2,
+            _ => 0,
+        };
+        let steps = match hid_ev {
+            // left/right pick the segment of dates and times
+            HidEvent::Left | HidEvent::Right if segments > 0 => {
+                let editing = self.editing.as_mut()?;
+                editing.segment = match hid_ev {
+                    HidEvent::Left => editing.segment.saturating_sub(1),
+                    _ => (editing.segment + 1).min(segments - 1),
+                };
+                self.renderer.set_edit_segment(editing.segment);
+                self.redraw_items = true;
+                return None;
+            },
+            HidEvent::Up | HidEvent::Right => 1,
+            HidEvent::Down | HidEvent::Left => -1,
+            HidEvent::ButtonPress => {
+                let editing = self.finish_edit()?;
+                let item = self.layout.tab(self.tab_pos.index())?.item(row, col)?;
+                let ret = (*item != editing.original).then(|| change_event(item)).flatten();
+                self.record_item((row, col), editing.original, false);
+                return ret;
+            },
+            HidEvent::Back => {
+                let editing = self.finish_edit()?;
+                let item = self.layout.tab_mut(self.tab_pos.index())?.item_mut(row, col)?;
+                *item = editing.original;
+                return None;
+            },
+            HidEvent::Quit => return Some(GuiEvent::Quit),
+            _ => return None,
+        };
+
+        let segment = self.editing.as_ref()?.segment;
+        let item = self.layout.tab_mut(self.tab_pos.index())?.item_mut(row, col)?;
+        let changed = match item {
+            Item::Date(_, date, _) => {
+                date.adjust(segment, steps);
+                true
+            },
+            Item::Time(_, time, _) => {
+                time.adjust(segment, steps);
+                true
+            },
+            _ => adjust_item(item, steps).is_some(),
+        };
+        if changed {
+            self.redraw_items = true;
+        }
+        None
+    }
+    fn finish_edit(&mut self) -> Option<Editing> {
+        let editing = self.editing.take()?;
+        self.renderer.set_edit_mode(false);
+        self.redraw_items = true;
+        Some(editing)
+    }
+    // Reverts the last recorded change (toggles, values, radio picks, moved rows) and returns the event
+    // describing the value it went back to, so the app can follow along.
+    pub fn undo(&mut self) -> Option<GuiEvent> {
+        let ret = self.apply_history(true);
+        self.flush_redraws();
+        ret
+    }
+    pub fn redo(&mut self) -> Option<GuiEvent> {
+        let ret = self.apply_history(false);
+        self.flush_redraws();
+        ret
+    }
+    // records the change of the item at `pos` on the current tab, if there was one
+    fn record_item(&mut self, pos: (usize, usize), before: Item, merge: bool) {
+        let tab = self.tab_pos.index();
+        let Some(after) = self.layout.tab(tab).and_then(|v| v.item(pos.0, pos.1)) else {return};
+        if *after != before {
+            let (before, after) = (Box::new(before), Box::new(after.clone()));
+            self.history.record(Change::Item { tab, pos, before, after }, merge);
+        }
+    }
+    fn apply_history(&mut self, undo: bool) -> Option<GuiEvent> {
+        if self.popup.is_some() || self.editing.is_some() || self.grab.is_some() {
+            return None;
+        }
+        let change = if undo { self.history.undo()? } else { self.history.redo()? };
+        let current = self.tab_pos.index();
+
+        match change {
+            Change::Item { tab, pos, before, after } => {
+                let item = self.layout.tab_mut(tab)?.item_mut(pos.0, pos.1)?;
+                *item = *if undo { before } else { after };
+                let ret = change_event(item);
+                self.redraw_items |= tab == current;
+                self.origin = Some(self.origin_at(tab, pos, false));
+                ret
+            },
+            Change::Moved { tab, pane, id, from, to } => {
+                let (from, to) = if undo { (to, from) } else { (from, to) };
+                let t = self.layout.tab_mut(tab)?;
+                match pane {
+                    Pane::List => {
+                        let list = t.list_mut().filter(|v| from.max(to) < v.len())?;
+                        let entry = list.remove(from);
+                        list.insert(to, entry);
+                    },
+                    Pane::Detail => {
+                        let rows = Some(t.items_mut()).filter(|v| from.max(to) < v.len())?;
+                        let row = rows.remove(from);
+                        rows.insert(to, row);
+                    },
+                }
+                self.redraw_items |= tab == current;
+                self.origin = Some(match pane {
+                    Pane::List => self.origin_at(tab, (to, 0), true),
+                    Pane::Detail => {
+                        let column = self.layout.tab(tab)?.items()[to].iter().position(|v| item_id(v) == Some(id)).unwrap_or(0);
+                        self.origin_at(tab, (to, column), false)
+                    },
+                });
+                Some(GuiEvent::ItemMoved(id, from, to))
+            },
+            Change::Radio { tab, id, before, after } => {
+                let index = if undo { before } else { Some(after) };
+                self.set_radio(tab, id, index)?;
+                let index = index?;
+                let t = self.layout.tab(tab)?;
+                self.origin = (0..=t.footer_row()).find_map(|row| {
+                    let column = t.row(row)?.iter().position(|v| matches!(v, Item::Radio(_, i, _, group) if *group == id && *i == index))?;
+                    Some(self.origin_at(tab, (row, column), false))
+                });
+                Some(GuiEvent::RadioChanged(id, index))
+            },
+        }
+    }
+    // starts the command of the Item::Command at `pos` on the current tab
+    fn run_command(&mut self, (row, col): (usize, usize)) -> Option<GuiEvent> {
+        let Some(Item::Command(_, spec, _, id)) = self.layout.tab(self.tab_pos.index())?.item(row, col) else {return None};
+        let (spec, id) = (spec.clone(), *id);
+
+        if let Some(log) = spec.log {
+            if let Some(Item::Log(lines, _)) = self.layout.all_items_mut().find(|v| item_id(v) == Some(log)) {
+                lines.push(format!("$ {}", spec.command_line()));
+            }
+        }
+        self.redraw_items = true;
+        let origin = self.origin_at(self.tab_pos.index(), (row, col), false);
+        self.command_origins.push((id, origin.clone()));
+
+        match command::spawn(id, &spec, self.cmd_tx.clone()) {
+            Ok(()) => {
+                if let Some(Item::Command(_, _, running, _)) = self.layout.tab_mut(self.tab_pos.index())?.item_mut(row, col) {
+                    *running = true;
+                }
+                self.origin = Some(origin);
+                Some(GuiEvent::CommandStarted(id))
+            },
+            Err(e) => {
+                // shows up in the log like any other output, followed by the exit
+                let _ = self.cmd_tx.send(CommandOutput::Line(id, format!("Failed to start: {}", e)));
+                let _ = self.cmd_tx.send(CommandOutput::Exited(id, None));
+                None
+            },
+        }
+    }
+    fn command_output(&mut self, out: CommandOutput) -> Option<GuiEvent> {
+        // logs only keep this many lines
+        const LOG_LIMIT: usize = 500;
+
+        #[cfg(feature = "wifi")]
+        if let Some(ret) = self.wifi_output(&out) {
+            return ret;
+        }
+
+        let (id, line, ret) = match out {
+            // taken care of by wifi_output()
+            #[cfg(feature = "wifi")]
+            CommandOutput::Wifi(_) => return None,
+            CommandOutput::Line(id, line) => (id, line, None),
+            CommandOutput::Exited(id, code) => {
+                if let Some(Item::Command(_, _, running, _)) = self.layout.all_items_mut().find(|v| item_id(v) == Some(id)) {
+                    *running = false;
+                }
+                if let Some(i) = self.command_origins.iter().position(|(v, _)| *v == id) {
+                    self.origin = Some(self.command_origins.remove(i).1);
+                }
+                let line = match code {
+                    Some(code) => format!("exited with code {}", code),
+                    None => "killed".to_string(),
+                };
+                (id, line, Some(GuiEvent::CommandFinished(id, code)))
+            },
+        };
+
+        let log = self.layout.all_items_mut()
+            .find_map(|v| match v {
+                Item::Command(_, spec, _, cmd_id) if *cmd_id == id => spec.log,
+                _ => None,
+            });
+        if let Some(Item::Log(lines, _)) = log.and_then(|log| self.layout.all_items_mut().find(|v| item_id(v) == Some(log))) {
+            lines.push(line);
+            if lines.len() > LOG_LIMIT {
+                lines.drain(..lines.len() - LOG_LIMIT);
+            }
+        }
+        // a chatty command would otherwise redraw once per line, exits show right away
+        if ret.is_some() {
+            self.log_next = None;
+            self.redraw_items = true;
+        } else {
+            self.log_next.get_or_insert(self.clock.now() + LOG_FRAME);
+        }
+        ret
+    }
+    fn start_grab(&mut self) {
+        let Some(tab) = self.layout.tab(self.tab_pos.index()) else {return};
+        if !tab.reorderable() || !self.focus_scopes.is_empty() {
+            return;
+        }
+        let (origin, item) = match self.pane {
+            Pane::List if tab.list().is_some() => (self.list_pos, tab.list().and_then(|v| v.get(self.list_pos))),
+            _ => (self.item_pos.0, tab.items().get(self.item_pos.0).and_then(|v| v.get(self.item_pos.1))),
+        };
+        if let Some(id) = item.and_then(item_id) {
+            self.grab = Some(Grab { id, origin, current: origin, pane: self.pane });
+            self.renderer.set_edit_mode(true);
+            self.redraw_items = true;
+        }
+    }
+    // a grabbed row moves with up/down, Grab or the action button drops it and Back puts it back
+    fn grab_event(&mut self, hid_ev: HidEvent) -> Option<GuiEvent> {
+        let step = match hid_ev {
+            HidEvent::Up => -1,
+            HidEvent::Down => 1,
+            HidEvent::Analog{axis, value} => self.dpad_from_axis(axis, value).map(|v| v.0).unwrap_or(0),
+            HidEvent::Grab | HidEvent::ButtonPress => {
+                let grab = self.grab.take()?;
+                self.renderer.set_edit_mode(false);
+                self.redraw_items = true;
+                if grab.origin == grab.current {
+                    return None;
+                }
+                self.history.record(Change::Moved {
+                    tab: self.tab_pos.index(),
+                    pane: grab.pane,
+                    id: grab.id,
+                    from: grab.origin,
+                    to: grab.current,
+                }, false);
+                return Some(GuiEvent::ItemMoved(grab.id, grab.origin, grab.current));
+            },
+            HidEvent::Back => {
+                let grab = self.grab.as_ref()?;
+                let back = grab.origin as i32 - grab.current as i32;
+                self.move_grabbed(back);
+                self.grab = None;
+                self.renderer.set_edit_mode(false);
+                // the highlight has to go even if the row never moved
+                self.redraw_items = true;
+                return None;
+            },
+            HidEvent::Quit => return Some(GuiEvent::Quit),
+            _ => 0,
+        };
+        if step != 0 {
+            self.move_grabbed(step);
+        }
+        None
+    }
+    fn move_grabbed(&mut self, step: i32) {
+        let Some(grab) = self.grab.as_mut() else {return};
+        let Some(tab) = self.layout.tab_mut(self.tab_pos.index()) else {return};
+        let len = match grab.pane {
+            Pane::List => tab.list().map(|v| v.len()).unwrap_or(0),
+            Pane::Detail => tab.items().len(),
+        };
+        let new = (grab.current as i32 + step).clamp(0, len as i32 - 1) as usize;
+        if len == 0 || new == grab.current {
+            return;
+        }
+
+        // shift everything in between instead of swapping so the order stays intact
+        match grab.pane {
+            Pane::List => {
+                let list = tab.list_mut().expect("Grabbed list entry without a list");
+                let entry = list.remove(grab.current);
+                list.insert(new, entry);
+                self.list_pos = new;
+            },
+            Pane::Detail => {
+                let row = tab.items_mut().remove(grab.current);
+                tab.items_mut().insert(new, row);
+                self.item_pos.0 = new;
+                if let Some(size) = tab.page_size() {
+                    self.page = new / size;
+                    self.redraw_tabs = true;
+                }
+            },
+        }
+        grab.current = new;
+        self.redraw_items = true;
+    }
+    // Traps focus inside `scope` on the current tab until it's popped. Tabs, pages and panes
+    // can't be switched while a scope is active.
+    pub fn push_focus_scope(&mut self, scope: FocusScope) {
+        let prev = self.item_pos;
+        self.item_pos = scope.clamp(self.item_pos);
+        self.pane = Pane::Detail;
+        self.focus_scopes.push((scope, prev));
+        self.redraw_items = true;
+        self.flush_redraws();
+    }
+    pub fn pop_focus_scope(&mut self) -> Option<FocusScope> {
+        let (scope, prev) = self.focus_scopes.pop()?;
+        self.item_pos = prev;
+        self.redraw_items = true;
+        self.flush_redraws();
+        Some(scope)
+    }
+    // Shows `items` on the current tab in place of its own, e.g. a submenu. Back (or
+    // pop_page()) brings the previous items back, the header shows the trail of pages.
+    pub fn push_page(&mut self, name: &str, items: Vec<Vec<Item>>) {
+        let tab = self.tab_pos.index();
+        let Some(covered) = self.layout.tab(tab).map(|v| v.items().clone()) else {return};
+        self.nav_pages.push(NavPage {
+            tab,
+            name: name.to_string(),
+            items: covered,
+            pos: self.item_pos,
+            page: self.page,
+        });
+        self.item_pos = (0, 0);
+        self.page = 0;
+        self.redraw_tabs = true;
+        self.set_tab_items(tab, items);
+    }
+    // leaves the innermost page of the current tab, returns its name
+    pub fn pop_page(&mut self) -> Option<String> {
+        let tab = self.tab_pos.index();
+        let idx = self.nav_pages.iter().rposition(|v| v.tab == tab)?;
+        let nav = self.nav_pages.remove(idx);
+        self.item_pos = nav.pos;
+        self.page = nav.page;
+        self.redraw_tabs = true;
+        self.set_tab_items(tab, nav.items);
+        Some(nav.name)
+    }
+    // rows picked on a multi-select tab, see TabBuilder::multi_select()
+    pub fn selection(&self, tab: usize) -> Vec<u128> {
+        self.multi_selected.iter()
+            .filter(|(v, _)| *v == tab)
+            .map(|(_, id)| *id)
+            .collect()
+    }
+    pub fn clear_selection(&mut self, tab: usize) {
+        self.multi_selected.retain(|(v, _)| *v != tab);
+        self.redraw_tabs = true;
+        self.redraw_items = true;
+        self.flush_redraws();
+    }
+    // adds the focused row to the selection or drops it, returns false if this isn't a
+    // row of a multi-select tab
+    fn toggle_row_selection(&mut self) -> bool {
+        let tab = self.tab_pos.index();
+        let Some(t) = self.layout.tab(tab).filter(|v| v.multi_select().is_some()) else {return false};
+        let Some(id) = t.items().get(self.item_pos.0).and_then(|row| row.iter().find_map(item_id)) else {return false};
+        if self.pane != Pane::Detail {
+            return false;
+        }
+        match self.multi_selected.iter().position(|v| *v == (tab, id)) {
+            Some(idx) => {
+                self.multi_selected.remove(idx);
+            },
+            None => self.multi_selected.push((tab, id)),
+        }
+        self.redraw_tabs = true;
+        self.redraw_items = true;
+        true
+    }
+    // first item of the first row with something in it, of the current tab
+    fn top_left(&self) -> (usize, usize) {
+        let tab = self.layout.tab(self.tab_pos.index());
+        let row = tab.and_then(|t| t.page_rows(0).find(|v| t.items()[*v].iter().any(Item::focusable)));
+        let column = tab.zip(row).map(|(t, row)| focusable_column(&t.items()[row], 0)).unwrap_or(0);
+        (row.unwrap_or(0), column)
+    }
+    // named after the tab that's shown now
+    fn tab_changed(&self) -> Option<GuiEvent> {
+        self.layout.tab(self.tab_pos.index()).map(|v| GuiEvent::TabChanged(v.name().to_string()))
+    }
+    // focus goes back to the top left, or to the list for split-pane tabs
+    fn reset_tab_focus(&mut self) {
+        // unconfirmed edits are dropped
+        if let Some(editing) = self.finish_edit() {
+            let (row, col) = editing.origin;
+            if let Some(item) = self.layout.tab_mut(self.tab_pos.index()).and_then(|v| v.item_mut(row, col)) {
+                *item = editing.original;
+            }
+        }
+        self.popup = None;
+        self.item_pos = self.top_left();
+        self.page = 0;
+        self.list_pos = 0;
+        self.pane = match self.layout.tab(self.tab_pos.index()).and_then(|v| v.list()) {
+            Some(_) => Pane::List,
+            None => Pane::Detail,
+        };
+        self.resume_tab_scroll();
+    }
+    // changes what an Item::Value shows, on every tab, e.g. set_value(id, "10.0.0.2")
+    pub fn set_value(&mut self, id: u128, value: impl Into<format::Value>) {
+        let value = value.into();
+        for item in self.layout.all_items_mut() {
+            if let Item::Value(_, current, item_id) = item {
+                if *item_id == id && *current != value {
+                    *current = value.clone();
+                    self.redraw_items = true;
+                }
+            }
+        }
+        self.refresh_since();
+        self.flush_redraws();
+    }
+    // moves an Item::ProgressBar, on every tab, `value` goes from 0.0 to 1.0
+    pub fn set_progress(&mut self, id: u128, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        for item in self.layout.all_items_mut() {
+            if let Item::ProgressBar(_, current, item_id) = item {
+                if *item_id == id && *current != value {
+                    *current = value;
+                    self.redraw_items = true;
+                }
+            }
+        }
+        self.flush_redraws();
+    }
+    // picks an option of an Item::Selector, on every tab, out of range picks the last one
+    pub fn set_selector(&mut self, id: u128, selected: usize) {
+        self.update_selector(id, None, selected);
+    }
+    // new options for an Item::Selector, on every tab, e.g. devices that came and went. An
+    // open popup of the selector is closed, its options would be out of date.
+    pub fn set_selector_options(&mut self, id: u128, options: &[&str], selected: usize) {
+        self.update_selector(id, Some(options.iter().map(|v| v.to_string()).collect()), selected);
+    }
+    fn update_selector(&mut self, id: u128, new_options: Option<Vec<String>>, new_selected: usize) {
+        for item in self.layout.all_items_mut() {
+            if let Item::Selector(_, options, selected, _, item_id) = item {
+                if *item_id != id {
+                    continue;
+                }
+                if let Some(new_options) = new_options.as_ref() {
+                    *options = new_options.clone();
+                }
+                *selected = new_selected.min(options.len().saturating_sub(1));
+                self.redraw_items = true;
+            }
+        }
+        let popup_of_it = self.popup.as_ref()
+            .and_then(|v| self.layout.tab(self.tab_pos.index())?.item(v.origin.0, v.origin.1))
+            .is_some_and(|v| matches!(v, Item::Selector(.., item_id) if *item_id == id));
+        if popup_of_it && new_options.is_some() {
+            self.popup = None;
+            self.redraw_tabs = true;
+        }
+        self.flush_redraws();
+    }
+    // starts or stops an Item::Spinner, on every tab
+    pub fn set_spinner_visible(&mut self, id: u128, visible: bool) {
+        for item in self.layout.all_items_mut() {
+            if let Item::Spinner(_, _, spinning, item_id) = item {
+                if *item_id == id && *spinning != visible {
+                    *spinning = visible;
+                    self.redraw_items = true;
+                }
+            }
+        }
+        if self.spinner_next.is_none() {
+            self.refresh_spinners();
+        }
+        self.flush_redraws();
+    }
+    // appends to an Item::Sparkline, on every tab, dropping the oldest sample once it's full
+    pub fn push_sample(&mut self, id: u128, value: f32) {
+        for item in self.layout.all_items_mut() {
+            if let Item::Sparkline(samples, item_id) = item {
+                if *item_id == id {
+                    if samples.len() >= layout::SPARKLINE_SAMPLES {
+                        samples.remove(0);
+                    }
+                    samples.push(value);
+                    self.redraw_items = true;
+                }
+            }
+        }
+        self.flush_redraws();
+    }
+    // new reading for an Item::Gauge, on every tab
+    pub fn set_gauge(&mut self, id: u128, current: f32) {
+        for item in self.layout.all_items_mut() {
+            if let Item::Gauge(_, reading, _, item_id) = item {
+                if *item_id == id && *reading != current {
+                    *reading = current;
+                    self.redraw_items = true;
+                }
+            }
+        }
+        self.flush_redraws();
+    }
+    // replaces the item grid of a tab, e.g. the detail pane after ListFocused
+    pub fn set_tab_items(&mut self, tab: usize, items: Vec<Vec<Item>>) {
+        // anything open on the old grid would write into whatever takes its place
+        if tab == self.tab_pos.index() {
+            self.finish_edit();
+            if self.grab.take().is_some() {
+                self.renderer.set_edit_mode(false);
+            }
+            self.popup = None;
+            self.osk = None;
+            self.redraw_tabs = true;
+        }
+        // countdowns staying on are counted down to now before the new ones start
+        self.refresh_countdowns();
+        let Some(t) = self.layout.tab_mut(tab) else {return};
+        *t.items_mut() = items;
+        // recorded positions don't mean anything in the new grid
+        self.history.clear();
+        let rows: Vec<u128> = t.items().iter().filter_map(|row| row.iter().find_map(item_id)).collect();
+        self.multi_selected.retain(|(v, id)| *v != tab || rows.contains(id));
+        // new battery items shouldn't wait for the next refresh
+        self.refresh_battery();
+        self.refresh_since();
+        self.refresh_clocks();
+        self.refresh_spinners();
+        self.refresh_countdowns();
+
+        if tab == self.tab_pos.index() {
+            let (row, col) = self.item_pos;
+            if self.layout.tab(tab).and_then(|t| t.item(row, col)).filter(|v| v.focusable()).is_none() {
+                self.item_pos = self.top_left();
+                self.page = 0;
+            }
+            self.redraw_items = true;
+            self.flush_redraws();
+        }
+    }
+    // Switches to `layout` with as little disruption as Layout::diff() allows, for apps that
+    // build their layout from data again after every change. Focus stays where it was when
+    // the item is still there.
+    pub fn apply_layout(&mut self, layout: layout::Layout) {
+        self.refresh_countdowns();
+        let current = self.tab_pos.index();
+        for change in self.layout.diff(&layout) {
+            match change {
+                layout::LayoutChange::Replace => {
+                    self.finish_edit();
+                    if self.grab.take().is_some() {
+                        self.renderer.set_edit_mode(false);
+                    }
+                    self.popup = None;
+                    self.osk = None;
+                    self.focus_scopes.clear();
+                    self.nav_pages.clear();
+                    self.history.clear();
+                    self.multi_selected.retain(|(tab, _)| *tab < layout.tab_count());
+                    self.tab_scroll.clear();
+                    self.tab_pos = layout.clamp_tab(current);
+                    self.layout = layout;
+                    self.reset_tab_focus();
+                    self.redraw_tabs = true;
+                    self.redraw_items = true;
+                    break;
+                },
+                layout::LayoutChange::Item(tab, row, column, item) => {
+                    // whatever was open on the old item goes away with it
+                    if tab == current {
+                        if self.editing.as_ref().is_some_and(|v| v.origin == (row, column)) {
+                            self.finish_edit();
+                        }
+                        if self.popup.as_ref().is_some_and(|v| v.origin == (row, column)) {
+                            self.popup = None;
+                        }
+                        if self.osk.as_ref().is_some_and(|v| v.origin == (row, column)) {
+                            self.osk = None;
+                        }
+                        self.redraw_items = true;
+                    }
+                    if let Some(v) = self.layout.tab_mut(tab).and_then(|t| t.items_mut().get_mut(row)).and_then(|r| r.get_mut(column)) {
+                        *v = item;
+                    }
+                },
+                layout::LayoutChange::Grid(tab, items) => self.set_tab_items(tab, items),
+                layout::LayoutChange::List(tab, list) => {
+                    let Some(v) = self.layout.tab_mut(tab).and_then(|t| t.list_mut()) else {continue};
+                    *v = list;
+                    if tab == current {
+                        self.list_pos = self.list_pos.min(v.len().saturating_sub(1));
+                        self.redraw_items = true;
+                    }
+                },
+                layout::LayoutChange::Footer(tab, footer) => {
+                    let Some(t) = self.layout.tab_mut(tab) else {continue};
+                    let footer_row = t.footer_row();
+                    let Some(v) = t.footer_mut() else {continue};
+                    *v = footer;
+                    if tab == current {
+                        if self.item_pos.0 == footer_row && self.item_pos.1 >= v.len() {
+                            self.item_pos = self.top_left();
+                            self.page = 0;
+                        }
+                        self.redraw_items = true;
+                    }
+                },
+            }
+        }
+        self.refresh_battery();
+        self.refresh_since();
+        self.refresh_clocks();
+        self.refresh_spinners();
+        self.refresh_countdowns();
+        self.flush_redraws();
+    }
+    fn flush_redraws(&mut self) {
+        // focus only ever moves along with a redraw
+        if self.redraw_items {
+            self.report_focus();
+        }
+        if self.frozen || (!self.redraw_tabs && !self.redraw_items) {
+            return;
+        }
+
+        let mut damage = Vec::new();
+        if self.redraw_tabs {
+            damage.push(frame::Damage::Header);
+        }
+        if self.redraw_items {
+            damage.extend([frame::Damage::Items, frame::Damage::Overlays]);
+        }
+
+        let mut overlays = Vec::new();
+        if let (Some(state), Some(IdleMode::Blank(logo))) = (self.idle_state.as_ref(), self.idle.as_ref().map(|v| &v.mode)) {
+            overlays.push(frame::Overlay::Blank { logo, position: idle::logo_position(state.logo_step) });
+        }
+        let focused = self.layout.tab(self.tab_pos.index()).and_then(|v| v.item(self.item_pos.0, self.item_pos.1));
+        match (focused, self.pane) {
+            (Some(Item::QrCode(data)), Pane::Detail) => overlays.push(frame::Overlay::QrCode { data }),
+            (Some(Item::Table(_, headers, rows, selected, _)), Pane::Detail) => {
+                overlays.push(frame::Overlay::Table { headers, rows, selected: *selected });
+            },
+            (Some(Item::Log(lines, _)), Pane::Detail) => overlays.push(frame::Overlay::Log { lines }),
+            _ => (),
+        }
+        let readout = self.editing.as_ref().and_then(|editing| {
+            let (row, col) = editing.origin;
+            let item = self.layout.tab(self.tab_pos.index())?.item(row, col)?;
+            readout(item, editing.segment)
+        });
+        if let Some(text) = readout.as_ref() {
+            overlays.push(frame::Overlay::Readout { text });
+        }
+        if let Some(popup) = self.popup.as_ref() {
+            overlays.push(frame::Overlay::Popup { options: &popup.options, selected: popup.selected });
+        }
+        if let Some(osk) = self.osk.as_ref() {
+            overlays.push(frame::Overlay::Keyboard { text: &osk.shown, keys: &osk.labels, selected: osk.selected });
+        }
+
+        let curtab = self.layout.tab(self.tab_pos.index());
+        let rows = curtab.map(|v| v.page_rows(self.page)).unwrap_or(0..0);
+        let marked = curtab.filter(|v| v.multi_select().is_some()).map(|tab| {
+            let picked = self.selection(self.tab_pos.index());
+            tab.items()[rows.clone()].iter()
+                .map(|row| row.iter().find_map(item_id).is_some_and(|id| picked.contains(&id)))
+                .collect()
+        });
+        let selected = (self.item_pos.0.saturating_sub(rows.start), self.item_pos.1);
+        let list = curtab.and_then(|v| v.list());
+        let frame = frame::Frame {
+            tab: self.tab_pos.index(),
+            tab_names: self.layout.tab_names().into_iter().skip(self.tab_pos.index()).collect(),
+            page: curtab.filter(|v| v.page_size().is_some()).map(|v| (self.page, v.page_count())),
+            items: curtab.map(|v| &v.items()[rows]).unwrap_or(&[]),
+            focus: (list.is_none() || self.pane == Pane::Detail).then_some(selected),
+            list: list.map(|v| (v.as_slice(), self.list_pos)),
+            breadcrumbs: self.nav_pages.iter()
+                .filter(|v| v.tab == self.tab_pos.index())
+                .map(|v| v.name.as_str())
+                .collect(),
+            carousel: curtab.is_some_and(|v| v.carousel()),
+            marked,
+            selected_count: self.multi_selected.iter().filter(|(v, _)| *v == self.tab_pos.index()).count(),
+            footer: curtab.and_then(|tab| {
+                let selected = (self.item_pos.0 == tab.footer_row()).then_some(self.item_pos.1);
+                tab.footer().map(|v| (v.as_slice(), selected))
+            }),
+            overlays,
+            colors: &self.colors,
+            damage,
+        };
+        self.renderer.render(&frame)
+            .expect("Failed to render frame");
+
+        self.update_mirror();
+        self.redraw_tabs = false;
+        self.redraw_items = false;
+    }
+    pub fn new(layout: layout::Layout) -> Gui {
+        Self::builder(layout).build()
+    }
+    pub fn builder(layout: layout::Layout) -> GuiBuilder {
+        GuiBuilder::new(layout)
+    }
+}
+
+pub struct GuiBuilder {
+    layout: layout::Layout,
+    renderer: Option<Box<dyn Renderer>>,
+    colors: ColorPalette,
+    inputs: Vec<Box<dyn InputSource>>,
+    default_inputs: bool,
+    clock: Option<Arc<dyn Clock>>,
+    accel: Option<NavAcceleration>,
+    analog: AnalogConfig,
+    history_limit: usize,
+    event_log_size: usize,
+    theme_fade_time: Duration,
+    open_links: bool,
+    activation_cooldown: Duration,
+    controller_style: Option<ControllerStyle>,
+    config: Option<config::Config>,
+    themes: Vec<(String, ColorPalette)>,
+    idle: Option<IdleConfig>,
+    idle_events: Option<Duration>,
+    brightness: Option<Brightness>,
+    feedback: FeedbackTheme,
+    forms: Vec<Form>,
+    battery_interval: Duration,
+    cache_limits: CacheLimits,
+}
+
+impl GuiBuilder {
+    pub fn new(layout: layout::Layout) -> Self {
+        Self {
+            layout,
+            renderer: None,
+            colors: ColorPalette::default(),
+            inputs: Vec::new(),
+            default_inputs: true,
+            clock: None,
+            accel: None,
+            analog: AnalogConfig::default(),
+            history_limit: 32,
+            event_log_size: 64,
+            theme_fade_time: Duration::from_millis(250),
+            open_links: false,
+            activation_cooldown: Duration::ZERO,
+            controller_style: None,
+            config: None,
+            themes: Vec::new(),
+            idle: None,
+            idle_events: None,
+            brightness: None,
+            feedback: FeedbackTheme::default(),
+            forms: Vec::new(),
+            battery_interval: Duration::from_secs(30),
+            cache_limits: CacheLimits::default(),
+        }
+    }
+    pub fn renderer(mut self, renderer: Box<dyn Renderer>) -> Self {
+        self.renderer = Some(renderer);
+        self
+    }
+    pub fn palette(mut self, colors: ColorPalette) -> Self {
+        self.colors = colors;
+        self
+    }
+    pub fn input(mut self, source: impl InputSource + 'static) -> Self {
+        self.inputs.push(Box::new(source));
+        self
+    }
+    // don't open any input devices on our own, only use sources passed to input()
+    pub fn no_default_inputs(mut self) -> Self {
+        self.default_inputs = false;
+        self
+    }
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+    // off by default, every repeat then moves a single cell. A [repeat] config section turns it on too
+    pub fn nav_acceleration(mut self, accel: Option<NavAcceleration>) -> Self {
+        self.accel = accel;
+        self
+    }
+    // how many changes undo() can go back, 0 turns the history off
+    pub fn undo_history(mut self, limit: usize) -> Self {
+        self.history_limit = limit;
+        self
+    }
+    // makes a custom palette selectable by name, from set_theme(), the config file or SGUI_THEME
+    pub fn register_theme(mut self, name: &str, palette: ColorPalette) -> Self {
+        self.themes.retain(|(v, _)| !v.eq_ignore_ascii_case(name));
+        self.themes.push((name.to_string(), palette));
+        self
+    }
+    // how long set_theme() cross-fades on renderers that support it, zero switches instantly
+    pub fn theme_fade(mut self, time: Duration) -> Self {
+        self.theme_fade_time = time;
+        self
+    }
+    // Applies user preferences, usually from config::Config::load_user(). The user's theme
+    // and repeat rates win over palette() and nav_acceleration().
+    pub fn config(mut self, config: config::Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+    // activated links get opened with xdg-open on top of the LinkActivated event
+    pub fn open_links(mut self, open: bool) -> Self {
+        self.open_links = open;
+        self
+    }
+    // see Gui::set_controller_style()
+    pub fn controller_style(mut self, style: ControllerStyle) -> Self {
+        self.controller_style = Some(style);
+        self
+    }
+    // see Gui::set_activation_cooldown()
+    pub fn activation_cooldown(mut self, cooldown: Duration) -> Self {
+        self.activation_cooldown = cooldown;
+        self
+    }
+    // how many events Gui::event_history() remembers, 0 turns logging off
+    pub fn event_log(mut self, size: usize) -> Self {
+        self.event_log_size = size;
+        self
+    }
+    // dim or blank the screen after a while without input, off by default
+    pub fn idle(mut self, idle: IdleConfig) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+    // Reports GuiEvent::Idle after `after` without input and GuiEvent::Active on the next
+    // input, which is swallowed. Works with or without idle().
+    pub fn idle_events(mut self, after: Duration) -> Self {
+        self.idle_events = Some(after);
+        self
+    }
+    // how often battery items are updated
+    pub fn battery_refresh(mut self, interval: Duration) -> Self {
+        self.battery_interval = interval;
+        self
+    }
+    // keeps the renderer's text and picture caches small, e.g. on devices with little
+    // video memory
+    pub fn cache_limits(mut self, limits: CacheLimits) -> Self {
+        self.cache_limits = limits;
+        self
+    }
+    pub fn form(mut self, form: Form) -> Self {
+        self.forms.push(form);
+        self
+    }
+    pub fn brightness(mut self, brightness: Brightness) -> Self {
+        self.brightness = Some(brightness);
+        self
+    }
+    // sounds or rumble on presses, silent by default
+    pub fn feedback(mut self, feedback: FeedbackTheme) -> Self {
+        self.feedback = feedback;
+        self
+    }
+    pub fn analog(mut self, analog: AnalogConfig) -> Self {
+        self.analog = analog;
+        self
+    }
+    pub fn build(self) -> Gui {
+        let config = self.config.unwrap_or_default();
+        // SGUI_THEME beats the config file, which beats palette()
+        let colors = std::env::var("SGUI_THEME").ok()
+            .and_then(|v| find_theme(&self.themes, &v))
+            .or_else(|| config.theme.as_deref().and_then(|v| find_theme(&self.themes, v)))
+            .unwrap_or(self.colors);
+        let accel = config.repeat.clone().or(self.accel);
+        let layout = self.layout;
+        let mut renderer = self.renderer.unwrap_or_else(|| autopick_renderer(&config));
+        renderer.set_bindings(&config.bindings);
+        let clock = self.clock.unwrap_or_else(|| Arc::new(clock::SystemClock::new()));
+        renderer.set_clock(clock.clone());
+        renderer.set_cache_limits(self.cache_limits);
+        let renderer_rx = renderer.get_event();
+
+        let confirm_swap = controller::ConfirmSwap::default();
+        renderer.set_confirm_swap(confirm_swap.clone());
+        let mut inputs = self.inputs;
+        if self.default_inputs {
+            let picked = input::autopick_sources_for(&config.bindings, &confirm_swap);
+            if picked.is_empty() {
+                renderer.read_pad_buttons();
+            }
+            inputs.extend(picked);
+        }
+        let hid_rx = input::spawn_sources(inputs);
+        let (cmd_tx, cmd_rx) = unbounded();
+
+        let mut gui = Gui {
+            layout,
+            renderer,
+            colors,
+            hid_rx,
+            renderer_rx,
+            tab_pos: TabId::default(),
+            item_pos: (0, 0),
+            page: 0,
+            pane: Pane::Detail,
+            list_pos: 0,
+            popup: None,
+            osk: None,
+            keyboard_layouts: vec![osk::KeyboardLayout::qwerty(), osk::KeyboardLayout::symbols()],
+            input_keyboards: Vec::new(),
+            editing: None,
+            focus_scopes: Vec::new(),
+            nav_pages: Vec::new(),
+            multi_selected: Vec::new(),
+            last_origin: None,
+            origin: None,
+            command_origins: Vec::new(),
+            tab_scroll: Vec::new(),
+            resume_scroll: Vec::new(),
+            journal: None,
+            journal_redactor: None,
+            grab: None,
+            history: History::new(self.history_limit),
+            event_log: EventLog::new(self.event_log_size),
+            theme_fade: None,
+            open_links: self.open_links,
+            activation_cooldown: self.activation_cooldown,
+            controller_style: ControllerStyle::default(),
+            pinned_style: self.controller_style,
+            confirm_swap,
+            item_cooldowns: Vec::new(),
+            last_activation: None,
+            cmd_tx,
+            cmd_rx,
+            theme_fade_time: self.theme_fade_time,
+            themes: self.themes,
+            analog: self.analog,
+            analog_x: 0,
+            analog_next: None,
+            dpad_held: (0, 0),
+            dpad_next: None,
+            accel,
+            idle: self.idle,
+            last_input: Duration::ZERO,
+            idle_state: None,
+            idle_events: self.idle_events,
+            idle_reported: false,
+            brightness: self.brightness,
+            feedback: self.feedback,
+            brightness_level: None,
+            brightness_next: None,
+            battery_interval: self.battery_interval,
+            battery_next: None,
+            since_next: None,
+            clock_next: None,
+            spinner_next: None,
+            reveal_next: None,
+            log_next: None,
+            countdown_next: None,
+            countdown_last: None,
+            expired_timers: std::collections::VecDeque::new(),
+            marquee_next: None,
+            osd: None,
+            forms: Vec::new(),
+            #[cfg(feature = "wifi")]
+            wifi: None,
+            #[cfg(feature = "wifi")]
+            wifi_status: Vec::new(),
+            #[cfg(feature = "wifi")]
+            wifi_status_next: None,
+            repeat: RepeatTracker::default(),
+            ignore_hid: false,
+            raw_hid_taps: Vec::new(),
+            input_filter: None,
+            focus_callback: None,
+            last_focused: None,
+            shortcuts: shortcut::Shortcuts::default(),
+            clock,
+            redraw_tabs: false,
+            redraw_items: false,
+            frozen: false,
+            #[cfg(feature = "test-util")]
+            queued: std::collections::VecDeque::new(),
+            #[cfg(feature = "debug-mirror")]
+            mirror: None,
+        };
+        gui.last_input = gui.clock.now();
+        for form in self.forms {
+            gui.add_form(form);
+        }
+        gui.refresh_battery();
+        gui.refresh_since();
+        gui.refresh_clocks();
+        gui.refresh_spinners();
+        gui.refresh_countdowns();
+        // the selector starts out at the current brightness, nothing to apply yet
+        gui.brightness_level = gui.brightness.as_ref()
+            .and_then(|v| gui.layout.all_items().find(|item| item_id(item) == Some(v.id)))
+            .and_then(brightness::level);
+        gui.reset_tab_focus();
+        gui.apply_controller_style();
+        gui.redraw_all();
+        gui
+    }
+}
+
+// nearest column to `column` that can take focus, `column` itself when there's none
+fn focusable_column(row: &[Item], column: usize) -> usize {
+    (0..row.len())
+        .filter(|v| row[*v].focusable())
+        .min_by_key(|v| v.abs_diff(column))
+        .unwrap_or(column)
+}
+
+fn item_id(item: &Item) -> Option<u128> {
+    match item {
+        Item::Text(_) | Item::QrCode(_) | Item::Battery(_) | Item::Clock(_) | Item::Message(..) | Item::Separator | Item::Header(_) => None,
+        Item::StatefulButton(_, _, id) | Item::Toggle(_, _, id) | Item::StatelessButton(_, id) | Item::Selector(_, _, _, _, id) | Item::Link(_, _, id)
+            | Item::Command(_, _, _, id) | Item::Log(_, id) | Item::Date(_, _, id) | Item::Time(_, _, id) | Item::Tile(_, _, id)
+            | Item::Value(_, _, id) | Item::Slider(_, _, _, _, _, id) | Item::TextInput(_, _, _, id)
+            | Item::ProgressBar(_, _, id) | Item::Gauge(_, _, _, id) | Item::Image(_, id) | Item::Stepper(_, _, _, _, _, id)
+            | Item::Radio(_, _, _, id) | Item::Spinner(_, _, _, id) | Item::Sparkline(_, id) | Item::Countdown(_, id)
+            | Item::Table(_, _, _, _, id) => Some(*id),
+    }
 }
 
-fn autopick_renderer() -> Box<dyn Renderer> {
+// id of the item an event is about
+fn event_item(ev: &GuiEvent) -> Option<u128> {
+    match ev {
+        GuiEvent::StatefulButtonChange(_, _, id) | GuiEvent::ToggleChanged(id, _) | GuiEvent::StatelessButtonPress(_, id) | GuiEvent::SelectorChanged(id, _)
+            | GuiEvent::LinkActivated(id, _) | GuiEvent::CommandStarted(id) | GuiEvent::CommandFinished(id, _)
+            | GuiEvent::ItemAction(id, _) | GuiEvent::ItemMoved(id, _, _) | GuiEvent::DateChanged(id, _)
+            | GuiEvent::TimeChanged(id, _) | GuiEvent::SliderChanged(id, _) | GuiEvent::StepperChanged(id, _)
+            | GuiEvent::RadioChanged(id, _) | GuiEvent::TableRowSelected(id, _) | GuiEvent::TextChanged(id, _)
+            | GuiEvent::TimerExpired(id) => Some(*id),
+        _ => None,
+    }
+}
+
+// what the readout shows while `item` is being edited
+fn readout(item: &Item, segment: usize) -> Option<String> {
+    match item {
+        Item::Selector(_, options, selected, _, _) => options.get(*selected).cloned(),
+        Item::Slider(_, _, _, _, value, _) => Some(value.to_string()),
+        Item::Stepper(_, value, _, _, _, _) => Some(value.to_string()),
+        Item::Date(_, date, _) => Some(datetime::format_segments(&date.parts(), '-', Some(segment))),
+        Item::Time(_, time, _) => Some(datetime::format_segments(&time.parts(), ':', Some(segment))),
+        _ => None,
+    }
+}
+
+// the event reporting an item's current value
+fn change_event(item: &Item) -> Option<GuiEvent> {
+    match item {
+        Item::StatefulButton(text, state, id) => Some(GuiEvent::StatefulButtonChange(text.to_string(), *state, *id)),
+        Item::Toggle(_, state, id) => Some(GuiEvent::ToggleChanged(*id, *state)),
+        Item::Selector(_, _, selected, _, id) => Some(GuiEvent::SelectorChanged(*id, *selected)),
+        Item::Date(_, date, id) => Some(GuiEvent::DateChanged(*id, *date)),
+        Item::Time(_, time, id) => Some(GuiEvent::TimeChanged(*id, *time)),
+        Item::Slider(_, _, _, _, value, id) => Some(GuiEvent::SliderChanged(*id, *value)),
+        Item::Stepper(_, value, _, _, _, id) => Some(GuiEvent::StepperChanged(*id, *value)),
+        Item::Radio(_, index, true, id) => Some(GuiEvent::RadioChanged(*id, *index)),
+        Item::TextInput(_, text, _, id) => Some(GuiEvent::TextChanged(*id, text.clone())),
+        _ => None,
+    }
+}
+
+// moves an adjustable item by `steps`, returns the resulting change event
+fn adjust_item(item: &mut Item, steps: i32) -> Option<GuiEvent> {
+    match item {
+        Item::Selector(_, options, selected, _, id) if !options.is_empty() => {
+            let new = (*selected as i32 + steps).clamp(0, options.len() as i32 - 1) as usize;
+            if new == *selected {
+                return None;
+            }
+            *selected = new;
+            Some(GuiEvent::SelectorChanged(*id, new))
+        },
+        Item::Slider(_, min, max, step, value, id) => {
+            let new = value.saturating_add(steps.saturating_mul(*step)).clamp(*min, *max);
+            if new == *value {
+                return None;
+            }
+            *value = new;
+            Some(GuiEvent::SliderChanged(*id, new))
+        },
+        Item::Stepper(_, value, min, max, step, id) => {
+            let new = value.saturating_add((steps as i64).saturating_mul(*step)).clamp(*min, *max);
+            if new == *value {
+                return None;
+            }
+            *value = new;
+            Some(GuiEvent::StepperChanged(*id, new))
+        },
+        _ => None,
+    }
+}
+
+// first visible index of a scrolled list of `len` entries so that `selected` stays
+// on screen, moving as little as possible from the previous `first`
+#[cfg(any(feature = "crossterm", feature = "sdl2"))]
+pub(crate) fn keep_visible(first: usize, selected: usize, visible: usize, len: usize) -> usize {
+    keep_visible_with_margin(first, selected, visible, len, 0)
+}
+
+// same as keep_visible() with `margin` more entries kept visible on either side of
+// `selected` where there are any, see Gui::set_scroll_margin()
+#[cfg(any(feature = "crossterm", feature = "sdl2"))]
+pub(crate) fn keep_visible_with_margin(first: usize, selected: usize, visible: usize, len: usize, margin: usize) -> usize {
+    let visible = visible.max(1);
+    let margin = margin.min((visible - 1) / 2);
+    let mut first = first.min(len.saturating_sub(visible));
+    if selected < first + margin {
+        first = selected.saturating_sub(margin);
+    } else if selected + margin >= first + visible {
+        first = (selected + margin + 1).saturating_sub(visible).min(len.saturating_sub(visible));
+    }
+    first
+}
+
+fn find_theme(themes: &[(String, ColorPalette)], name: &str) -> Option<ColorPalette> {
+    themes.iter()
+        .find(|(v, _)| v.eq_ignore_ascii_case(name))
+        .map(|(_, palette)| palette.clone())
+        .or_else(|| ColorPalette::named(name))
+}
+
+#[allow(unused_variables)]
+fn autopick_renderer(config: &config::Config) -> Box<dyn Renderer> {
     #[cfg(feature = "sdl2")]
-    if let Ok(sdl) = renderer_sdl2::new() {
-        return Box::new(sdl);
+    {
+        let mut fonts = renderer_sdl2::FontConfig::default();
+        if let Some(font) = config.font.as_ref() {
+            fonts.primary = font.clone();
+        }
+        if let Some(size) = config.font_size {
+            fonts.size = size;
+        }
+        if let Ok(sdl) = renderer_sdl2::new_with_fonts(fonts) {
+            return Box::new(sdl);
+        }
     }
 
-    Box::new(renderer_crossterm::new().unwrap())
+    #[cfg(feature = "crossterm")]
+    return Box::new(renderer_crossterm::new().unwrap());
+
+    #[cfg(not(feature = "crossterm"))]
+    panic!("No usable renderer, enable the `sdl2` or `crossterm` feature");
 }
@@ -0,0 +1,56 @@
+use crate::{ColorPalette, draw::DrawRect, layout::Item};
+use std::time::Duration;
+
+// seconds a marquee rests at either end before moving on
+const REST: f32 = 1.0;
+
+// labels that scroll in marquee mode, items that are nothing but their label
+pub(crate) fn label(item: &Item) -> Option<&str> {
+    match item {
+        Item::Text(text) | Item::StatelessButton(text, _) | Item::Tile(text, _, _) => Some(text),
+        _ => None,
+    }
+}
+
+// The selected label while it's too long for its cell, scrolled along from Renderer::tick()
+// in marquee mode. Comes back to the start after resting at the end for a moment.
+pub(crate) struct Marquee {
+    pub(crate) item: Item,
+    pub(crate) colors: ColorPalette,
+    pub(crate) cell: DrawRect,
+    // how far the label sticks out of its cell
+    overflow: u32,
+    // clock time it started moving at
+    started: Duration,
+    // offset it was last drawn at
+    pub(crate) shown: u32,
+    // reset before the items are redrawn, one that wasn't drawn again is gone
+    pub(crate) seen: bool,
+}
+
+impl Marquee {
+    // keeps `old` going if it's for the same item in the same place, `now` is the clock time
+    pub(crate) fn follow(old: Option<Marquee>, item: &Item, colors: &ColorPalette, cell: DrawRect, overflow: u32, now: Duration) -> Marquee {
+        let mut marquee = match old {
+            Some(old) if old.item == *item && old.cell == cell && old.overflow == overflow => old,
+            _ => Marquee {
+                item: item.clone(),
+                colors: colors.clone(),
+                cell,
+                overflow,
+                started: now,
+                shown: 0,
+                seen: true,
+            },
+        };
+        marquee.colors = colors.clone();
+        marquee.seen = true;
+        marquee
+    }
+    // where the label is at `now` moving `per_second` units a second
+    pub(crate) fn offset(&self, per_second: f32, now: Duration) -> u32 {
+        let run = self.overflow as f32 / per_second.max(f32::EPSILON);
+        let t = now.saturating_sub(self.started).as_secs_f32() % (2.0 * REST + run);
+        ((t - REST).clamp(0.0, run) * per_second) as u32
+    }
+}
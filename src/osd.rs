@@ -0,0 +1,79 @@
+use crate::{Renderer, ColorPalette, clock::{Clock, SystemClock}};
+use anyhow::Result;
+use std::{sync::Arc, time::Duration};
+
+// On-screen display: a single bar (volume, brightness, ...) without tabs or items that shows
+// up on show() and goes away on its own. Meant for renderer_sdl2::new_overlay(), but any
+// renderer works. Either call tick() whenever next_timeout() runs out, or hand it to
+// Gui::set_osd() and let the gui do that.
+pub struct Osd {
+    renderer: Box<dyn Renderer>,
+    colors: ColorPalette,
+    timeout: Duration,
+    clock: Arc<dyn Clock>,
+    hide_at: Option<Duration>,
+}
+
+impl Osd {
+    pub fn new(renderer: Box<dyn Renderer>, colors: ColorPalette) -> Self {
+        let mut renderer = renderer;
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock::new());
+        renderer.set_visible(false);
+        renderer.set_clock(clock.clone());
+        Self {
+            renderer,
+            colors,
+            timeout: Duration::from_secs(2),
+            clock,
+            hide_at: None,
+        }
+    }
+    // how long the bar stays up after the last show()
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.set_clock(Arc::new(clock));
+        self
+    }
+    pub(crate) fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+        self.renderer.set_clock(self.clock.clone());
+    }
+    // `value` goes from 0.0 to 1.0, showing it again while up just updates it
+    pub fn show(&mut self, label: &str, value: f32) -> Result<()> {
+        if self.hide_at.is_none() {
+            self.renderer.set_visible(true);
+        }
+        self.renderer.draw_osd(label, value.clamp(0.0, 1.0), &self.colors)?;
+        self.hide_at = Some(self.clock.now() + self.timeout);
+        Ok(())
+    }
+    pub fn visible(&self) -> bool {
+        self.hide_at.is_some()
+    }
+    // time until the bar goes away, None while hidden
+    pub fn next_timeout(&self) -> Option<Duration> {
+        self.hide_at.map(|v| v.saturating_sub(self.clock.now()))
+    }
+    // clock time the bar goes away at
+    pub(crate) fn hide_at(&self) -> Option<Duration> {
+        self.hide_at
+    }
+    // hides the bar once it's due, call this whenever next_timeout() runs out
+    pub fn tick(&mut self) {
+        self.renderer.tick();
+        if self.hide_at.is_some_and(|v| v <= self.clock.now()) {
+            self.hide_at = None;
+            self.renderer.set_visible(false);
+        }
+    }
+}
+
+// text version of the bar for renderers without a graphical one
+pub(crate) fn bar_text(label: &str, value: f32) -> String {
+    const WIDTH: usize = 10;
+    let filled = (value.clamp(0.0, 1.0) * WIDTH as f32).round() as usize;
+    format!("{} {}{} {}%", label, "█".repeat(filled), "░".repeat(WIDTH - filled), (value * 100.0).round() as u32)
+}
@@ -0,0 +1,276 @@
+use crate::{HidEvent, ActionButton};
+use anyhow::{anyhow, bail, Result};
+use std::path::Path;
+
+// one key of the on-screen keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Shift,
+    Space,
+    // switches to the next layout, see Gui::set_keyboard_layouts()
+    NextLayout,
+    Backspace,
+    Done,
+}
+
+// Keys of the on-screen keyboard, a string of characters per row. Shift picks the shifted
+// rows, layouts without any get their letters uppercased instead. Shifted rows have to
+// line up with the plain ones key for key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyboardLayout {
+    // shown on the key that switches to this layout
+    pub name: String,
+    rows: Vec<String>,
+    shifted: Vec<String>,
+}
+
+impl KeyboardLayout {
+    pub fn new(name: &str, rows: &[&str], shifted: &[&str]) -> Result<Self> {
+        let layout = Self::from_rows(name, rows, shifted);
+        layout.check()?;
+        Ok(layout)
+    }
+    pub fn qwerty() -> Self {
+        Self::from_rows("ABC", &["1234567890", "qwertyuiop", "asdfghjkl-", "zxcvbnm,._"], &["!@#$%&*()?", "QWERTYUIOP", "ASDFGHJKL+", "ZXCVBNM;:/"])
+    }
+    pub fn azerty() -> Self {
+        Self::from_rows("AZE", &["1234567890", "azertyuiop", "qsdfghjklm", "wxcvbn,.-_"], &["!@#$%&*()?", "AZERTYUIOP", "QSDFGHJKLM", "WXCVBN;:/+"])
+    }
+    pub fn symbols() -> Self {
+        Self::from_rows("?123", &["1234567890", "!@#$%^&*()", "-_=+[]{}<>", "'\"\\|;:,./?", "`~€£¥§°±×÷"], &[])
+    }
+    // digits only, e.g. for PINs
+    pub fn numeric() -> Self {
+        Self::from_rows("123", &["123", "456", "789", "-0."], &[])
+    }
+    // unchecked, for the built-in layouts
+    fn from_rows(name: &str, rows: &[&str], shifted: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            rows: rows.iter().map(|v| v.to_string()).collect(),
+            shifted: shifted.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+    fn check(&self) -> Result<()> {
+        if self.rows.is_empty() {
+            bail!("no keys in layout {}", self.name);
+        }
+        if self.rows.iter().chain(&self.shifted).any(String::is_empty) {
+            bail!("empty row in layout {}", self.name);
+        }
+        if self.shifted.is_empty() {
+            return Ok(());
+        }
+        if self.shifted.len() != self.rows.len() {
+            bail!("layout {}: {} shifted rows for {} rows", self.name, self.shifted.len(), self.rows.len());
+        }
+        for (i, (row, shifted)) in self.rows.iter().zip(&self.shifted).enumerate() {
+            if row.chars().count() != shifted.chars().count() {
+                bail!("layout {}: shifted row {} doesn't have as many keys as the row", self.name, i + 1);
+            }
+        }
+        Ok(())
+    }
+    // built-in layouts by name: "qwerty", "azerty", "symbols" and "numeric"
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "qwerty" => Some(Self::qwerty()),
+            "azerty" => Some(Self::azerty()),
+            "symbols" => Some(Self::symbols()),
+            "numeric" => Some(Self::numeric()),
+            _ => None,
+        }
+    }
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+    // A layout written out as text: `name = ...` first, then a row of keys per line and
+    // optionally `---` followed by the shifted rows. Empty lines and lines starting with #
+    // are skipped, so # itself can only be a key after the first one in a row.
+    //
+    //   name = AZE
+    //   azertyuiop
+    //   qsdfghjklm
+    //   ---
+    //   AZERTYUIOP
+    //   QSDFGHJKLM
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate()
+            .map(|(i, v)| (i, v.trim()))
+            .filter(|(_, v)| !v.is_empty() && !v.starts_with('#'));
+        let (i, first) = lines.next().ok_or_else(|| anyhow!("empty layout"))?;
+        let Some(("name", name)) = first.split_once('=').map(|(k, v)| (k.trim(), v.trim())) else {
+            bail!("line {}: expected name = ...", i + 1);
+        };
+        let mut layout = Self::from_rows(name, &[], &[]);
+        let mut shifted = false;
+        for (_, line) in lines {
+            match (line, shifted) {
+                ("---", _) => shifted = true,
+                (_, false) => layout.rows.push(line.to_string()),
+                (_, true) => layout.shifted.push(line.to_string()),
+            }
+        }
+        layout.check()?;
+        Ok(layout)
+    }
+    fn shifted_rows(&self) -> Vec<String> {
+        match self.shifted.is_empty() {
+            true => self.rows.iter().map(|v| v.to_uppercase()).collect(),
+            false => self.shifted.clone(),
+        }
+    }
+}
+
+impl Default for KeyboardLayout {
+    fn default() -> Self {
+        Self::qwerty()
+    }
+}
+
+// what an event did to the keyboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Typing,
+    Done(String),
+    Cancelled,
+}
+
+// Keyboard for an Item::TextInput, driven by the d-pad. ButtonPress types the focused key,
+// West deletes, Start confirms, the shoulder buttons switch layouts and Back leaves without
+// changing the item. Holding North shows a masked text, see Gui::osk_event().
+#[derive(Debug, Clone)]
+pub(crate) struct Osk {
+    // the Item::TextInput being typed into
+    pub origin: (usize, usize),
+    pub text: String,
+    pub masked: bool,
+    revealed: bool,
+    // (row, key)
+    pub selected: (usize, usize),
+    // next letter only
+    shift: bool,
+    layouts: Vec<KeyboardLayout>,
+    layout: usize,
+    // what the renderer gets, rebuilt on every change
+    pub labels: Vec<Vec<String>>,
+    pub shown: String,
+}
+
+impl Osk {
+    // starts out with the first of `layouts`
+    pub fn new(origin: (usize, usize), text: String, masked: bool, layouts: Vec<KeyboardLayout>) -> Self {
+        let layouts = if layouts.is_empty() { vec![KeyboardLayout::default()] } else { layouts };
+        let mut osk = Self {
+            origin,
+            text,
+            masked,
+            revealed: false,
+            selected: (1, 0),
+            shift: false,
+            layouts,
+            layout: 0,
+            labels: Vec::new(),
+            shown: String::new(),
+        };
+        // the first layout might not have a second row
+        osk.switch_layout(0);
+        osk.refresh();
+        osk
+    }
+    fn keys(&self) -> Vec<Vec<Key>> {
+        let layout = &self.layouts[self.layout];
+        let shifted = layout.shifted_rows();
+        let rows = if self.shift { &shifted } else { &layout.rows };
+        // shift only where it changes something, switching only with more than one layout
+        let bottom = [
+            (shifted != layout.rows).then_some(Key::Shift),
+            Some(Key::Space),
+            (self.layouts.len() > 1).then_some(Key::NextLayout),
+            Some(Key::Backspace),
+            Some(Key::Done),
+        ];
+        rows.iter()
+            .map(|row| row.chars().map(Key::Char).collect())
+            .chain(std::iter::once(bottom.into_iter().flatten().collect()))
+            .collect()
+    }
+    fn switch_layout(&mut self, steps: usize) {
+        self.layout = (self.layout + steps) % self.layouts.len();
+        self.set_shift(false);
+    }
+    // keeps the selection on a key, rows can change along with shift
+    fn set_shift(&mut self, shift: bool) {
+        self.shift = shift;
+        let rows = self.keys();
+        let row = self.selected.0.min(rows.len() - 1);
+        self.selected = (row, self.selected.1.min(rows[row].len().saturating_sub(1)));
+    }
+    fn refresh(&mut self) {
+        self.labels = self.keys().iter()
+            .map(|row| row.iter().map(|key| match key {
+                Key::Char(c) => c.to_string(),
+                Key::Shift => "Shift".to_string(),
+                Key::Space => "Space".to_string(),
+                Key::NextLayout => self.layouts[(self.layout + 1) % self.layouts.len()].name.clone(),
+                Key::Backspace => "Del".to_string(),
+                Key::Done => "OK".to_string(),
+            }).collect())
+            .collect();
+        self.shown = mask(&self.text, self.masked && !self.revealed);
+    }
+    pub fn reveal(&mut self, revealed: bool) {
+        self.revealed = revealed;
+        self.refresh();
+    }
+    pub fn handle(&mut self, ev: &HidEvent) -> Outcome {
+        let keys = self.keys();
+        let (row, col) = self.selected;
+        match ev {
+            // the column keeps its place across rows of different lengths
+            HidEvent::Up | HidEvent::Down => {
+                let new_row = match ev {
+                    HidEvent::Up => (row + keys.len() - 1) % keys.len(),
+                    _ => (row + 1) % keys.len(),
+                };
+                let col = col * keys[new_row].len() / keys[row].len();
+                self.selected = (new_row, col.min(keys[new_row].len() - 1));
+            },
+            HidEvent::Left => self.selected.1 = (col + keys[row].len() - 1) % keys[row].len(),
+            HidEvent::Right => self.selected.1 = (col + 1) % keys[row].len(),
+            HidEvent::ButtonPress => match keys[row][col] {
+                Key::Char(c) => {
+                    self.text.push(c);
+                    self.set_shift(false);
+                },
+                Key::Shift => self.set_shift(!self.shift),
+                Key::Space => self.text.push(' '),
+                Key::NextLayout => self.switch_layout(1),
+                Key::Backspace => {
+                    self.text.pop();
+                },
+                Key::Done => return Outcome::Done(self.text.clone()),
+            },
+            HidEvent::Action(ActionButton::West) => {
+                self.text.pop();
+            },
+            HidEvent::Action(ActionButton::Start) => return Outcome::Done(self.text.clone()),
+            HidEvent::NextTab => self.switch_layout(1),
+            HidEvent::PreviousTab => self.switch_layout(self.layouts.len() - 1),
+            HidEvent::Back => return Outcome::Cancelled,
+            _ => (),
+        }
+        self.refresh();
+        Outcome::Typing
+    }
+}
+
+// what a text input shows, a dot per character when masked
+pub(crate) fn mask(text: &str, masked: bool) -> String {
+    match masked {
+        true => "•".repeat(text.chars().count()),
+        false => text.to_string(),
+    }
+}
@@ -0,0 +1,66 @@
+use std::{fmt, fs, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeState {
+    Charging,
+    Discharging,
+    // plugged in and not charging, either full or held back by the charger
+    NotCharging,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Battery {
+    // 0 - 100
+    pub percent: u8,
+    pub state: ChargeState,
+}
+
+impl Battery {
+    // first battery under /sys/class/power_supply, None if there isn't one
+    pub fn read() -> Option<Battery> {
+        Self::read_from(Path::new("/sys/class/power_supply"))
+    }
+    pub fn read_from(dir: &Path) -> Option<Battery> {
+        let mut supplies: Vec<_> = fs::read_dir(dir).ok()?
+            .filter_map(|v| v.ok())
+            .map(|v| v.path())
+            .collect();
+        // same battery every time on systems with several
+        supplies.sort();
+        supplies.into_iter()
+            .filter(|path| read_attr(path, "type").as_deref() == Some("Battery"))
+            .find_map(|path| {
+                let percent = read_attr(&path, "capacity")?.parse::<u8>().ok()?.min(100);
+                let state = match read_attr(&path, "status").as_deref() {
+                    Some("Charging") => ChargeState::Charging,
+                    Some("Discharging") => ChargeState::Discharging,
+                    Some("Full") | Some("Not charging") => ChargeState::NotCharging,
+                    _ => ChargeState::Unknown,
+                };
+                Some(Battery { percent, state })
+            })
+    }
+}
+
+impl fmt::Display for Battery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.state {
+            ChargeState::Charging => write!(f, "{}% ⚡", self.percent),
+            ChargeState::NotCharging => write!(f, "{}% 🔌", self.percent),
+            _ => write!(f, "{}%", self.percent),
+        }
+    }
+}
+
+fn read_attr(supply: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(supply.join(name)).ok().map(|v| v.trim().to_string())
+}
+
+// text of an Item::Battery
+pub fn label(battery: Option<&Battery>) -> String {
+    match battery {
+        Some(battery) => format!("Battery {}", battery),
+        None => "Battery --%".to_string(),
+    }
+}
@@ -0,0 +1,12 @@
+use qrcode::{QrCode, Color};
+
+// Modules of the code for `data` row by row, true for dark ones, without the quiet zone.
+// None if the data doesn't fit into a QR code.
+pub(crate) fn modules(data: &str) -> Option<(usize, Vec<bool>)> {
+    let code = QrCode::new(data.as_bytes()).ok()?;
+    let dark = code.to_colors().into_iter().map(|v| v == Color::Dark).collect();
+    Some((code.width(), dark))
+}
+
+// light modules around the code that scanners need to find it
+pub(crate) const QUIET_ZONE: usize = 4;
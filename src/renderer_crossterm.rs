@@ -1,9 +1,13 @@
-use crate::{Renderer, ColorPalette, HidEvent, RendererEvent, layout::Item};
+use crate::{Renderer, ViewportInfo, clock::{Clock, SystemClock}, frame::{self, Frame}, marquee::{self, Marquee}, screen::Screen, Color, ColorPalette, HidEvent, RendererEvent, layout::{Item, gauge_fraction, slider_fraction, sparkline_levels}, keep_visible, keep_visible_with_margin, draw::{self, DrawContext, DrawHooks, DrawRect, Primitive, TextStyle}, config::Bindings, datetime, power, osk, format, feedback::Feedback};
 use anyhow::Result;
+#[cfg(feature = "qr")]
+use crate::qr;
 use std::{
     io::{self, Write},
     collections::HashSet,
+    sync::{Arc, Mutex, atomic::{AtomicI32, Ordering}},
     thread,
+    time::Duration,
 };
 use crossterm::{
     ExecutableCommand,
@@ -14,169 +18,1404 @@ use crossterm::{
         KeyCode,
         Event,
         KeyEventKind,
+        MouseEventKind,
     },
     terminal,
     style,
 };
-use crossbeam_channel::{Sender, Receiver, bounded};
+use crossbeam_channel::{Sender, Receiver, TrySendError, bounded};
 
 pub fn new() -> Result<CrosstermRenderer> {
+    let console = detect_console();
     let mut out = io::stdout();
     out.execute(terminal::EnterAlternateScreen)?;
     out.execute(cursor::Hide)?;
     terminal::enable_raw_mode()?;
 
     Ok(CrosstermRenderer {
-        out: io::stdout(),
-    })
+        out: Output { stdout: io::stdout(), screen: None },
+        h_scroll: Vec::new(),
+        title_pushed: false,
+        v_scroll: 0,
+        last_selected: None,
+        scroll_margin: 0,
+        marquee: false,
+        scrolling: None,
+        wheel: Arc::new(AtomicI32::new(0)),
+        editing: false,
+        edit_segment: 0,
+        overlay_rows: 0,
+        header_rows: 3,
+        hooks: DrawHooks::default(),
+        bindings: Bindings::default(),
+        // https://no-color.org
+        monochrome: std::env::var_os("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false),
+        dimmed: false,
+        glyphs: if console.ascii { &ASCII } else { &UNICODE },
+        basic_colors: console.basic_colors,
+        low_bandwidth: false,
+        mouse_wheel: false,
+        last_fg: None,
+        last_render: None,
+        refresh: Mutex::new(None),
+        refresh_at: None,
+        clock: Arc::new(SystemClock::new()),
+        frame_hook: None,
+    }.with_low_bandwidth(env_flag("SGUI_LOW_BANDWIDTH")).with_mouse_wheel(env_flag("SGUI_MOUSE_WHEEL")))
+}
+
+// set to anything but empty or 0
+fn env_flag(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+// shortest time between two frames in low bandwidth mode
+const LOW_BANDWIDTH_GAP: Duration = Duration::from_millis(250);
+
+// what the terminal can show, guessed from the environment
+struct Console {
+    ascii: bool,
+    basic_colors: bool,
+}
+
+// The Linux VT (TERM=linux) has 8 colors and a font with just a few hundred glyphs, and
+// without a UTF-8 locale nothing beyond ASCII comes out right anywhere.
+fn detect_console() -> Console {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let locale = var("LC_ALL").or_else(|| var("LC_CTYPE")).or_else(|| var("LANG")).unwrap_or_default().to_lowercase();
+    let utf8 = locale.contains("utf-8") || locale.contains("utf8");
+    let vt = var("TERM").as_deref() == Some("linux");
+    Console {
+        ascii: !utf8 || vt,
+        basic_colors: vt,
+    }
+}
+
+// characters everything is drawn with
+struct Glyphs {
+    // box corners, lines and the tees where tab separators meet the header border
+    corners: [&'static str; 4],
+    horizontal: &'static str,
+    vertical: &'static str,
+    tee_down: &'static str,
+    tee_up: &'static str,
+    // more cells or options that way
+    more: [&'static str; 4],
+    // list entry the detail pane belongs to
+    current: &'static str,
+    page: [&'static str; 2],
+    qr_item: &'static str,
+    image: &'static str,
+    running: &'static str,
+    editing: [&'static str; 2],
+    // filled and empty part of progress bars
+    bar: [&'static str; 2],
+    // gauge cells from empty to full, partly filled ones in between
+    gauge: &'static [&'static str],
+    // sparkline bars from lowest to highest
+    spark: &'static [&'static str],
+    // picked and other options of radio groups
+    radio: [&'static str; 2],
+    // icons of info, warning and error messages
+    severity: [&'static str; 3],
+    // frames of Item::Spinner, one after the other
+    spinner: &'static [&'static str],
+    // QR codes with two rows of modules per line
+    #[cfg(feature = "qr")]
+    half_blocks: bool,
+}
+
+// left, right, up, down
+const UNICODE: Glyphs = Glyphs {
+    corners: ["┌", "┐", "└", "┘"],
+    horizontal: "─",
+    vertical: "│",
+    tee_down: "┬",
+    tee_up: "┴",
+    more: ["◀", "▶", "▲", "▼"],
+    current: "›",
+    page: ["●", "○"],
+    qr_item: "▣",
+    image: "▨",
+    running: "…",
+    editing: ["«", "»"],
+    bar: ["█", "░"],
+    gauge: &[" ", "▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"],
+    spark: &["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"],
+    radio: ["(•)", "( )"],
+    severity: ["ℹ", "⚠", "✖"],
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+    #[cfg(feature = "qr")]
+    half_blocks: true,
+};
+
+const ASCII: Glyphs = Glyphs {
+    corners: ["+", "+", "+", "+"],
+    horizontal: "-",
+    vertical: "|",
+    tee_down: "+",
+    tee_up: "+",
+    more: ["<", ">", "^", "v"],
+    current: ">",
+    page: ["*", "."],
+    qr_item: "#",
+    image: "img",
+    running: "...",
+    editing: ["<<", ">>"],
+    bar: ["#", "."],
+    gauge: &[".", "#"],
+    spark: &["_", ".", "-", "=", "^"],
+    radio: ["(*)", "( )"],
+    severity: ["i", "!", "x"],
+    spinner: &["|", "/", "-", "\\"],
+    #[cfg(feature = "qr")]
+    half_blocks: false,
+};
+
+// closest of the 8 colors every terminal has
+fn basic_color(color: Color) -> style::Color {
+    let (r, g, b) = color.as_tuple();
+    let max = r.max(g).max(b) as u32;
+    // channels at least half as bright as the brightest one count as on
+    let on = |v: u8| max > 40 && v as u32 * 2 >= max;
+    match (on(r), on(g), on(b)) {
+        (false, false, false) => style::Color::Black,
+        (true, false, false) => style::Color::DarkRed,
+        (false, true, false) => style::Color::DarkGreen,
+        (true, true, false) => style::Color::DarkYellow,
+        (false, false, true) => style::Color::DarkBlue,
+        (true, false, true) => style::Color::DarkMagenta,
+        (false, true, true) => style::Color::DarkCyan,
+        (true, true, true) => style::Color::Grey,
+    }
+}
+
+fn term_color(color: Color, basic: bool) -> style::Color {
+    if basic {
+        basic_color(color)
+    } else {
+        color.as_crossterm_color()
+    }
 }
 
 impl Drop for CrosstermRenderer {
     fn drop(&mut self) {
+        if self.title_pushed {
+            // xterm title stack, brings back whatever the title was before set_title()
+            self.out.execute(style::Print("\x1b[23;0t")).unwrap();
+        }
+        if self.mouse_wheel {
+            self.out.execute(event::DisableMouseCapture).unwrap();
+        }
         self.out.execute(cursor::Show).unwrap();
         self.out.execute(terminal::LeaveAlternateScreen).unwrap();
         terminal::disable_raw_mode().unwrap();
     }
 }
 
+// rows with more cells than this many columns per cell allow get scrolled horizontally
+const MIN_CELL_WIDTH: u16 = 16;
+// rows moved per mouse wheel notch
+const WHEEL_STEP: i32 = 3;
+// cells a second marquees move along
+const MARQUEE_SPEED: f32 = 4.0;
+
+type FrameHook = Box<dyn FnMut(&mut io::Stdout) -> Result<()>>;
+
+// Where the renderer writes to. In low bandwidth mode that's a copy of the screen first,
+// flushing sends only the cells that changed.
+struct Output {
+    stdout: io::Stdout,
+    screen: Option<Screen>,
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.screen.as_mut() {
+            Some(screen) => {
+                screen.queue(buf);
+                Ok(buf.len())
+            },
+            None => self.stdout.write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(screen) = self.screen.as_mut() {
+            let (columns, rows) = terminal::size()?;
+            screen.flush_to(&mut self.stdout, (columns as usize, rows as usize))?;
+        }
+        self.stdout.flush()
+    }
+}
+
 pub struct CrosstermRenderer {
-    out: io::Stdout,
-}
-
-fn handle_events(tx: Sender<RendererEvent>) {
-    loop {
-        match event::read() {
-            Ok(ev) => {
-                if match ev {
-                    Event::Key(key) => {
-                        if key.kind != KeyEventKind::Press {
-                            continue;
-                        }
-                        let ev = match key.code {
-                            KeyCode::Up => HidEvent::Up,
-                            KeyCode::Down => HidEvent::Down,
-                            KeyCode::Left => HidEvent::Left,
-                            KeyCode::Right => HidEvent::Right,
-                            KeyCode::Enter => HidEvent::ButtonPress,
-                            KeyCode::Tab => HidEvent::NextTab,
-                            KeyCode::BackTab => HidEvent::PreviousTab,
-                            KeyCode::Esc => HidEvent::Quit,
-                            _ => continue,
-                        };
-                        tx.send(RendererEvent::Hid(ev))
-                    },
-                    Event::Resize(_, _) => tx.send(RendererEvent::Refresh),
+    out: Output,
+    // first visible cell of every row
+    h_scroll: Vec<usize>,
+    // the original title was saved and has to be restored on drop
+    title_pushed: bool,
+    // first visible row, follows the selection unless the mouse wheel moved it
+    v_scroll: usize,
+    last_selected: Option<(usize, usize)>,
+    // see Renderer::set_scroll_margin()
+    scroll_margin: usize,
+    // see Renderer::set_marquee(), with the label scrolling right now
+    marquee: bool,
+    scrolling: Option<Marquee>,
+    // wheel notches not applied to v_scroll yet
+    wheel: Arc<AtomicI32>,
+    editing: bool,
+    edit_segment: usize,
+    // the last rows hold a readout or keyboard that has to go once it's closed
+    overlay_rows: u16,
+    // borders included, grows with multi-line names and wrapped tabs
+    header_rows: u16,
+    hooks: DrawHooks,
+    bindings: Bindings,
+    // NO_COLOR is set, only attributes are used
+    monochrome: bool,
+    dimmed: bool,
+    glyphs: &'static Glyphs,
+    // only the 8 basic colors work, see detect_console()
+    basic_colors: bool,
+    // see set_low_bandwidth()
+    low_bandwidth: bool,
+    // see set_mouse_wheel()
+    mouse_wheel: bool,
+    // color the terminal is set to, so it's only sent again when it changes
+    last_fg: Option<Color>,
+    // clock time of the last frame drawn in low bandwidth mode
+    last_render: Option<Duration>,
+    // where a repaint for frames dropped in low bandwidth mode is asked for, and when
+    refresh: Mutex<Option<Sender<RendererEvent>>>,
+    refresh_at: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    // see set_frame_hook()
+    frame_hook: Option<FrameHook>,
+}
+
+// Terminal columns taken up by `text`. Wide CJK and Hangul take two, combining marks
+// none, everything else one.
+pub(crate) fn text_cells(text: &str) -> usize {
+    text.chars().map(|ch| match ch as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F | 0x2E80..=0x303E | 0x3041..=0x33FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1F64F | 0x1F900..=0x1F9FF => 2,
+        _ => 1,
+    }).sum()
+}
+
+// `text` cut down to `width` cells with an ellipsis if it's longer, padded if it's shorter
+fn fit_cells(text: &str, width: usize) -> String {
+    if text_cells(text) <= width {
+        return format!("{}{}", text, " ".repeat(width - text_cells(text)));
+    }
+    let mut fitted = String::new();
+    for ch in text.chars() {
+        if text_cells(&fitted) + text_cells(&ch.to_string()) + 1 > width {
+            break;
+        }
+        fitted.push(ch);
+    }
+    let fitted = if width > 0 { format!("{}…", fitted) } else { fitted };
+    format!("{}{}", fitted, " ".repeat(width.saturating_sub(text_cells(&fitted))))
+}
+
+// `width` cells of `text` from `skip` cells in, padded if it's shorter
+fn marquee_window(text: &str, skip: usize, width: usize) -> String {
+    let mut skipped = 0;
+    let mut window = String::new();
+    for ch in text.chars() {
+        let cells = text_cells(&ch.to_string());
+        if skipped < skip {
+            skipped += cells;
+            continue;
+        }
+        if text_cells(&window) + cells > width {
+            break;
+        }
+        window.push(ch);
+    }
+    format!("{}{}", window, " ".repeat(width.saturating_sub(text_cells(&window))))
+}
+
+// name used for `code` in user key bindings
+fn key_name(code: KeyCode) -> Option<String> {
+    Some(match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        _ => return None,
+    })
+}
+
+fn handle_events(tx: Sender<RendererEvent>, wheel: Arc<AtomicI32>, bindings: Bindings) {
+    while let Ok(ev) = event::read() {
+        if match ev {
+            Event::Key(key) => {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if let Some(ev) = key_name(key.code).and_then(|v| bindings.key(&v)) {
+                    if tx.send(RendererEvent::Hid(ev)).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                let ev = match key.code {
+                    KeyCode::Up => HidEvent::Up,
+                    KeyCode::Down => HidEvent::Down,
+                    KeyCode::Left => HidEvent::Left,
+                    KeyCode::Right => HidEvent::Right,
+                    KeyCode::Enter => HidEvent::ButtonPress,
+                    KeyCode::Home => HidEvent::First,
+                    KeyCode::End => HidEvent::Last,
+                    KeyCode::PageUp => HidEvent::PreviousTab,
+                    KeyCode::PageDown => HidEvent::NextTab,
+                    KeyCode::Tab => HidEvent::NextTab,
+                    KeyCode::BackTab => HidEvent::PreviousTab,
+                    KeyCode::Char('p') => HidEvent::SwitchPane,
+                    KeyCode::Char('g') => HidEvent::Grab,
+                    KeyCode::Char('u') => HidEvent::Undo,
+                    KeyCode::Char('r') => HidEvent::Redo,
+                    KeyCode::Backspace => HidEvent::Back,
+                    KeyCode::Esc => HidEvent::Quit,
                     _ => continue,
-                }.is_err() {
-                    break;
+                };
+                tx.send(RendererEvent::Hid(ev))
+            },
+            Event::Resize(_, _) => tx.send(RendererEvent::Resized),
+            // scrolling only moves the view, focus stays where it is
+            Event::Mouse(mouse) => {
+                match mouse.kind {
+                    MouseEventKind::ScrollDown => wheel.fetch_add(WHEEL_STEP, Ordering::Relaxed),
+                    MouseEventKind::ScrollUp => wheel.fetch_sub(WHEEL_STEP, Ordering::Relaxed),
+                    _ => continue,
+                };
+                tx.send(RendererEvent::Refresh)
+            },
+            _ => continue,
+        }.is_err() {
+            break;
+        }
+    }
+}
+
+struct CrosstermDrawContext<'a> {
+    out: &'a mut Output,
+    monochrome: bool,
+    basic_colors: bool,
+    glyphs: &'static Glyphs,
+}
+
+impl CrosstermDrawContext<'_> {
+    fn fg(&mut self, color: Color) -> Result<()> {
+        if !self.monochrome {
+            self.out.queue(style::SetForegroundColor(term_color(color, self.basic_colors)))?;
+        }
+        Ok(())
+    }
+}
+
+impl DrawContext for CrosstermDrawContext<'_> {
+    fn fill_rect(&mut self, rect: DrawRect, color: Color) -> Result<()> {
+        if self.monochrome {
+            return Ok(());
+        }
+        self.out.queue(style::SetBackgroundColor(term_color(color, self.basic_colors)))?;
+        for y in rect.y..rect.y + rect.height as i32 {
+            self.out.queue(cursor::MoveTo(rect.x as u16, y as u16))?;
+            self.out.queue(style::Print(" ".repeat(rect.width as usize)))?;
+        }
+        self.out.queue(style::SetBackgroundColor(style::Color::Reset))?;
+        Ok(())
+    }
+    // box drawing characters, a single row rect gets brackets at both ends instead
+    fn draw_rect(&mut self, rect: DrawRect, color: Color) -> Result<()> {
+        if rect.width < 2 {
+            return Ok(());
+        }
+        let (x, y, inner) = (rect.x as u16, rect.y as u16, rect.width as usize - 2);
+        self.fg(color)?;
+        if rect.height < 2 {
+            self.out.queue(cursor::MoveTo(x, y))?;
+            self.out.queue(style::Print("["))?;
+            self.out.queue(cursor::MoveTo(x + rect.width as u16 - 1, y))?;
+            self.out.queue(style::Print("]"))?;
+            return Ok(());
+        }
+        let g = self.glyphs;
+        self.out.queue(cursor::MoveTo(x, y))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[0], g.horizontal.repeat(inner), g.corners[1])))?;
+        for row in 1..rect.height as u16 - 1 {
+            self.out.queue(cursor::MoveTo(x, y + row))?;
+            self.out.queue(style::Print(g.vertical))?;
+            self.out.queue(cursor::MoveTo(x + rect.width as u16 - 1, y + row))?;
+            self.out.queue(style::Print(g.vertical))?;
+        }
+        self.out.queue(cursor::MoveTo(x, y + rect.height as u16 - 1))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[2], g.horizontal.repeat(inner), g.corners[3])))?;
+        Ok(())
+    }
+    // box drawing characters, straight lines only
+    fn draw_line(&mut self, from: (i32, i32), to: (i32, i32), color: Color) -> Result<()> {
+        self.fg(color)?;
+        if from.1 == to.1 {
+            self.out.queue(cursor::MoveTo(from.0.min(to.0) as u16, from.1 as u16))?;
+            self.out.queue(style::Print(self.glyphs.horizontal.repeat(from.0.abs_diff(to.0) as usize + 1)))?;
+        } else if from.0 == to.0 {
+            for y in from.1.min(to.1)..=from.1.max(to.1) {
+                self.out.queue(cursor::MoveTo(from.0 as u16, y as u16))?;
+                self.out.queue(style::Print(self.glyphs.vertical))?;
+            }
+        }
+        Ok(())
+    }
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, color: Color) -> Result<()> {
+        self.out.queue(cursor::MoveTo(x as u16, y as u16))?;
+        self.fg(color)?;
+        self.out.queue(style::Print(text))?;
+        Ok(())
+    }
+}
+
+impl CrosstermRenderer {
+    // For slow serial or SSH links: the 8 basic colors with their short escape codes, only
+    // the cells that changed are sent and at most four frames a second, frames in between
+    // are dropped and made up for with one repaint. Also set by SGUI_LOW_BANDWIDTH=1.
+    pub fn set_low_bandwidth(&mut self, enabled: bool) {
+        if enabled != self.out.screen.is_some() {
+            let _ = self.out.flush();
+            self.out.screen = enabled.then(Screen::new);
+        }
+        self.low_bandwidth = enabled;
+        self.basic_colors = enabled || detect_console().basic_colors;
+        self.last_fg = None;
+    }
+    // Scrolls with the mouse wheel. The terminal can't select text while the mouse is
+    // captured for it, so it's off unless turned on here or by SGUI_MOUSE_WHEEL=1.
+    pub fn set_mouse_wheel(&mut self, enabled: bool) {
+        if enabled == self.mouse_wheel {
+            return;
+        }
+        let _ = match enabled {
+            true => self.out.execute(event::EnableMouseCapture),
+            false => self.out.execute(event::DisableMouseCapture),
+        };
+        self.mouse_wheel = enabled;
+    }
+    // Gets the terminal after every frame, whatever it queues is flushed with it. What it
+    // prints stays until the ui redraws those cells, in low bandwidth mode until what the
+    // ui has in them changes.
+    pub fn set_frame_hook(&mut self, hook: impl FnMut(&mut io::Stdout) -> Result<()> + 'static) {
+        self.frame_hook = Some(Box::new(hook));
+    }
+    pub fn clear_frame_hook(&mut self) {
+        self.frame_hook = None;
+    }
+    // redraws the scrolling label once it's moved on by a cell
+    fn step_marquee(&mut self) -> Result<()> {
+        let Some(marquee) = self.scrolling.as_mut() else {return Ok(())};
+        let offset = marquee.offset(MARQUEE_SPEED, self.clock.now());
+        if offset == marquee.shown {
+            return Ok(());
+        }
+        marquee.shown = offset;
+        let (cell, color) = (marquee.cell, marquee.colors.item_accent);
+        let window = marquee::label(&marquee.item)
+            .map(|text| marquee_window(text, offset as usize, cell.width.saturating_sub(1) as usize))
+            .unwrap_or_default();
+        self.out.queue(cursor::MoveTo(cell.x as u16, cell.y as u16))?;
+        self.fg(color)?;
+        if self.monochrome {
+            self.out.queue(style::SetAttribute(style::Attribute::Reverse))?;
+        }
+        self.out.queue(style::Print(window))?;
+        if self.monochrome {
+            self.out.queue(style::SetAttribute(style::Attribute::NoReverse))?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+    fn with_low_bandwidth(mut self, enabled: bool) -> Self {
+        self.set_low_bandwidth(enabled);
+        self
+    }
+    fn with_mouse_wheel(mut self, enabled: bool) -> Self {
+        self.set_mouse_wheel(enabled);
+        self
+    }
+    // asks the gui for a full repaint once it's due, tried again on the next tick while the
+    // event channel is full
+    fn send_refresh(&mut self) {
+        if self.refresh_at.is_none_or(|v| v > self.clock.now()) {
+            return;
+        }
+        let sent = match self.refresh.lock().unwrap().as_ref() {
+            Some(tx) => !matches!(tx.try_send(RendererEvent::Refresh), Err(TrySendError::Full(_))),
+            None => true,
+        };
+        if sent {
+            self.refresh_at = None;
+        }
+    }
+    // every color change also reapplies dimming, other attributes may have reset it
+    fn fg(&mut self, color: Color) -> Result<()> {
+        if self.dimmed {
+            self.out.queue(style::SetAttribute(style::Attribute::Dim))?;
+        }
+        let unchanged = self.low_bandwidth && self.last_fg == Some(color);
+        if !self.monochrome && !unchanged {
+            self.out.queue(style::SetForegroundColor(term_color(color, self.basic_colors)))?;
+            self.last_fg = Some(color);
+        }
+        Ok(())
+    }
+    // QR code from plain cells for consoles without block characters, each module is a
+    // square of two cells
+    #[cfg(feature = "qr")]
+    fn draw_qr_cells(&mut self, size: usize, dark: impl Fn(usize, usize) -> bool) -> Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let x = columns.saturating_sub(size as u16 * 2) / 2;
+        let y = rows.saturating_sub(size as u16) / 2;
+        for line in 0..size {
+            self.out.queue(cursor::MoveTo(x, y + line as u16))?;
+            for col in 0..size {
+                let dark = dark(col, line);
+                if self.monochrome {
+                    // the terminal's own colors inverted for the light modules
+                    let attr = if dark {style::Attribute::NoReverse} else {style::Attribute::Reverse};
+                    self.out.queue(style::SetAttribute(attr))?;
+                } else {
+                    let color = if dark {style::Color::Black} else {style::Color::White};
+                    self.out.queue(style::SetBackgroundColor(color))?;
                 }
+                self.out.queue(style::Print("  "))?;
+            }
+        }
+        self.out.queue(style::SetAttribute(style::Attribute::Reset))?;
+        self.out.queue(style::SetBackgroundColor(style::Color::Reset))?;
+        self.out.flush()?;
+        Ok(())
+    }
+    // draws the grid into the `columns` wide area starting at column `x`
+    fn draw_grid(&mut self, items: &[Vec<Item>], colors: &ColorPalette, selected_item_idx: (usize, usize), x: u16, columns: u16) -> Result<()> {
+        let (_, rows) = terminal::size()?;
+        let (selected_item_i, selected_item_j) = selected_item_idx;
+        for row in 0..std::mem::take(&mut self.overlay_rows) {
+            self.out.queue(cursor::MoveTo(0, rows.saturating_sub(1 + row)))?;
+            self.out.queue(terminal::Clear(terminal::ClearType::CurrentLine))?;
+        }
+
+        // header takes the first rows, the footer the last one
+        let visible_rows = rows.saturating_sub(self.header_rows + 1).max(1) as usize;
+        let max_scroll = items.len().saturating_sub(visible_rows);
+        let wheel = self.wheel.swap(0, Ordering::Relaxed);
+        if wheel != 0 {
+            self.v_scroll = (self.v_scroll as i32 + wheel).clamp(0, max_scroll as i32) as usize;
+        } else if self.last_selected != Some(selected_item_idx) && selected_item_i < items.len() {
+            self.v_scroll = keep_visible_with_margin(self.v_scroll, selected_item_i, visible_rows, items.len(), self.scroll_margin);
+        }
+        self.v_scroll = self.v_scroll.min(max_scroll);
+        self.last_selected = Some(selected_item_idx);
+
+        for i in 0..visible_rows {
+            let y = self.header_rows + i as u16;
+            self.out.queue(cursor::MoveTo(0, y))?;
+            self.out.queue(terminal::Clear(terminal::ClearType::CurrentLine))?;
+            let cur_line = self.v_scroll + i;
+            let Some(line) = items.get(cur_line) else {continue};
+            if line.is_empty() {
+                continue;
+            }
+
+            // rows that don't fit scroll to keep the selection visible, leaving space for arrows
+            let visible = (columns / MIN_CELL_WIDTH).max(1) as usize;
+            if self.h_scroll.len() <= cur_line {
+                self.h_scroll.resize(cur_line + 1, 0);
+            }
+            let (first, area_x, area_width) = if line.len() > visible {
+                let prev = self.h_scroll[cur_line];
+                let selected = if cur_line == selected_item_i { selected_item_j } else { prev };
+                (keep_visible(prev, selected, visible, line.len()), 1, columns.saturating_sub(2))
+            } else {
+                (0, 0, columns)
+            };
+            self.h_scroll[cur_line] = first;
+            let shown = line.len().min(visible);
+            let item_x_offset = area_width / shown as u16;
+
+            for (j, item) in line.iter().enumerate().skip(first).take(shown) {
+                let cell = DrawRect::new((x + area_x + item_x_offset * (j - first) as u16) as i32, y as i32, item_x_offset as u32, 1);
+                self.out.queue(cursor::MoveTo(cell.x as u16, y))?;
+                self.draw_item(item, colors, cell, cur_line == selected_item_i && j == selected_item_j)?;
+            }
+
+            self.fg(colors.item_outline)?;
+            if first > 0 {
+                self.out.queue(cursor::MoveTo(x, y))?;
+                self.out.queue(style::Print(self.glyphs.more[0]))?;
+            }
+            if first + shown < line.len() {
+                self.out.queue(cursor::MoveTo(x + columns - 1, y))?;
+                self.out.queue(style::Print(self.glyphs.more[1]))?;
+            }
+        }
+        Ok(())
+    }
+    // expects the cursor at the start of `cell`
+    fn draw_item(&mut self, item: &Item, colors: &ColorPalette, cell: DrawRect, selected: bool) -> Result<()> {
+        if !self.hooks.is_empty() {
+            let mut hooks = std::mem::take(&mut self.hooks);
+            let handled = hooks.run(item, selected, cell, colors, &mut CrosstermDrawContext {
+                out: &mut self.out,
+                monochrome: self.monochrome,
+                basic_colors: self.basic_colors,
+                glyphs: self.glyphs,
+            });
+            self.hooks = hooks;
+            self.last_fg = None;
+            if handled {
+                return Ok(());
+            }
+            self.out.queue(cursor::MoveTo(cell.x as u16, cell.y as u16))?;
+        }
+
+        let text_color = if selected {
+            colors.item_accent
+        } else {
+            colors.item_text
+        };
+        self.fg(text_color)?;
+        // without colors the selection is drawn reversed
+        if selected && self.monochrome {
+            self.out.queue(style::SetAttribute(style::Attribute::Reverse))?;
+        }
+        // items being edited or moved are underlined
+        if selected && self.editing {
+            self.out.queue(style::SetAttribute(style::Attribute::Underlined))?;
+        }
+        match item {
+            Item::Text(text) | Item::StatelessButton(text, _) | Item::Tile(text, _, _) => {
+                let width = cell.width.saturating_sub(1) as usize;
+                let overflow = text_cells(text).saturating_sub(width);
+                if selected && self.marquee && overflow > 0 {
+                    let now = self.clock.now();
+                    let mut marquee = Marquee::follow(self.scrolling.take(), item, colors, cell, overflow as u32, now);
+                    marquee.shown = marquee.offset(MARQUEE_SPEED, now);
+                    self.out.queue(style::Print(marquee_window(text, marquee.shown as usize, width)))?;
+                    self.scrolling = Some(marquee);
+                } else {
+                    self.out.queue(style::Print(&text))?;
+                }
+            },
+            Item::QrCode(data) => {
+                self.out.queue(style::Print(format!("{} {}", self.glyphs.qr_item, data)))?;
+            },
+            Item::Separator => {
+                self.fg(colors.item_outline)?;
+                self.out.queue(style::Print(self.glyphs.horizontal.repeat(cell.width.saturating_sub(1) as usize)))?;
+            },
+            Item::Header(text) => {
+                self.out.queue(style::SetAttribute(style::Attribute::Bold))?;
+                self.out.queue(style::Print(&text))?;
+                self.out.queue(style::SetAttribute(style::Attribute::NormalIntensity))?;
+            },
+            // no pictures in the terminal, items only get a line each, just the name
+            Item::Image(source, _) => {
+                self.out.queue(style::Print(format!("[{} {}]", self.glyphs.image, source.name())))?;
+            },
+            Item::Battery(battery) => {
+                self.out.queue(style::Print(power::label(battery.as_ref())))?;
+            },
+            Item::Clock(time) => {
+                self.out.queue(style::Print(datetime::clock_label(time.as_ref())))?;
+            },
+            Item::Value(text, value, _) => {
+                self.out.queue(style::Print(format!("{}: {}", text, value)))?;
+            },
+            Item::TextInput(text, value, masked, _) => {
+                self.out.queue(style::Print(format!("{}: [{}]", text, osk::mask(value, *masked))))?;
+            },
+            Item::ProgressBar(text, value, _) => {
+                const WIDTH: usize = 10;
+                let filled = (value * WIDTH as f32).round() as usize;
+                self.out.queue(style::Print(format!("{} {}{} {}", text, self.glyphs.bar[0].repeat(filled), self.glyphs.bar[1].repeat(WIDTH - filled), format::percent(*value))))?;
+            },
+            Item::Gauge(text, current, max, _) => {
+                const WIDTH: usize = 10;
+                let glyphs = self.glyphs.gauge;
+                let levels = glyphs.len() - 1;
+                let filled = (gauge_fraction(*current, *max) * (WIDTH * levels) as f32).round() as usize;
+                let (full, part) = (filled / levels, filled % levels);
+                let bar = format!("{}{}{}", glyphs[levels].repeat(full), if part > 0 { glyphs[part] } else { "" }, glyphs[0].repeat(WIDTH - full - usize::from(part > 0)));
+                self.out.queue(style::Print(format!("{} [", text)))?;
+                self.fg(colors.item_accent)?;
+                self.out.queue(style::Print(bar))?;
+                self.fg(text_color)?;
+                self.out.queue(style::Print(format!("] {}/{}", format::number(*current), format::number(*max))))?;
+            },
+            // arrows only where there's room to go
+            Item::Stepper(text, value, min, max, _, _) => {
+                let left = if value > min { self.glyphs.more[0] } else { " " };
+                let right = if value < max { self.glyphs.more[1] } else { " " };
+                self.out.queue(style::Print(format!("{}: {} {} {}", text, left, value, right)))?;
+            },
+            Item::Slider(text, min, max, _, value, _) => {
+                const WIDTH: usize = 10;
+                let filled = (slider_fraction(*min, *max, *value) * WIDTH as f32).round() as usize;
+                self.out.queue(style::Print(format!("{} [{}{}] {}", text, "=".repeat(filled), " ".repeat(WIDTH - filled), value)))?;
             },
-            Err(_) => break,
+            // greyed out while it can't be pressed
+            Item::Command(text, _, running, _) => {
+                if *running {
+                    self.fg(colors.item_outline)?;
+                    self.out.queue(style::Print(format!("{} {}", text, self.glyphs.running)))?;
+                } else {
+                    self.out.queue(style::Print(&text))?;
+                }
+            },
+            // only the newest line fits into a cell
+            Item::Log(lines, _) => {
+                self.out.queue(style::Print(lines.last().map(String::as_str).unwrap_or("")))?;
+            },
+            // OSC 8, terminals without support just show the text. Control characters could
+            // end the sequence early, urls with any are shown as text only.
+            Item::Link(text, url, _) => {
+                self.out.queue(style::SetAttribute(style::Attribute::Underlined))?;
+                if url.chars().any(char::is_control) {
+                    self.out.queue(style::Print(text))?;
+                } else {
+                    self.out.queue(style::Print(format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)))?;
+                }
+                self.out.queue(style::SetAttribute(style::Attribute::NoUnderline))?;
+            },
+            Item::Radio(text, _, picked, _) => {
+                self.out.queue(style::Print(format!("{} {}", self.glyphs.radio[usize::from(!*picked)], text)))?;
+            },
+            Item::Countdown(left, _) => {
+                self.out.queue(style::Print(format::countdown(*left)))?;
+            },
+            // the newest samples that fit, a cell each
+            Item::Sparkline(samples, _) => {
+                let glyphs = self.glyphs.spark;
+                let first = samples.len().saturating_sub(cell.width.saturating_sub(1) as usize);
+                let bars: String = sparkline_levels(&samples[first..]).iter()
+                    .map(|v| glyphs[((v * (glyphs.len() - 1) as f32).round() as usize).min(glyphs.len() - 1)])
+                    .collect();
+                self.out.queue(style::Print(bars))?;
+            },
+            Item::Table(text, _, rows, selected, _) => {
+                self.out.queue(style::Print(format!("{} ({}/{})", text, (selected + 1).min(rows.len()), rows.len())))?;
+            },
+            Item::Message(severity, text) => {
+                self.fg(colors.severity_color(*severity))?;
+                self.out.queue(style::Print(format!("{} {}", self.glyphs.severity[*severity as usize], text)))?;
+            },
+            Item::Spinner(text, frame, spinning, _) => {
+                self.out.queue(style::Print(&text))?;
+                if *spinning {
+                    let glyphs = self.glyphs.spinner;
+                    self.fg(colors.item_accent)?;
+                    self.out.queue(style::Print(format!(" {}", glyphs[frame % glyphs.len()])))?;
+                }
+            },
+            Item::StatefulButton(text, state, _) => {
+                if let Some(color) = colors.marker_color {
+                    self.fg(color)?;
+                    self.out.queue(style::Print(colors.marker(*state)))?;
+                    self.fg(text_color)?;
+                } else {
+                    self.out.queue(style::Print(colors.marker(*state)))?;
+                }
+                self.out.queue(style::Print(" "))?;
+                self.out.queue(style::Print(&text))?;
+            },
+            Item::Toggle(text, state, _) => {
+                self.out.queue(style::Print(format!("{} ", text)))?;
+                if *state {
+                    self.fg(colors.item_accent)?;
+                    self.out.queue(style::Print("[ON]"))?;
+                } else {
+                    self.out.queue(style::Print("[OFF]"))?;
+                }
+            },
+            Item::Date(text, date, _) => {
+                let active = (selected && self.editing).then_some(self.edit_segment);
+                self.out.queue(style::Print(format!("{}: {}", text, datetime::format_segments(&date.parts(), '-', active))))?;
+            },
+            Item::Time(text, time, _) => {
+                let active = (selected && self.editing).then_some(self.edit_segment);
+                self.out.queue(style::Print(format!("{}: {}", text, datetime::format_segments(&time.parts(), ':', active))))?;
+            },
+            Item::Selector(text, options, option_idx, _, _) => {
+                let option = options.get(*option_idx).map(String::as_str).unwrap_or("");
+                if selected && self.editing {
+                    let [open, close] = self.glyphs.editing;
+                    self.out.queue(style::Print(format!("{}: {} {} {}", text, open, option, close)))?;
+                } else {
+                    self.out.queue(style::Print(format!("{}: < {} >", text, option)))?;
+                }
+            },
+        };
+        if selected && self.editing {
+            self.out.queue(style::SetAttribute(style::Attribute::NoUnderline))?;
+        }
+        if selected && self.monochrome {
+            self.out.queue(style::SetAttribute(style::Attribute::NoReverse))?;
         }
+        Ok(())
     }
 }
 
 impl Renderer for CrosstermRenderer {
+    // the header is as tall as it was last drawn
+    fn viewport_info(&mut self) -> Option<ViewportInfo> {
+        let (columns, rows) = terminal::size().ok()?;
+        Some(ViewportInfo {
+            width: columns as u32,
+            height: rows as u32,
+            cell_width: 1,
+            cell_height: 1,
+            // the footer takes the last row
+            rows: rows.saturating_sub(self.header_rows + 1) as u32,
+            columns: columns as u32,
+        })
+    }
+    fn render(&mut self, frame: &Frame) -> Result<()> {
+        if self.low_bandwidth {
+            let now = self.clock.now();
+            let wait = self.last_render.map(|v| LOW_BANDWIDTH_GAP.saturating_sub(now - v)).unwrap_or_default();
+            if !wait.is_zero() {
+                // a repaint that's already planned covers this frame too
+                self.refresh_at.get_or_insert(now + wait);
+                return Ok(());
+            }
+            self.last_render = Some(now);
+        }
+        // the color may have been changed behind fg()'s back, e.g. by draw hooks
+        self.last_fg = None;
+        // a marquee the new items don't draw again has nothing left to scroll, neither has
+        // one that's covered
+        let items = frame.damaged(frame::Damage::Items);
+        if let Some(marquee) = self.scrolling.as_mut().filter(|_| items) {
+            marquee.seen = false;
+        }
+        frame::render_parts(self, frame)?;
+        if !frame.overlays.is_empty() || self.scrolling.as_ref().is_some_and(|v| !v.seen) {
+            self.scrolling = None;
+        }
+        if let Some(hook) = self.frame_hook.as_mut() {
+            hook(&mut self.out.stdout)?;
+            self.last_fg = None;
+            self.out.flush()?;
+        }
+        Ok(())
+    }
     fn get_event(&self) -> Option<Receiver<RendererEvent>> {
         let (tx, rx) = bounded(1);
-        thread::spawn(move || handle_events(tx));
+        *self.refresh.lock().unwrap() = Some(tx.clone());
+        let wheel = self.wheel.clone();
+        let bindings = self.bindings.clone();
+        thread::spawn(move || handle_events(tx, wheel, bindings));
         Some(rx)
     }
     fn draw_tab_header(&mut self, names: &[&str], colors: &ColorPalette) -> Result<()> {
         let (columns, _) = terminal::size()?;
+        self.h_scroll.clear();
 
-        // set up places where to put | characters
-        let mut vert_x = HashSet::new();
+        // every line of a name gets a line of its own, tabs that don't fit start another row
+        let lines = names.iter().map(|v| v.lines().count().max(1)).max().unwrap_or(1);
+        let mut rows: Vec<Vec<(usize, &str)>> = vec![Vec::new()];
         let mut tmp = 0;
         for name in names.iter() {
-            vert_x.insert(tmp);
-            tmp += name.len();
-            tmp += 1;
+            let width = name.lines().map(text_cells).max().unwrap_or(0) + 1;
+            if tmp > 0 && tmp + width >= columns as usize {
+                rows.push(Vec::new());
+                tmp = 0;
+            }
+            rows.last_mut().unwrap().push((tmp, name));
+            tmp += width;
         }
-        vert_x.insert(tmp);
-        vert_x.insert(columns as usize - 1);
+        self.header_rows = (2 + rows.len() * lines) as u16;
 
+        // set up places where to put | characters, per row
+        let vert_x: Vec<HashSet<usize>> = rows.iter().map(|row| {
+            let mut xs: HashSet<usize> = row.iter().map(|(x, _)| *x).collect();
+            if let Some((x, name)) = row.last() {
+                xs.insert(x + name.lines().map(text_cells).max().unwrap_or(0) + 1);
+            }
+            xs.insert(columns as usize - 1);
+            xs
+        }).collect();
 
         // draw vertical line above and below
         self.out.queue(terminal::Clear(terminal::ClearType::All))?;
         self.out.queue(cursor::MoveTo(0, 0))?;
-        self.out.queue(style::SetForegroundColor(colors.tab_outline.as_crossterm_color()))?;
+        self.fg(colors.tab_outline)?;
+        let g = self.glyphs;
         for x in 0..columns {
             match x { // note to Maya in future: the order really is important
-                0 => self.out.queue(style::Print("┌"))?,
-                _ if x == columns-1 => self.out.queue(style::Print("┐"))?,
-                _ if vert_x.contains(&(x as usize)) => self.out.queue(style::Print("┬"))?,
-                _ => self.out.queue(style::Print("─"))?,
+                0 => self.out.queue(style::Print(g.corners[0]))?,
+                _ if x == columns-1 => self.out.queue(style::Print(g.corners[1]))?,
+                _ if vert_x[0].contains(&(x as usize)) => self.out.queue(style::Print(g.tee_down))?,
+                _ => self.out.queue(style::Print(g.horizontal))?,
             };
         }
-        self.out.queue(cursor::MoveTo(0, 2))?;
+        self.out.queue(cursor::MoveTo(0, self.header_rows - 1))?;
         for x in 0..columns {
             match x {
-                0 => self.out.queue(style::Print("└"))?,
-                _ if x == columns-1 => self.out.queue(style::Print("┘"))?,
-                _ if vert_x.contains(&(x as usize)) => self.out.queue(style::Print("┴"))?,
-                _ => self.out.queue(style::Print("─"))?,
+                0 => self.out.queue(style::Print(g.corners[2]))?,
+                _ if x == columns-1 => self.out.queue(style::Print(g.corners[3]))?,
+                _ if vert_x[rows.len() - 1].contains(&(x as usize)) => self.out.queue(style::Print(g.tee_up))?,
+                _ => self.out.queue(style::Print(g.horizontal))?,
             };
         }
 
         // draw tab names
-        self.out.queue(cursor::MoveTo(0, 1))?;
-        self.out.queue(style::SetForegroundColor(colors.tab_text.as_crossterm_color()))?;
-        for (i, name) in names.iter().map(|v| format!(" {}", v)).enumerate() {
-            if i == 0 {
-                self.out.queue(style::SetForegroundColor(colors.tab_accent.as_crossterm_color()))?;
-                self.out.queue(style::Print(&name))?;
-                self.out.queue(style::SetForegroundColor(colors.tab_text.as_crossterm_color()))?;
-            } else {
-                self.out.queue(style::Print(&name))?;
+        self.fg(colors.tab_text)?;
+        for (r, row) in rows.iter().enumerate() {
+            for (x, name) in row.iter() {
+                let current = r == 0 && *x == 0;
+                if current {
+                    self.fg(colors.tab_accent)?;
+                    if self.monochrome {
+                        self.out.queue(style::SetAttribute(style::Attribute::Bold))?;
+                    }
+                }
+                for (l, line) in name.lines().enumerate() {
+                    self.out.queue(cursor::MoveTo(*x as u16, (1 + r * lines + l) as u16))?;
+                    self.out.queue(style::Print(format!(" {}", line)))?;
+                }
+                if current {
+                    if self.monochrome {
+                        self.out.queue(style::SetAttribute(style::Attribute::NormalIntensity))?;
+                    }
+                    self.fg(colors.tab_text)?;
+                }
             }
         }
 
         // draw | between tab names
-        self.out.queue(style::SetForegroundColor(colors.tab_outline.as_crossterm_color()))?;
-        for x in vert_x.into_iter() {
-            self.out.queue(cursor::MoveTo(x.try_into().unwrap(), 1))?;
-            self.out.queue(style::Print("│"))?;
+        self.fg(colors.tab_outline)?;
+        for (r, xs) in vert_x.into_iter().enumerate() {
+            for x in xs.into_iter() {
+                for l in 0..lines {
+                    self.out.queue(cursor::MoveTo(x.try_into().unwrap(), (1 + r * lines + l) as u16))?;
+                    self.out.queue(style::Print(g.vertical))?;
+                }
+            }
         }
 
         self.out.flush()?;
         Ok(())
     }
-    fn draw_items(&mut self, items: &Vec<Vec<Item>>, colors: &ColorPalette, selected_item_idx: (usize, usize)) -> Result<()> {
+    fn draw_items(&mut self, items: &[Vec<Item>], colors: &ColorPalette, selected_item_idx: (usize, usize)) -> Result<()> {
+        let (columns, _) = terminal::size()?;
+        self.draw_grid(items, colors, selected_item_idx, 0, columns)?;
+        self.out.flush()?;
+        Ok(())
+    }
+    fn draw_split(&mut self, list: &[Item], list_selected: usize, detail: &[Vec<Item>], detail_selected: Option<(usize, usize)>, colors: &ColorPalette) -> Result<()> {
         let (columns, rows) = terminal::size()?;
-        let (selected_item_i, selected_item_j) = selected_item_idx;
-        // TODO: scrolling
-        for (cur_line, line) in items.iter().enumerate() {
-            if cur_line > rows as usize {
-                break;
-            }
-            let items_num = line.len() as u16;
-            let item_x_offset = columns/items_num;
-
-            for (j, item) in line.iter().enumerate() {
-                self.out.queue(cursor::MoveTo(item_x_offset * j as u16, 3 + cur_line as u16))?;
-                if cur_line == selected_item_i && j == selected_item_j {
-                    self.out.queue(style::SetForegroundColor(colors.item_accent.as_crossterm_color()))?;
+        let list_width = columns / 3;
+
+        // the grid clears whole lines, so it goes first
+        self.draw_grid(detail, colors, detail_selected.unwrap_or((usize::MAX, usize::MAX)), list_width + 1, columns - list_width - 1)?;
+
+        let visible = rows.saturating_sub(self.header_rows) as usize;
+        let first = keep_visible_with_margin(0, list_selected, visible, list.len(), self.scroll_margin);
+        for i in 0..visible {
+            let y = self.header_rows + i as u16;
+            self.out.queue(cursor::MoveTo(0, y))?;
+            self.out.queue(style::Print(" ".repeat(list_width as usize)))?;
+            if let Some(item) = list.get(first + i) {
+                self.out.queue(cursor::MoveTo(0, y))?;
+                if first + i == list_selected && detail_selected.is_some() {
+                    // keep showing which entry the detail pane belongs to
+                    self.fg(colors.item_outline)?;
+                    self.out.queue(style::Print(self.glyphs.current))?;
                 } else {
-                    self.out.queue(style::SetForegroundColor(colors.item_text.as_crossterm_color()))?;
-                }
-                match item {
-                    Item::Text(text) | Item::StatelessButton(text, _) => {
-                        self.out.queue(style::Print(&text))?;
-                    },
-                    Item::StatefulButton(text, state, _) => {
-                        if *state {
-                            self.out.queue(style::Print("[ ] "))?;
-                        } else {
-                            self.out.queue(style::Print("[X] "))?;
-                        }
-                        self.out.queue(style::Print(&text))?;
-                    },
-                };
+                    self.out.queue(style::Print(" "))?;
+                }
+                let cell = DrawRect::new(1, y as i32, list_width.saturating_sub(1) as u32, 1);
+                self.draw_item(item, colors, cell, first + i == list_selected && detail_selected.is_none())?;
+            }
+            self.out.queue(cursor::MoveTo(list_width, y))?;
+            self.fg(colors.tab_outline)?;
+            self.out.queue(style::Print(self.glyphs.vertical))?;
+        }
+
+        self.out.flush()?;
+        Ok(())
+    }
+    fn draw_table(&mut self, headers: &[String], rows: &[Vec<String>], selected: usize, colors: &ColorPalette) -> Result<()> {
+        let (columns, term_rows) = terminal::size()?;
+        let count = rows.iter().map(Vec::len).max().unwrap_or(0).max(headers.len());
+        let natural: Vec<u32> = (0..count)
+            .map(|c| headers.get(c).into_iter().chain(rows.iter().filter_map(|v| v.get(c))).map(|v| text_cells(v)).max().unwrap_or(0) as u32)
+            .collect();
+        let widths = draw::column_widths(&natural, columns.saturating_sub(6) as u32, 3);
+        let inner_width = widths.iter().sum::<u32>() as usize + 3 * count.saturating_sub(1);
+        // borders and the header with the line under it take four rows
+        let visible = rows.len().min(term_rows.saturating_sub(8) as usize).max(1);
+        let first = keep_visible(0, selected, visible, rows.len());
+        let x = (columns.saturating_sub(inner_width as u16 + 4)) / 2;
+        let y = (term_rows.saturating_sub(visible as u16 + 4)) / 2;
+        let g = self.glyphs;
+        let line = |row: &[String]| widths.iter().enumerate()
+            .map(|(c, w)| fit_cells(row.get(c).map(String::as_str).unwrap_or(""), *w as usize))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", g.vertical));
+
+        self.fg(colors.item_outline)?;
+        self.out.queue(cursor::MoveTo(x, y))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[0], g.horizontal.repeat(inner_width + 2), g.corners[1])))?;
+        self.out.queue(cursor::MoveTo(x, y + 1))?;
+        self.out.queue(style::Print(format!("{} ", g.vertical)))?;
+        self.fg(colors.item_text)?;
+        self.out.queue(style::SetAttribute(style::Attribute::Bold))?;
+        self.out.queue(style::Print(line(headers)))?;
+        self.out.queue(style::SetAttribute(style::Attribute::NormalIntensity))?;
+        self.fg(colors.item_outline)?;
+        self.out.queue(style::Print(format!(" {}", g.vertical)))?;
+        self.out.queue(cursor::MoveTo(x, y + 2))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.vertical, g.horizontal.repeat(inner_width + 2), g.vertical)))?;
+        self.out.queue(cursor::MoveTo(x, y + visible as u16 + 3))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[2], g.horizontal.repeat(inner_width + 2), g.corners[3])))?;
+
+        for i in first..first + visible {
+            let row_y = y + 3 + (i - first) as u16;
+            self.out.queue(cursor::MoveTo(x, row_y))?;
+            self.fg(colors.item_outline)?;
+            self.out.queue(style::Print(format!("{} ", g.vertical)))?;
+            self.fg(if i == selected { colors.item_accent } else { colors.item_text })?;
+            let reverse = i == selected && self.monochrome;
+            if reverse {
+                self.out.queue(style::SetAttribute(style::Attribute::Reverse))?;
+            }
+            self.out.queue(style::Print(line(rows.get(i).map(Vec::as_slice).unwrap_or(&[]))))?;
+            if reverse {
+                self.out.queue(style::SetAttribute(style::Attribute::NoReverse))?;
+            }
+            self.fg(colors.item_outline)?;
+            // scroll hints on the right border
+            let border = match i {
+                _ if i == first && first > 0 => g.more[2],
+                _ if i == first + visible - 1 && first + visible < rows.len() => g.more[3],
+                _ => g.vertical,
+            };
+            self.out.queue(style::Print(format!(" {}", border)))?;
+        }
+
+        self.out.flush()?;
+        Ok(())
+    }
+    // the newest lines at the bottom, a hint on the border while older ones are cut off
+    fn draw_log(&mut self, lines: &[String], colors: &ColorPalette) -> Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let inner_width = lines.iter().map(|v| text_cells(v)).max().unwrap_or(0)
+            .min(columns.saturating_sub(6) as usize);
+        let visible = lines.len().min(rows.saturating_sub(6) as usize).max(1);
+        let first = lines.len().saturating_sub(visible);
+        let x = (columns.saturating_sub(inner_width as u16 + 4)) / 2;
+        let y = (rows.saturating_sub(visible as u16 + 2)) / 2;
+        let g = self.glyphs;
+
+        self.fg(colors.item_outline)?;
+        self.out.queue(cursor::MoveTo(x, y))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[0], g.horizontal.repeat(inner_width + 2), g.corners[1])))?;
+        self.out.queue(cursor::MoveTo(x, y + visible as u16 + 1))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[2], g.horizontal.repeat(inner_width + 2), g.corners[3])))?;
+
+        for row in 0..visible {
+            self.out.queue(cursor::MoveTo(x, y + 1 + row as u16))?;
+            self.fg(colors.item_outline)?;
+            self.out.queue(style::Print(format!("{} ", g.vertical)))?;
+            self.fg(colors.item_text)?;
+            self.out.queue(style::Print(fit_cells(lines.get(first + row).map(String::as_str).unwrap_or(""), inner_width)))?;
+            self.fg(colors.item_outline)?;
+            let border = if row == 0 && first > 0 { g.more[2] } else { g.vertical };
+            self.out.queue(style::Print(format!(" {}", border)))?;
+        }
+
+        self.out.flush()?;
+        Ok(())
+    }
+    fn draw_popup(&mut self, options: &[String], selected: usize, colors: &ColorPalette) -> Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let inner_width = options.iter().map(|v| text_cells(v)).max().unwrap_or(0)
+            .min(columns.saturating_sub(6) as usize);
+        let visible = options.len().min(rows.saturating_sub(6) as usize).max(1);
+        let first = keep_visible(0, selected, visible, options.len());
+        let x = (columns.saturating_sub(inner_width as u16 + 4)) / 2;
+        let y = (rows.saturating_sub(visible as u16 + 2)) / 2;
+
+        self.fg(colors.item_outline)?;
+        self.out.queue(cursor::MoveTo(x, y))?;
+        let g = self.glyphs;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[0], g.horizontal.repeat(inner_width + 2), g.corners[1])))?;
+        self.out.queue(cursor::MoveTo(x, y + visible as u16 + 1))?;
+        self.out.queue(style::Print(format!("{}{}{}", g.corners[2], g.horizontal.repeat(inner_width + 2), g.corners[3])))?;
+
+        for (i, option) in options.iter().enumerate().skip(first).take(visible) {
+            let row_y = y + 1 + (i - first) as u16;
+            self.out.queue(cursor::MoveTo(x, row_y))?;
+            self.fg(colors.item_outline)?;
+            self.out.queue(style::Print(format!("{} ", g.vertical)))?;
+            if i == selected {
+                self.fg(colors.item_accent)?;
+            } else {
+                self.fg(colors.item_text)?;
             }
+            let reverse = i == selected && self.monochrome;
+            if reverse {
+                self.out.queue(style::SetAttribute(style::Attribute::Reverse))?;
+            }
+            self.out.queue(style::Print(fit_cells(option, inner_width)))?;
+            if reverse {
+                self.out.queue(style::SetAttribute(style::Attribute::NoReverse))?;
+            }
+            self.fg(colors.item_outline)?;
+            // scroll hints on the right border
+            let border = match i {
+                _ if i == first && first > 0 => g.more[2],
+                _ if i == first + visible - 1 && first + visible < options.len() => g.more[3],
+                _ => g.vertical,
+            };
+            self.out.queue(style::Print(format!(" {}", border)))?;
+        }
+
+        self.out.flush()?;
+        Ok(())
+    }
+    // two rows of modules per line with half blocks, or a module per two cells without them
+    #[cfg(feature = "qr")]
+    fn draw_qr(&mut self, data: &str, _colors: &ColorPalette) -> Result<()> {
+        let Some((width, modules)) = qr::modules(data) else {return Ok(())};
+        let (columns, rows) = terminal::size()?;
+        let size = width + 2 * qr::QUIET_ZONE;
+        let dark = |x: usize, y: usize| {
+            let (x, y) = (x.wrapping_sub(qr::QUIET_ZONE), y.wrapping_sub(qr::QUIET_ZONE));
+            x < width && y < width && modules[y * width + x]
+        };
+        if !self.glyphs.half_blocks {
+            return self.draw_qr_cells(size, dark);
+        }
+        let x = columns.saturating_sub(size as u16) / 2;
+        let y = rows.saturating_sub((size as u16).div_ceil(2)) / 2;
+
+        // scanners want dark on light, without colors the terminal's own are inverted
+        if self.monochrome {
+            self.out.queue(style::SetAttribute(style::Attribute::Reverse))?;
+        } else {
+            self.out.queue(style::SetForegroundColor(style::Color::Black))?;
+            self.out.queue(style::SetBackgroundColor(style::Color::White))?;
+            self.last_fg = None;
+        }
+        for line in 0..size.div_ceil(2) {
+            let text: String = (0..size).map(|col| match (dark(col, line * 2), dark(col, line * 2 + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            }).collect();
+            self.out.queue(cursor::MoveTo(x, y + line as u16))?;
+            self.out.queue(style::Print(text))?;
+        }
+        self.out.queue(style::SetAttribute(style::Attribute::Reset))?;
+        self.out.queue(style::SetBackgroundColor(style::Color::Reset))?;
+
+        self.out.flush()?;
+        Ok(())
+    }
+    fn set_scroll_margin(&mut self, rows: usize) {
+        self.scroll_margin = rows;
+        // scrolls into place on the next frame
+        self.last_selected = None;
+    }
+    fn set_marquee(&mut self, enabled: bool) {
+        self.marquee = enabled;
+        self.scrolling = None;
+    }
+    fn set_edit_mode(&mut self, editing: bool) {
+        self.editing = editing;
+    }
+    fn set_edit_segment(&mut self, segment: usize) {
+        self.edit_segment = segment;
+    }
+    // wipe everything so no cell keeps the old colors
+    fn palette_changed(&mut self) {
+        let _ = self.out.queue(terminal::Clear(terminal::ClearType::All));
+    }
+    fn set_title(&mut self, title: &str) {
+        if !self.title_pushed {
+            let _ = self.out.queue(style::Print("\x1b[22;0t"));
+            self.title_pushed = true;
         }
+        let _ = self.out.queue(terminal::SetTitle(title));
+        let _ = self.out.flush();
+    }
+    fn set_bindings(&mut self, bindings: &Bindings) {
+        self.bindings = bindings.clone();
+    }
+    fn draw_hooks(&mut self) -> Option<&mut DrawHooks> {
+        Some(&mut self.hooks)
+    }
+    fn measure_text(&mut self, text: &str, _style: TextStyle) -> (u32, u32) {
+        (text_cells(text) as u32, 1)
+    }
+    fn set_dimmed(&mut self, dimmed: bool) -> bool {
+        self.dimmed = dimmed;
+        if !dimmed {
+            let _ = self.out.queue(style::SetAttribute(style::Attribute::NormalIntensity));
+        }
+        true
+    }
+    fn draw_blank(&mut self, logo: &str, position: (f32, f32), colors: &ColorPalette) -> Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let free_x = columns.saturating_sub(text_cells(logo) as u16);
+        self.out.queue(terminal::Clear(terminal::ClearType::All))?;
+        self.out.queue(cursor::MoveTo((free_x as f32 * position.0) as u16, (rows.saturating_sub(1) as f32 * position.1) as u16))?;
+        self.fg(colors.item_text)?;
+        self.out.queue(style::Print(logo))?;
         self.out.flush()?;
         Ok(())
     }
-    fn tick(&mut self) {}
+    // the terminal bell is all there is, so only errors get it
+    fn feedback(&mut self, feedback: Feedback) {
+        if feedback == Feedback::Error {
+            let _ = self.out.queue(style::Print('\x07'));
+            let _ = self.out.flush();
+        }
+    }
+    // status line on the last row, on top of the footer if there is one
+    fn draw_readout(&mut self, text: &str, colors: &ColorPalette) -> Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let text = format!(" {} ", text);
+        let x = (columns as usize).saturating_sub(text_cells(&text)) / 2;
+        self.out.queue(cursor::MoveTo(0, rows.saturating_sub(1)))?;
+        self.out.queue(terminal::Clear(terminal::ClearType::CurrentLine))?;
+        self.out.queue(cursor::MoveTo(x as u16, rows.saturating_sub(1)))?;
+        self.fg(colors.item_accent)?;
+        self.out.queue(style::SetAttribute(style::Attribute::Reverse))?;
+        self.out.queue(style::Print(text))?;
+        self.out.queue(style::SetAttribute(style::Attribute::NoReverse))?;
+        self.overlay_rows = self.overlay_rows.max(1);
+        self.out.flush()?;
+        Ok(())
+    }
+    // typed text on top, then a row per row of keys, all at the bottom of the terminal
+    fn draw_keyboard(&mut self, text: &str, keys: &[Vec<String>], selected: (usize, usize), colors: &ColorPalette) -> Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let height = keys.len() as u16 + 1;
+        let top = rows.saturating_sub(height);
+        for row in top..rows {
+            self.out.queue(cursor::MoveTo(0, row))?;
+            self.out.queue(terminal::Clear(terminal::ClearType::CurrentLine))?;
+        }
+
+        // the end of long text stays in view
+        let shown: String = {
+            let skip = (text_cells(text) + 3).saturating_sub(columns as usize);
+            text.chars().skip(skip).collect()
+        };
+        self.out.queue(cursor::MoveTo(0, top))?;
+        self.fg(colors.item_accent)?;
+        self.out.queue(style::Print(format!("> {}_", shown)))?;
+
+        for (i, row) in keys.iter().enumerate() {
+            let line: usize = row.iter().map(|v| text_cells(v) + 2).sum();
+            self.out.queue(cursor::MoveTo((columns as usize).saturating_sub(line) as u16 / 2, top + 1 + i as u16))?;
+            for (j, key) in row.iter().enumerate() {
+                let focused = (i, j) == selected;
+                self.fg(if focused { colors.item_accent } else { colors.item_text })?;
+                if focused {
+                    self.out.queue(style::SetAttribute(style::Attribute::Reverse))?;
+                }
+                self.out.queue(style::Print(format!(" {} ", key)))?;
+                if focused {
+                    self.out.queue(style::SetAttribute(style::Attribute::NoReverse))?;
+                }
+            }
+        }
+        self.overlay_rows = self.overlay_rows.max(height);
+        self.out.flush()?;
+        Ok(())
+    }
+    fn draw_primitives(&mut self, primitives: &[Primitive], _colors: &ColorPalette) -> Result<()> {
+        draw::draw_primitives(&mut CrosstermDrawContext {
+            out: &mut self.out,
+            monochrome: self.monochrome,
+            basic_colors: self.basic_colors,
+            glyphs: self.glyphs,
+        }, primitives)?;
+        self.out.flush()?;
+        Ok(())
+    }
+    fn draw_footer(&mut self, items: &[Item], colors: &ColorPalette, selected: Option<usize>) -> Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let y = rows.saturating_sub(1);
+        self.out.queue(cursor::MoveTo(0, y))?;
+        self.out.queue(terminal::Clear(terminal::ClearType::CurrentLine))?;
+
+        if !items.is_empty() {
+            let x_step = columns / items.len() as u16;
+            for (j, item) in items.iter().enumerate() {
+                let cell = DrawRect::new((x_step * j as u16) as i32, y as i32, x_step as u32, 1);
+                self.out.queue(cursor::MoveTo(cell.x as u16, y))?;
+                self.draw_item(item, colors, cell, selected == Some(j))?;
+            }
+        }
+
+        self.out.flush()?;
+        Ok(())
+    }
+    fn draw_page_indicator(&mut self, current: usize, total: usize, colors: &ColorPalette) -> Result<()> {
+        let (columns, _) = terminal::size()?;
+        let label = format!(" {}/{} ", current + 1, total);
+        let width = total + text_cells(&label);
+
+        // sits on the bottom border of the tab header
+        self.out.queue(cursor::MoveTo((columns as usize).saturating_sub(width) as u16 / 2, self.header_rows - 1))?;
+        for i in 0..total {
+            if i == current {
+                self.fg(colors.tab_accent)?;
+                self.out.queue(style::Print(self.glyphs.page[0]))?;
+            } else {
+                self.fg(colors.tab_outline)?;
+                self.out.queue(style::Print(self.glyphs.page[1]))?;
+            }
+        }
+        self.fg(colors.tab_text)?;
+        self.out.queue(style::Print(&label))?;
+
+        self.out.flush()?;
+        Ok(())
+    }
+    // nowhere to report a failed write from here, the next frame draws it all again anyway
+    fn tick(&mut self) {
+        self.send_refresh();
+        let _ = self.step_marquee();
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn next_tick(&self) -> Option<Duration> {
+        self.refresh_at
+    }
 }
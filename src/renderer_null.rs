@@ -0,0 +1,161 @@
+use crate::{Renderer, ColorPalette, RendererEvent, ViewportInfo, clock::Clock, feedback::Feedback, layout::Item};
+use anyhow::Result;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+// Draws nothing, for driving a Gui from tests or other headless code
+#[derive(Default)]
+pub struct NullRenderer;
+
+impl Renderer for NullRenderer {
+    fn draw_tab_header(&mut self, _names: &[&str], _colors: &ColorPalette) -> Result<()> {
+        Ok(())
+    }
+    fn draw_items(&mut self, _items: &[Vec<Item>], _colors: &ColorPalette, _selected_item_idx: (usize, usize)) -> Result<()> {
+        Ok(())
+    }
+    fn get_event(&self) -> Option<Receiver<RendererEvent>> {
+        None
+    }
+    fn tick(&mut self) {}
+}
+
+// what a RecordingRenderer was last asked to draw
+#[derive(Default)]
+pub struct Recording {
+    pub tabs: Vec<String>,
+    pub items: Vec<Vec<Item>>,
+    pub selected: (usize, usize),
+    // whether edit mode was on for the last draw_items, e.g. the grab highlight
+    pub edit_drawn: bool,
+    // text of the on-screen keyboard, None before it was ever drawn
+    pub keyboard: Option<String>,
+    // options and selection of the last popup
+    pub popup: Option<(Vec<String>, usize)>,
+    // value readout drawn over the last draw_items, if there was one
+    pub readout: Option<String>,
+    // data of the QR code drawn over the last draw_items, if there was one
+    pub qr: Option<String>,
+    // every sound or rumble asked for
+    pub feedback: Vec<Feedback>,
+    // Gui clock at every tick
+    pub ticks: Vec<Duration>,
+    // reported by viewport_info()
+    pub viewport: Option<ViewportInfo>,
+    // reported by controller_name()
+    pub controller: Option<String>,
+    // palette of the last draw_items
+    pub palette: Option<ColorPalette>,
+    // set by set_marquee()
+    pub marquee: bool,
+    // reported by smooth_transitions(), lets theme changes fade
+    pub smooth: bool,
+    editing: bool,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+// Draws nothing but keeps what it was asked to draw, for tests to look at. Clones share
+// the recording, so one can go to the Gui and the other stay with the test.
+#[derive(Clone)]
+pub struct RecordingRenderer {
+    recording: Arc<Mutex<Recording>>,
+    tx: Sender<RendererEvent>,
+    rx: Receiver<RendererEvent>,
+}
+
+impl Default for RecordingRenderer {
+    fn default() -> Self {
+        let (tx, rx) = unbounded();
+        Self {
+            recording: Arc::default(),
+            tx,
+            rx,
+        }
+    }
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn recording(&self) -> MutexGuard<'_, Recording> {
+        self.recording.lock().unwrap()
+    }
+    // item of the last draw at (row, column)
+    pub fn item(&self, row: usize, column: usize) -> Item {
+        self.recording().items[row][column].clone()
+    }
+    // handed to the Gui like an event from the window
+    pub fn send(&self, ev: RendererEvent) {
+        let _ = self.tx.send(ev);
+    }
+    pub fn set_controller(&self, name: Option<&str>) {
+        self.recording().controller = name.map(str::to_string);
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn draw_tab_header(&mut self, names: &[&str], _colors: &ColorPalette) -> Result<()> {
+        self.recording().tabs = names.iter().map(|v| v.to_string()).collect();
+        Ok(())
+    }
+    fn draw_items(&mut self, items: &[Vec<Item>], colors: &ColorPalette, selected_item_idx: (usize, usize)) -> Result<()> {
+        let mut recording = self.recording();
+        recording.items = items.to_vec();
+        recording.selected = selected_item_idx;
+        recording.edit_drawn = recording.editing;
+        recording.palette = Some(colors.clone());
+        recording.readout = None;
+        recording.qr = None;
+        Ok(())
+    }
+    fn draw_popup(&mut self, options: &[String], selected: usize, _colors: &ColorPalette) -> Result<()> {
+        self.recording().popup = Some((options.to_vec(), selected));
+        Ok(())
+    }
+    fn draw_qr(&mut self, data: &str, _colors: &ColorPalette) -> Result<()> {
+        self.recording().qr = Some(data.to_string());
+        Ok(())
+    }
+    fn draw_readout(&mut self, text: &str, _colors: &ColorPalette) -> Result<()> {
+        self.recording().readout = Some(text.to_string());
+        Ok(())
+    }
+    fn draw_keyboard(&mut self, text: &str, _keys: &[Vec<String>], _selected: (usize, usize), _colors: &ColorPalette) -> Result<()> {
+        self.recording().keyboard = Some(text.to_string());
+        Ok(())
+    }
+    fn feedback(&mut self, feedback: Feedback) {
+        self.recording().feedback.push(feedback);
+    }
+    fn set_edit_mode(&mut self, editing: bool) {
+        self.recording().editing = editing;
+    }
+    fn set_marquee(&mut self, enabled: bool) {
+        self.recording().marquee = enabled;
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.recording().clock = Some(clock);
+    }
+    fn viewport_info(&mut self) -> Option<ViewportInfo> {
+        self.recording().viewport
+    }
+    fn controller_name(&self) -> Option<String> {
+        self.recording().controller.clone()
+    }
+    fn smooth_transitions(&self) -> bool {
+        self.recording().smooth
+    }
+    fn get_event(&self) -> Option<Receiver<RendererEvent>> {
+        Some(self.rx.clone())
+    }
+    fn tick(&mut self) {
+        let mut recording = self.recording();
+        if let Some(now) = recording.clock.as_ref().map(|v| v.now()) {
+            recording.ticks.push(now);
+        }
+    }
+}
@@ -1,42 +1,450 @@
 use crate::{
     Item,
+    layout::{ImageSource, Severity},
+    Color,
     ColorPalette,
     RendererEvent,
     Renderer,
+    ViewportInfo,
+    CacheLimits,
+    clock::{Clock, SystemClock},
+    keep_visible,
+    keep_visible_with_margin,
+    draw::{self, DrawContext, DrawHooks, DrawRect, Primitive, TextStyle},
+    config::Bindings,
+    controller::ConfirmSwap,
+    datetime,
+    power,
+    osk,
+    format,
+    feedback::Feedback,
+    marquee::{self, Marquee},
+    frame::{self, Frame},
+    layout::{gauge_fraction, slider_fraction, sparkline_levels},
 };
 use std::{
-    sync::{mpsc, Mutex},
-    collections::HashMap,
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
+    time::Duration,
+    collections::{HashMap, HashSet},
+    thread,
+    path::{Path, PathBuf},
 };
 use sdl2::{
     render::{self, Texture},
     rect::Rect,
+    pixels::PixelFormatEnum,
+    surface::Surface,
     event,
     ttf,
     video,
 };
+use crossbeam_channel::{Sender, Receiver, unbounded};
 use anyhow::Result;
+#[cfg(feature = "qr")]
+use crate::qr;
 
 pub fn new() -> Result<SdlRenderer> {
-    SdlRenderer::new()
+    SdlRenderer::new(FontConfig::default(), None)
+}
+
+pub fn new_with_fonts(fonts: FontConfig) -> Result<SdlRenderer> {
+    SdlRenderer::new(fonts, None)
+}
+
+// Borderless always-on-top window of (width, height) that starts out hidden, for osd::Osd.
+pub fn new_overlay(fonts: FontConfig, size: (u32, u32)) -> Result<SdlRenderer> {
+    SdlRenderer::new(fonts, Some(size))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Cjk,
+    Symbols,
+}
+
+impl Script {
+    pub fn of(ch: char) -> Option<Script> {
+        Some(match ch as u32 {
+            0x0000..=0x024F | 0x1E00..=0x1EFF => Script::Latin,
+            0x0370..=0x03FF | 0x1F00..=0x1FFF => Script::Greek,
+            0x0400..=0x052F => Script::Cyrillic,
+            0x0590..=0x05FF => Script::Hebrew,
+            0x0600..=0x06FF | 0x0750..=0x077F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Script::Arabic,
+            0x0900..=0x097F => Script::Devanagari,
+            0x0E00..=0x0E7F => Script::Thai,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+            0x2E80..=0x2FDF | 0x3000..=0x30FF | 0x3190..=0x31FF | 0x3400..=0x4DBF
+                | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF => Script::Cjk,
+            0x2000..=0x2BFF | 0x1F000..=0x1FAFF => Script::Symbols,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FontFallback {
+    // None means the font is tried for every script
+    pub script: Option<Script>,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct FontConfig {
+    pub primary: PathBuf,
+    pub fallbacks: Vec<FontFallback>,
+    pub size: u16,
+}
+
+impl FontConfig {
+    pub fn new(primary: impl Into<PathBuf>, size: u16) -> Self {
+        Self {
+            primary: primary.into(),
+            fallbacks: Vec::new(),
+            size,
+        }
+    }
+    pub fn fallback(mut self, script: Option<Script>, path: impl Into<PathBuf>) -> Self {
+        self.fallbacks.push(FontFallback{ script, path: path.into() });
+        self
+    }
+}
+
+// what's drawn behind the tab header and the items instead of the palette's background colors
+#[derive(Debug, Clone)]
+pub enum Background {
+    Solid,
+    // stretched over the whole window, bmp only unless the sdl2-image feature is on
+    Image { path: PathBuf, dim: f32 },
+    Gradient { top: Color, bottom: Color, dim: f32 },
+}
+
+// what got dropped from a cache, passed to the eviction callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evicted<'a> {
+    Text(&'a str),
+    Image(&'a ImageSource),
+}
+
+// a picture decoded off the render thread, as pixels since surfaces can't cross threads
+struct Decoded {
+    source: ImageSource,
+    // (ARGB8888 data, width, height, pitch), None if it couldn't be loaded
+    pixels: Option<(Vec<u8>, u32, u32, u32)>,
+}
+
+fn decode_image(path: &Path) -> Result<Surface<'static>> {
+    #[cfg(feature = "sdl2-image")]
+    let surface = {
+        use sdl2::image::LoadSurface;
+        Surface::from_file(path).map_err(anyhow::Error::msg)?
+    };
+    #[cfg(not(feature = "sdl2-image"))]
+    let surface = Surface::load_bmp(path).map_err(anyhow::Error::msg)?;
+    Ok(surface)
+}
+
+// bytes go through the same loaders as files
+fn decode_source(source: &ImageSource) -> Result<Surface<'static>> {
+    let data = match source {
+        ImageSource::Path(path) => return decode_image(path),
+        ImageSource::Bytes(_, data) => data,
+    };
+    let rwops = sdl2::rwops::RWops::from_bytes(data).map_err(anyhow::Error::msg)?;
+    #[cfg(feature = "sdl2-image")]
+    let surface = {
+        use sdl2::image::ImageRWops;
+        rwops.load().map_err(anyhow::Error::msg)?
+    };
+    #[cfg(not(feature = "sdl2-image"))]
+    let surface = {
+        let mut rwops = rwops;
+        Surface::load_bmp_rw(&mut rwops).map_err(anyhow::Error::msg)?
+    };
+    Ok(surface)
+}
+
+fn decode_pixels(source: &ImageSource) -> Result<(Vec<u8>, u32, u32, u32)> {
+    let surface = decode_source(source)?
+        .convert_format(PixelFormatEnum::ARGB8888)
+        .map_err(anyhow::Error::msg)?;
+    let pixels = surface.with_lock(|v| v.to_vec());
+    Ok((pixels, surface.width(), surface.height(), surface.pitch()))
+}
+
+type EvictionCallback = Box<dyn FnMut(Evicted)>;
+type FrameHook = Box<dyn FnMut(&mut render::Canvas<video::Window>) -> Result<()>>;
+
+// Textures by key, the least recently used ones go once they add up to more than the
+// limit. The newest entry always stays, however big it is, and so does everything the
+// frame on screen and the one being drawn use. Visible pictures would otherwise be
+// decoded, dropped and decoded again for as long as they're shown.
+struct TextureCache<K> {
+    entries: HashMap<K, (Option<Texture>, u64)>,
+    used: u64,
+    // `used` when the frame on screen and the one being drawn were started
+    frames: (u64, u64),
+    bytes: usize,
+    limit: Option<usize>,
+}
+
+impl<K: std::hash::Hash + Eq + Clone> TextureCache<K> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            used: 0,
+            frames: (0, 0),
+            bytes: 0,
+            limit: None,
+        }
+    }
+    fn contains<Q: std::hash::Hash + Eq + ?Sized>(&self, key: &Q) -> bool where K: std::borrow::Borrow<Q> {
+        self.entries.contains_key(key)
+    }
+    fn start_frame(&mut self) {
+        self.frames = (self.frames.1, self.used);
+    }
+    // None for keys that are cached as failed
+    fn get<Q: std::hash::Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut Texture> where K: std::borrow::Borrow<Q> {
+        self.used += 1;
+        let (texture, used) = self.entries.get_mut(key)?;
+        *used = self.used;
+        texture.as_mut()
+    }
+    // returns what had to go to make room
+    fn insert(&mut self, key: K, texture: Option<Texture>) -> Vec<K> {
+        self.used += 1;
+        self.bytes += texture.as_ref().map(texture_bytes).unwrap_or(0);
+        if let Some((Some(old), _)) = self.entries.insert(key.clone(), (texture, self.used)) {
+            self.bytes -= texture_bytes(&old);
+            unsafe { old.destroy() };
+        }
+        self.shrink(Some(&key))
+    }
+    // drops the oldest entries other than `keep` until the rest fits into the limit
+    fn shrink(&mut self, keep: Option<&K>) -> Vec<K> {
+        let mut evicted = Vec::new();
+        while self.limit.is_some_and(|v| self.bytes > v) {
+            let Some(oldest) = self.entries.iter()
+                .filter(|(k, (_, used))| Some(*k) != keep && *used <= self.frames.0)
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(k, _)| k.clone()) else {break};
+            self.remove(&oldest);
+            evicted.push(oldest);
+        }
+        evicted
+    }
+    fn remove(&mut self, key: &K) {
+        if let Some((Some(texture), _)) = self.entries.remove(key) {
+            self.bytes -= texture_bytes(&texture);
+            unsafe { texture.destroy() };
+        }
+    }
+}
+
+fn texture_bytes(texture: &Texture) -> usize {
+    let query = texture.query();
+    query.width as usize * query.height as usize * 4
+}
+
+// how the screen changes over to another tab, both take `Duration` to finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabTransition {
+    None,
+    // the new tab pushes the old one out, from the right when moving to a later tab
+    Slide(Duration),
+    Crossfade(Duration),
+}
+
+// a tab transition that's playing, stepped from tick()
+struct Transition {
+    kind: TabTransition,
+    old: Texture,
+    new: Texture,
+    forward: bool,
+    started: Duration,
+}
+
+// how outlines, the selection highlight and popups are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderStyle {
+    pub radius: u32,
+    pub thickness: u32,
+    // fill the selected cell with the outline color at low opacity on top of outlining it
+    pub filled_selection: bool,
+}
+
+impl Default for BorderStyle {
+    fn default() -> Self {
+        Self {
+            radius: 0,
+            thickness: 1,
+            filled_selection: false,
+        }
+    }
+}
+
+// how far row `y` (relative to the top of a `height` tall rect) is pushed in by the corners
+fn corner_inset(radius: u32, height: u32, y: u32) -> i32 {
+    let radius = radius.min(height / 2) as f32;
+    let dy = if (y as f32) < radius {
+        radius - y as f32 - 0.5
+    } else if y as f32 >= height as f32 - radius {
+        y as f32 + 0.5 - (height as f32 - radius)
+    } else {
+        return 0;
+    };
+    (radius - (radius * radius - dy * dy).max(0.0).sqrt()).round() as i32
+}
+
+fn fill_rounded_rect(canvas: &mut render::Canvas<video::Window>, rect: Rect, radius: u32) -> Result<()> {
+    if radius == 0 {
+        return canvas.fill_rect(rect).map_err(anyhow::Error::msg);
+    }
+    let radius = radius.min(rect.width() / 2);
+    for y in 0..rect.height() {
+        let inset = corner_inset(radius, rect.height(), y);
+        let row = rect.y() + y as i32;
+        canvas.draw_line((rect.x() + inset, row), (rect.right() - 1 - inset, row))
+            .map_err(anyhow::Error::msg)?;
+    }
+    Ok(())
+}
+
+// `sweep` degrees of a ring, clockwise from `start` degrees with 0 pointing up
+fn draw_arc(canvas: &mut render::Canvas<video::Window>, center: (i32, i32), radius: u32, thickness: u32, start: f32, sweep: f32) -> Result<()> {
+    let steps = (sweep / 10.0).ceil().max(1.0) as usize;
+    let point = |r: f32, degrees: f32| {
+        let angle = degrees.to_radians();
+        (center.0 + (angle.sin() * r).round() as i32, center.1 - (angle.cos() * r).round() as i32)
+    };
+    for layer in 0..thickness {
+        let r = radius.saturating_sub(layer) as f32;
+        for step in 0..steps {
+            let from = start + sweep * step as f32 / steps as f32;
+            let to = start + sweep * (step + 1) as f32 / steps as f32;
+            canvas.draw_line(point(r, from), point(r, to))
+                .map_err(anyhow::Error::msg)?;
+        }
+    }
+    Ok(())
+}
+
+fn draw_rounded_rect(canvas: &mut render::Canvas<video::Window>, rect: Rect, style: &BorderStyle) -> Result<()> {
+    let t = style.thickness.max(1).min(rect.width() / 2).min(rect.height() / 2).max(1);
+    if style.radius == 0 && t == 1 {
+        return canvas.draw_rect(rect).map_err(anyhow::Error::msg);
+    }
+    let radius = style.radius.min(rect.width() / 2);
+    let inner_radius = radius.saturating_sub(t);
+
+    // every row is the outer span minus the inner rect's span, if the row crosses it
+    for y in 0..rect.height() {
+        let row = rect.y() + y as i32;
+        let (left, right) = (rect.x() + corner_inset(radius, rect.height(), y), rect.right() - 1 - corner_inset(radius, rect.height(), y));
+        if y < t || y >= rect.height() - t {
+            canvas.draw_line((left, row), (right, row)).map_err(anyhow::Error::msg)?;
+            continue;
+        }
+        let inset = corner_inset(inner_radius, rect.height() - 2 * t, y - t);
+        let (inner_left, inner_right) = (rect.x() + t as i32 + inset, rect.right() - 1 - t as i32 - inset);
+        canvas.draw_line((left, row), (inner_left - 1, row)).map_err(anyhow::Error::msg)?;
+        canvas.draw_line((inner_right + 1, row), (right, row)).map_err(anyhow::Error::msg)?;
+    }
+    Ok(())
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self::new("/usr/share/fonts/liberation/LiberationSans-Regular.ttf", 28)
+            .fallback(Some(Script::Cjk), "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc")
+            .fallback(Some(Script::Hangul), "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc")
+            .fallback(Some(Script::Cjk), "/usr/share/fonts/wenquanyi/wqy-zenhei/wqy-zenhei.ttc")
+            .fallback(None, "/usr/share/fonts/noto/NotoSans-Regular.ttf")
+            .fallback(Some(Script::Symbols), "/usr/share/fonts/noto/NotoSansSymbols2-Regular.ttf")
+            .fallback(None, "/usr/share/fonts/TTF/DejaVuSans.ttf")
+    }
 }
 
 pub struct SdlRenderer {
-    sdl2: sdl2::Sdl,
-    video: sdl2::VideoSubsystem,
+    // only held on to, SDL shuts down with them
+    _sdl2: sdl2::Sdl,
+    _video: sdl2::VideoSubsystem,
     canvas: render::Canvas<video::Window>,
-    ttf: ttf::Sdl2TtfContext,
+    // never dropped, the fonts loaded from it live as long as the renderer does
+    ttf: &'static ttf::Sdl2TtfContext,
+    // primary font and the fallbacks that exist on this system by size, with their paths
+    loaded_fonts: HashMap<u16, Vec<(Option<Script>, ttf::Font<'static, 'static>, PathBuf)>>,
     text_creator: render::TextureCreator<video::WindowContext>,
-    text_map: HashMap<String, Texture>,
-    rx_mutex: Mutex<Option<mpsc::Receiver<RendererEvent>>>,
-    event_watch: event::EventWatch<'static, RendererEventWatch>,
+    text_map: TextureCache<String>,
+    // measure_text() results for text that wasn't drawn yet
+    text_sizes: HashMap<String, (u32, u32)>,
+    fonts: FontConfig,
+    rx_mutex: Mutex<Option<Receiver<RendererEvent>>>,
+    _event_watch: event::EventWatch<'static, RendererEventWatch>,
+    game_controller: Option<sdl2::GameControllerSubsystem>,
+    // controllers only report axis motion while they're open
+    controllers: Vec<sdl2::controller::GameController>,
+    // see RendererEventWatch
+    hotplug: Arc<AtomicBool>,
+    swap: Arc<Mutex<ConfirmSwap>>,
     pump: sdl2::EventPump,
     fontsize: u16,
     font_height: u32,
+    // grows with the tab names, never below min_header_height
+    header_height: u32,
+    min_header_height: u32,
+    // bottom row taken by the footer of the current tab, 0 without one
+    footer_height: u32,
+    // first visible cell of every row
+    h_scroll: Vec<usize>,
+    // see Renderer::set_scroll_margin()
+    scroll_margin: usize,
+    // see Renderer::set_marquee(), with the label scrolling right now and the viewport of its cell
+    marquee: bool,
+    scrolling: Option<(Marquee, Rect)>,
+    editing: bool,
+    edit_segment: usize,
+    background: Background,
+    background_texture: Option<Texture>,
+    // pictures of Item::Tile and Item::Image, None for ones that failed to load
+    tile_images: TextureCache<ImageSource>,
+    on_evict: Option<EvictionCallback>,
+    // see set_frame_hook()
+    frame_hook: Option<FrameHook>,
+    // tile pictures are decoded one after the other on a thread of their own
+    image_jobs: Sender<ImageSource>,
+    decoded: Receiver<Decoded>,
+    decoding: HashSet<ImageSource>,
+    border: BorderStyle,
+    hooks: DrawHooks,
+    bindings: Arc<Mutex<Bindings>>,
+    pad_buttons: Arc<AtomicBool>,
+    // the draw_* methods leave presenting to render() while it's putting a frame together
+    composing: bool,
+    tab_transition: TabTransition,
+    last_tab: Option<usize>,
+    transition: Option<Transition>,
+    clock: Arc<dyn Clock>,
+    #[cfg(feature = "shaping")]
+    shaper: crate::shaping::Shaper,
 }
 
 struct RendererEventWatch {
-    chan: mpsc::Sender<RendererEvent>,
+    chan: Sender<RendererEvent>,
+    bindings: Arc<Mutex<Bindings>>,
+    // whether buttons other than guide are read here, see Renderer::read_pad_buttons()
+    pad_buttons: Arc<AtomicBool>,
+    swap: Arc<Mutex<ConfirmSwap>>,
+    // tells tick() to open the controllers again
+    hotplug: Arc<AtomicBool>,
 }
 
 impl sdl2::event::EventWatchCallback for RendererEventWatch {
@@ -47,24 +455,96 @@ impl sdl2::event::EventWatchCallback for RendererEventWatch {
                 WindowEvent,
             },
             keyboard::Keycode,
+            controller,
         };
-        use crate::HidEvent;
+        use crate::{HidEvent, Axis, ActionButton};
 
         let new_ev = match ev {
             Event::Quit{..} => RendererEvent::WindowClosed,
             Event::Window{win_event, ..} => match win_event {
-                WindowEvent::Resized(..) | WindowEvent::SizeChanged(..) => RendererEvent::Refresh,
+                // comes after Resized for every change, including ones made by the app
+                WindowEvent::SizeChanged(..) => RendererEvent::Resized,
                 WindowEvent::Close => RendererEvent::WindowClosed,
                 _ => return,
             },
+            Event::ControllerDeviceAdded{..} | Event::ControllerDeviceRemoved{..} => {
+                self.hotplug.store(true, Ordering::Relaxed);
+                RendererEvent::ControllersChanged
+            },
+            // the rest of the buttons come in through the input sources, unless there are none
+            Event::ControllerButtonDown{button: controller::Button::Guide, ..} => RendererEvent::Hid(
+                self.bindings.lock().unwrap().button("guide").unwrap_or(HidEvent::Home)
+            ),
+            Event::ControllerButtonDown{button, ..} if self.pad_buttons.load(Ordering::Relaxed) => {
+                let name = match button {
+                    controller::Button::A => Some("south"),
+                    controller::Button::B => Some("east"),
+                    controller::Button::Y => Some("north"),
+                    controller::Button::X => Some("west"),
+                    controller::Button::LeftShoulder => Some("l"),
+                    controller::Button::RightShoulder => Some("r"),
+                    controller::Button::Start => Some("start"),
+                    controller::Button::Back => Some("select"),
+                    _ => None,
+                };
+                let swapped = self.swap.lock().unwrap().swapped();
+                if let Some(ev) = name.and_then(|v| self.bindings.lock().unwrap().button(v)) {
+                    RendererEvent::Hid(ev)
+                } else {
+                    RendererEvent::Hid(match button {
+                        controller::Button::DPadUp => HidEvent::Up,
+                        controller::Button::DPadDown => HidEvent::Down,
+                        controller::Button::DPadLeft => HidEvent::Left,
+                        controller::Button::DPadRight => HidEvent::Right,
+                        controller::Button::A if swapped => HidEvent::Back,
+                        controller::Button::B if swapped => HidEvent::ButtonPress,
+                        controller::Button::A => HidEvent::ButtonPress,
+                        controller::Button::B => HidEvent::Back,
+                        controller::Button::Y => HidEvent::Action(ActionButton::North),
+                        controller::Button::X => HidEvent::Action(ActionButton::West),
+                        controller::Button::Start => HidEvent::Action(ActionButton::Start),
+                        controller::Button::Back => HidEvent::Action(ActionButton::Select),
+                        controller::Button::LeftShoulder => HidEvent::PreviousTab,
+                        controller::Button::RightShoulder => HidEvent::NextTab,
+                        _ => return,
+                    })
+                }
+            },
+            Event::ControllerAxisMotion{axis, value, ..} => RendererEvent::Hid(HidEvent::Analog{
+                axis: match axis {
+                    controller::Axis::LeftX => Axis::LeftX,
+                    controller::Axis::LeftY => Axis::LeftY,
+                    controller::Axis::RightX => Axis::RightX,
+                    controller::Axis::RightY => Axis::RightY,
+                    controller::Axis::TriggerLeft => Axis::TriggerLeft,
+                    controller::Axis::TriggerRight => Axis::TriggerRight,
+                },
+                value,
+            }),
+            // key names are SDL's, e.g. "Return" or "J", spelled the way bindings expect by config::key_name()
+            Event::KeyDown{keycode: Some(key), ..} if self.bindings.lock().unwrap().key(&key.name()).is_some() => {
+                RendererEvent::Hid(self.bindings.lock().unwrap().key(&key.name()).unwrap())
+            },
+            // arrows, WASD and hjkl all move, number keys jump to tabs
             Event::KeyDown{keycode: Some(key), ..} => match key {
-                Keycode::X      => RendererEvent::Hid(HidEvent::NextTab),
-                Keycode::Z      => RendererEvent::Hid(HidEvent::PreviousTab),
-                Keycode::Left   => RendererEvent::Hid(HidEvent::Left),
-                Keycode::Right  => RendererEvent::Hid(HidEvent::Right),
-                Keycode::Up     => RendererEvent::Hid(HidEvent::Up),
-                Keycode::Down   => RendererEvent::Hid(HidEvent::Down),
+                Keycode::X | Keycode::PageDown => RendererEvent::Hid(HidEvent::NextTab),
+                Keycode::Z | Keycode::PageUp   => RendererEvent::Hid(HidEvent::PreviousTab),
+                Keycode::Left  | Keycode::A | Keycode::H => RendererEvent::Hid(HidEvent::Left),
+                Keycode::Right | Keycode::D | Keycode::L => RendererEvent::Hid(HidEvent::Right),
+                Keycode::Up    | Keycode::W | Keycode::K => RendererEvent::Hid(HidEvent::Up),
+                Keycode::Down  | Keycode::S | Keycode::J => RendererEvent::Hid(HidEvent::Down),
+                Keycode::Home   => RendererEvent::Hid(HidEvent::First),
+                Keycode::End    => RendererEvent::Hid(HidEvent::Last),
+                Keycode::Num1 | Keycode::Num2 | Keycode::Num3 | Keycode::Num4 | Keycode::Num5
+                    | Keycode::Num6 | Keycode::Num7 | Keycode::Num8 | Keycode::Num9 => {
+                    RendererEvent::Hid(HidEvent::GoToTab(key as usize - Keycode::Num1 as usize))
+                },
                 Keycode::Return => RendererEvent::Hid(HidEvent::ButtonPress),
+                Keycode::P      => RendererEvent::Hid(HidEvent::SwitchPane),
+                Keycode::G      => RendererEvent::Hid(HidEvent::Grab),
+                Keycode::U      => RendererEvent::Hid(HidEvent::Undo),
+                Keycode::R      => RendererEvent::Hid(HidEvent::Redo),
+                Keycode::Backspace => RendererEvent::Hid(HidEvent::Back),
                 _ => return,
             }
             _ => return,
@@ -75,18 +555,30 @@ impl sdl2::event::EventWatchCallback for RendererEventWatch {
 }
 
 impl SdlRenderer {
-    fn new() -> Result<Self> {
+    fn new(fonts: FontConfig, overlay: Option<(u32, u32)>) -> Result<Self> {
         sdl2::hint::set("SDL_VIDEO_EGL_ALLOW_TRANSPARENCY", "1");
 
         let sdl2 = sdl2::init().expect("Failed to initialize SDL2");
         let video = sdl2.video().expect("Failed to initalize SDL2 video subsystem");
         let ev = sdl2.event().expect("Failed to initialize SDL2 event subsystem");
 
-        let ttf = ttf::init()?;
+        let ttf: &'static _ = Box::leak(Box::new(ttf::init()?));
 
-        let window = video.window("SGui window", 480, 320)
-            .resizable()
-            .build()?;
+        let window = match overlay {
+            Some((width, height)) => {
+                let flags = sdl2::sys::SDL_WindowFlags::SDL_WINDOW_ALWAYS_ON_TOP as u32
+                    | sdl2::sys::SDL_WindowFlags::SDL_WINDOW_SKIP_TASKBAR as u32
+                    | sdl2::sys::SDL_WindowFlags::SDL_WINDOW_HIDDEN as u32;
+                video.window("SGui overlay", width, height)
+                    .set_window_flags(flags)
+                    .borderless()
+                    .position_centered()
+                    .build()?
+            },
+            None => video.window("SGui window", 480, 320)
+                .resizable()
+                .build()?,
+        };
         let mut canvas = window.into_canvas()
             .present_vsync()
             .build()?;
@@ -96,134 +588,1311 @@ impl SdlRenderer {
         canvas.clear();
         canvas.present();
 
-        let (tx, rx) = mpsc::channel();
-        let event_watch = ev.add_event_watch(RendererEventWatch{chan: tx});
+        let (tx, rx) = unbounded();
+
+        // redraws once a picture is ready, which is when render() picks it up
+        let (image_jobs, jobs) = unbounded::<ImageSource>();
+        let (done, decoded) = unbounded();
+        let refresh = tx.clone();
+        thread::spawn(move || {
+            for source in jobs {
+                let pixels = decode_pixels(&source).ok();
+                if done.send(Decoded { source, pixels }).is_err() {
+                    break;
+                }
+                let _ = refresh.send(RendererEvent::Refresh);
+            }
+        });
+
+        let bindings = Arc::new(Mutex::new(Bindings::default()));
+        let pad_buttons = Arc::new(AtomicBool::new(false));
+        let swap = Arc::new(Mutex::new(ConfirmSwap::default()));
+        let hotplug = Arc::new(AtomicBool::new(false));
+        let event_watch = ev.add_event_watch(RendererEventWatch{
+            chan: tx,
+            bindings: bindings.clone(),
+            pad_buttons: pad_buttons.clone(),
+            swap: swap.clone(),
+            hotplug: hotplug.clone(),
+        });
         let pump = sdl2.event_pump().expect("Failed to get SDL2 event pump");
 
-        //let font_rwops = rwops::RWops::from_file("/usr/share/fonts/liberation/LiberationSans-Regular.ttf", "r");
-        let font = ttf.load_font("/usr/share/fonts/liberation/LiberationSans-Regular.ttf", 28).expect("Failed to load font");
-        let font_height = font.height() as u32;
-        drop(font);
+        let game_controller = sdl2.game_controller().ok();
+        let controllers = game_controller.as_ref().map(open_controllers).unwrap_or_default();
+
+        let loaded = load_fonts(ttf, &fonts, fonts.size);
+        let font_height = loaded[0].1.height() as u32;
 
         Ok(Self {
-            sdl2,
-            video,
+            _sdl2: sdl2,
+            _video: video,
             canvas,
             ttf,
+            loaded_fonts: HashMap::from([(fonts.size, loaded)]),
             text_creator,
-            text_map: HashMap::new(),
+            text_map: TextureCache::new(),
+            text_sizes: HashMap::new(),
+            fontsize: fonts.size,
+            fonts,
             rx_mutex: Mutex::new(Some(rx)),
-            event_watch,
+            _event_watch: event_watch,
+            game_controller,
+            controllers,
+            hotplug,
             pump,
-            fontsize: 28,
             font_height,
+            header_height: font_height,
+            min_header_height: font_height,
+            footer_height: 0,
+            h_scroll: Vec::new(),
+            scroll_margin: 0,
+            marquee: false,
+            scrolling: None,
+            editing: false,
+            edit_segment: 0,
+            background: Background::Solid,
+            background_texture: None,
+            tile_images: TextureCache::new(),
+            on_evict: None,
+            frame_hook: None,
+            image_jobs,
+            decoded,
+            decoding: HashSet::new(),
+            border: BorderStyle::default(),
+            hooks: DrawHooks::default(),
+            bindings,
+            pad_buttons,
+            swap,
+            composing: false,
+            tab_transition: TabTransition::None,
+            last_tab: None,
+            transition: None,
+            clock: Arc::new(SystemClock::new()),
+            #[cfg(feature = "shaping")]
+            shaper: crate::shaping::Shaper::default(),
         })
     }
-    fn ensure_text_is_rendered(&mut self, input: &str, color: (u8, u8, u8), size: u16) -> Result<()> {
-        if self.text_map.get_mut(&input.to_string()).is_some() {
+    pub fn set_border_style(&mut self, border: BorderStyle) {
+        self.border = border;
+    }
+    // Gets the canvas after every frame is drawn, right before it's shown, e.g. to put a
+    // camera preview over the ui. What it draws stays until the ui redraws that part.
+    pub fn set_frame_hook(&mut self, hook: impl FnMut(&mut render::Canvas<video::Window>) -> Result<()> + 'static) {
+        self.frame_hook = Some(Box::new(hook));
+    }
+    pub fn clear_frame_hook(&mut self) {
+        self.frame_hook = None;
+    }
+    // the hook always sees the whole window, whatever viewport the last draw left behind
+    fn run_frame_hook(&mut self) -> Result<()> {
+        let Some(hook) = self.frame_hook.as_mut() else {return Ok(())};
+        let viewport = self.canvas.viewport();
+        self.canvas.set_viewport(None);
+        let result = hook(&mut self.canvas);
+        self.canvas.set_viewport(viewport);
+        result
+    }
+    // called with everything the caches drop to stay within their CacheLimits, e.g. to keep
+    // track of what has to be preloaded again
+    pub fn set_eviction_callback(&mut self, callback: impl FnMut(Evicted) + 'static) {
+        self.on_evict = Some(Box::new(callback));
+    }
+    fn report_evicted(&mut self, texts: Vec<String>, images: Vec<ImageSource>) {
+        let Some(callback) = self.on_evict.as_mut() else {return};
+        for text in texts.iter() {
+            callback(Evicted::Text(text));
+        }
+        for source in images.iter() {
+            callback(Evicted::Image(source));
+        }
+    }
+    // Starts decoding the picture unless it's cached or on its way already. Failures get
+    // cached too, so they aren't tried again on every frame.
+    fn ensure_image_is_loaded(&mut self, source: &ImageSource) {
+        if self.tile_images.contains(source) || self.decoding.contains(source) {
+            return;
+        }
+        self.decoding.insert(source.clone());
+        let _ = self.image_jobs.send(source.clone());
+    }
+    // moves the pictures the decoding thread finished into the cache
+    fn take_decoded(&mut self) {
+        while let Ok(decoded) = self.decoded.try_recv() {
+            if !self.decoding.remove(&decoded.source) {
+                continue;
+            }
+            let texture = decoded.pixels.and_then(|(pixels, width, height, pitch)| {
+                let mut texture = self.text_creator.create_texture_static(PixelFormatEnum::ARGB8888, width, height).ok()?;
+                texture.update(None, &pixels, pitch as usize).ok()?;
+                texture.set_blend_mode(render::BlendMode::Blend);
+                Some(texture)
+            });
+            let evicted = self.tile_images.insert(decoded.source, texture);
+            self.report_evicted(Vec::new(), evicted);
+        }
+    }
+    // extra room in the tab header, e.g. for icons drawn with hooks
+    pub fn set_header_height(&mut self, height: u32) {
+        self.min_header_height = height.max(self.font_height);
+    }
+    pub fn set_tab_transition(&mut self, transition: TabTransition) {
+        self.tab_transition = transition;
+    }
+    // `dim` is how much of the palette's background color gets blended over the image
+    // or gradient (0.0 - 1.0), so text stays readable on busy images
+    pub fn set_background(&mut self, background: Background) -> Result<()> {
+        self.background_texture = match &background {
+            Background::Image { path, .. } => Some(self.load_image(path)?),
+            _ => None,
+        };
+        self.background = background;
+        Ok(())
+    }
+    fn load_image(&self, path: &Path) -> Result<Texture> {
+        let surface = decode_image(path)?;
+        Ok(self.text_creator.create_texture_from_surface(&surface)?)
+    }
+    // redraws the cell of the scrolling label once it's moved on
+    fn step_marquee(&mut self) -> Result<()> {
+        let Some((marquee, viewport)) = self.scrolling.as_ref() else {return Ok(())};
+        if marquee.offset(self.font_height as f32 * 2.0, self.clock.now()) == marquee.shown {
+            return Ok(());
+        }
+        let (item, colors, viewport) = (marquee.item.clone(), marquee.colors.clone(), *viewport);
+        let cell = Rect::new(marquee.cell.x, marquee.cell.y, marquee.cell.width, marquee.cell.height);
+        self.canvas.set_viewport(viewport);
+        self.fill_background(cell, colors.item_bg)?;
+        self.draw_item(&item, &colors, cell, true)?;
+        self.present();
+        self.canvas.set_viewport(None);
+        Ok(())
+    }
+    fn present(&mut self) {
+        if !self.composing {
+            // nowhere to report it from here, the ui itself still gets shown
+            let _ = self.run_frame_hook();
+            self.canvas.present();
+        }
+    }
+    // what's been drawn so far, read back from the canvas
+    fn snapshot(&mut self) -> Result<Texture> {
+        let (width, height) = self.canvas.output_size().map_err(anyhow::Error::msg)?;
+        let pixels = self.canvas.read_pixels(None, PixelFormatEnum::ARGB8888)
+            .map_err(anyhow::Error::msg)?;
+        let mut texture = self.text_creator.create_texture_static(PixelFormatEnum::ARGB8888, width, height)?;
+        texture.update(None, &pixels, width as usize * 4)?;
+        Ok(texture)
+    }
+    // draws the playing tab transition as far as the clock has got, the new frame and
+    // nothing else once it's done
+    fn step_transition(&mut self) -> Result<()> {
+        let Some(transition) = self.transition.as_mut() else {return Ok(())};
+        let duration = match transition.kind {
+            TabTransition::None => Duration::ZERO,
+            TabTransition::Slide(v) | TabTransition::Crossfade(v) => v,
+        };
+        let elapsed = self.clock.now().saturating_sub(transition.started);
+        if elapsed >= duration {
+            self.finish_transition()?;
+            self.present();
             return Ok(());
+        }
+
+        let t = elapsed.as_secs_f32() / duration.as_secs_f32().max(f32::EPSILON);
+        let (width, height) = self.canvas.output_size().map_err(anyhow::Error::msg)?;
+        self.canvas.set_draw_color((0, 0, 0));
+        self.canvas.clear();
+        match transition.kind {
+            TabTransition::Slide(_) => {
+                // eased so it settles in gently
+                let shift = ((1.0 - (1.0 - t).powi(3)) * width as f32) as i32;
+                let dir = if transition.forward { -1 } else { 1 };
+                self.canvas.copy(&transition.old, None, Rect::new(dir * shift, 0, width, height))
+                    .map_err(anyhow::Error::msg)?;
+                self.canvas.copy(&transition.new, None, Rect::new(dir * shift - dir * width as i32, 0, width, height))
+                    .map_err(anyhow::Error::msg)?;
+            },
+            _ => {
+                self.canvas.copy(&transition.old, None, None)
+                    .map_err(anyhow::Error::msg)?;
+                transition.new.set_blend_mode(render::BlendMode::Blend);
+                transition.new.set_alpha_mod((t * 255.0) as u8);
+                self.canvas.copy(&transition.new, None, None)
+                    .map_err(anyhow::Error::msg)?;
+            },
+        }
+        self.canvas.present();
+        Ok(())
+    }
+    // puts the new frame back on the canvas, later frames only redraw what changed
+    fn finish_transition(&mut self) -> Result<()> {
+        let Some(mut transition) = self.transition.take() else {return Ok(())};
+        transition.new.set_blend_mode(render::BlendMode::None);
+        transition.new.set_alpha_mod(255);
+        let result = self.canvas.copy(&transition.new, None, None)
+            .map_err(anyhow::Error::msg);
+        unsafe {
+            transition.old.destroy();
+            transition.new.destroy();
+        }
+        result
+    }
+    // clears `area` of the current viewport to the background, falling back to `color`
+    fn fill_background(&mut self, area: Rect, color: Color) -> Result<()> {
+        let viewport = self.canvas.viewport();
+        let (_, window_height) = self.canvas.output_size().map_err(anyhow::Error::msg)?;
+        let abs_y = viewport.y() + area.y();
+
+        let dim = match &self.background {
+            Background::Solid => 1.0,
+            Background::Image { dim, .. } => {
+                if let Some(texture) = self.background_texture.as_ref() {
+                    // the part of the stretched image that lands under `area`
+                    let (window_width, _) = self.canvas.output_size().map_err(anyhow::Error::msg)?;
+                    let query = texture.query();
+                    let scale_x = query.width as f32 / window_width.max(1) as f32;
+                    let scale_y = query.height as f32 / window_height.max(1) as f32;
+                    let src = Rect::new(
+                        ((viewport.x() + area.x()) as f32 * scale_x) as i32,
+                        (abs_y as f32 * scale_y) as i32,
+                        ((area.width() as f32 * scale_x) as u32).max(1),
+                        ((area.height() as f32 * scale_y) as u32).max(1),
+                    );
+                    self.canvas.copy(texture, src, area)
+                        .map_err(anyhow::Error::msg)?;
+                }
+                *dim
+            },
+            Background::Gradient { top, bottom, dim } => {
+                for y in 0..area.height() as i32 {
+                    let t = (abs_y + y) as f32 / window_height.max(1) as f32;
+                    self.canvas.set_draw_color(top.blend(bottom, t).as_tuple());
+                    self.canvas.draw_line((area.x(), area.y() + y), (area.x() + area.width() as i32, area.y() + y))
+                        .map_err(anyhow::Error::msg)?;
+                }
+                *dim
+            },
         };
 
-        let font = self.ttf.load_font("/usr/share/fonts/liberation/LiberationSans-Regular.ttf", size).expect("Failed to load font");
-        let surface = font.render(input).blended(color)?;
-        let texture = self.text_creator.create_texture_from_surface(&surface)?;
+        let (r, g, b) = color.as_tuple();
+        self.canvas.set_blend_mode(render::BlendMode::Blend);
+        self.canvas.set_draw_color((r, g, b, (dim.clamp(0.0, 1.0) * 255.0) as u8));
+        self.canvas.fill_rect(area)
+            .map_err(anyhow::Error::msg)?;
+        self.canvas.set_blend_mode(render::BlendMode::None);
+        Ok(())
+    }
+    // cached in white, the color is put on with set_color_mod when it's drawn
+    fn ensure_text_is_rendered(&mut self, input: &str, size: u16) -> Result<()> {
+        if self.text_map.contains(input) {
+            return Ok(());
+        };
 
-        self.text_map.insert(input.to_string(), texture);
+        let combined = self.render_text(input, (255, 255, 255), size)?;
+        let texture = self.text_creator.create_texture_from_surface(&combined)?;
+
+        let evicted = self.text_map.insert(input.to_string(), Some(texture));
+        self.report_evicted(evicted, Vec::new());
         Ok(())
     }
+    fn render_text(&mut self, input: &str, color: (u8, u8, u8), size: u16) -> Result<Surface<'static>> {
+        let fonts = self.loaded_fonts.entry(size).or_insert_with(|| load_fonts(self.ttf, &self.fonts, size));
+
+        // split the text into runs that can be drawn with a single font
+        let mut runs: Vec<(usize, String)> = Vec::new();
+        for ch in input.chars() {
+            let script = Script::of(ch);
+            let font_idx = fonts.iter()
+                .position(|(s, font, _)| (s.is_none() || *s == script) && font.find_glyph(ch).is_some())
+                .or_else(|| fonts.iter().position(|(_, font, _)| font.find_glyph(ch).is_some()))
+                .unwrap_or(0);
+            match runs.last_mut() {
+                Some((idx, run)) if *idx == font_idx => run.push(ch),
+                _ => runs.push((font_idx, ch.to_string())),
+            }
+        }
+
+        let mut surfaces = Vec::new();
+        for (idx, run) in runs.iter() {
+            #[cfg(feature = "shaping")]
+            if crate::shaping::needs_shaping(run) {
+                if let Some(surface) = self.shaper.render(&fonts[*idx].2, run, size, color) {
+                    surfaces.push(surface);
+                    continue;
+                }
+            }
+            surfaces.push(fonts[*idx].1.render(run).blended(color)?);
+        }
+
+        let width = surfaces.iter().map(|v| v.width()).sum::<u32>().max(1);
+        let height = surfaces.iter().map(|v| v.height()).max().unwrap_or(self.font_height).max(1);
+        let mut combined = Surface::new(width, height, PixelFormatEnum::ARGB8888)
+            .map_err(anyhow::Error::msg)?;
+        let mut x = 0;
+        for mut surface in surfaces.into_iter() {
+            surface.set_blend_mode(render::BlendMode::None)
+                .map_err(anyhow::Error::msg)?;
+            let dst = Rect::new(x, (height - surface.height()) as i32 / 2, surface.width(), surface.height());
+            surface.blit(None, &mut combined, dst)
+                .map_err(anyhow::Error::msg)?;
+            x += surface.width() as i32;
+        }
+        Ok(combined)
+    }
+}
+
+// every controller SDL knows the mapping of
+fn open_controllers(gc: &sdl2::GameControllerSubsystem) -> Vec<sdl2::controller::GameController> {
+    (0..gc.num_joysticks().unwrap_or(0))
+        .filter(|v| gc.is_game_controller(*v))
+        .filter_map(|v| gc.open(v).ok())
+        .collect()
+}
+
+// primary font first, then every fallback that exists on this system
+fn load_fonts(ttf: &'static ttf::Sdl2TtfContext, config: &FontConfig, size: u16) -> Vec<(Option<Script>, ttf::Font<'static, 'static>, PathBuf)> {
+    let mut fonts = vec![(None, ttf.load_font(&config.primary, size).expect("Failed to load font"), config.primary.clone())];
+    for fallback in config.fallbacks.iter() {
+        if let Ok(font) = ttf.load_font(&fallback.path, size) {
+            fonts.push((fallback.script, font, fallback.path.clone()));
+        }
+    }
+    fonts
+}
+
+impl SdlRenderer {
+    fn draw_text(&mut self, text: &str, colors: &ColorPalette, x: i32, y: i32, accent: bool) -> Result<()> {
+        let color = match accent {
+            true => colors.item_accent,
+            false => colors.item_text,
+        };
+        self.draw_tinted_text(text, x, y, color)
+    }
+    // `text` cut down with an ellipsis until it's at most `width` wide
+    fn ellipsize(&mut self, text: &str, width: u32) -> String {
+        if self.measure_text(text, TextStyle::Item).0 <= width {
+            return text.to_string();
+        }
+        let chars: Vec<char> = text.chars().collect();
+        let (mut fits, mut too_long) = (0, chars.len());
+        while too_long - fits > 1 {
+            let mid = (fits + too_long) / 2;
+            let candidate: String = chars[..mid].iter().chain(['…'].iter()).collect();
+            match self.measure_text(&candidate, TextStyle::Item).0 <= width {
+                true => fits = mid,
+                false => too_long = mid,
+            }
+        }
+        match fits {
+            0 => String::new(),
+            _ => chars[..fits].iter().chain(['…'].iter()).collect(),
+        }
+    }
+    // item text in any color, the cached white text gets tinted with it
+    fn draw_tinted_text(&mut self, text: &str, x: i32, y: i32, color: Color) -> Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.ensure_text_is_rendered(text, self.fontsize)?;
+        let texture = self.text_map.get(text).unwrap();
+        texture.set_color_mod(color.r, color.g, color.b);
+        let query = texture.query();
+        self.canvas.copy(texture, None, Rect::new(x, y, query.width, query.height))
+            .map_err(anyhow::Error::msg)
+    }
+    // draws the grid into the `width` wide area starting at `x` of the current viewport
+    fn draw_grid(&mut self, items: &[Vec<Item>], colors: &ColorPalette, selected_item_idx: (usize, usize), x: i32, width: u32) -> Result<()> {
+        let font_height = self.font_height;
+        let (selected_row, selected_column) = selected_item_idx;
+
+        for (row, (y_offset, line)) in items.iter().enumerate().map(|(i, v)| (i * font_height as usize, v)).enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+
+            // rows that don't fit scroll to keep the selection visible
+            let visible = (width / (font_height * 5)).max(1) as usize;
+            if self.h_scroll.len() <= row {
+                self.h_scroll.resize(row + 1, 0);
+            }
+            let first = if line.len() > visible {
+                let prev = self.h_scroll[row];
+                let selected = if row == selected_row { selected_column } else { prev };
+                keep_visible(prev, selected, visible, line.len())
+            } else {
+                0
+            };
+            self.h_scroll[row] = first;
+            let shown = line.len().min(visible);
+            let x_step = width as usize / shown;
+
+            for (i, item) in line.iter().skip(first).take(shown).enumerate() {
+                let cell = Rect::new(x + (i * x_step) as i32, y_offset as i32, x_step as u32, font_height);
+                self.draw_item(item, colors, cell, row == selected_row && first + i == selected_column)?;
+            }
+
+            // fade out the edges that have more cells behind them
+            let fade = (x_step as u32 / 4).max(1);
+            let (r, g, b) = colors.item_bg.as_tuple();
+            self.canvas.set_blend_mode(render::BlendMode::Blend);
+            for k in 0..fade {
+                let alpha = (255 * (fade - k) / fade) as u8;
+                self.canvas.set_draw_color((r, g, b, alpha));
+                if first > 0 {
+                    self.canvas.draw_line((x + k as i32, y_offset as i32), (x + k as i32, (y_offset as u32 + font_height) as i32))
+                        .expect("Failed to draw scroll fade");
+                }
+                if first + shown < line.len() {
+                    let edge = x + (width - 1 - k) as i32;
+                    self.canvas.draw_line((edge, y_offset as i32), (edge, (y_offset as u32 + font_height) as i32))
+                        .expect("Failed to draw scroll fade");
+                }
+            }
+            self.canvas.set_blend_mode(render::BlendMode::None);
+        }
+        Ok(())
+    }
+    fn draw_item(&mut self, item: &Item, colors: &ColorPalette, cell: Rect, selected: bool) -> Result<()> {
+        if !self.hooks.is_empty() {
+            let mut hooks = std::mem::take(&mut self.hooks);
+            let rect = DrawRect::new(cell.x(), cell.y(), cell.width(), cell.height());
+            let handled = hooks.run(item, selected, rect, colors, &mut SdlDrawContext { renderer: self });
+            self.hooks = hooks;
+            if handled {
+                return Ok(());
+            }
+        }
+        let (text, accent) = match item {
+            Item::Text(text) | Item::StatelessButton(text, _) | Item::Link(text, _, _) | Item::Tile(text, _, _) => (text.clone(), selected && self.editing),
+            Item::QrCode(data) => (format!("▣ {}", data), false),
+            Item::Image(..) | Item::Sparkline(..) | Item::Separator | Item::Message(..) => (String::new(), false),
+            Item::Spinner(text, _, _, _) => (text.clone(), false),
+            Item::Countdown(left, _) => (format::countdown(*left), false),
+            Item::Table(text, _, rows, selected, _) => (format!("{} ({}/{})", text, (selected + 1).min(rows.len()), rows.len()), false),
+            Item::Header(text) => (text.clone(), true),
+            Item::Battery(battery) => (power::label(battery.as_ref()), false),
+            Item::Clock(time) => (datetime::clock_label(time.as_ref()), false),
+            Item::Value(text, value, _) => (format!("{}: {}", text, value), false),
+            Item::Slider(text, _, _, _, value, _) => (format!("{}: {}", text, value), false),
+            Item::Stepper(text, value, _, _, _, _) => (format!("{}: ‹ {} ›", text, value), false),
+            Item::ProgressBar(text, value, _) => (format!("{}: {}", text, format::percent(*value)), false),
+            Item::Gauge(text, current, max, _) => (format!("{}: {}/{}", text, format::number(*current), format::number(*max)), false),
+            Item::TextInput(text, value, masked, _) => (format!("{}: {}", text, osk::mask(value, *masked)), false),
+            Item::Command(text, _, running, _) => (if *running { format!("{} …", text) } else { text.clone() }, *running),
+            Item::Log(lines, _) => (lines.last().cloned().unwrap_or_default(), false),
+            Item::StatefulButton(text, state, _) => (text.clone(), *state || (selected && self.editing)),
+            Item::Radio(text, _, picked, _) => (format!("{} {}", if *picked { "(•)" } else { "( )" }, text), *picked),
+            Item::Toggle(text, _, _) => (text.clone(), false),
+            // items being edited or moved are drawn in the accent color
+            Item::Date(text, date, _) => {
+                let active = (selected && self.editing).then_some(self.edit_segment);
+                (format!("{}: {}", text, datetime::format_segments(&date.parts(), '-', active)), selected && self.editing)
+            },
+            Item::Time(text, time, _) => {
+                let active = (selected && self.editing).then_some(self.edit_segment);
+                (format!("{}: {}", text, datetime::format_segments(&time.parts(), ':', active)), selected && self.editing)
+            },
+            Item::Selector(text, options, option_idx, _, _) => {
+                (format!("{}: {}", text, options.get(*option_idx).map(String::as_str).unwrap_or("")), selected && self.editing)
+            },
+        };
+        if selected && self.border.filled_selection {
+            let (r, g, b) = colors.item_outline.as_tuple();
+            self.canvas.set_blend_mode(render::BlendMode::Blend);
+            self.canvas.set_draw_color((r, g, b, 64));
+            fill_rounded_rect(&mut self.canvas, cell, self.border.radius)?;
+            self.canvas.set_blend_mode(render::BlendMode::None);
+        }
+
+        // labels that don't fit scroll through their cell in marquee mode, at two lines a second
+        let overflow = self.measure_text(&text, TextStyle::Item).0.saturating_sub(cell.width());
+        if selected && self.marquee && overflow > 0 && marquee::label(item).is_some() {
+            let rect = DrawRect::new(cell.x(), cell.y(), cell.width(), cell.height());
+            let now = self.clock.now();
+            let mut marquee = Marquee::follow(self.scrolling.take().map(|v| v.0), item, colors, rect, overflow, now);
+            marquee.shown = marquee.offset(self.font_height as f32 * 2.0, now);
+            let clip = self.canvas.clip_rect();
+            self.canvas.set_clip_rect(cell);
+            self.draw_text(&text, colors, cell.x() - marquee.shown as i32, cell.y(), accent)?;
+            self.canvas.set_clip_rect(clip);
+            self.scrolling = Some((marquee, self.canvas.viewport()));
+        } else {
+            self.draw_text(&text, colors, cell.x(), cell.y(), accent)?;
+        }
+        // no bold font around, so it's drawn twice a pixel apart
+        if let Item::Header(_) = item {
+            self.draw_text(&text, colors, cell.x() + 1, cell.y(), accent)?;
+        }
+
+        if let Item::Separator = item {
+            let y = cell.y() + cell.height() as i32 / 2;
+            self.canvas.set_draw_color(colors.item_outline.as_tuple());
+            self.canvas.draw_line((cell.x(), y), (cell.right() - 1, y))
+                .map_err(anyhow::Error::msg)?;
+        }
+
+        // the rest of the cell is a bar filled up to the value
+        let fraction = match item {
+            Item::Slider(_, min, max, _, value, _) => Some(slider_fraction(*min, *max, *value)),
+            Item::ProgressBar(_, value, _) => Some(*value),
+            Item::Gauge(_, current, max, _) => Some(gauge_fraction(*current, *max)),
+            _ => None,
+        };
+        if let Some(fraction) = fraction {
+            let pad = (self.font_height / 4) as i32;
+            let (text_width, _) = self.measure_text(&text, TextStyle::Item);
+            let x = cell.x() + text_width as i32 + pad;
+            let width = (cell.right() - pad - x).max(0) as u32;
+            let height = (self.font_height / 3).max(2);
+            if width > 0 {
+                let bar = Rect::new(x, cell.y() + (cell.height().saturating_sub(height) / 2) as i32, width, height);
+                let filled = (width as f32 * fraction) as u32;
+                if filled > 0 {
+                    self.canvas.set_draw_color(colors.item_accent.as_tuple());
+                    self.canvas.fill_rect(Rect::new(bar.x(), bar.y(), filled, height))
+                        .map_err(anyhow::Error::msg)?;
+                }
+                self.canvas.set_draw_color(colors.item_outline.as_tuple());
+                self.canvas.draw_rect(bar)
+                    .map_err(anyhow::Error::msg)?;
+            }
+        }
+
+        // pill at the right end of the cell, filled and with the knob on the right while on
+        if let Item::Toggle(_, state, _) = item {
+            let height = (self.font_height * 2 / 3).max(4);
+            let width = height * 2;
+            let pad = (self.font_height / 4) as i32;
+            let pill = Rect::new(cell.right() - pad - width as i32, cell.y() + (cell.height().saturating_sub(height) / 2) as i32, width, height);
+            self.canvas.set_draw_color(if *state { colors.item_accent } else { colors.item_outline }.as_tuple());
+            fill_rounded_rect(&mut self.canvas, pill, height / 2)?;
+            let knob = height.saturating_sub(4);
+            let knob_x = if *state { pill.right() - 2 - knob as i32 } else { pill.x() + 2 };
+            self.canvas.set_draw_color(colors.item_bg.as_tuple());
+            fill_rounded_rect(&mut self.canvas, Rect::new(knob_x, pill.y() + 2, knob, knob), knob / 2)?;
+        }
+
+        // a sample every two pixels, newest on the right, filled below a line in the accent color
+        if let Item::Sparkline(samples, _) = item {
+            let pad = (self.font_height / 6) as i32;
+            let (width, height) = (cell.width() as i32 - 2 * pad, cell.height() as i32 - 2 * pad);
+            let step = 2;
+            let first = samples.len().saturating_sub((width / step).max(0) as usize + 1);
+            let points: Vec<(i32, i32)> = sparkline_levels(&samples[first..]).iter().rev().enumerate()
+                .map(|(i, v)| (cell.right() - pad - i as i32 * step, cell.bottom() - pad - (v * height as f32) as i32))
+                .collect();
+            self.canvas.set_draw_color(colors.item_outline.as_tuple());
+            for (x, y) in points.iter() {
+                self.canvas.draw_line((*x, *y), (*x, cell.bottom() - pad))
+                    .map_err(anyhow::Error::msg)?;
+            }
+            self.canvas.set_draw_color(colors.item_accent.as_tuple());
+            for pair in points.windows(2) {
+                self.canvas.draw_line(pair[0], pair[1])
+                    .map_err(anyhow::Error::msg)?;
+            }
+        }
+
+        // icon on a square in the severity color, then the text in that color
+        if let Item::Message(severity, text) = item {
+            let color = colors.severity_color(*severity);
+            let size = self.font_height * 4 / 5;
+            let icon = Rect::new(cell.x(), cell.y() + (cell.height().saturating_sub(size) / 2) as i32, size, size);
+            self.canvas.set_draw_color(color.as_tuple());
+            fill_rounded_rect(&mut self.canvas, icon, self.border.radius)?;
+            let glyph = match severity {
+                Severity::Info => "i",
+                Severity::Warning => "!",
+                Severity::Error => "×",
+            };
+            let (glyph_width, glyph_height) = self.measure_text(glyph, TextStyle::Item);
+            let glyph_x = icon.x() + (size as i32 - glyph_width as i32) / 2;
+            let glyph_y = icon.y() + (size as i32 - glyph_height as i32) / 2;
+            self.draw_tinted_text(glyph, glyph_x, glyph_y, colors.item_bg)?;
+            self.draw_tinted_text(text, cell.x() + (size + self.font_height / 4) as i32, cell.y(), color)?;
+        }
+
+        // a three quarter arc after the text, turning clockwise a bit every frame
+        if let Item::Spinner(_, frame, true, _) = item {
+            let (text_width, _) = self.measure_text(&text, TextStyle::Item);
+            let radius = self.font_height / 3;
+            let center = (cell.x() + text_width as i32 + (self.font_height / 2 + radius) as i32, cell.y() + cell.height() as i32 / 2);
+            self.canvas.set_draw_color(colors.item_accent.as_tuple());
+            draw_arc(&mut self.canvas, center, radius, (radius / 3).max(2), *frame as f32 * 30.0, 270.0)?;
+        }
+
+        // as big as the cell allows without stretching it, nothing until it's decoded
+        if let Item::Image(source, _) = item {
+            self.ensure_image_is_loaded(source);
+            if let Some(texture) = self.tile_images.get(source) {
+                let query = texture.query();
+                let scale = (cell.width() as f32 / query.width.max(1) as f32).min(cell.height() as f32 / query.height.max(1) as f32);
+                let (width, height) = ((query.width as f32 * scale) as u32, (query.height as f32 * scale) as u32);
+                let rect = Rect::new(cell.x() + (cell.width() - width) as i32 / 2, cell.y() + (cell.height() - height) as i32 / 2, width, height);
+                self.canvas.copy(texture, None, rect)
+                    .map_err(anyhow::Error::msg)?;
+            }
+        }
+
+        if selected {
+            self.canvas.set_draw_color(colors.item_outline.as_tuple());
+            draw_rounded_rect(&mut self.canvas, cell, &self.border)?;
+        }
+        Ok(())
+    }
+}
+
+struct SdlDrawContext<'a> {
+    renderer: &'a mut SdlRenderer,
+}
+
+impl DrawContext for SdlDrawContext<'_> {
+    fn fill_rect(&mut self, rect: DrawRect, color: Color) -> Result<()> {
+        self.renderer.canvas.set_draw_color(color.as_tuple());
+        fill_rounded_rect(&mut self.renderer.canvas, Rect::new(rect.x, rect.y, rect.width, rect.height), self.renderer.border.radius)
+    }
+    fn draw_rect(&mut self, rect: DrawRect, color: Color) -> Result<()> {
+        self.renderer.canvas.set_draw_color(color.as_tuple());
+        draw_rounded_rect(&mut self.renderer.canvas, Rect::new(rect.x, rect.y, rect.width, rect.height), &self.renderer.border)
+    }
+    fn draw_line(&mut self, from: (i32, i32), to: (i32, i32), color: Color) -> Result<()> {
+        self.renderer.canvas.set_draw_color(color.as_tuple());
+        self.renderer.canvas.draw_line(from, to)
+            .map_err(anyhow::Error::msg)
+    }
+    // outlined while it's still decoding, like carousel tiles
+    fn draw_image(&mut self, path: &Path, rect: DrawRect, color: Color) -> Result<()> {
+        let source = ImageSource::Path(path.to_path_buf());
+        self.renderer.ensure_image_is_loaded(&source);
+        let rect = Rect::new(rect.x, rect.y, rect.width, rect.height);
+        match self.renderer.tile_images.get(&source) {
+            Some(texture) => self.renderer.canvas.copy(texture, None, rect)
+                .map_err(anyhow::Error::msg),
+            None => {
+                self.renderer.canvas.set_draw_color(color.as_tuple());
+                draw_rounded_rect(&mut self.renderer.canvas, rect, &self.renderer.border)
+            },
+        }
+    }
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, color: Color) -> Result<()> {
+        self.renderer.draw_tinted_text(text, x, y, color)
+    }
 }
 
 impl Renderer for SdlRenderer {
+    // cells are as wide as an "M" of the item font
+    fn viewport_info(&mut self) -> Option<ViewportInfo> {
+        let (width, height) = self.canvas.output_size().ok()?;
+        let cell_width = self.measure_text("M", TextStyle::Item).0.max(1);
+        Some(ViewportInfo {
+            width,
+            height,
+            cell_width,
+            cell_height: self.font_height,
+            rows: height.saturating_sub(self.header_height + self.footer_height) / self.font_height.max(1),
+            columns: width / cell_width,
+        })
+    }
+    // every part of the frame goes into the back buffer first and gets presented at once,
+    // so a tab switch never shows the new header over the old items
+    fn render(&mut self, frame: &Frame) -> Result<()> {
+        self.text_map.start_frame();
+        self.tile_images.start_frame();
+        self.take_decoded();
+        self.finish_transition()?;
+        let previous = self.last_tab.replace(frame.tab);
+        // the old tab is still on the canvas, it's read back only when there's going to be
+        // a transition away from it
+        let old = match previous.filter(|v| *v != frame.tab && self.tab_transition != TabTransition::None) {
+            Some(_) => Some(self.snapshot()?),
+            None => None,
+        };
+        // a marquee the new items don't draw again has nothing left to scroll, neither has
+        // one that's covered
+        let items = frame.damaged(frame::Damage::Items);
+        if let Some((marquee, _)) = self.scrolling.as_mut().filter(|_| items) {
+            marquee.seen = false;
+        }
+        self.footer_height = if frame.footer.is_some() { self.font_height } else { 0 };
+        self.composing = true;
+        let result = frame::render_parts(self, frame);
+        self.composing = false;
+        result?;
+        if !frame.overlays.is_empty() || self.scrolling.as_ref().is_some_and(|v| !v.0.seen) {
+            self.scrolling = None;
+        }
+
+        if let (Some(old), Some(previous)) = (old, previous) {
+            self.transition = Some(Transition {
+                kind: self.tab_transition,
+                old,
+                new: self.snapshot()?,
+                forward: frame.tab > previous,
+                started: self.clock.now(),
+            });
+            return self.step_transition();
+        }
+        self.run_frame_hook()?;
+        self.canvas.present();
+        Ok(())
+    }
     fn tick(&mut self) {
         self.pump.pump_events();
+        // before the gui gets to ask for the name of the new one
+        if self.hotplug.swap(false, Ordering::Relaxed) {
+            if let Some(gc) = self.game_controller.as_ref() {
+                self.controllers = open_controllers(gc);
+            }
+        }
+        // nowhere to report a failed draw from here, the next frame draws it all again anyway
+        match self.transition.is_some() {
+            true => {
+                let _ = self.step_transition();
+            },
+            false => {
+                let _ = self.step_marquee();
+            },
+        }
     }
-    fn get_event(&self) -> Option<mpsc::Receiver<RendererEvent>> {
+    fn get_event(&self) -> Option<Receiver<RendererEvent>> {
         self.rx_mutex.lock().unwrap().take()
     }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    // drops whatever no longer fits, other than what's on screen
+    fn set_cache_limits(&mut self, limits: CacheLimits) {
+        self.text_map.limit = limits.texts;
+        self.tile_images.limit = limits.images;
+        let texts = self.text_map.shrink(None);
+        let images = self.tile_images.shrink(None);
+        self.report_evicted(texts, images);
+    }
+    // a frame of the tab transition about every 16ms, vsync paces it
+    fn next_tick(&self) -> Option<Duration> {
+        self.transition.as_ref().map(|_| self.clock.now() + Duration::from_millis(16))
+    }
     fn draw_tab_header(&mut self, names: &[&str], colors: &ColorPalette) -> Result<()> {
         let width = self.canvas.viewport().width();
-        self.canvas.set_viewport(Rect::new(0 as i32, 0 as i32, width, self.font_height));
-        self.canvas.set_draw_color(colors.tab_bg.as_tuple());
-        self.canvas.clear();
+        self.h_scroll.clear();
 
-        let mut offset = 0;
+        // every line of a name gets a line of its own, tabs that don't fit start another row
+        let lines = names.iter().map(|v| v.lines().count().max(1)).max().unwrap_or(1) as u32;
+        let row_height = lines * self.font_height;
+        let mut tabs = Vec::new();
+        let (mut offset, mut row) = (0, 0);
+        for name in names.iter() {
+            let tab_width = name.lines().map(|v| self.measure_text(v, TextStyle::Tab).0).max().unwrap_or(0) + 1;
+            if offset > 0 && offset + tab_width > width {
+                offset = 0;
+                row += 1;
+            }
+            tabs.push((offset, row, tab_width));
+            offset += tab_width;
+        }
+        self.header_height = ((row + 1) * row_height).max(self.min_header_height);
 
-        for (i, name) in names.iter().enumerate() {
-            self.ensure_text_is_rendered(name, colors.tab_text.as_tuple(), self.fontsize)?;
-            let texture = self.text_map.get_mut(&name.to_string()).unwrap();
+        self.canvas.set_viewport(Rect::new(0, 0, width, self.header_height));
+        self.fill_background(Rect::new(0, 0, width, self.header_height), colors.tab_bg)?;
 
-            if i == 0 {
-                texture.set_color_mod(colors.tab_accent.r, colors.tab_accent.g, colors.tab_accent.b);
+        for (i, (name, (offset, row, tab_width))) in names.iter().zip(tabs).enumerate() {
+            // nothing to render for unnamed tabs
+            if name.is_empty() {
+                continue;
             }
-
-            let query = texture.query();
+            let y = (row * row_height) as i32;
 
             // outline
-            let outline_rect = Rect::new(offset, 0, query.width + 1, self.font_height);
+            let outline_rect = Rect::new(offset as i32, y, tab_width, row_height);
             self.canvas.set_draw_color(colors.tab_outline.as_tuple());
-            self.canvas.draw_rect(outline_rect)
-                .expect("Failed to draw tab outline");
+            draw_rounded_rect(&mut self.canvas, outline_rect, &self.border)?;
 
             // tab name
-            let text_rect = Rect::new(offset, 0, query.width, query.height);
-            self.canvas.copy(&texture, None, text_rect)
-                .expect("Failed to draw tab header text");
-
-            offset += query.width as i32;
+            for (l, line) in name.lines().enumerate().filter(|(_, v)| !v.is_empty()) {
+                self.ensure_text_is_rendered(line, self.fontsize)?;
+                let texture = self.text_map.get(line).unwrap();
+                let color = match i {
+                    0 => colors.tab_accent,
+                    _ => colors.tab_text,
+                };
+                texture.set_color_mod(color.r, color.g, color.b);
+                let query = texture.query();
+                let text_rect = Rect::new(offset as i32, y + (l as u32 * self.font_height) as i32, query.width, query.height);
+                self.canvas.copy(texture, None, text_rect)
+                    .expect("Failed to draw tab header text");
+            }
         }
 
-        let full_outline = Rect::new(0, 0, width, self.font_height);
+        let full_outline = Rect::new(0, 0, width, self.header_height);
         self.canvas.set_draw_color(colors.tab_outline.as_tuple());
         self.canvas.draw_rect(full_outline)
             .expect("Failed to draw tab outline");
 
-        self.canvas.present();
+        self.present();
 
         self.canvas.set_viewport(None);
         Ok(())
     }
-    fn draw_items(&mut self, items: &Vec<Vec<Item>>, colors: &ColorPalette, selected_item_idx: (usize, usize)) -> Result<()> {
-        let old_viewport = self.canvas.viewport();
-        self.canvas.set_viewport(Rect::new(0 as i32, self.font_height as i32, old_viewport.width(), old_viewport.height() - self.font_height));
+    fn draw_table(&mut self, headers: &[String], rows: &[Vec<String>], selected: usize, colors: &ColorPalette) -> Result<()> {
+        let viewport = self.canvas.viewport();
+        let pad = (self.font_height / 4) as i32;
+        let gap = self.font_height / 2;
+        let count = rows.iter().map(Vec::len).max().unwrap_or(0).max(headers.len());
+        let mut natural = vec![0; count];
+        for row in std::iter::once(headers).chain(rows.iter().map(Vec::as_slice)) {
+            for (c, text) in row.iter().enumerate() {
+                natural[c] = natural[c].max(self.measure_text(text, TextStyle::Item).0);
+            }
+        }
+        let widths = draw::column_widths(&natural, (viewport.width() * 9 / 10).saturating_sub(4 * pad as u32), gap);
+        let width = widths.iter().sum::<u32>() + gap * count.saturating_sub(1) as u32 + 4 * pad as u32;
+        // one line for the headers
+        let visible = rows.len().min((viewport.height() / self.font_height).saturating_sub(3) as usize).max(1);
+        let first = keep_visible(0, selected, visible, rows.len());
+        let height = (visible as u32 + 1) * self.font_height + self.font_height / 2;
+        let x = (viewport.width().saturating_sub(width) / 2) as i32;
+        let y = (viewport.height().saturating_sub(height) / 2) as i32;
+
         self.canvas.set_draw_color(colors.item_bg.as_tuple());
-        self.canvas.draw_rect(self.canvas.viewport())
-            .expect("Failed to clear area on which items will be drawn");
-        let font_height = self.font_height;
+        fill_rounded_rect(&mut self.canvas, Rect::new(x, y, width, height), self.border.radius)?;
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        draw_rounded_rect(&mut self.canvas, Rect::new(x, y, width, height), &self.border)?;
 
-        for (y_offset, line) in items.iter().enumerate().map(|(i, v)| (i * font_height as usize, v)) {
-            if line.len() == 0 {
-                continue;
+        let lines = std::iter::once((None, headers)).chain((first..first + visible).map(|i| (Some(i), rows.get(i).map(Vec::as_slice).unwrap_or(&[]))));
+        for (line, (i, row)) in lines.enumerate() {
+            let row_y = y + pad + (line as u32 * self.font_height) as i32;
+            if i == Some(selected) {
+                self.canvas.set_draw_color(colors.item_outline.as_tuple());
+                draw_rounded_rect(&mut self.canvas, Rect::new(x + pad, row_y, width - 2 * pad as u32, self.font_height), &self.border)?;
+            }
+            let mut cell_x = x + 2 * pad;
+            for (c, column_width) in widths.iter().enumerate() {
+                let text = self.ellipsize(row.get(c).map(String::as_str).unwrap_or(""), *column_width);
+                match i {
+                    None => self.draw_tinted_text(&text, cell_x, row_y, colors.item_outline)?,
+                    Some(i) => self.draw_text(&text, colors, cell_x, row_y, i == selected)?,
+                }
+                cell_x += (column_width + gap) as i32;
             }
-            let x_step = old_viewport.width() as usize / line.len();
+            // the headers are underlined
+            if i.is_none() {
+                let line_y = row_y + self.font_height as i32;
+                self.canvas.set_draw_color(colors.item_outline.as_tuple());
+                self.canvas.draw_line((x + pad, line_y), (x + width as i32 - pad, line_y))
+                    .map_err(anyhow::Error::msg)?;
+            }
+        }
+
+        self.present();
+        Ok(())
+    }
+    // the newest lines at the bottom, older ones that don't fit are left out
+    fn draw_log(&mut self, lines: &[String], colors: &ColorPalette) -> Result<()> {
+        let viewport = self.canvas.viewport();
+        let visible = lines.len().min((viewport.height() / self.font_height).saturating_sub(2) as usize).max(1);
+        let first = lines.len().saturating_sub(visible);
+        let width = viewport.width() * 9 / 10;
+        let height = visible as u32 * self.font_height + self.font_height / 2;
+        let x = ((viewport.width() - width) / 2) as i32;
+        let y = ((viewport.height().saturating_sub(height)) / 2) as i32;
+        let pad = (self.font_height / 4) as i32;
+
+        self.canvas.set_draw_color(colors.item_bg.as_tuple());
+        fill_rounded_rect(&mut self.canvas, Rect::new(x, y, width, height), self.border.radius)?;
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        draw_rounded_rect(&mut self.canvas, Rect::new(x, y, width, height), &self.border)?;
 
-            for (x_offset, item) in line.iter().enumerate().map(|(i, v)| (i * x_step, v)) {
-                match item {
-                    Item::Text(text) | Item::StatelessButton(text) => {                
-                        self.ensure_text_is_rendered(text, colors.item_text.as_tuple(), self.fontsize)?;
-                        let texture = self.text_map.get_mut(&text.to_string()).unwrap();
-                        let query = texture.query();
-                        let text_rect = Rect::new(x_offset as i32, y_offset as i32, query.width, query.height);
+        for (i, line) in lines.iter().skip(first).enumerate() {
+            let row_y = y + pad + (i as u32 * self.font_height) as i32;
+            let text = self.ellipsize(line, width.saturating_sub(4 * pad as u32));
+            self.draw_text(&text, colors, x + 2 * pad, row_y, false)?;
+        }
 
-                        self.canvas.copy(&texture, None, text_rect)
-                            .expect("Failed to draw tab header text");
-                    },
-                    Item::StatefulButton(text, state) => {
-                        self.ensure_text_is_rendered(text, colors.item_text.as_tuple(), self.fontsize)?;
-                        let texture = self.text_map.get_mut(&text.to_string()).unwrap();
-                        if *state {
-                            texture.set_color_mod(colors.item_accent.r, colors.item_accent.g, colors.item_accent.b);
-                        }
-                        let query = texture.query();
-                        let text_rect = Rect::new(x_offset as i32, y_offset as i32, query.width, query.height);
-
-                        self.canvas.copy(&texture, None, text_rect)
-                            .expect("Failed to draw tab header text");
+        self.present();
+        Ok(())
+    }
+    fn draw_popup(&mut self, options: &[String], selected: usize, colors: &ColorPalette) -> Result<()> {
+        let viewport = self.canvas.viewport();
+        let visible = options.len().min((viewport.height() / self.font_height).saturating_sub(2) as usize).max(1);
+        let first = keep_visible(0, selected, visible, options.len());
+        let width = viewport.width() * 2 / 3;
+        let height = visible as u32 * self.font_height + self.font_height / 2;
+        let x = ((viewport.width() - width) / 2) as i32;
+        let y = ((viewport.height().saturating_sub(height)) / 2) as i32;
+        let pad = (self.font_height / 4) as i32;
+
+        self.canvas.set_draw_color(colors.item_bg.as_tuple());
+        fill_rounded_rect(&mut self.canvas, Rect::new(x, y, width, height), self.border.radius)?;
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        draw_rounded_rect(&mut self.canvas, Rect::new(x, y, width, height), &self.border)?;
+
+        for (i, option) in options.iter().enumerate().skip(first).take(visible) {
+            let row_y = y + pad + ((i - first) as u32 * self.font_height) as i32;
+            if i == selected {
+                self.canvas.set_draw_color(colors.item_outline.as_tuple());
+                draw_rounded_rect(&mut self.canvas, Rect::new(x + pad, row_y, width - 2 * pad as u32, self.font_height), &self.border)?;
+            }
+            self.draw_text(option, colors, x + 2 * pad, row_y, i == selected)?;
+        }
+
+        self.present();
+        Ok(())
+    }
+    // as big as fits into the item area, always dark on white so it scans
+    #[cfg(feature = "qr")]
+    fn draw_qr(&mut self, data: &str, _colors: &ColorPalette) -> Result<()> {
+        let Some((width, modules)) = qr::modules(data) else {return Ok(())};
+        let viewport = self.canvas.viewport();
+        let size = width + 2 * qr::QUIET_ZONE;
+        let module = (viewport.width().min(viewport.height()) * 9 / 10 / size as u32).max(1);
+        let x = (viewport.width().saturating_sub(module * size as u32) / 2) as i32;
+        let y = (viewport.height().saturating_sub(module * size as u32) / 2) as i32;
+
+        self.canvas.set_draw_color((255, 255, 255));
+        self.canvas.fill_rect(Rect::new(x, y, module * size as u32, module * size as u32))
+            .map_err(anyhow::Error::msg)?;
+        self.canvas.set_draw_color((0, 0, 0));
+        let dark: Vec<Rect> = modules.iter().enumerate()
+            .filter(|(_, dark)| **dark)
+            .map(|(i, _)| {
+                let (col, row) = ((i % width + qr::QUIET_ZONE) as i32, (i / width + qr::QUIET_ZONE) as i32);
+                Rect::new(x + col * module as i32, y + row * module as i32, module, module)
+            })
+            .collect();
+        self.canvas.fill_rects(&dark)
+            .map_err(anyhow::Error::msg)?;
+
+        self.present();
+        Ok(())
+    }
+    fn set_scroll_margin(&mut self, rows: usize) {
+        self.scroll_margin = rows;
+    }
+    fn set_marquee(&mut self, enabled: bool) {
+        self.marquee = enabled;
+        self.scrolling = None;
+    }
+    fn set_edit_mode(&mut self, editing: bool) {
+        self.editing = editing;
+    }
+    fn set_edit_segment(&mut self, segment: usize) {
+        self.edit_segment = segment;
+    }
+    fn smooth_transitions(&self) -> bool {
+        true
+    }
+    fn set_title(&mut self, title: &str) {
+        let _ = self.canvas.window_mut().set_title(title);
+    }
+    // tab names and items share the font
+    fn measure_text(&mut self, text: &str, _style: TextStyle) -> (u32, u32) {
+        if let Some(texture) = self.text_map.get(text) {
+            let query = texture.query();
+            return (query.width, query.height);
+        }
+        if let Some(size) = self.text_sizes.get(text) {
+            return *size;
+        }
+        // rendered but not kept
+        let size = match text.is_empty() {
+            true => (0, self.font_height),
+            false => self.render_text(text, (255, 255, 255), self.fontsize)
+                .map(|v| (v.width(), v.height()))
+                .unwrap_or((0, self.font_height)),
+        };
+        self.text_sizes.insert(text.to_string(), size);
+        size
+    }
+    // gamma of the display the window is on, never all the way to black
+    fn set_brightness(&mut self, level: f32) {
+        let _ = self.canvas.window_mut().set_brightness(level.clamp(0.05, 1.0) as f64);
+    }
+    fn controller_name(&self) -> Option<String> {
+        self.controllers.first().map(|v| v.name())
+    }
+    fn set_bindings(&mut self, bindings: &Bindings) {
+        *self.bindings.lock().unwrap() = bindings.clone();
+    }
+    fn read_pad_buttons(&mut self) {
+        self.pad_buttons.store(true, Ordering::Relaxed);
+    }
+    fn set_confirm_swap(&mut self, swap: ConfirmSwap) {
+        *self.swap.lock().unwrap() = swap;
+    }
+    fn draw_hooks(&mut self) -> Option<&mut DrawHooks> {
+        Some(&mut self.hooks)
+    }
+    fn draw_blank(&mut self, logo: &str, position: (f32, f32), colors: &ColorPalette) -> Result<()> {
+        self.canvas.set_viewport(None);
+        self.canvas.set_draw_color((0, 0, 0));
+        self.canvas.clear();
+        if !logo.is_empty() {
+            self.ensure_text_is_rendered(logo, self.fontsize)?;
+            let query = self.text_map.get(logo).unwrap().query();
+            let (width, height) = self.canvas.output_size().map_err(anyhow::Error::msg)?;
+            let x = (width.saturating_sub(query.width) as f32 * position.0) as i32;
+            let y = (height.saturating_sub(query.height) as f32 * position.1) as i32;
+            self.draw_text(logo, colors, x, y, false)?;
+        }
+        self.present();
+        Ok(())
+    }
+    // decoded in the background into the same cache Item::Tile pictures come from
+    fn preload_images(&mut self, paths: &[&Path]) -> Result<()> {
+        for path in paths {
+            self.ensure_image_is_loaded(&ImageSource::Path(path.to_path_buf()));
+        }
+        Ok(())
+    }
+    fn draw_primitives(&mut self, primitives: &[Primitive], _colors: &ColorPalette) -> Result<()> {
+        draw::draw_primitives(&mut SdlDrawContext { renderer: self }, primitives)?;
+        self.present();
+        Ok(())
+    }
+    fn draw_osd(&mut self, label: &str, value: f32, colors: &ColorPalette) -> Result<()> {
+        let viewport = self.canvas.viewport();
+        let (width, height) = (viewport.width(), viewport.height());
+        let pad = self.font_height / 2;
+
+        self.fill_background(Rect::new(0, 0, width, height), colors.item_bg)?;
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        draw_rounded_rect(&mut self.canvas, Rect::new(0, 0, width, height), &self.border)?;
+
+        let text = format!("{} {}%", label, (value * 100.0).round() as u32);
+        self.draw_text(&text, colors, pad as i32, pad as i32, false)?;
+
+        let bar_y = (2 * pad + self.font_height) as i32;
+        let bar = Rect::new(pad as i32, bar_y, width.saturating_sub(2 * pad).max(1), height.saturating_sub(bar_y as u32 + pad).max(1));
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        self.canvas.draw_rect(bar)
+            .map_err(anyhow::Error::msg)?;
+        let filled = (bar.width() as f32 * value) as u32;
+        if filled > 0 {
+            self.canvas.set_draw_color(colors.item_accent.as_tuple());
+            self.canvas.fill_rect(Rect::new(bar.x(), bar.y(), filled, bar.height()))
+                .map_err(anyhow::Error::msg)?;
+        }
+
+        self.present();
+        Ok(())
+    }
+    fn set_visible(&mut self, visible: bool) {
+        match visible {
+            true => self.canvas.window_mut().show(),
+            false => self.canvas.window_mut().hide(),
+        }
+    }
+    fn draw_readout(&mut self, text: &str, colors: &ColorPalette) -> Result<()> {
+        // twice the usual size, rendered every time since the value keeps changing
+        let surface = self.render_text(text, colors.item_accent.as_tuple(), self.fontsize * 2)?;
+        let texture = self.text_creator.create_texture_from_surface(&surface)?;
+        let query = texture.query();
+        let viewport = self.canvas.viewport();
+        let pad = self.font_height / 2;
+        let (width, height) = (query.width + 2 * pad, query.height + pad);
+        let rect = Rect::new(
+            (viewport.width().saturating_sub(width) / 2) as i32,
+            (viewport.height().saturating_sub(height) / 2) as i32,
+            width,
+            height,
+        );
+
+        self.canvas.set_draw_color(colors.item_bg.as_tuple());
+        fill_rounded_rect(&mut self.canvas, rect, self.border.radius)?;
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        draw_rounded_rect(&mut self.canvas, rect, &self.border)?;
+        self.canvas.copy(&texture, None, Rect::new(rect.x() + pad as i32, rect.y() + pad as i32 / 2, query.width, query.height))
+            .map_err(anyhow::Error::msg)?;
+        // not cached, so it goes right away
+        unsafe { texture.destroy() };
+
+        self.present();
+        Ok(())
+    }
+    // panel over the bottom of the window, the typed text above rows of keys
+    fn draw_keyboard(&mut self, text: &str, keys: &[Vec<String>], selected: (usize, usize), colors: &ColorPalette) -> Result<()> {
+        let viewport = self.canvas.viewport();
+        let key_height = self.font_height + self.font_height / 2;
+        let pad = (self.font_height / 4) as i32;
+        let height = (self.font_height + keys.len() as u32 * key_height + 2 * pad as u32).min(viewport.height());
+        let panel = Rect::new(0, viewport.height().saturating_sub(height) as i32, viewport.width(), height);
+
+        self.canvas.set_draw_color(colors.item_bg.as_tuple());
+        self.canvas.fill_rect(panel)
+            .map_err(anyhow::Error::msg)?;
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        self.canvas.draw_line((0, panel.y()), (panel.width() as i32, panel.y()))
+            .map_err(anyhow::Error::msg)?;
+        self.draw_text(&format!("{}_", text), colors, pad * 2, panel.y() + pad, true)?;
+
+        // every row is spread over the whole width, so shorter rows get wider keys
+        for (i, row) in keys.iter().enumerate() {
+            let y = panel.y() + pad + (self.font_height + i as u32 * key_height) as i32;
+            let key_width = (panel.width().saturating_sub(2 * pad as u32) / row.len().max(1) as u32).max(1);
+            for (j, key) in row.iter().enumerate() {
+                let rect = Rect::new(pad + (j as u32 * key_width) as i32, y, key_width.saturating_sub(2).max(1), key_height.saturating_sub(2).max(1));
+                let focused = (i, j) == selected;
+                if focused {
+                    let (r, g, b) = colors.item_outline.as_tuple();
+                    self.canvas.set_blend_mode(render::BlendMode::Blend);
+                    self.canvas.set_draw_color((r, g, b, 96));
+                    fill_rounded_rect(&mut self.canvas, rect, self.border.radius)?;
+                    self.canvas.set_blend_mode(render::BlendMode::None);
+                }
+                self.canvas.set_draw_color(colors.item_outline.as_tuple());
+                draw_rounded_rect(&mut self.canvas, rect, &self.border)?;
+                let (text_width, text_height) = self.measure_text(key, TextStyle::Item);
+                let x = rect.x() + (rect.width() as i32 - text_width as i32) / 2;
+                self.draw_text(key, colors, x, rect.y() + (rect.height() as i32 - text_height as i32) / 2, focused)?;
+            }
+        }
+
+        self.present();
+        Ok(())
+    }
+    // rumble on every open controller, (low, high frequency motor, ms)
+    fn feedback(&mut self, feedback: Feedback) {
+        let (low, high, duration) = match feedback {
+            Feedback::None => return,
+            Feedback::Click => (0, 0x4000, 30),
+            Feedback::Success => (0x4000, 0x8000, 120),
+            Feedback::Error => (0xc000, 0x2000, 250),
+        };
+        for controller in self.controllers.iter_mut() {
+            let _ = controller.set_rumble(low, high, duration);
+        }
+    }
+    fn draw_footer(&mut self, items: &[Item], colors: &ColorPalette, selected: Option<usize>) -> Result<()> {
+        let viewport = self.canvas.viewport();
+        let y = viewport.height().saturating_sub(self.font_height) as i32;
+        let width = viewport.width();
+
+        self.fill_background(Rect::new(0, y, width, self.font_height), colors.item_bg)?;
+        self.canvas.set_draw_color(colors.item_outline.as_tuple());
+        self.canvas.draw_line((0, y), (width as i32, y))
+            .expect("Failed to draw footer separator");
+
+        if !items.is_empty() {
+            let x_step = width / items.len() as u32;
+            for (j, item) in items.iter().enumerate() {
+                let cell = Rect::new((x_step * j as u32) as i32, y, x_step, self.font_height);
+                self.draw_item(item, colors, cell, selected == Some(j))?;
+            }
+        }
+
+        self.present();
+        Ok(())
+    }
+    fn draw_page_indicator(&mut self, current: usize, total: usize, colors: &ColorPalette) -> Result<()> {
+        let width = self.canvas.viewport().width();
+        let dot = (self.font_height / 6).max(2);
+        let spacing = dot * 2;
+        let total_width = total as u32 * spacing;
+        let x_start = (width.saturating_sub(total_width) / 2) as i32;
+        let y = (self.header_height - dot * 2) as i32;
+
+        // a row of dots along the bottom edge of the tab header
+        for i in 0..total {
+            if i == current {
+                self.canvas.set_draw_color(colors.tab_accent.as_tuple());
+            } else {
+                self.canvas.set_draw_color(colors.tab_outline.as_tuple());
+            }
+            self.canvas.fill_rect(Rect::new(x_start + (i as u32 * spacing) as i32, y, dot, dot))
+                .expect("Failed to draw page indicator");
+        }
+
+        self.present();
+        Ok(())
+    }
+    fn draw_items(&mut self, items: &[Vec<Item>], colors: &ColorPalette, selected_item_idx: (usize, usize)) -> Result<()> {
+        let old_viewport = self.canvas.viewport();
+        let height = old_viewport.height().saturating_sub(self.header_height + self.footer_height);
+        self.canvas.set_viewport(Rect::new(0, self.header_height as i32, old_viewport.width(), height));
+        self.fill_background(Rect::new(0, 0, old_viewport.width(), height), colors.item_bg)?;
+
+        self.draw_grid(items, colors, selected_item_idx, 0, old_viewport.width())?;
+
+        self.present();
+        self.canvas.set_viewport(None);
+        Ok(())
+    }
+    fn draw_carousel(&mut self, tiles: &[Item], selected: usize, colors: &ColorPalette) -> Result<()> {
+        let old_viewport = self.canvas.viewport();
+        let (width, height) = (old_viewport.width(), old_viewport.height().saturating_sub(self.header_height + self.footer_height));
+        self.canvas.set_viewport(Rect::new(0, self.header_height as i32, width, height));
+        self.fill_background(Rect::new(0, 0, width, height), colors.item_bg)?;
+
+        // the focused tile is centered and a quarter bigger than the rest
+        let size = (height.saturating_sub(2 * self.font_height) * 3 / 5).min(width / 3).max(1);
+        let big = size * 5 / 4;
+        let gap = (size / 8) as i32;
+        let center = selected.min(tiles.len().saturating_sub(1)) as i32;
+        for (i, item) in tiles.iter().enumerate() {
+            let Item::Tile(text, image, _) = item else {continue};
+            let offset = i as i32 - center;
+            let focused = i == selected;
+            let tile = if focused { big } else { size };
+            let mid = width as i32 / 2 + offset * (size as i32 + gap) + offset.signum() * (big - size) as i32 / 2;
+            let rect = Rect::new(mid - tile as i32 / 2, (height.saturating_sub(tile + self.font_height) / 2) as i32, tile, tile);
+            if rect.right() < 0 || rect.left() > width as i32 {
+                continue;
+            }
+
+            if let Some(path) = image {
+                let source = ImageSource::Path(path.clone());
+                self.ensure_image_is_loaded(&source);
+                match self.tile_images.get(&source) {
+                    Some(texture) => self.canvas.copy(texture, None, rect)
+                        .map_err(anyhow::Error::msg)?,
+                    // faint block while it's still decoding, or if it couldn't be
+                    None => {
+                        let (r, g, b) = colors.item_outline.as_tuple();
+                        self.canvas.set_blend_mode(render::BlendMode::Blend);
+                        self.canvas.set_draw_color((r, g, b, 48));
+                        fill_rounded_rect(&mut self.canvas, rect, self.border.radius)?;
+                        self.canvas.set_blend_mode(render::BlendMode::None);
                     },
                 }
             }
+            self.canvas.set_draw_color(if focused { colors.item_accent.as_tuple() } else { colors.item_outline.as_tuple() });
+            draw_rounded_rect(&mut self.canvas, rect, &self.border)?;
+
+            let (text_width, _) = self.measure_text(text, TextStyle::Item);
+            self.draw_text(text, colors, mid - text_width as i32 / 2, rect.bottom() + gap / 2, focused)?;
         }
 
-        self.canvas.present();
+        self.present();
+        self.canvas.set_viewport(None);
+        Ok(())
+    }
+    fn draw_split(&mut self, list: &[Item], list_selected: usize, detail: &[Vec<Item>], detail_selected: Option<(usize, usize)>, colors: &ColorPalette) -> Result<()> {
+        let old_viewport = self.canvas.viewport();
+        let height = old_viewport.height().saturating_sub(self.header_height + self.footer_height);
+        let list_width = old_viewport.width() / 3;
+        self.canvas.set_viewport(Rect::new(0, self.header_height as i32, old_viewport.width(), height));
+        self.fill_background(Rect::new(0, 0, old_viewport.width(), height), colors.item_bg)?;
+
+        self.draw_grid(detail, colors, detail_selected.unwrap_or((usize::MAX, usize::MAX)), list_width as i32 + 1, old_viewport.width() - list_width - 1)?;
+
+        let visible = (height / self.font_height).max(1) as usize;
+        let first = keep_visible_with_margin(0, list_selected, visible, list.len(), self.scroll_margin);
+        for (i, item) in list.iter().enumerate().skip(first).take(visible) {
+            let cell = Rect::new(0, ((i - first) as u32 * self.font_height) as i32, list_width, self.font_height);
+            if i == list_selected && detail_selected.is_some() {
+                // keep showing which entry the detail pane belongs to
+                self.canvas.set_draw_color(colors.item_outline.as_tuple());
+                self.canvas.fill_rect(Rect::new(0, cell.y(), 4, cell.height()))
+                    .expect("Failed to draw list marker");
+            }
+            self.draw_item(item, colors, cell, i == list_selected && detail_selected.is_none())?;
+        }
+
+        self.canvas.set_draw_color(colors.tab_outline.as_tuple());
+        self.canvas.draw_line((list_width as i32, 0), (list_width as i32, height as i32))
+            .expect("Failed to draw pane separator");
+
+        self.present();
         self.canvas.set_viewport(None);
         Ok(())
     }
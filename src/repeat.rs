@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+// Step sizes used while a direction is held down (auto-repeated), so long lists
+// can be crossed quickly. Steps grow from 1 to fast_step and then to page_step.
+#[derive(Debug, Clone)]
+pub struct NavAcceleration {
+    // events further apart than this count as separate presses
+    pub repeat_gap: Duration,
+    pub fast_after: Duration,
+    pub fast_step: usize,
+    pub page_after: Duration,
+    pub page_step: usize,
+}
+
+impl Default for NavAcceleration {
+    fn default() -> Self {
+        Self {
+            repeat_gap: Duration::from_millis(200),
+            fast_after: Duration::from_millis(1500),
+            fast_step: 3,
+            page_after: Duration::from_millis(4000),
+            page_step: 10,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct RepeatTracker {
+    direction: (i32, i32),
+    started: Duration,
+    last: Option<Duration>,
+}
+
+impl RepeatTracker {
+    // how many cells a move in `direction` at `now` should cover
+    pub(crate) fn step(&mut self, direction: (i32, i32), now: Duration, accel: &NavAcceleration, page: Option<usize>) -> usize {
+        let held = match self.last {
+            Some(last) => direction == self.direction && now.saturating_sub(last) <= accel.repeat_gap,
+            None => false,
+        };
+        if !held {
+            self.direction = direction;
+            self.started = now;
+        }
+        self.last = Some(now);
+
+        let held_for = now.saturating_sub(self.started);
+        if held_for >= accel.page_after {
+            page.unwrap_or(accel.page_step)
+        } else if held_for >= accel.fast_after {
+            accel.fast_step
+        } else {
+            1
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::renderer_crossterm::text_cells;
+use std::io::{self, Write};
+
+// colors and attributes of a cell, as the SGR parameters that set them
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: bool,
+    dim: bool,
+    underline: bool,
+    reverse: bool,
+    // OSC 8 target
+    link: Option<String>,
+}
+
+impl Style {
+    // only the background is kept by erasing, like terminals do
+    fn erased(&self) -> Style {
+        Style { bg: self.bg.clone(), ..Style::default() }
+    }
+    fn apply_sgr(&mut self, params: &str) {
+        let mut params = params.split(';');
+        while let Some(param) = params.next() {
+            match param {
+                "" | "0" => *self = Style { link: self.link.take(), ..Style::default() },
+                "1" => self.bold = true,
+                "2" => self.dim = true,
+                "22" => (self.bold, self.dim) = (false, false),
+                "4" => self.underline = true,
+                "24" => self.underline = false,
+                "7" => self.reverse = true,
+                "27" => self.reverse = false,
+                "39" => self.fg = None,
+                "49" => self.bg = None,
+                "38" | "48" => {
+                    let color = match params.next() {
+                        Some("5") => params.next().map(|n| format!("{};5;{}", param, n)),
+                        Some("2") => Some(format!("{};2;{}", param, params.by_ref().take(3).collect::<Vec<_>>().join(";"))),
+                        _ => None,
+                    };
+                    if param == "38" { self.fg = color } else { self.bg = color }
+                },
+                // curly, dotted and friends
+                _ if param.starts_with("4:") => self.underline = param != "4:0",
+                _ => match param.parse::<u8>() {
+                    Ok(30..=37 | 90..=97) => self.fg = Some(param.to_string()),
+                    Ok(40..=47 | 100..=107) => self.bg = Some(param.to_string()),
+                    _ => (),
+                },
+            }
+        }
+    }
+    fn sgr(&self) -> String {
+        let mut params = vec!["0"];
+        for (on, param) in [(self.bold, "1"), (self.dim, "2"), (self.underline, "4"), (self.reverse, "7")] {
+            if on {
+                params.push(param);
+            }
+        }
+        params.extend(self.fg.as_deref());
+        params.extend(self.bg.as_deref());
+        format!("\x1b[{}m", params.join(";"))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Cell {
+    // empty on the right half of a wide glyph
+    text: String,
+    style: Style,
+}
+
+impl Cell {
+    fn blank(style: Style) -> Cell {
+        Cell { text: " ".to_string(), style }
+    }
+}
+
+// Copy of the terminal for low bandwidth mode. Everything written to it is played onto its
+// cells, flushing sends only the cells that ended up different from what the terminal
+// already shows, so erasing and redrawing the same text costs nothing. Knows cursor
+// positioning, erasing, SGR and OSC 8, any other sequence is passed on as it is.
+pub(crate) struct Screen {
+    // bytes written since the last flush
+    queued: Vec<u8>,
+    cells: Vec<Vec<Cell>>,
+    // what the terminal shows, None until the first flush wiped it
+    shown: Option<Vec<Vec<Cell>>>,
+    // (column, row)
+    cursor: (usize, usize),
+    style: Style,
+}
+
+impl Screen {
+    pub(crate) fn new() -> Screen {
+        Screen {
+            queued: Vec::new(),
+            cells: Vec::new(),
+            shown: None,
+            cursor: (0, 0),
+            style: Style::default(),
+        }
+    }
+    pub(crate) fn queue(&mut self, buf: &[u8]) {
+        self.queued.extend_from_slice(buf);
+    }
+    // plays what was queued onto the `columns` x `rows` screen and sends the difference
+    pub(crate) fn flush_to(&mut self, out: &mut impl Write, (columns, rows): (usize, usize)) -> io::Result<()> {
+        if self.cells.len() != rows || self.cells.first().is_some_and(|v| v.len() != columns) {
+            self.cells = vec![vec![Cell::blank(Style::default()); columns]; rows];
+            self.shown = None;
+        }
+        let queued = std::mem::take(&mut self.queued);
+        let text = String::from_utf8_lossy(&queued);
+        let mut chars = text.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        let mut last = None;
+                        for ch in chars.by_ref() {
+                            if ('@'..='~').contains(&ch) {
+                                last = Some(ch);
+                                break;
+                            }
+                            params.push(ch);
+                        }
+                        let Some(last) = last else {break};
+                        match last {
+                            'H' if !params.starts_with('?') => {
+                                let mut at = params.split(';').map(|v| v.parse::<usize>().unwrap_or(1).max(1) - 1);
+                                let row = at.next().unwrap_or(0);
+                                self.cursor = (at.next().unwrap_or(0), row);
+                            },
+                            'm' => self.style.apply_sgr(&params),
+                            'J' if params != "3" => self.erase_screen(&params),
+                            'K' => self.erase_line(&params),
+                            _ => write!(out, "\x1b[{}{}", params, last)?,
+                        }
+                    },
+                    Some(']') => {
+                        let mut body = String::new();
+                        // ends with BEL or ESC \
+                        while let Some(ch) = chars.next() {
+                            match ch {
+                                '\x07' => break,
+                                '\x1b' => {
+                                    chars.next();
+                                    break;
+                                },
+                                _ => body.push(ch),
+                            }
+                        }
+                        match body.strip_prefix("8;") {
+                            Some(rest) => self.style.link = rest.split_once(';').map(|v| v.1.to_string()).filter(|v| !v.is_empty()),
+                            None => write!(out, "\x1b]{}\x07", body)?,
+                        }
+                    },
+                    Some(ch) => write!(out, "\x1b{}", ch)?,
+                    None => (),
+                },
+                '\x07' => out.write_all(b"\x07")?,
+                '\r' => self.cursor.0 = 0,
+                '\n' => self.cursor.1 += 1,
+                _ if ch.is_control() => (),
+                _ => self.put(ch),
+            }
+        }
+        self.send(out)
+    }
+    fn put(&mut self, ch: char) {
+        let width = text_cells(ch.encode_utf8(&mut [0; 4]));
+        let (x, y) = self.cursor;
+        let Some(row) = self.cells.get_mut(y) else {return};
+        if width == 0 {
+            if let Some(cell) = x.checked_sub(1).and_then(|x| row.get_mut(x)) {
+                cell.text.push(ch);
+            }
+            return;
+        }
+        self.cursor.0 = x + width;
+        // whatever doesn't fit is cut off
+        if x + width > row.len() {
+            return;
+        }
+        // a wide glyph written over by half leaves a blank behind
+        if x > 0 && row[x].text.is_empty() {
+            row[x - 1].text = " ".to_string();
+        }
+        row[x] = Cell { text: ch.to_string(), style: self.style.clone() };
+        if width == 2 {
+            row[x + 1] = Cell { text: String::new(), style: self.style.clone() };
+        }
+        if let Some(cell) = row.get_mut(x + width).filter(|v| v.text.is_empty()) {
+            cell.text = " ".to_string();
+        }
+    }
+    fn erase_screen(&mut self, params: &str) {
+        let (x, y) = self.cursor;
+        let blank = Cell::blank(self.style.erased());
+        for (i, row) in self.cells.iter_mut().enumerate() {
+            let range = match (params, i.cmp(&y)) {
+                ("2", _) => 0..row.len(),
+                ("1", std::cmp::Ordering::Less) | ("", std::cmp::Ordering::Greater) => 0..row.len(),
+                ("1", std::cmp::Ordering::Equal) => 0..(x + 1).min(row.len()),
+                ("", std::cmp::Ordering::Equal) => x.min(row.len())..row.len(),
+                _ => continue,
+            };
+            row[range].fill(blank.clone());
+        }
+    }
+    fn erase_line(&mut self, params: &str) {
+        let (x, y) = self.cursor;
+        let blank = Cell::blank(self.style.erased());
+        let Some(row) = self.cells.get_mut(y) else {return};
+        let range = match params {
+            "2" => 0..row.len(),
+            "1" => 0..(x + 1).min(row.len()),
+            _ => x.min(row.len())..row.len(),
+        };
+        row[range].fill(blank);
+    }
+    // sends the cells that changed, the terminal is left with the default style
+    fn send(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let shown = match self.shown.as_mut() {
+            Some(shown) => shown,
+            None => {
+                out.write_all(b"\x1b[0m\x1b[2J")?;
+                let blank = vec![vec![Cell::blank(Style::default()); self.cells.first().map_or(0, Vec::len)]; self.cells.len()];
+                self.shown.insert(blank)
+            },
+        };
+        // None until the first cell sets it, the terminal could be in any style
+        let mut style: Option<&Style> = None;
+        let mut cursor = None;
+        for (y, row) in self.cells.iter().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let width = if row.get(x + 1).is_some_and(|v| v.text.is_empty()) { 2 } else { 1 };
+                let cell = &row[x];
+                if cell.text.is_empty() || shown[y][x..x + width] == row[x..x + width] {
+                    x += width;
+                    continue;
+                }
+                if cursor != Some((x, y)) {
+                    write!(out, "\x1b[{};{}H", y + 1, x + 1)?;
+                }
+                if style.map(|v| &v.link) != Some(&cell.style.link) {
+                    if style.is_none_or(|v| v.link.is_some()) {
+                        out.write_all(b"\x1b]8;;\x1b\\")?;
+                    }
+                    if let Some(link) = cell.style.link.as_ref() {
+                        write!(out, "\x1b]8;;{}\x1b\\", link)?;
+                    }
+                }
+                if style.is_none_or(|v| v.sgr() != cell.style.sgr()) {
+                    out.write_all(cell.style.sgr().as_bytes())?;
+                }
+                out.write_all(cell.text.as_bytes())?;
+                style = Some(&cell.style);
+                shown[y][x..x + width].clone_from_slice(&row[x..x + width]);
+                cursor = Some((x + width, y));
+                x += width;
+            }
+        }
+        if let Some(style) = style {
+            if style.link.is_some() {
+                out.write_all(b"\x1b]8;;\x1b\\")?;
+            }
+            out.write_all(b"\x1b[0m")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use crate::renderer_sdl2::Script;
+use rustybuzz::{
+    Face,
+    UnicodeBuffer,
+    ttf_parser::{GlyphId, OutlineBuilder},
+};
+use ab_glyph_rasterizer::{Rasterizer, Point, point};
+use sdl2::{surface::Surface, pixels::PixelFormatEnum};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+// scripts SDL_ttf gets wrong without shaping, joining forms, reordering or right-to-left
+pub(crate) fn needs_shaping(text: &str) -> bool {
+    text.chars().any(|ch| matches!(Script::of(ch), Some(Script::Arabic | Script::Hebrew | Script::Devanagari | Script::Thai)))
+}
+
+// Shapes with rustybuzz and rasterizes the glyphs ourselves, SDL_ttf can only render
+// characters, not the glyphs a shaper picks.
+#[derive(Default)]
+pub(crate) struct Shaper {
+    // font files stay loaded, faces only borrow them
+    fonts: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl Shaper {
+    // None if the font can't be read, the caller falls back to SDL_ttf
+    pub(crate) fn render(&mut self, path: &Path, text: &str, size: u16, color: (u8, u8, u8)) -> Option<Surface<'static>> {
+        if !self.fonts.contains_key(path) {
+            self.fonts.insert(path.to_path_buf(), std::fs::read(path).ok()?);
+        }
+        let face = Face::from_slice(&self.fonts[path], 0)?;
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        let glyphs = rustybuzz::shape(&face, &[], buffer);
+
+        // SDL_ttf sizes are pixels per em as well
+        let scale = size as f32 / face.units_per_em() as f32;
+        let ascent = face.ascender() as f32 * scale;
+        let height = ((face.ascender() as f32 - face.descender() as f32) * scale).ceil().max(1.0) as u32;
+        let advance: i32 = glyphs.glyph_positions().iter().map(|v| v.x_advance).sum();
+        let width = (advance as f32 * scale).ceil().max(1.0) as u32;
+
+        let mut rasterizer = Rasterizer::new(width as usize, height as usize);
+        let mut pen_x = 0.0;
+        for (info, pos) in glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()) {
+            let mut outline = Outline {
+                rasterizer: &mut rasterizer,
+                scale,
+                origin: (pen_x + pos.x_offset as f32 * scale, ascent - pos.y_offset as f32 * scale),
+                start: point(0.0, 0.0),
+                last: point(0.0, 0.0),
+            };
+            face.outline_glyph(GlyphId(info.glyph_id as u16), &mut outline);
+            pen_x += pos.x_advance as f32 * scale;
+        }
+
+        let mut surface = Surface::new(width, height, PixelFormatEnum::ARGB8888).ok()?;
+        let pitch = surface.pitch() as usize;
+        let (r, g, b) = color;
+        surface.with_lock_mut(|pixels| {
+            rasterizer.for_each_pixel_2d(|x, y, alpha| {
+                // ARGB8888 is B, G, R, A in memory on little endian
+                let i = y as usize * pitch + x as usize * 4;
+                pixels[i..i + 4].copy_from_slice(&[b, g, r, (alpha.min(1.0) * 255.0) as u8]);
+            });
+        });
+        Some(surface)
+    }
+}
+
+// font units are y-up, the surface is y-down
+struct Outline<'a> {
+    rasterizer: &'a mut Rasterizer,
+    scale: f32,
+    origin: (f32, f32),
+    start: Point,
+    last: Point,
+}
+
+impl Outline<'_> {
+    fn point(&self, x: f32, y: f32) -> Point {
+        point(self.origin.0 + x * self.scale, self.origin.1 - y * self.scale)
+    }
+}
+
+impl OutlineBuilder for Outline<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.point(x, y);
+        self.last = self.start;
+    }
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.rasterizer.draw_line(self.last, p);
+        self.last = p;
+    }
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (c, p) = (self.point(x1, y1), self.point(x, y));
+        self.rasterizer.draw_quad(self.last, c, p);
+        self.last = p;
+    }
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (c1, c2, p) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.rasterizer.draw_cubic(self.last, c1, c2, p);
+        self.last = p;
+    }
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.rasterizer.draw_line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
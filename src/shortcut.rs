@@ -0,0 +1,48 @@
+use crate::HidEvent;
+use std::time::Duration;
+
+// presses further apart than this don't make up a shortcut
+const GAP: Duration = Duration::from_millis(500);
+
+// Button sequences reserved by the app with Gui::reserve_shortcut(), e.g. Select followed
+// by Start. Buttons held down together arrive one after the other, so combos work too.
+#[derive(Default)]
+pub(crate) struct Shortcuts {
+    shortcuts: Vec<(String, Vec<HidEvent>)>,
+    // latest presses and when they came in, oldest first
+    recent: Vec<(HidEvent, Duration)>,
+}
+
+impl Shortcuts {
+    // a later shortcut with the same name replaces the earlier one
+    pub fn add(&mut self, name: &str, events: Vec<HidEvent>) {
+        self.shortcuts.retain(|(v, _)| v != name);
+        if !events.is_empty() {
+            self.shortcuts.push((name.to_string(), events));
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.shortcuts.is_empty()
+    }
+    // name of the shortcut `ev` completes, the presses that made it up are forgotten
+    pub fn feed(&mut self, ev: &HidEvent, now: Duration) -> Option<String> {
+        // sticks report every little move, that would break up any sequence
+        if matches!(ev, HidEvent::Analog { .. }) {
+            return None;
+        }
+        let longest = self.shortcuts.iter().map(|(_, v)| v.len()).max()?;
+        if self.recent.last().is_some_and(|(_, at)| now.saturating_sub(*at) > GAP) {
+            self.recent.clear();
+        }
+        self.recent.push((ev.clone(), now));
+        if self.recent.len() > longest {
+            self.recent.remove(0);
+        }
+        let (name, _) = self.shortcuts.iter().find(|(_, events)| {
+            self.recent.len() >= events.len()
+                && self.recent[self.recent.len() - events.len()..].iter().map(|(v, _)| v).eq(events.iter())
+        })?;
+        self.recent.clear();
+        Some(name.clone())
+    }
+}
@@ -0,0 +1,228 @@
+use std::{fs, path::Path, time::{SystemTime, UNIX_EPOCH}};
+
+// Offset of local time from UTC in seconds at `at`, following TZ or /etc/localtime the way
+// libc does, without going through it. UTC when neither can be made sense of.
+pub(crate) fn local_offset(at: SystemTime) -> i64 {
+    let secs = match at.duration_since(UNIX_EPOCH) {
+        Ok(v) => v.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    let zone = match std::env::var("TZ") {
+        Ok(tz) => {
+            let tz = tz.strip_prefix(':').unwrap_or(&tz);
+            let path = match tz.starts_with('/') {
+                true => Path::new(tz).to_path_buf(),
+                false => Path::new("/usr/share/zoneinfo").join(tz),
+            };
+            // anything that isn't a zone file has to be a rule, e.g. "CET-1CEST,M3.5.0,M10.5.0/3"
+            fs::read(path).ok().and_then(|v| Zone::parse(&v)).or_else(|| Rule::parse(tz).map(Zone::from))
+        },
+        Err(_) => fs::read("/etc/localtime").ok().and_then(|v| Zone::parse(&v)),
+    };
+    zone.map(|v| v.offset(secs)).unwrap_or(0)
+}
+
+// the parts of a TZif file that matter for the offset
+struct Zone {
+    // seconds since the epoch and the offset from then on, oldest first
+    transitions: Vec<(i64, i64)>,
+    // before the first transition
+    initial: i64,
+    // after the last one, the footer of version 2+ files
+    rule: Option<Rule>,
+}
+
+impl From<Rule> for Zone {
+    fn from(rule: Rule) -> Self {
+        Self { transitions: Vec::new(), initial: rule.std, rule: Some(rule) }
+    }
+}
+
+impl Zone {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let (version, counts) = header(data)?;
+        let v1 = data.get(44..)?;
+        if !matches!(version, b'2'..=b'9') {
+            return Self::block(v1, &counts, 4);
+        }
+        // the 32-bit block comes first, then the same again with 64-bit times and a rule for
+        // everything after the last transition
+        let v2 = v1.get(block_len(&counts, 4)..)?;
+        let (_, counts) = header(v2)?;
+        let zone = Self::block(v2.get(44..)?, &counts, 8)?;
+        let footer = v2.get(44 + block_len(&counts, 8)..).unwrap_or_default();
+        let rule = std::str::from_utf8(footer).ok()
+            .and_then(|v| v.trim().lines().next())
+            .and_then(Rule::parse);
+        Some(Self { rule, ..zone })
+    }
+    fn block(data: &[u8], counts: &[usize; 6], time_size: usize) -> Option<Self> {
+        let [_, _, _, timecnt, typecnt, _] = *counts;
+        let times = data.get(..timecnt * time_size)?;
+        let indices = data.get(timecnt * time_size..timecnt * (time_size + 1))?;
+        let types = data.get(timecnt * (time_size + 1)..timecnt * (time_size + 1) + typecnt * 6)?;
+        let offset_of = |index: usize| -> Option<i64> {
+            let info = types.get(index * 6..index * 6 + 4)?;
+            Some(i32::from_be_bytes(info.try_into().ok()?) as i64)
+        };
+        let transitions = times.chunks(time_size)
+            .zip(indices)
+            .map(|(time, index)| {
+                let time = match time_size {
+                    8 => i64::from_be_bytes(time.try_into().ok()?),
+                    _ => i32::from_be_bytes(time.try_into().ok()?) as i64,
+                };
+                Some((time, offset_of(*index as usize)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { transitions, initial: offset_of(0)?, rule: None })
+    }
+    fn offset(&self, secs: i64) -> i64 {
+        match self.transitions.iter().rposition(|(time, _)| *time <= secs) {
+            Some(last) if last + 1 < self.transitions.len() || self.rule.is_none() => self.transitions[last].1,
+            None if !self.transitions.is_empty() => self.initial,
+            _ => self.rule.as_ref().map(|v| v.offset(secs)).unwrap_or(self.initial),
+        }
+    }
+}
+
+// version and the six counts, isutcnt, isstdcnt, leapcnt, timecnt, typecnt and charcnt
+fn header(data: &[u8]) -> Option<(u8, [usize; 6])> {
+    if data.get(..4)? != b"TZif" {
+        return None;
+    }
+    let mut counts = [0; 6];
+    for (i, count) in counts.iter_mut().enumerate() {
+        *count = u32::from_be_bytes(data.get(20 + i * 4..24 + i * 4)?.try_into().ok()?) as usize;
+    }
+    Some((data[4], counts))
+}
+
+fn block_len(counts: &[usize; 6], time_size: usize) -> usize {
+    let [isutcnt, isstdcnt, leapcnt, timecnt, typecnt, charcnt] = *counts;
+    timecnt * (time_size + 1) + typecnt * 6 + charcnt + leapcnt * (time_size + 4) + isstdcnt + isutcnt
+}
+
+// POSIX TZ rule, e.g. "EST5EDT,M3.2.0,M11.1.0", only the M month.week.day form of dates
+struct Rule {
+    // offsets east of UTC in seconds
+    std: i64,
+    dst: Option<Dst>,
+}
+
+struct Dst {
+    offset: i64,
+    // local times they start and end at, as month, week, weekday and seconds into the day
+    start: (u32, u32, u32, i64),
+    end: (u32, u32, u32, i64),
+}
+
+impl Rule {
+    fn parse(text: &str) -> Option<Self> {
+        let rest = skip_name(text)?;
+        let (std, rest) = parse_offset(rest)?;
+        if rest.is_empty() {
+            return Some(Self { std: -std, dst: None });
+        }
+        let rest = skip_name(rest)?;
+        let (dst, rest) = match rest.starts_with(',') {
+            true => (std - 3600, rest),
+            false => parse_offset(rest)?,
+        };
+        let (start, end) = rest.strip_prefix(',')?.split_once(',')?;
+        Some(Self {
+            std: -std,
+            dst: Some(Dst { offset: -dst, start: parse_date(start)?, end: parse_date(end)? }),
+        })
+    }
+    fn offset(&self, secs: i64) -> i64 {
+        let Some(dst) = self.dst.as_ref() else {return self.std};
+        let year = civil_from_days((secs + self.std).div_euclid(86400)).0;
+        // starts on standard time and ends on summer time
+        let start = transition(year, dst.start) - self.std;
+        let end = transition(year, dst.end) - dst.offset;
+        let summer = match start < end {
+            true => (start..end).contains(&secs),
+            // southern hemisphere, summer time goes over new year
+            false => secs >= start || secs < end,
+        };
+        if summer { dst.offset } else { self.std }
+    }
+}
+
+fn skip_name(text: &str) -> Option<&str> {
+    if let Some(quoted) = text.strip_prefix('<') {
+        return Some(quoted.split_once('>')?.1);
+    }
+    let len = text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len());
+    (len >= 3).then_some(&text[len..])
+}
+
+// [+-]hh[:mm[:ss]] in seconds, west of UTC is positive
+fn parse_offset(text: &str) -> Option<(i64, &str)> {
+    let len = text.find(|c: char| !(c.is_ascii_digit() || matches!(c, ':' | '+' | '-'))).unwrap_or(text.len());
+    Some((parse_time(&text[..len])?, &text[len..]))
+}
+
+fn parse_time(text: &str) -> Option<i64> {
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(v) => (-1, v),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let mut secs = 0;
+    for (i, part) in text.split(':').enumerate() {
+        if i > 2 {
+            return None;
+        }
+        secs += part.parse::<i64>().ok()? * [3600, 60, 1][i];
+    }
+    Some(sign * secs)
+}
+
+// Mm.w.d[/time], the time defaults to 02:00
+fn parse_date(text: &str) -> Option<(u32, u32, u32, i64)> {
+    let (date, time) = match text.split_once('/') {
+        Some((date, time)) => (date, parse_time(time)?),
+        None => (text, 7200),
+    };
+    let mut parts = date.strip_prefix('M')?.split('.').map(|v| v.parse::<u32>().ok());
+    let (month, week, day) = (parts.next()??, parts.next()??, parts.next()??);
+    ((1..=12).contains(&month) && (1..=5).contains(&week) && day <= 6).then_some((month, week, day, time))
+}
+
+// seconds since the epoch of the local time a rule date falls on in `year`
+fn transition(year: i64, (month, week, day, time): (u32, u32, u32, i64)) -> i64 {
+    let first = days_from_civil(year, month, 1);
+    // the epoch was a thursday
+    let first_weekday = (first + 4).rem_euclid(7) as u32;
+    let mut mday = 1 + (day + 7 - first_weekday) % 7 + (week - 1) * 7;
+    // week 5 means the last one
+    let days = days_from_civil(year + i64::from(month == 12), month % 12 + 1, 1) - first;
+    while mday as i64 > days {
+        mday -= 7;
+    }
+    (first + mday as i64 - 1) * 86400 + time
+}
+
+// days since the epoch of a date in the proleptic gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// year, month and day of a number of days since the epoch
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
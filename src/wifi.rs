@@ -0,0 +1,197 @@
+use crate::{command::{CommandOutput, CommandSpec}, layout::Item};
+use anyhow::{bail, Result};
+use crossbeam_channel::Sender;
+use std::{process::Command, thread};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub ssid: String,
+    // 0 - 100
+    pub signal: u8,
+    pub secured: bool,
+    // NetworkManager has a connection profile for it, so it connects without a password
+    pub known: bool,
+    pub connected: bool,
+}
+
+impl Network {
+    // text of the network's button in the picker
+    pub(crate) fn label(&self) -> String {
+        let bars = match self.signal {
+            0..=24 => "▂   ",
+            25..=49 => "▂▄  ",
+            50..=74 => "▂▄▆ ",
+            _ => "▂▄▆█",
+        };
+        let lock = if self.secured { " 🔒" } else { "" };
+        let mark = if self.connected { "✓ " } else { "" };
+        format!("{}{} {}{}", mark, bars, self.ssid, lock)
+    }
+}
+
+// Visible networks according to nmcli, strongest first. Hidden networks are left out since
+// there's nothing to show for them. Blocks until nmcli is done scanning.
+pub fn scan() -> Result<Vec<Network>> {
+    let known = nmcli(&["-t", "-f", "NAME,TYPE", "connection", "show"])?;
+    let known: Vec<String> = known.lines()
+        .map(split_fields)
+        .filter(|v| v.get(1).map(String::as_str) == Some("802-11-wireless"))
+        .filter_map(|v| v.into_iter().next())
+        .collect();
+    list("auto", &known)
+}
+
+// the network connected to right now, from what nmcli saw last without scanning again
+pub fn status() -> Result<Option<Network>> {
+    Ok(list("no", &[])?.into_iter().find(|v| v.connected))
+}
+
+fn list(rescan: &str, known: &[String]) -> Result<Vec<Network>> {
+    let list = nmcli(&["-t", "-f", "IN-USE,SSID,SIGNAL,SECURITY", "device", "wifi", "list", "--rescan", rescan])?;
+    let mut networks: Vec<Network> = Vec::new();
+    for fields in list.lines().map(split_fields) {
+        let [in_use, ssid, signal, security] = fields.as_slice() else {continue};
+        if ssid.is_empty() {
+            continue;
+        }
+        let network = Network {
+            ssid: ssid.clone(),
+            signal: signal.parse::<u8>().unwrap_or(0).min(100),
+            secured: !security.is_empty() && security != "--",
+            known: known.contains(ssid),
+            connected: in_use == "*",
+        };
+        // one entry per access point, keep the best one of each network
+        match networks.iter_mut().find(|v| v.ssid == network.ssid) {
+            Some(v) => {
+                v.connected |= network.connected;
+                v.signal = v.signal.max(network.signal);
+            },
+            None => networks.push(network),
+        }
+    }
+    networks.sort_by(|a, b| b.connected.cmp(&a.connected).then(b.signal.cmp(&a.signal)));
+    Ok(networks)
+}
+
+fn nmcli(args: &[&str]) -> Result<String> {
+    let out = Command::new("nmcli").args(args).output()?;
+    if !out.status.success() {
+        bail!("nmcli failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+// nmcli's terse output separates fields with ':' and escapes ':' and '\' inside them
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().expect("Failed to get field").extend(chars.next()),
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().expect("Failed to get field").push(c),
+        }
+    }
+    fields
+}
+
+// text of a network status item, see Gui::show_wifi_status()
+pub(crate) fn status_text(network: Option<&Network>) -> String {
+    match network {
+        Some(network) => format!("{} {}%", network.ssid, network.signal),
+        None => "Not connected".to_string(),
+    }
+}
+
+// what the threads nmcli runs on come back with
+#[derive(Debug)]
+pub(crate) enum Update {
+    Scanned(Result<Vec<Network>, String>),
+    Status(Option<Network>),
+}
+
+// scan() on a thread of its own, the networks come back through `tx`
+pub(crate) fn spawn_scan(tx: Sender<CommandOutput>) {
+    thread::spawn(move || {
+        let networks = scan().map_err(|e| e.to_string());
+        let _ = tx.send(CommandOutput::Wifi(Update::Scanned(networks)));
+    });
+}
+
+// status() on a thread of its own, a failed one counts as not connected
+pub(crate) fn spawn_status(tx: Sender<CommandOutput>) {
+    thread::spawn(move || {
+        let network = status().ok().flatten();
+        let _ = tx.send(CommandOutput::Wifi(Update::Status(network)));
+    });
+}
+
+// Lists networks as buttons and connects to the one that gets pressed, see
+// Gui::show_wifi_picker(). Button ids follow the picker id: id + 1 is the first network, the
+// password input of secured networks gets the picker id itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WifiPicker {
+    pub id: u128,
+    pub tab: usize,
+    pub networks: Vec<Network>,
+    pub scanning: bool,
+    // why the last scan failed
+    pub error: Option<String>,
+    // secured network waiting for its password
+    pub asking: Option<String>,
+    // network being connected to and the last line nmcli printed
+    pub connecting: Option<(String, Option<String>)>,
+}
+
+impl WifiPicker {
+    pub fn new(id: u128, tab: usize) -> Self {
+        Self {
+            id,
+            tab,
+            networks: Vec::new(),
+            scanning: true,
+            error: None,
+            asking: None,
+            connecting: None,
+        }
+    }
+    pub fn rows(&self) -> Vec<Vec<Item>> {
+        let message = match (&self.error, self.scanning) {
+            _ if !self.networks.is_empty() => None,
+            (Some(error), false) => Some(format!("Scan failed: {}", error)),
+            (None, false) => Some("No networks found".to_string()),
+            (_, true) => Some("Scanning…".to_string()),
+        };
+        if let Some(message) = message {
+            return vec![vec![Item::Text(message)]];
+        }
+        let mut rows = Vec::new();
+        for (i, network) in self.networks.iter().enumerate() {
+            rows.push(vec![Item::StatelessButton(network.label(), self.id + 1 + i as u128)]);
+            if self.asking.as_ref() == Some(&network.ssid) {
+                rows.push(vec![Item::TextInput("Password".to_string(), String::new(), true, self.id)]);
+            }
+        }
+        rows
+    }
+    // row of the password input, if one is shown
+    pub fn password_row(&self) -> Option<usize> {
+        let ssid = self.asking.as_ref()?;
+        Some(self.networks.iter().position(|v| v.ssid == *ssid)? + 1)
+    }
+    pub fn network(&self, id: u128) -> Option<&Network> {
+        let idx = id.checked_sub(self.id + 1)?;
+        self.networks.get(usize::try_from(idx).ok()?)
+    }
+}
+
+// the password goes to nmcli's prompt instead of its arguments, which anyone can read from /proc
+pub(crate) fn connect_spec(network: &Network, password: Option<&str>) -> CommandSpec {
+    match password {
+        Some(password) => CommandSpec::new("nmcli", &["--ask", "device", "wifi", "connect", &network.ssid])
+            .stdin(&format!("{}\n", password)),
+        None if network.known => CommandSpec::new("nmcli", &["connection", "up", "id", &network.ssid]),
+        None => CommandSpec::new("nmcli", &["device", "wifi", "connect", &network.ssid]),
+    }
+}
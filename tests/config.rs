@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use sgui::{config::Config, HidEvent};
+
+#[test]
+fn parses_every_section() {
+    let config = Config::parse(r#"
+# shared by every app
+theme = "light"
+
+[font]
+path = "/usr/share/fonts/TTF/DejaVuSans.ttf"
+size = 24
+# unknown, left alone
+weight = 700
+
+[repeat]
+repeat_gap_ms = 150
+fast_step = 3
+
+[keys]
+k = "up"
+Return = "press"
+
+[buttons]
+west = "grab"
+"#).unwrap();
+
+    assert_eq!(config.theme.as_deref(), Some("light"));
+    assert_eq!(config.font_size, Some(24));
+    let repeat = config.repeat.unwrap();
+    assert_eq!(repeat.repeat_gap, Duration::from_millis(150));
+    assert_eq!(repeat.fast_step, 3);
+    assert_eq!(config.bindings.key("k"), Some(HidEvent::Up));
+    // backends call it differently
+    assert_eq!(config.bindings.key("enter"), Some(HidEvent::ButtonPress));
+    assert_eq!(config.bindings.button("West"), Some(HidEvent::Grab));
+    assert_eq!(config.bindings.key("j"), None);
+}
+
+#[test]
+fn rejects_what_it_cant_use() {
+    assert!(Config::parse("theme").is_err());
+    assert!(Config::parse("[font]\nsize = 0").is_err());
+    assert!(Config::parse("[keys]\nnotakey = \"up\"").is_err());
+    assert!(Config::parse("[buttons]\nwest = \"jump\"").is_err());
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "test-util")]
+
+use sgui::{
+    controller::ControllerStyle,
+    layout::Layout,
+    renderer_null::RecordingRenderer,
+    Gui, RendererEvent,
+};
+
+fn gui() -> (Gui, RecordingRenderer) {
+    let pads = RecordingRenderer::new();
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .text("Text")
+        .build();
+    let gui = Gui::builder(layout)
+        .renderer(Box::new(pads.clone()))
+        .no_default_inputs()
+        .build();
+    (gui, pads)
+}
+
+#[test]
+fn style_follows_hotplug() {
+    let (mut gui, pads) = gui();
+    assert_eq!(gui.controller_style(), ControllerStyle::Xbox);
+
+    pads.set_controller(Some("Nintendo Switch Pro Controller"));
+    pads.send(RendererEvent::ControllersChanged);
+    gui.step();
+    assert_eq!(gui.controller_style(), ControllerStyle::Nintendo);
+    assert!(gui.confirm_swap().swapped());
+
+    pads.set_controller(None);
+    pads.send(RendererEvent::ControllersChanged);
+    gui.step();
+    assert_eq!(gui.controller_style(), ControllerStyle::Xbox);
+    assert!(!gui.confirm_swap().swapped());
+}
+
+#[test]
+fn pinned_style_stays() {
+    let (mut gui, pads) = gui();
+    gui.set_controller_style(Some(ControllerStyle::PlayStation));
+
+    pads.set_controller(Some("Nintendo Switch Pro Controller"));
+    pads.send(RendererEvent::ControllersChanged);
+    gui.step();
+    assert_eq!(gui.controller_style(), ControllerStyle::PlayStation);
+}
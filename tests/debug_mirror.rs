@@ -0,0 +1,41 @@
+#![cfg(all(feature = "debug-mirror", feature = "test-util"))]
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use sgui::{layout::Layout, renderer_null::RecordingRenderer, Gui, HidEvent};
+
+fn page(port: u16) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let mut page = String::new();
+    stream.read_to_string(&mut page).unwrap();
+    page
+}
+
+#[test]
+fn mirror_shows_the_current_state() {
+    let layout = Layout::builder()
+        .tab("<Main>")
+            .line()
+                .password_input("Password", "hunter2", 1)
+            .line()
+                .button_stateless("OK", 2)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(RecordingRenderer::new()))
+        .no_default_inputs()
+        .build();
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    gui.serve_debug_mirror(("127.0.0.1", port)).unwrap();
+
+    gui.push_event(HidEvent::Down);
+    gui.settle();
+    let page = page(port);
+    assert!(page.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(page.contains("focus: row 1, column 0"));
+    assert!(page.contains("&lt;Main&gt;"));
+    assert!(!page.contains("hunter2"));
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use anyhow::Result;
+use sgui::{
+    draw::{draw_primitives, DrawContext, DrawHooks, DrawRect, DrawTarget, Primitive},
+    layout::{Item, ItemKind},
+    Color, ColorPalette,
+};
+
+// writes down every call instead of drawing
+#[derive(Default)]
+struct Calls(Vec<String>);
+
+impl DrawContext for Calls {
+    fn fill_rect(&mut self, rect: DrawRect, _color: Color) -> Result<()> {
+        self.0.push(format!("fill {} {} {} {}", rect.x, rect.y, rect.width, rect.height));
+        Ok(())
+    }
+    fn draw_rect(&mut self, rect: DrawRect, _color: Color) -> Result<()> {
+        self.0.push(format!("rect {} {} {} {}", rect.x, rect.y, rect.width, rect.height));
+        Ok(())
+    }
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, _color: Color) -> Result<()> {
+        self.0.push(format!("text {} {} {}", text, x, y));
+        Ok(())
+    }
+}
+
+#[test]
+fn primitives_fall_back_on_what_every_context_has() {
+    let white = Color::new(255, 255, 255);
+    let mut calls = Calls::default();
+    draw_primitives(&mut calls, &[
+        Primitive::FillRect(DrawRect::new(0, 0, 10, 2), white),
+        Primitive::Line((5, 4), (1, 4), white),
+        // no diagonals where the context can't do them
+        Primitive::Line((0, 0), (3, 3), white),
+        Primitive::Image(Path::new("cover.png"), DrawRect::new(2, 2, 4, 4), white),
+        Primitive::Text("Hi", 1, 1, white),
+    ]).unwrap();
+    assert_eq!(calls.0, ["fill 0 0 10 2", "fill 1 4 5 1", "rect 2 2 4 4", "text Hi 1 1"]);
+}
+
+#[test]
+fn hooks_for_an_id_win_over_hooks_for_a_kind() {
+    let mut hooks = DrawHooks::default();
+    assert!(hooks.is_empty());
+    hooks.add(DrawTarget::Kind(ItemKind::StatelessButton), Box::new(|item, _, cell, _, ctx| {
+        let Item::StatelessButton(text, _) = item else {return false};
+        ctx.draw_text(&format!("<{}>", text), cell.x, cell.y, Color::new(0, 0, 0)).is_ok()
+    }));
+    hooks.add(DrawTarget::Id(2), Box::new(|_, selected, _, _, _| selected));
+
+    let (cell, colors) = (DrawRect::new(3, 1, 8, 1), ColorPalette::default());
+    let mut calls = Calls::default();
+    let ok = Item::StatelessButton("OK".to_string(), 1);
+    let quit = Item::StatelessButton("Quit".to_string(), 2);
+    assert!(hooks.run(&ok, false, cell, &colors, &mut calls));
+    // the id's hook left it to the renderer instead of falling through to the kind's
+    assert!(!hooks.run(&quit, false, cell, &colors, &mut calls));
+    assert!(hooks.run(&quit, true, cell, &colors, &mut calls));
+    assert!(!hooks.run(&Item::Text("Hi".to_string()), false, cell, &colors, &mut calls));
+    assert_eq!(calls.0, ["text <OK> 3 1"]);
+
+    // a later hook replaces the earlier one
+    hooks.add(DrawTarget::Kind(ItemKind::StatelessButton), Box::new(|_, _, _, _, _| false));
+    assert!(!hooks.run(&ok, false, cell, &colors, &mut calls));
+}
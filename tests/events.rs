@@ -0,0 +1,123 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use sgui::{
+    clock::ManualClock,
+    event_log::LoggedEvent,
+    layout::Layout,
+    renderer_null::RecordingRenderer,
+    Gui, GuiEvent, HidEvent, RendererEvent, ViewportInfo,
+};
+
+fn layout() -> Layout {
+    Layout::builder()
+        .tab("Tab")
+            .line()
+                .toggle("Wifi", false, 1)
+            .line()
+                .button_stateless("OK", 2)
+        .build()
+}
+
+#[test]
+fn event_history_keeps_the_last_few() {
+    let clock = ManualClock::new();
+    let mut gui = Gui::builder(layout())
+        .renderer(Box::new(RecordingRenderer::new()))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .event_log(3)
+        .build();
+
+    gui.push_event(HidEvent::Down);
+    gui.settle();
+    clock.advance(Duration::from_secs(1));
+    gui.push_event(HidEvent::Up);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.settle();
+    let history: Vec<_> = gui.event_history().map(|v| (v.at.as_secs(), v.event.clone())).collect();
+    assert_eq!(history, vec![
+        (1, LoggedEvent::Hid(HidEvent::Up)),
+        (1, LoggedEvent::Hid(HidEvent::ButtonPress)),
+        (1, LoggedEvent::Gui(GuiEvent::ToggleChanged(1, true))),
+    ]);
+}
+
+#[test]
+fn event_history_can_be_turned_off() {
+    let mut gui = Gui::builder(layout())
+        .renderer(Box::new(RecordingRenderer::new()))
+        .no_default_inputs()
+        .event_log(0)
+        .build();
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.settle(), vec![GuiEvent::ToggleChanged(1, true)]);
+    assert_eq!(gui.event_history().count(), 0);
+}
+
+#[test]
+fn journal_gets_a_line_per_returned_event() {
+    let path = std::env::temp_dir().join(format!("sgui-journal-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut gui = Gui::builder(layout())
+        .renderer(Box::new(RecordingRenderer::new()))
+        .no_default_inputs()
+        .build();
+    gui.start_journal(&path).unwrap();
+    // leaves out the button
+    gui.set_journal_redactor(|entry| entry.event != "StatelessButtonPress");
+
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.settle();
+    gui.stop_journal();
+    gui.push_event(HidEvent::Up);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.settle();
+
+    let journal = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<_> = journal.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains(r#""event":"ToggleChanged","detail":"1, true""#));
+    assert!(lines[0].contains(r#""item":{"id":"1","kind":"Toggle","label":"Wifi","tab":"Tab"}"#));
+}
+
+#[test]
+fn focus_callback_hears_of_every_focused_item() {
+    let mut gui = Gui::builder(layout())
+        .renderer(Box::new(RecordingRenderer::new()))
+        .no_default_inputs()
+        .build();
+    let (tx, rx) = std::sync::mpsc::channel();
+    gui.set_focus_callback(move |id| tx.send(id).unwrap());
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![1]);
+
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::Up);
+    gui.settle();
+    // staying on the last row isn't a change of focus
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![2, 1]);
+}
+
+#[test]
+fn resizes_report_the_new_viewport() {
+    let recorder = RecordingRenderer::new();
+    let mut gui = Gui::builder(layout())
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .build();
+    // nothing to tell without a size
+    assert_eq!(gui.viewport_info(), None);
+    recorder.send(RendererEvent::Resized);
+    assert_eq!(gui.settle(), vec![]);
+
+    let viewport = ViewportInfo { width: 80, height: 24, cell_width: 1, cell_height: 1, rows: 21, columns: 78 };
+    recorder.recording().viewport = Some(viewport);
+    assert_eq!(gui.viewport_info(), Some(viewport));
+    recorder.send(RendererEvent::Resized);
+    assert_eq!(gui.settle(), vec![GuiEvent::Resized(viewport)]);
+}
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use sgui::format::{self, Value};
+
+#[test]
+fn numbers_read_like_people_write_them() {
+    assert_eq!(format::bytes(512), "512 B");
+    assert_eq!(format::bytes(1536), "1.5 KiB");
+    assert_eq!(format::bytes(1288490188), "1.2 GiB");
+    assert_eq!(format::bytes(50 * 1024 * 1024), "50 MiB");
+    assert_eq!(format::duration(Duration::from_secs(185)), "3 min 5 s");
+    assert_eq!(format::duration(Duration::from_secs(7200)), "2 h");
+    assert_eq!(format::duration(Duration::from_secs(100_000)), "1 d 3 h");
+    assert_eq!(format::countdown(Duration::from_millis(41_200)), "42 s");
+    assert_eq!(format::ago(Duration::from_secs(30)), "just now");
+    assert_eq!(format::ago(Duration::from_secs(190)), "3 min ago");
+    assert_eq!(format::percent(0.424), "42%");
+    assert_eq!(format::percent(1.5), "100%");
+    assert_eq!(format::number(45.0), "45");
+    assert_eq!(format::number(37.46), "37.5");
+    assert_eq!(Value::Bytes(2048).to_string(), "2.0 KiB");
+}
@@ -0,0 +1,442 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use sgui::{
+    analog::AnalogConfig,
+    clock::ManualClock,
+    input::{parse_command, Filter},
+    layout::{Item, Layout, SelectorMode},
+    renderer_null::{NullRenderer, RecordingRenderer},
+    repeat::NavAcceleration,
+    ActionButton, Axis, Gui, GuiEvent, HidEvent,
+};
+
+fn gui(layout: Layout) -> Gui {
+    Gui::builder(layout)
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .build()
+}
+
+fn layout() -> Layout {
+    Layout::builder()
+        .tab("Tab")
+            .line()
+                .text_input("Name", "", 1)
+            .line()
+                .button_stateless("OK", 2)
+        .build()
+}
+
+#[test]
+fn filter_captures_navigation() {
+    let mut gui = gui(layout());
+    gui.set_input_filter(|_| Filter::Capture);
+    gui.push_event(HidEvent::Down);
+    assert_eq!(gui.step(), Some(GuiEvent::HidCaptured(HidEvent::Down)));
+    assert_eq!(gui.focused_cell(), (0, 0));
+}
+
+#[test]
+fn filter_leaves_the_keyboard_alone() {
+    let mut gui = gui(layout());
+    // opens the keyboard
+    gui.push_event(HidEvent::ButtonPress);
+    gui.step();
+    gui.set_input_filter(|_| Filter::Capture);
+
+    gui.push_event(HidEvent::Down);
+    assert_ne!(gui.step(), Some(GuiEvent::HidCaptured(HidEvent::Down)));
+    // leaves the keyboard, from then on the filter gets everything
+    gui.push_event(HidEvent::Back);
+    assert_ne!(gui.step(), Some(GuiEvent::HidCaptured(HidEvent::Back)));
+    gui.push_event(HidEvent::Down);
+    assert_eq!(gui.step(), Some(GuiEvent::HidCaptured(HidEvent::Down)));
+}
+
+#[test]
+fn ignored_input_beats_bindings() {
+    let layout = Layout::builder()
+        .tab("Tab")
+            .bind(HidEvent::Down, Filter::Capture)
+            .line()
+                .button_stateless("OK", 2)
+        .build();
+    let mut gui = gui(layout);
+    gui.set_ignore_hid(true);
+    gui.push_event(HidEvent::Down);
+    assert_eq!(gui.step(), Some(GuiEvent::IgnoredHid));
+    gui.set_ignore_hid(false);
+    gui.push_event(HidEvent::Down);
+    assert_eq!(gui.step(), Some(GuiEvent::HidCaptured(HidEvent::Down)));
+}
+
+#[test]
+fn tab_changes_carry_the_name() {
+    let layout = Layout::builder()
+        .tab("Network")
+            .line()
+                .button_stateless("A", 1)
+        .tab("Sound")
+            .line()
+                .button_stateless("B", 2)
+        .tab("About")
+            .line()
+                .text("sgui")
+        .build();
+    let mut gui = gui(layout);
+    gui.push_event(HidEvent::NextTab);
+    assert_eq!(gui.step(), Some(GuiEvent::TabChanged("Sound".to_string())));
+    gui.push_event(HidEvent::PreviousTab);
+    assert_eq!(gui.step(), Some(GuiEvent::TabChanged("Network".to_string())));
+    gui.push_event(HidEvent::GoToTab(2));
+    assert_eq!(gui.step(), Some(GuiEvent::TabChanged("About".to_string())));
+}
+
+#[test]
+fn selector_popup_picks_and_follows_new_options() {
+    let layout = Layout::builder()
+        .tab("Sound")
+            .line()
+                .selector_popup("Output", &["Speakers", "HDMI"], 0, 3)
+        .build();
+    let mut gui = gui(layout);
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.step(), None);
+    gui.push_event(HidEvent::Down);
+    gui.step();
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.step(), Some(GuiEvent::SelectorChanged(3, 1)));
+
+    // a device went away while the list was open
+    gui.push_event(HidEvent::ButtonPress);
+    gui.step();
+    gui.set_selector_options(3, &["Speakers"], 0);
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.step(), None);
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.step(), Some(GuiEvent::SelectorChanged(3, 0)));
+
+    gui.set_selector_options(3, &["Speakers", "HDMI", "Headphones"], 0);
+    gui.set_selector(3, 7);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.step();
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.step(), Some(GuiEvent::SelectorChanged(3, 2)));
+}
+
+#[test]
+fn radio_picks_are_undone_and_located() {
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .radio_group(&["Low", "Mid", "High"], 0, 4)
+        .build();
+    let mut gui = gui(layout);
+    gui.push_event(HidEvent::Right);
+    gui.step();
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.step(), Some(GuiEvent::RadioChanged(4, 1)));
+    assert_eq!(gui.event_origin().map(|v| v.column), Some(1));
+    gui.push_event(HidEvent::Right);
+    gui.step();
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.step(), Some(GuiEvent::RadioChanged(4, 2)));
+
+    // one pick at a time, the origin is the option picked back, not the focused one
+    gui.push_event(HidEvent::Undo);
+    assert_eq!(gui.step(), Some(GuiEvent::RadioChanged(4, 1)));
+    assert_eq!(gui.event_origin().map(|v| v.column), Some(1));
+    assert_eq!(gui.undo(), Some(GuiEvent::RadioChanged(4, 0)));
+    assert_eq!(gui.redo(), Some(GuiEvent::RadioChanged(4, 1)));
+}
+
+#[test]
+fn sliders_let_the_focus_go_at_their_ends() {
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .slider("Volume", 0, 10, 5, 5, 1)
+                .button_stateless("Mute", 2)
+        .build();
+    let mut gui = gui(layout);
+    gui.push_event(HidEvent::Right);
+    assert_eq!(gui.step(), Some(GuiEvent::SliderChanged(1, 10)));
+    assert_eq!(gui.focused_cell(), (0, 0));
+    gui.push_event(HidEvent::Right);
+    gui.step();
+    assert_eq!(gui.focused_cell(), (0, 1));
+}
+
+#[test]
+fn stick_noise_leaves_shortcuts_alone() {
+    let mut gui = gui(layout());
+    gui.reserve_shortcut("menu", &[HidEvent::PreviousTab, HidEvent::NextTab]);
+    gui.push_event(HidEvent::PreviousTab);
+    gui.step();
+    gui.push_event(HidEvent::Analog { axis: Axis::RightY, value: 900 });
+    gui.step();
+    gui.push_event(HidEvent::NextTab);
+    assert_eq!(gui.step(), Some(GuiEvent::Shortcut("menu".to_string())));
+}
+
+#[test]
+fn held_directions_only_speed_up_when_asked_to() {
+    let mut builder = Layout::builder().tab("List").line().button_stateless("Item 0", 0);
+    for i in 1..20 {
+        builder = builder.line().button_stateless(&format!("Item {}", i), i);
+    }
+    let layout = builder.build();
+    // Down held for two seconds, repeating every 100ms
+    let hold = |accelerate: bool| {
+        let clock = ManualClock::new();
+        let mut builder = Gui::builder(layout.clone())
+            .renderer(Box::new(NullRenderer))
+            .no_default_inputs()
+            .clock(clock.clone());
+        if accelerate {
+            builder = builder.nav_acceleration(Some(NavAcceleration::default()));
+        }
+        let mut gui = builder.build();
+        for _ in 0..17 {
+            gui.push_event(HidEvent::Down);
+            gui.step();
+            clock.advance(Duration::from_millis(100));
+        }
+        gui.focused_cell()
+    };
+    assert_eq!(hold(false), (17, 0));
+    assert!(hold(true).0 > 17);
+}
+
+#[test]
+fn back_drops_the_grab_highlight() {
+    let layout = Layout::builder()
+        .tab("Queue")
+            .reorderable()
+            .line()
+                .button_stateless("First", 1)
+            .line()
+                .button_stateless("Second", 2)
+        .build();
+    let recorder = RecordingRenderer::new();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .build();
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::Grab);
+    gui.settle();
+    assert!(recorder.recording().edit_drawn);
+
+    // put back without having moved
+    gui.push_event(HidEvent::Back);
+    gui.settle();
+    assert!(!recorder.recording().edit_drawn);
+    assert_eq!(gui.focused_cell(), (1, 0));
+}
+
+#[test]
+fn new_tab_items_close_what_was_open_on_the_old_ones() {
+    let layout = Layout::builder()
+        .tab("Audio")
+            .line()
+                .selector_popup("Output", &["Speakers", "HDMI"], 0, 1)
+                .text_input("Name", "Living room", 2)
+        .build();
+    let mut gui = gui(layout);
+    let items = || vec![vec![
+        Item::StatelessButton("Rescan".to_string(), 3),
+        Item::Selector("Output".to_string(), vec!["HDMI".to_string()], 0, SelectorMode::Popup, 1),
+    ]];
+
+    // popup open, then the grid changes under it
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::Down);
+    gui.settle();
+    gui.set_tab_items(0, items());
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.settle(), vec![GuiEvent::StatelessButtonPress("Rescan".to_string(), 3)]);
+
+    // same for the keyboard, confirming would rename another input
+    gui.set_tab_items(0, vec![vec![Item::Text("Name".to_string()), Item::TextInput("Name".to_string(), "Kitchen".to_string(), false, 2)]]);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.settle();
+    gui.set_tab_items(0, vec![vec![Item::Text("Host".to_string()), Item::TextInput("Host".to_string(), "sgui".to_string(), false, 4)]]);
+    gui.push_event(HidEvent::Action(ActionButton::Start));
+    assert_eq!(gui.settle(), vec![]);
+}
+
+#[test]
+fn dropdown_reports_the_picked_option() {
+    let layout = Layout::builder()
+        .tab("Display")
+            .line()
+                .select("Resolution", &["640x480", "1280x720", "1920x1080"], 1, 5)
+        .build();
+    let mut gui = gui(layout);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.settle(), vec![GuiEvent::SelectorChanged(5, 2)]);
+}
+
+#[test]
+fn raw_taps_see_everything_that_comes_in() {
+    let mut gui = gui(layout());
+    let tap = gui.subscribe_raw_hid();
+    // the keyboard swallows these
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::Right);
+    gui.settle();
+    gui.set_ignore_hid(true);
+    gui.push_event(HidEvent::Back);
+    gui.settle();
+    assert_eq!(tap.try_iter().collect::<Vec<_>>(), vec![HidEvent::ButtonPress, HidEvent::Right, HidEvent::Back]);
+
+    // a dropped tap unsubscribes, the gui carries on without it
+    drop(tap);
+    gui.push_event(HidEvent::Down);
+    gui.settle();
+}
+
+#[test]
+fn commands_read_from_lines() {
+    assert_eq!(parse_command("up\n"), Some(HidEvent::Up));
+    assert_eq!(parse_command("  PRESS "), Some(HidEvent::ButtonPress));
+    assert_eq!(parse_command("tab 2"), Some(HidEvent::GoToTab(2)));
+    assert_eq!(parse_command("tab"), Some(HidEvent::NextTab));
+    assert_eq!(parse_command("action north"), Some(HidEvent::Action(ActionButton::North)));
+    assert_eq!(parse_command("axis lx -32768"), Some(HidEvent::Analog { axis: Axis::LeftX, value: -32768 }));
+    // anything that doesn't make sense is skipped
+    assert_eq!(parse_command(""), None);
+    assert_eq!(parse_command("jump"), None);
+    assert_eq!(parse_command("tab two"), None);
+    assert_eq!(parse_command("axis lx 99999"), None);
+}
+
+#[test]
+fn left_stick_works_the_d_pad() {
+    let clock = ManualClock::new();
+    let mut builder = Layout::builder().tab("Tab").line().button_stateless("0", 0);
+    for i in 1..6 {
+        builder = builder.line().button_stateless(&i.to_string(), i);
+    }
+    let mut gui = Gui::builder(builder.build())
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .build();
+    let stick = |gui: &mut Gui, value| {
+        gui.push_event(HidEvent::Analog { axis: Axis::LeftY, value });
+        gui.settle();
+    };
+
+    // inside the deadzone
+    stick(&mut gui, 5000);
+    assert_eq!(gui.focused_cell(), (0, 0));
+    stick(&mut gui, 30000);
+    assert_eq!(gui.focused_cell(), (1, 0));
+    // wobbling above the release threshold doesn't press again
+    stick(&mut gui, 24000);
+    stick(&mut gui, 30000);
+    assert_eq!(gui.focused_cell(), (1, 0));
+    // held, it repeats
+    clock.advance(Duration::from_millis(400));
+    gui.settle();
+    assert_eq!(gui.focused_cell(), (2, 0));
+    clock.advance(Duration::from_millis(120));
+    gui.settle();
+    assert_eq!(gui.focused_cell(), (3, 0));
+
+    stick(&mut gui, 0);
+    clock.advance(Duration::from_secs(1));
+    gui.settle();
+    assert_eq!(gui.focused_cell(), (3, 0));
+    assert_eq!(AnalogConfig::default().normalized(Axis::LeftY, -i16::MAX), -1.0);
+}
+
+#[test]
+fn guide_and_shortcuts_get_through_captures() {
+    let clock = ManualClock::new();
+    let mut gui = Gui::builder(layout())
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .build();
+    gui.set_input_filter(|_| Filter::Capture);
+    gui.reserve_shortcut("menu", &[HidEvent::Action(ActionButton::Select), HidEvent::Action(ActionButton::Start)]);
+
+    gui.push_event(HidEvent::Home);
+    assert_eq!(gui.settle(), vec![GuiEvent::HomePressed]);
+    gui.push_event(HidEvent::Action(ActionButton::Select));
+    gui.push_event(HidEvent::Action(ActionButton::Start));
+    assert_eq!(gui.settle(), vec![
+        GuiEvent::HidCaptured(HidEvent::Action(ActionButton::Select)),
+        GuiEvent::Shortcut("menu".to_string()),
+    ]);
+    // too slow to count
+    gui.push_event(HidEvent::Action(ActionButton::Select));
+    gui.settle();
+    clock.advance(Duration::from_secs(1));
+    gui.push_event(HidEvent::Action(ActionButton::Start));
+    assert_eq!(gui.settle(), vec![GuiEvent::HidCaptured(HidEvent::Action(ActionButton::Start))]);
+}
+
+#[test]
+fn custom_sources_feed_the_gui() {
+    let mut events = vec![HidEvent::Down, HidEvent::ButtonPress].into_iter();
+    let mut gui = Gui::builder(layout())
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .input(move || events.next())
+        .build();
+
+    // the source runs on a thread of its own
+    let mut got = Vec::new();
+    for _ in 0..200 {
+        got.extend(gui.settle());
+        if !got.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(got, vec![GuiEvent::StatelessButtonPress("OK".to_string(), 2)]);
+}
+
+#[test]
+fn stick_moves_sliders_faster_the_further_it_goes() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .slider("Volume", 0, 100, 1, 50, 1)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .build();
+    let mut stick = |value| {
+        gui.push_event(HidEvent::Analog { axis: Axis::LeftX, value });
+        gui.settle()
+    };
+
+    assert_eq!(stick(i16::MAX), vec![GuiEvent::SliderChanged(1, 51)]);
+    // all the way out it repeats every 40ms
+    clock.advance(Duration::from_millis(40));
+    assert_eq!(stick(i16::MAX), vec![GuiEvent::SliderChanged(1, 52)]);
+    // half way, a lot slower
+    clock.advance(Duration::from_millis(40));
+    stick(20400);
+    clock.advance(Duration::from_millis(100));
+    assert_eq!(stick(20400), vec![]);
+    clock.advance(Duration::from_millis(200));
+    assert_eq!(stick(20400), vec![GuiEvent::SliderChanged(1, 54)]);
+
+    assert_eq!(stick(0), vec![]);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(stick(0), vec![]);
+    assert_eq!(stick(-i16::MAX), vec![GuiEvent::SliderChanged(1, 53)]);
+}
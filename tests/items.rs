@@ -0,0 +1,435 @@
+#![cfg(feature = "test-util")]
+
+use sgui::{
+    command::CommandSpec,
+    datetime::{Date, Time},
+    feedback::{Feedback, FeedbackTheme},
+    form::{Form, FormValue},
+    format::Value,
+    layout::{Item, ItemAction, Layout, SPARKLINE_SAMPLES},
+    renderer_null::RecordingRenderer,
+    ActionButton, Gui, GuiEvent, HidEvent,
+};
+
+fn gui(layout: Layout) -> (Gui, RecordingRenderer) {
+    let recorder = RecordingRenderer::new();
+    let gui = Gui::builder(layout)
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .build();
+    (gui, recorder)
+}
+
+// presses every event in turn, returns what came out
+fn press(gui: &mut Gui, events: &[HidEvent]) -> Vec<GuiEvent> {
+    for ev in events {
+        gui.push_event(ev.clone());
+    }
+    gui.settle()
+}
+
+#[test]
+fn undo_and_redo_walk_the_changes() {
+    let layout = Layout::builder()
+        .tab("Settings")
+            .line()
+                .toggle("Wifi", false, 1)
+            .line()
+                .toggle("Bluetooth", false, 2)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Down, HidEvent::ButtonPress]);
+    assert_eq!(gui.undo(), Some(GuiEvent::ToggleChanged(2, false)));
+    assert_eq!(gui.undo(), Some(GuiEvent::ToggleChanged(1, false)));
+    assert_eq!(gui.undo(), None);
+    assert_eq!(gui.redo(), Some(GuiEvent::ToggleChanged(1, true)));
+    gui.settle();
+    assert!(matches!(recorder.item(0, 0), Item::Toggle(_, true, 1)));
+    assert!(matches!(recorder.item(1, 0), Item::Toggle(_, false, 2)));
+
+    // a new change drops what could have been redone
+    press(&mut gui, &[HidEvent::ButtonPress]);
+    assert_eq!(gui.redo(), None);
+}
+
+#[test]
+fn items_answer_their_own_buttons() {
+    let layout = Layout::builder()
+        .tab("Games")
+            .item_action(1, HidEvent::Action(ActionButton::North), ItemAction::Emit("details".into()))
+            .item_action(1, HidEvent::Action(ActionButton::Start), ItemAction::Activate)
+            .line()
+                .button_stateless("Doom", 1)
+        .build();
+    let (mut gui, _) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::Action(ActionButton::North)]), vec![GuiEvent::ItemAction(1, "details".into())]);
+    assert_eq!(press(&mut gui, &[HidEvent::Action(ActionButton::Start)]), vec![GuiEvent::StatelessButtonPress("Doom".into(), 1)]);
+    // Start took over activating it
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress]), vec![]);
+}
+
+#[test]
+fn progress_bars_follow_set_progress() {
+    let layout = Layout::builder()
+        .tab("Update")
+            .line()
+                .progress_bar("Download", 0.0, 1)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    gui.set_progress(1, 0.25);
+    gui.settle();
+    assert!(matches!(recorder.item(0, 0), Item::ProgressBar(_, v, 1) if v == 0.25));
+    // out of range ends up full
+    gui.set_progress(1, 3.0);
+    gui.settle();
+    assert!(matches!(recorder.item(0, 0), Item::ProgressBar(_, v, 1) if v == 1.0));
+}
+
+#[test]
+fn gauges_follow_set_gauge_on_every_tab() {
+    let layout = Layout::builder()
+        .tab("Dashboard")
+            .line()
+                .gauge("CPU", 40.0, 90.0, 1)
+        .tab("Thermals")
+            .line()
+                .gauge("CPU", 40.0, 90.0, 1)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    gui.set_gauge(1, 72.5);
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Gauge("CPU".into(), 72.5, 90.0, 1));
+    press(&mut gui, &[HidEvent::NextTab]);
+    assert_eq!(recorder.item(0, 0), Item::Gauge("CPU".into(), 72.5, 90.0, 1));
+}
+
+#[test]
+fn empty_layouts_tabs_and_rows_are_shown() {
+    let (mut empty, recorder) = gui(Layout::builder().build());
+    assert_eq!(press(&mut empty, &[HidEvent::Down, HidEvent::ButtonPress, HidEvent::NextTab]), vec![]);
+    // a placeholder instead
+    assert!(matches!(recorder.item(0, 0), Item::Text(_)));
+
+    let layout = Layout::builder()
+        .tab("Empty")
+        .tab("Blank rows")
+            .line()
+            .line()
+        .build();
+    let (mut gui, _) = gui(layout);
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::ButtonPress]), vec![]);
+    assert_eq!(press(&mut gui, &[HidEvent::NextTab, HidEvent::Down, HidEvent::Right, HidEvent::ButtonPress]),
+        vec![GuiEvent::TabChanged("Blank rows".into())]);
+}
+
+#[test]
+fn separators_and_headers_are_stepped_over() {
+    let layout = Layout::builder()
+        .tab("Settings")
+            .line()
+                .header("Display")
+            .line()
+                .toggle("Night mode", false, 1)
+            .line()
+                .separator()
+            .line()
+                .toggle("Sound", false, 2)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    gui.settle();
+    assert_eq!(recorder.recording().selected, (1, 0));
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::ButtonPress]), vec![GuiEvent::ToggleChanged(2, true)]);
+    assert_eq!(press(&mut gui, &[HidEvent::Up, HidEvent::Up, HidEvent::ButtonPress]), vec![GuiEvent::ToggleChanged(1, true)]);
+}
+
+#[test]
+fn forms_apply_and_revert_as_a_whole() {
+    let layout = Layout::builder()
+        .tab("Network")
+            .line()
+                .toggle("DHCP", true, 1)
+            .line()
+                .slider("MTU", 500, 1500, 100, 1500, 2)
+            .line()
+                .button_stateless("Apply", 3)
+                .button_stateless("Revert", 4)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(RecordingRenderer::new()))
+        .no_default_inputs()
+        .form(Form::new(9, &[1, 2]).apply_button(3).revert_button(4))
+        .build();
+
+    // changes aren't reported one by one
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Down, HidEvent::Left]), vec![]);
+    assert_eq!(gui.form_values(9), Some(vec![(1, FormValue::Bool(false)), (2, FormValue::Number(1400))]));
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::ButtonPress]),
+        vec![GuiEvent::FormSubmitted(9, vec![(1, FormValue::Bool(false)), (2, FormValue::Number(1400))])]);
+
+    press(&mut gui, &[HidEvent::Up, HidEvent::Left, HidEvent::Left]);
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::Right, HidEvent::ButtonPress]), vec![GuiEvent::FormReverted(9)]);
+    assert_eq!(gui.form_values(9), Some(vec![(1, FormValue::Bool(false)), (2, FormValue::Number(1400))]));
+}
+
+#[test]
+fn dates_and_times_are_edited_a_segment_at_a_time() {
+    let layout = Layout::builder()
+        .tab("Alarm")
+            .line()
+                .date("Day", Date::new(2024, 1, 31), 1)
+            .line()
+                .time("At", Time::new(23, 59), 2)
+        .build();
+    let (mut gui, _) = gui(layout);
+
+    // the day follows the shorter month
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Right, HidEvent::Up, HidEvent::ButtonPress]),
+        vec![GuiEvent::DateChanged(1, Date::new(2024, 2, 29))]);
+    // minutes wrap around on their own
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::ButtonPress, HidEvent::Right, HidEvent::Up, HidEvent::ButtonPress]),
+        vec![GuiEvent::TimeChanged(2, Time::new(23, 0))]);
+    // Back leaves it as it was
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Up, HidEvent::Back, HidEvent::ButtonPress, HidEvent::ButtonPress]), vec![]);
+}
+
+#[test]
+fn steppers_count_within_their_range() {
+    let layout = Layout::builder()
+        .tab("Screen")
+            .line()
+                .stepper("Timeout", 50, 0, 60, 15, 1)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::Right]), vec![GuiEvent::StepperChanged(1, 60)]);
+    assert_eq!(press(&mut gui, &[HidEvent::Right]), vec![]);
+    assert_eq!(press(&mut gui, &[HidEvent::Left, HidEvent::Left]),
+        vec![GuiEvent::StepperChanged(1, 45), GuiEvent::StepperChanged(1, 30)]);
+    assert!(matches!(recorder.item(0, 0), Item::Stepper(_, 30, 0, 60, 15, 1)));
+}
+
+#[test]
+fn toggles_report_their_own_event() {
+    let layout = Layout::builder()
+        .tab("Settings")
+            .line()
+                .toggle("Wifi", true, 1)
+                .button_stateful("Favourite", false, 2)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Right, HidEvent::ButtonPress]),
+        vec![GuiEvent::ToggleChanged(1, false), GuiEvent::StatefulButtonChange("Favourite".into(), true, 2)]);
+    assert!(matches!(recorder.item(0, 0), Item::Toggle(_, false, 1)));
+}
+
+#[test]
+fn sparklines_keep_the_newest_samples() {
+    let layout = Layout::builder()
+        .tab("CPU")
+            .line()
+                .sparkline(&[0.5; SPARKLINE_SAMPLES + 10], 1)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    gui.settle();
+    assert!(matches!(recorder.item(0, 0), Item::Sparkline(v, 1) if v.len() == SPARKLINE_SAMPLES));
+    gui.push_sample(1, 0.9);
+    gui.settle();
+    let Item::Sparkline(samples, _) = recorder.item(0, 0) else {panic!()};
+    assert_eq!(samples.len(), SPARKLINE_SAMPLES);
+    assert_eq!(samples.last(), Some(&0.9));
+}
+
+#[test]
+fn values_change_on_every_tab() {
+    let layout = Layout::builder()
+        .tab("Status")
+            .line()
+                .value("IP", Value::from("-"), 1)
+        .tab("Network")
+            .line()
+                .value("Address", Value::from("-"), 1)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    gui.set_value(1, "10.0.0.2");
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Value("IP".into(), Value::Text("10.0.0.2".into()), 1));
+    press(&mut gui, &[HidEvent::NextTab]);
+    assert_eq!(recorder.item(0, 0), Item::Value("Address".into(), Value::Text("10.0.0.2".into()), 1));
+}
+
+#[test]
+fn popups_are_drawn_over_the_tab_and_cancelled_with_back() {
+    let layout = Layout::builder()
+        .tab("Sound")
+            .line()
+                .selector_popup("Output", &["Speakers", "HDMI", "USB"], 1, 3)
+        .tab("Video")
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Down]);
+    assert_eq!(recorder.recording().popup, Some((vec!["Speakers".into(), "HDMI".into(), "USB".into()], 2)));
+    // the popup keeps the input to itself
+    assert_eq!(press(&mut gui, &[HidEvent::NextTab, HidEvent::Back]), vec![]);
+    assert_eq!(gui.tab_index(), 0);
+    assert!(matches!(recorder.item(0, 0), Item::Selector(_, _, 1, _, 3)));
+}
+
+#[test]
+fn edit_mode_keeps_the_d_pad_until_confirmed() {
+    let layout = Layout::builder()
+        .tab("Display")
+            .line()
+                .selector_edit("Mode", &["Fit", "Fill", "Stretch"], 0, 1)
+                .button_stateless("OK", 2)
+            .line()
+                .button_stateless("Cancel", 3)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    // nothing is reported while editing, and the focus stays put
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Right, HidEvent::Down, HidEvent::Right]), vec![]);
+    assert!(recorder.recording().edit_drawn);
+    assert_eq!(gui.focused_cell(), (0, 0));
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress]), vec![GuiEvent::SelectorChanged(1, 1)]);
+    assert!(!recorder.recording().edit_drawn);
+
+    // Back puts the value back
+    press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Left, HidEvent::Back]);
+    assert!(matches!(recorder.item(0, 0), Item::Selector(_, _, 1, _, 1)));
+    assert_eq!(press(&mut gui, &[HidEvent::Right]), vec![]);
+    assert_eq!(gui.focused_cell(), (0, 1));
+}
+
+#[test]
+fn frozen_guis_draw_once_thawed() {
+    let layout = Layout::builder().tab("Status").line().value("IP", Value::from("-"), 1).value("Mask", Value::from("-"), 2).build();
+    let (mut gui, recorder) = gui(layout);
+    gui.settle();
+
+    gui.freeze_redraws();
+    gui.set_value(1, "10.0.0.2");
+    gui.set_value(2, "255.0.0.0");
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Value("IP".into(), Value::from("-"), 1));
+    gui.thaw();
+    assert_eq!(recorder.item(0, 0), Item::Value("IP".into(), Value::from("10.0.0.2"), 1));
+    assert_eq!(recorder.item(0, 1), Item::Value("Mask".into(), Value::from("255.0.0.0"), 2));
+
+    // nothing changed, but it's drawn anyway
+    recorder.recording().items.clear();
+    gui.request_redraw();
+    assert_eq!(recorder.recording().items.len(), 1);
+}
+
+#[test]
+fn edited_values_get_a_big_readout() {
+    let layout = Layout::builder()
+        .tab("Display")
+            .line()
+                .selector_edit("Mode", &["Fit", "Fill", "Stretch"], 0, 1)
+            .line()
+                .date("Day", Date::new(2024, 1, 31), 2)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+    gui.settle();
+    assert_eq!(recorder.recording().readout, None);
+
+    press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Right]);
+    assert_eq!(recorder.recording().readout.as_deref(), Some("Fill"));
+    press(&mut gui, &[HidEvent::ButtonPress]);
+    assert_eq!(recorder.recording().readout, None);
+
+    // the segment being edited is marked
+    press(&mut gui, &[HidEvent::Down, HidEvent::ButtonPress, HidEvent::Right]);
+    assert_eq!(recorder.recording().readout.as_deref(), Some("2024-[01]-31"));
+}
+
+#[test]
+fn items_can_sound_different_from_the_rest() {
+    let layout = Layout::builder()
+        .tab("Settings")
+            .line()
+                .button_stateless("Back", 1)
+                .button_stateless("Apply", 2)
+            .line()
+                .command("Update", CommandSpec::new("sleep", &["1"]), 3)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+    gui.set_feedback(FeedbackTheme::new(Feedback::Click, Feedback::Error).item(2, Feedback::Success));
+
+    press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Right, HidEvent::ButtonPress]);
+    assert_eq!(recorder.recording().feedback, vec![Feedback::Click, Feedback::Success]);
+    // the second press comes while it's still running
+    press(&mut gui, &[HidEvent::Left, HidEvent::Down, HidEvent::ButtonPress, HidEvent::ButtonPress]);
+    assert_eq!(recorder.recording().feedback[2..], [Feedback::Click, Feedback::Error]);
+}
+
+#[test]
+fn tables_take_up_and_down_until_their_ends() {
+    let rows = ["eth0", "wlan0", "lo"].iter().map(|v| vec![v.to_string(), "up".to_string()]).collect();
+    let layout = Layout::builder()
+        .tab("Network")
+            .line()
+                .table("Interfaces", &["Name", "State"], rows, 1)
+            .line()
+                .button_stateless("Refresh", 2)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::Down, HidEvent::ButtonPress]), vec![GuiEvent::TableRowSelected(1, 2)]);
+    assert!(matches!(recorder.item(0, 0), Item::Table(_, _, _, 2, 1)));
+    press(&mut gui, &[HidEvent::Down]);
+    assert_eq!(gui.focused_cell(), (1, 0));
+    // coming back keeps the row
+    press(&mut gui, &[HidEvent::Up, HidEvent::Up]);
+    assert_eq!(gui.focused_cell(), (0, 0));
+    assert!(matches!(recorder.item(0, 0), Item::Table(_, _, _, 1, 1)));
+}
+
+#[test]
+fn qr_codes_are_shown_while_focused() {
+    let layout = Layout::builder()
+        .tab("Pairing")
+            .line()
+                .qr_code("https://example.com/pair?code=1234")
+            .line()
+                .button_stateless("Done", 1)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+    gui.settle();
+    assert_eq!(recorder.recording().qr.as_deref(), Some("https://example.com/pair?code=1234"));
+    press(&mut gui, &[HidEvent::Down]);
+    assert_eq!(recorder.recording().qr, None);
+}
+
+#[test]
+fn commands_stream_into_their_log() {
+    let script = "echo hi; sleep 0.2; echo oops >&2; exit 3";
+    let layout = Layout::builder()
+        .tab("Recovery")
+            .line()
+                .command("Repair", CommandSpec::new("sh", &["-c", script]).log_to(2), 1)
+            .line()
+                .log(2)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress]), vec![GuiEvent::CommandStarted(1)]);
+    assert!(matches!(recorder.item(0, 0), Item::Command(_, _, true, 1)));
+    // it doesn't start twice
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress]), vec![]);
+    assert_eq!(gui.get_ev(), GuiEvent::CommandFinished(1, Some(3)));
+    gui.settle();
+    assert!(matches!(recorder.item(0, 0), Item::Command(_, _, false, 1)));
+    let Item::Log(lines, 2) = recorder.item(1, 0) else {panic!("no log")};
+    assert_eq!(lines, vec![format!("$ sh -c {}", script), "hi".to_string(), "oops".to_string(), "exited with code 3".to_string()]);
+}
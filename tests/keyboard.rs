@@ -0,0 +1,108 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use sgui::{
+    clock::ManualClock,
+    event_log::LoggedEvent,
+    form::{Form, FormValue},
+    journal::REDACTED,
+    layout::Layout,
+    renderer_null::{NullRenderer, RecordingRenderer},
+    ActionButton, Gui, GuiEvent, HidEvent,
+};
+
+#[test]
+fn north_reveals_a_password_for_a_while() {
+    let clock = ManualClock::new();
+    let keyboard = RecordingRenderer::new();
+    let layout = Layout::builder()
+        .tab("Wi-Fi")
+            .line()
+                .password_input("Password", "hunter2", 1)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(keyboard.clone()))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .build();
+    let shown = || keyboard.recording().keyboard.clone().unwrap_or_default();
+
+    gui.push_event(HidEvent::ButtonPress);
+    gui.settle();
+    assert_eq!(shown(), "•••••••");
+
+    gui.push_event(HidEvent::Action(ActionButton::North));
+    gui.settle();
+    assert_eq!(shown(), "hunter2");
+
+    // held down, the repeats keep it readable
+    clock.advance(Duration::from_millis(900));
+    gui.push_event(HidEvent::Action(ActionButton::North));
+    gui.step();
+    clock.advance(Duration::from_millis(900));
+    gui.step();
+    assert_eq!(shown(), "hunter2");
+
+    clock.advance(Duration::from_millis(100));
+    gui.settle();
+    assert_eq!(shown(), "•••••••");
+}
+
+// every event the gui handed back, as the event history keeps it
+fn returned(gui: &Gui) -> Vec<GuiEvent> {
+    gui.event_history()
+        .filter_map(|v| match &v.event {
+            LoggedEvent::Gui(ev) => Some(ev.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn event_history_keeps_passwords_masked() {
+    let layout = Layout::builder()
+        .tab("Wi-Fi")
+            .line()
+                .password_input("Password", "hunter2", 1)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .build();
+    // opens the keyboard, types its first key and confirms
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::Action(ActionButton::Start));
+    let events = gui.settle();
+    assert!(matches!(events.as_slice(), [GuiEvent::TextChanged(1, text)] if text.starts_with("hunter2")));
+    assert_eq!(returned(&gui), vec![GuiEvent::TextChanged(1, REDACTED.to_string())]);
+}
+
+#[test]
+fn event_history_keeps_submitted_passwords_masked() {
+    let layout = Layout::builder()
+        .tab("Login")
+            .line()
+                .text_input("User", "root", 1)
+                .password_input("Password", "hunter2", 2)
+            .line()
+                .button_stateless("Log in", 3)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .form(Form::new(4, &[1, 2]).apply_button(3))
+        .build();
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::ButtonPress);
+    let events = gui.settle();
+    assert_eq!(events, vec![GuiEvent::FormSubmitted(4, vec![
+        (1, FormValue::Text("root".to_string())),
+        (2, FormValue::Text("hunter2".to_string())),
+    ])]);
+    assert_eq!(returned(&gui), vec![GuiEvent::FormSubmitted(4, vec![
+        (1, FormValue::Text("root".to_string())),
+        (2, FormValue::Text(REDACTED.to_string())),
+    ])]);
+}
@@ -0,0 +1,21 @@
+use sgui::osk::KeyboardLayout;
+
+#[test]
+fn parses_shifted_rows() {
+    let layout = KeyboardLayout::parse("name = AB\n# comment\nab\ncd\n---\nAB\nCD\n").unwrap();
+    assert_eq!(layout, KeyboardLayout::new("AB", &["ab", "cd"], &["AB", "CD"]).unwrap());
+}
+
+#[test]
+fn rejects_shifted_rows_that_dont_line_up() {
+    assert!(KeyboardLayout::parse("name = AB\nab\ncd\n---\nAB\n").is_err());
+    assert!(KeyboardLayout::parse("name = AB\nab\ncd\n---\nAB\nCDE\n").is_err());
+    assert!(KeyboardLayout::new("AB", &["ab"], &["A"]).is_err());
+}
+
+#[test]
+fn rejects_empty_rows() {
+    assert!(KeyboardLayout::parse("name = AB\n").is_err());
+    assert!(KeyboardLayout::new("AB", &["ab", ""], &[]).is_err());
+    assert!(KeyboardLayout::new("AB", &[], &[]).is_err());
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use sgui::{
+    draw::column_widths,
+    layout::{ImageSource, Item, ItemAction, Layout, LayoutChange},
+    ActionButton, HidEvent,
+};
+
+fn button(text: &str, id: u128) -> Item {
+    Item::StatelessButton(text.to_string(), id)
+}
+
+#[test]
+fn tabs_and_lines_come_from_iterators() {
+    let files = ["a.txt", "b.txt"];
+    let layout = Layout::builder()
+        .tabs_from([("Disks", vec![vec![button("sda", 1)]]), ("Empty", vec![])])
+        .tab("Files")
+            .lines_from(files.iter().enumerate().map(|(i, v)| vec![button(v, 10 + i as u128)]))
+            .line()
+                .button_stateless("Back", 20)
+        .build();
+
+    assert_eq!(layout.tab_count(), 3);
+    assert_eq!(layout.tab(0).unwrap().name(), "Disks");
+    assert_eq!(layout.tab(0).unwrap().items(), &[vec![button("sda", 1)]]);
+    assert!(layout.tab(1).unwrap().items().is_empty());
+    // builder lines go after the generated ones
+    assert_eq!(layout.tab(2).unwrap().items(),
+        &[vec![button("a.txt", 10)], vec![button("b.txt", 11)], vec![button("Back", 20)]]);
+}
+
+#[test]
+fn diff_keeps_to_what_changed() {
+    let devices = |names: &[&str]| {
+        Layout::builder()
+            .tab("Devices")
+                .lines_from(names.iter().enumerate().map(|(i, v)| vec![button(v, i as u128)]))
+                .footer()
+                    .text("ready")
+            .build()
+    };
+    let old = devices(&["Pad", "Mouse"]);
+
+    assert_eq!(old.diff(&old), vec![]);
+    assert_eq!(old.diff(&devices(&["Pad", "Keyboard"])), vec![LayoutChange::Item(0, 1, 0, button("Keyboard", 1))]);
+    assert_eq!(old.diff(&devices(&["Pad"])), vec![LayoutChange::Grid(0, vec![vec![button("Pad", 0)]])]);
+    // a tab set up differently, here without a footer, takes the whole layout
+    assert_eq!(old.diff(&Layout::builder().tab("Devices").build()), vec![LayoutChange::Replace]);
+}
+
+#[test]
+fn fragments_are_included_as_tabs_or_rows() {
+    let about = Layout::builder()
+        .tab("About")
+            .item_action(1, HidEvent::Action(ActionButton::North), ItemAction::Emit("license".into()))
+            .line()
+                .button_stateless("Version 1.0", 1)
+            .footer()
+                .text("left behind")
+        .build();
+    let layout = Layout::builder()
+        .tab("Main")
+            .line()
+                .button_stateless("Start", 2)
+            .endl()
+            .include(about.clone())
+            .end_tab()
+        .include(about)
+        .build();
+
+    assert_eq!(layout.tab_count(), 2);
+    let main = layout.tab(0).unwrap();
+    assert_eq!(main.items(), &[vec![button("Start", 2)], vec![button("Version 1.0", 1)]]);
+    assert!(main.footer().is_none());
+    assert_eq!(main.item_action(1, &HidEvent::Action(ActionButton::North)), Some(&ItemAction::Emit("license".into())));
+    assert_eq!(layout.tab(1).unwrap().name(), "About");
+    assert!(layout.tab(1).unwrap().footer().is_some());
+}
+
+#[test]
+fn tab_ids_stay_within_the_layout() {
+    let layout = Layout::builder().tab("A").tab("B").build();
+    let first = layout.clamp_tab(0);
+    let last = layout.next_tab(first);
+    assert_eq!((first.index(), last.index()), (0, 1));
+    assert_eq!(layout.next_tab(last), last);
+    assert_eq!(layout.prev_tab(first), first);
+    assert_eq!(layout.clamp_tab(7), last);
+
+    let empty = Layout::builder().build();
+    assert_eq!(empty.tab_count(), 0);
+    let id = empty.clamp_tab(3);
+    assert_eq!(empty.next_tab(id), id);
+    assert_eq!(empty.prev_tab(id), id);
+    assert!(empty.tab(id.index()).is_none());
+}
+
+#[test]
+fn wide_columns_share_what_narrow_ones_leave() {
+    // everything fits
+    assert_eq!(column_widths(&[4, 10], 20, 1), vec![4, 10]);
+    // the narrow column keeps its width, the wide ones get half each of the rest
+    assert_eq!(column_widths(&[3, 30, 40], 25, 1), vec![3, 10, 10]);
+    assert_eq!(column_widths(&[5, 5], 0, 1), vec![0, 0]);
+}
+
+#[test]
+fn images_come_from_files_or_memory() {
+    let layout = Layout::builder()
+        .tab("Games")
+            .line()
+                .image(Path::new("covers/doom.png"), 1)
+                .image_bytes("quake", vec![0x89, b'P', b'N', b'G'], 2)
+        .build();
+    let items = layout.tab(0).unwrap().items();
+    let Item::Image(file, 1) = &items[0][0] else {panic!("not an image")};
+    let Item::Image(bytes, 2) = &items[0][1] else {panic!("not an image")};
+    assert_eq!(file, &ImageSource::Path("covers/doom.png".into()));
+    // names stand in where pictures can't be shown
+    assert_eq!((file.name(), bytes.name()), ("doom".into(), "quake".into()));
+}
@@ -0,0 +1,264 @@
+#![cfg(feature = "test-util")]
+
+use sgui::{
+    focus::FocusScope,
+    layout::{Item, Layout},
+    renderer_null::RecordingRenderer,
+    Gui, GuiEvent, HidEvent, Pane,
+};
+
+fn gui(layout: Layout) -> (Gui, RecordingRenderer) {
+    let recorder = RecordingRenderer::new();
+    let gui = Gui::builder(layout)
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .build();
+    (gui, recorder)
+}
+
+// presses every event in turn, returns what came out
+fn press(gui: &mut Gui, events: &[HidEvent]) -> Vec<GuiEvent> {
+    for ev in events {
+        gui.push_event(ev.clone());
+    }
+    gui.settle()
+}
+
+#[test]
+fn shoulder_buttons_flip_pages_before_tabs() {
+    let mut builder = Layout::builder().tab("Songs").paged(2).line().button_stateless("Song 0", 0);
+    for i in 1..5 {
+        builder = builder.line().button_stateless(&format!("Song {}", i), i);
+    }
+    let layout = builder.tab("Queue").line().text("Empty").build();
+    let (mut gui, recorder) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::NextTab]), vec![GuiEvent::PageChanged(1)]);
+    assert_eq!(gui.focused_cell(), (2, 0));
+    // Down stays on the page
+    press(&mut gui, &[HidEvent::Down, HidEvent::Down]);
+    assert_eq!(gui.focused_cell(), (3, 0));
+    // only the page is drawn
+    assert_eq!(recorder.recording().items, vec![
+        vec![Item::StatelessButton("Song 2".to_string(), 2)],
+        vec![Item::StatelessButton("Song 3".to_string(), 3)],
+    ]);
+
+    assert_eq!(press(&mut gui, &[HidEvent::NextTab]), vec![GuiEvent::PageChanged(2)]);
+    assert_eq!(press(&mut gui, &[HidEvent::NextTab]), vec![GuiEvent::TabChanged("Queue".to_string())]);
+}
+
+#[test]
+fn focusable_footer_is_below_the_last_row() {
+    let layout = Layout::builder()
+        .tab("Settings")
+            .line()
+                .toggle("Wi-Fi", true, 1)
+            .line()
+                .toggle("Bluetooth", false, 2)
+            .focusable_footer()
+                .button_stateless("Save", 8)
+                .button_stateless("Cancel", 9)
+        .build();
+    let (mut gui, _) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::Down, HidEvent::Right, HidEvent::ButtonPress]),
+        vec![GuiEvent::StatelessButtonPress("Cancel".to_string(), 9)]);
+    // nothing below it, up goes back into the rows
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::Up, HidEvent::ButtonPress]),
+        vec![GuiEvent::ToggleChanged(2, true)]);
+}
+
+#[test]
+fn split_tab_moves_between_list_and_detail() {
+    let layout = Layout::builder()
+        .tab("Games")
+            .list()
+                .button_stateless("Tetris", 1)
+                .button_stateless("Doom", 2)
+            .line()
+                .button_stateless("Play", 10)
+                .button_stateless("Delete", 11)
+        .build();
+    let (mut gui, _) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::Down]), vec![GuiEvent::ListFocused(1)]);
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress]), vec![GuiEvent::ListActivated(1)]);
+    // right at the list's edge hops over
+    assert_eq!(press(&mut gui, &[HidEvent::Right]), vec![GuiEvent::PaneChanged(Pane::Detail)]);
+    assert_eq!(press(&mut gui, &[HidEvent::Right, HidEvent::ButtonPress]),
+        vec![GuiEvent::StatelessButtonPress("Delete".to_string(), 11)]);
+    assert_eq!(press(&mut gui, &[HidEvent::Left, HidEvent::Left]), vec![GuiEvent::PaneChanged(Pane::List)]);
+    assert_eq!(press(&mut gui, &[HidEvent::Up]), vec![GuiEvent::ListFocused(0)]);
+}
+
+#[test]
+fn focus_scope_keeps_the_focus_in_its_rows() {
+    let layout = Layout::builder()
+        .tab("Home")
+            .line()
+                .button_stateless("Settings", 1)
+            .line()
+                .text("Delete everything?")
+            .line()
+                .button_stateless("No", 2)
+                .button_stateless("Yes", 3)
+        .tab("About")
+            .line()
+                .text("sgui")
+        .build();
+    let (mut gui, _) = gui(layout);
+    gui.push_focus_scope(FocusScope::new(7, 2..3).dismissable());
+    assert_eq!(gui.focused_cell(), (2, 0));
+
+    // neither up nor to another tab
+    assert_eq!(press(&mut gui, &[HidEvent::Up, HidEvent::NextTab]), vec![]);
+    assert_eq!((gui.tab_index(), gui.focused_cell()), (0, (2, 0)));
+    assert_eq!(press(&mut gui, &[HidEvent::Back]), vec![GuiEvent::FocusScopeDismissed(7)]);
+    assert_eq!(gui.focused_cell(), (0, 0));
+}
+
+#[test]
+fn grabbed_rows_move_and_drop() {
+    let layout = Layout::builder()
+        .tab("Queue")
+            .reorderable()
+            .line()
+                .button_stateless("First", 1)
+            .line()
+                .button_stateless("Second", 2)
+            .line()
+                .button_stateless("Third", 3)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+
+    assert_eq!(press(&mut gui, &[HidEvent::Grab, HidEvent::Down, HidEvent::Down, HidEvent::Grab]),
+        vec![GuiEvent::ItemMoved(1, 0, 2)]);
+    assert_eq!(gui.focused_cell(), (2, 0));
+    let order: Vec<Item> = recorder.recording().items.iter().map(|v| v[0].clone()).collect();
+    assert_eq!(order, vec![
+        Item::StatelessButton("Second".to_string(), 2),
+        Item::StatelessButton("Third".to_string(), 3),
+        Item::StatelessButton("First".to_string(), 1),
+    ]);
+}
+
+#[test]
+fn pages_stack_and_back_leaves_them() {
+    let layout = Layout::builder()
+        .tab("Settings")
+            .line()
+                .button_stateless("Display", 1)
+            .line()
+                .button_stateless("Sound", 2)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+    press(&mut gui, &[HidEvent::Down]);
+
+    gui.push_page("Sound", vec![vec![Item::Toggle("Mute".to_string(), false, 3)]]);
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Toggle("Mute".to_string(), false, 3));
+    assert_eq!(recorder.recording().tabs, vec!["Settings ▸ Sound".to_string()]);
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress]), vec![GuiEvent::ToggleChanged(3, true)]);
+
+    assert_eq!(press(&mut gui, &[HidEvent::Back]), vec![GuiEvent::PagePopped("Sound".to_string())]);
+    assert_eq!(recorder.item(1, 0), Item::StatelessButton("Sound".to_string(), 2));
+    assert_eq!(recorder.recording().tabs, vec!["Settings".to_string()]);
+    assert_eq!(gui.focused_cell(), (1, 0));
+    // nothing left to leave
+    assert_eq!(press(&mut gui, &[HidEvent::Back]), vec![]);
+}
+
+#[test]
+fn multi_select_rows_are_confirmed_together() {
+    let layout = Layout::builder()
+        .tab("Delete")
+            .multi_select(9)
+            .line()
+                .text("a.txt")
+                .button_stateless("Open", 1)
+            .line()
+                .text("b.txt")
+                .button_stateless("Open", 2)
+            .line()
+                .text("c.txt")
+                .button_stateless("Open", 3)
+            .focusable_footer()
+                .button_stateless("Delete", 9)
+        .build();
+    let (mut gui, _) = gui(layout);
+
+    // pressing picks rows instead of pressing their buttons, a second press drops them again
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress, HidEvent::Down, HidEvent::Down, HidEvent::ButtonPress]), vec![]);
+    assert_eq!(press(&mut gui, &[HidEvent::Up, HidEvent::ButtonPress, HidEvent::ButtonPress]), vec![]);
+    assert_eq!(gui.selection(0), vec![1, 3]);
+    assert_eq!(press(&mut gui, &[HidEvent::Down, HidEvent::Down, HidEvent::ButtonPress]),
+        vec![GuiEvent::SelectionConfirmed(vec![1, 3])]);
+}
+
+#[test]
+fn saved_state_brings_back_every_tabs_scroll() {
+    let layout = || {
+        let mut builder = Layout::builder().tab("Songs").paged(2).line().button_stateless("Song 0", 0);
+        for i in 1..5 {
+            builder = builder.line().button_stateless(&format!("Song {}", i), i);
+        }
+        builder.tab("Queue").line().text("Now").line().button_stateless("Clear", 9).build()
+    };
+    let (mut before, _) = gui(layout());
+    press(&mut before, &[HidEvent::NextTab, HidEvent::Down, HidEvent::GoToTab(1), HidEvent::Down]);
+    let state = before.exit_dumping_state();
+
+    let (mut gui, _) = gui(layout());
+    gui.restore_state(&state);
+    assert_eq!(gui.focused_cell(), (1, 0));
+    // the other tab gets its scroll back once it's shown
+    press(&mut gui, &[HidEvent::GoToTab(0)]);
+    assert_eq!(gui.focused_cell(), (3, 0));
+    assert_eq!(press(&mut gui, &[HidEvent::NextTab]), vec![GuiEvent::PageChanged(2)]);
+}
+
+#[test]
+fn carousel_tiles_sit_in_one_row() {
+    let layout = Layout::builder()
+        .tab("Games").carousel()
+            .line()
+                .tile("Doom", None, 1)
+                .tile("Quake", None, 2)
+            .line()
+                .tile("Hexen", None, 3)
+        .build();
+    let (mut gui, recorder) = gui(layout);
+    gui.settle();
+    assert_eq!(recorder.recording().items.len(), 1);
+    assert_eq!(recorder.item(0, 2), Item::Tile("Hexen".to_string(), None, 3));
+
+    // Down has nowhere to go, Right does
+    press(&mut gui, &[HidEvent::Down, HidEvent::Right, HidEvent::Right]);
+    assert_eq!(recorder.recording().selected, (0, 2));
+    assert_eq!(press(&mut gui, &[HidEvent::ButtonPress]), vec![GuiEvent::StatelessButtonPress("Hexen".to_string(), 3)]);
+}
+
+#[test]
+fn only_what_changed_is_drawn_again() {
+    let layout = Layout::builder()
+        .tab("One")
+            .line()
+                .button_stateless("A", 1)
+            .line()
+                .button_stateless("B", 2)
+        .tab("Two")
+        .build();
+    let (mut gui, recorder) = gui(layout);
+    gui.settle();
+    assert_eq!(recorder.recording().tabs, ["One", "Two"]);
+
+    // moving the focus leaves the header as it is
+    recorder.recording().tabs.clear();
+    press(&mut gui, &[HidEvent::Down]);
+    assert_eq!(recorder.recording().selected, (1, 0));
+    assert!(recorder.recording().tabs.is_empty());
+    // names from the current tab on
+    press(&mut gui, &[HidEvent::NextTab]);
+    assert_eq!(recorder.recording().tabs, ["Two"]);
+}
@@ -0,0 +1,34 @@
+use std::{fs, path::Path};
+
+use sgui::power::{self, Battery, ChargeState};
+
+fn supply(dir: &Path, name: &str, attrs: &[(&str, &str)]) {
+    let path = dir.join(name);
+    fs::create_dir_all(&path).unwrap();
+    for (attr, value) in attrs {
+        fs::write(path.join(attr), format!("{}\n", value)).unwrap();
+    }
+}
+
+#[test]
+fn battery_is_read_from_sysfs() {
+    let dir = std::env::temp_dir().join(format!("sgui-power-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    supply(&dir, "AC", &[("type", "Mains"), ("online", "1")]);
+    assert_eq!(Battery::read_from(&dir), None);
+
+    supply(&dir, "BAT1", &[("type", "Battery"), ("capacity", "40"), ("status", "Discharging")]);
+    supply(&dir, "BAT0", &[("type", "Battery"), ("capacity", "87"), ("status", "Charging")]);
+    let battery = Battery::read_from(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(battery, Some(Battery { percent: 87, state: ChargeState::Charging }));
+}
+
+#[test]
+fn battery_labels_show_the_charge() {
+    assert_eq!(power::label(None), "Battery --%");
+    let battery = |state| Battery { percent: 100, state };
+    assert_eq!(power::label(Some(&battery(ChargeState::Charging))), "Battery 100% ⚡");
+    assert_eq!(power::label(Some(&battery(ChargeState::NotCharging))), "Battery 100% 🔌");
+    assert_eq!(power::label(Some(&battery(ChargeState::Discharging))), "Battery 100%");
+}
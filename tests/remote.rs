@@ -0,0 +1,42 @@
+#![cfg(feature = "remote")]
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use sgui::{input::InputSource, input_remote::RemoteSource, ActionButton, HidEvent};
+
+// a port that was free a moment ago
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn reply(stream: &TcpStream) -> String {
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).unwrap();
+    line
+}
+
+#[test]
+fn commands_come_in_over_tcp_and_http() {
+    let port = free_port();
+    let mut source = RemoteSource::bind(("127.0.0.1", port), "s3cret").unwrap();
+
+    let mut wrong = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    wrong.write_all(b"guess\nup\n").unwrap();
+    assert_eq!(reply(&wrong), "denied\n");
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client.write_all(b"s3cret\n").unwrap();
+    assert_eq!(reply(&client), "ok\n");
+    client.write_all(b"down\n").unwrap();
+    assert_eq!(source.next_event(), Some(HidEvent::Down));
+
+    let mut browser = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    browser.write_all(b"GET /cmd?secret=s3cret&c=action+north HTTP/1.1\r\nHost: pad\r\n\r\n").unwrap();
+    let mut response = String::new();
+    browser.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(source.next_event(), Some(HidEvent::Action(ActionButton::North)));
+}
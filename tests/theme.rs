@@ -0,0 +1,107 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use sgui::{
+    clock::ManualClock,
+    idle::IdleConfig,
+    layout::{Item, Layout, Severity},
+    renderer_null::RecordingRenderer,
+    Color, ColorPalette, Gui, GuiEvent, HidEvent,
+};
+
+fn grey(v: u8) -> ColorPalette {
+    let grey = Color::new(v, v, v);
+    ColorPalette::default().with_severity_colors(grey, grey, grey)
+}
+
+// one of the colors of the palette last drawn with
+fn drawn(recorder: &RecordingRenderer) -> Color {
+    recorder.recording().palette.as_ref().unwrap().severity_color(Severity::Info)
+}
+
+#[test]
+fn stateful_markers_come_from_the_palette() {
+    let palette = ColorPalette::default();
+    assert_eq!((palette.marker(true), palette.marker(false)), ("[X]", "[ ]"));
+    let palette = palette.with_markers("●", "○");
+    assert_eq!((palette.marker(true), palette.marker(false)), ("●", "○"));
+    // built-in themes keep the usual ones
+    assert_eq!(ColorPalette::named("light").unwrap().marker(true), "[X]");
+}
+
+#[test]
+fn themes_fade_over_on_renderers_that_can() {
+    let clock = ManualClock::new();
+    let recorder = RecordingRenderer::new();
+    recorder.recording().smooth = true;
+    let mut gui = Gui::builder(Layout::builder().tab("Tab").line().text("Hi").build())
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .palette(grey(0))
+        .register_theme("Silver", grey(200))
+        .theme_fade(Duration::from_millis(100))
+        .build();
+    gui.settle();
+    assert_eq!(drawn(&recorder), Color::new(0, 0, 0));
+
+    gui.set_theme("silver").unwrap();
+    clock.advance(Duration::from_millis(50));
+    gui.settle();
+    assert_eq!(drawn(&recorder), Color::new(100, 100, 100));
+    clock.advance(Duration::from_millis(50));
+    gui.settle();
+    assert_eq!(drawn(&recorder), Color::new(200, 200, 200));
+
+    assert!(gui.set_theme("nope").is_err());
+    // no fading where it would be too slow
+    recorder.recording().smooth = false;
+    gui.set_theme(grey(50)).unwrap();
+    gui.settle();
+    assert_eq!(drawn(&recorder), Color::new(50, 50, 50));
+}
+
+#[test]
+fn screen_dims_after_a_while_without_input() {
+    let clock = ManualClock::new();
+    let recorder = RecordingRenderer::new();
+    let mut gui = Gui::builder(Layout::builder().tab("Tab").line().button_stateless("OK", 1).build())
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .palette(grey(200))
+        .idle(IdleConfig::dim(Duration::from_secs(30)))
+        .build();
+    gui.settle();
+    clock.advance(Duration::from_secs(29));
+    gui.settle();
+    assert_eq!(drawn(&recorder), Color::new(200, 200, 200));
+
+    clock.advance(Duration::from_secs(1));
+    gui.settle();
+    assert_eq!(drawn(&recorder), Color::new(80, 80, 80));
+
+    // the press that wakes it up doesn't reach the button
+    gui.push_event(HidEvent::ButtonPress);
+    assert!(!gui.settle().contains(&GuiEvent::StatelessButtonPress("OK".into(), 1)));
+    assert_eq!(drawn(&recorder), Color::new(200, 200, 200));
+    gui.push_event(HidEvent::ButtonPress);
+    assert!(gui.settle().contains(&GuiEvent::StatelessButtonPress("OK".into(), 1)));
+}
+
+#[test]
+fn every_theme_tells_severities_apart() {
+    for name in ["dark", "light", "high-contrast", "amber"] {
+        let palette = ColorPalette::named(name).unwrap();
+        let colors = [Severity::Info, Severity::Warning, Severity::Error].map(|v| palette.severity_color(v));
+        assert!(colors[0] != colors[1] && colors[1] != colors[2] && colors[0] != colors[2], "{}", name);
+    }
+    let recorder = RecordingRenderer::new();
+    let mut gui = Gui::builder(Layout::builder().tab("Tab").line().message(Severity::Error, "No disk").build())
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .build();
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Message(Severity::Error, "No disk".to_string()));
+}
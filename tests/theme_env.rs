@@ -0,0 +1,43 @@
+#![cfg(feature = "test-util")]
+
+// a file of its own, the environment is shared by every test in a process
+
+use sgui::{
+    config::Config,
+    layout::{Layout, Severity},
+    renderer_null::RecordingRenderer,
+    Color, ColorPalette, Gui,
+};
+
+fn grey(v: u8) -> ColorPalette {
+    let grey = Color::new(v, v, v);
+    ColorPalette::default().with_severity_colors(grey, grey, grey)
+}
+
+// the Info color the first frame was drawn with
+fn started_with(theme: &str) -> Color {
+    let recorder = RecordingRenderer::new();
+    let mut gui = Gui::builder(Layout::builder().tab("Tab").line().text("Hi").build())
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .config(Config { theme: Some(theme.to_string()), ..Config::default() })
+        .palette(grey(0))
+        .register_theme("silver", grey(200))
+        .register_theme("gold", grey(100))
+        .build();
+    gui.settle();
+    let color = recorder.recording().palette.as_ref().unwrap().severity_color(Severity::Info);
+    color
+}
+
+#[test]
+fn sgui_theme_beats_the_config_file() {
+    std::env::remove_var("SGUI_THEME");
+    assert_eq!(started_with("gold"), Color::new(100, 100, 100));
+    std::env::set_var("SGUI_THEME", "Silver");
+    assert_eq!(started_with("gold"), Color::new(200, 200, 200));
+    // a theme that doesn't exist falls through to the config file, then to palette()
+    std::env::set_var("SGUI_THEME", "nope");
+    assert_eq!(started_with("gold"), Color::new(100, 100, 100));
+    assert_eq!(started_with("nope"), Color::new(0, 0, 0));
+}
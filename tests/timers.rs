@@ -0,0 +1,357 @@
+#![cfg(feature = "test-util")]
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sgui::{
+    clock::ManualClock,
+    datetime::Time,
+    focus::FocusScope,
+    idle::IdleConfig,
+    format::Value,
+    layout::{Item, Layout},
+    osd::Osd,
+    renderer_null::{NullRenderer, RecordingRenderer},
+    ColorPalette, Gui, GuiEvent, HidEvent, RendererEvent,
+};
+
+fn gui(layout: Layout, clock: &ManualClock) -> (Gui, RecordingRenderer) {
+    let recorder = RecordingRenderer::new();
+    let gui = Gui::builder(layout)
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .build();
+    (gui, recorder)
+}
+
+#[test]
+fn countdown_expires_on_the_clock() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Timer")
+            .line()
+                .countdown(Duration::from_secs(3), 7)
+        .build();
+    let (mut gui, recorder) = gui(layout, &clock);
+    assert_eq!(gui.settle(), vec![]);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(gui.settle(), vec![]);
+    assert_eq!(recorder.item(0, 0), Item::Countdown(Duration::from_secs(2), 7));
+
+    clock.advance(Duration::from_secs(2));
+    assert_eq!(gui.settle(), vec![GuiEvent::TimerExpired(7)]);
+    assert_eq!(recorder.item(0, 0), Item::Countdown(Duration::ZERO, 7));
+
+    // stays expired
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(gui.settle(), vec![]);
+}
+
+#[test]
+fn expired_countdown_knows_its_tab() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Oven")
+            .line()
+                .countdown(Duration::from_secs(20), 7)
+        .tab("Kettle")
+            .line()
+                .text("Boiling")
+                .countdown(Duration::from_secs(5), 7)
+        .build();
+    let (mut gui, _) = gui(layout, &clock);
+    gui.settle();
+
+    // same id on both tabs, the one that ran out isn't on the current one
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(gui.settle(), vec![GuiEvent::TimerExpired(7)]);
+    let origin = gui.event_origin().unwrap();
+    assert_eq!((origin.tab_name.as_str(), origin.row, origin.column), ("Kettle", 0, 1));
+}
+
+#[test]
+fn set_countdown_starts_over() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Timer")
+            .line()
+                .countdown(Duration::from_secs(1), 7)
+        .build();
+    let (mut gui, _) = gui(layout, &clock);
+
+    clock.advance(Duration::from_millis(500));
+    gui.set_countdown(7, Duration::from_secs(2));
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(gui.settle(), vec![]);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(gui.settle(), vec![GuiEvent::TimerExpired(7)]);
+}
+
+#[test]
+fn spinner_moves_only_while_visible() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Busy")
+            .line()
+                .spinner("Loading", 3)
+        .build();
+    let (mut gui, recorder) = gui(layout, &clock);
+    gui.settle();
+    let Item::Spinner(_, start, true, _) = recorder.item(0, 0) else {panic!("not spinning")};
+
+    clock.advance(Duration::from_millis(100));
+    gui.settle();
+    let Item::Spinner(_, frame, _, _) = recorder.item(0, 0) else {unreachable!()};
+    assert_eq!(frame, start + 1);
+
+    gui.set_spinner_visible(3, false);
+    clock.advance(Duration::from_secs(1));
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Spinner("Loading".to_string(), frame, false, 3));
+}
+
+#[test]
+fn clock_follows_the_wall_clock() {
+    let clock = ManualClock::new();
+    // 30s into a minute
+    let wall = UNIX_EPOCH + Duration::from_secs(1_700_000_010);
+    clock.set_wall(wall);
+    let layout = Layout::builder()
+        .tab("Clock")
+            .line()
+                .clock()
+        .build();
+    let (mut gui, recorder) = gui(layout, &clock);
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Clock(Some(Time::local(wall))));
+
+    clock.advance(Duration::from_secs(29));
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Clock(Some(Time::local(wall))));
+
+    clock.advance(Duration::from_secs(1));
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Clock(Some(Time::local(wall + Duration::from_secs(30)))));
+}
+
+#[test]
+fn since_counts_from_the_clock() {
+    let clock = ManualClock::new();
+    let wall = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    clock.set_wall(wall);
+    let updated: SystemTime = wall - Duration::from_secs(120);
+    let layout = Layout::builder()
+        .tab("Status")
+            .line()
+                .value("Updated", Value::from(updated), 5)
+        .build();
+    let (mut gui, recorder) = gui(layout, &clock);
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Value("Updated".to_string(), Value::Since(updated, Duration::from_secs(120)), 5));
+
+    clock.advance(Duration::from_secs(60));
+    gui.settle();
+    let Item::Value(_, Value::Since(_, elapsed), _) = recorder.item(0, 0) else {panic!("not a Since value")};
+    assert_eq!(elapsed, Duration::from_secs(180));
+}
+
+#[test]
+fn idle_and_active() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .button_stateless("A", 1)
+            .line()
+                .button_stateless("B", 2)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .idle_events(Duration::from_secs(30))
+        .build();
+
+    clock.advance(Duration::from_secs(29));
+    assert_eq!(gui.settle(), vec![]);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(gui.settle(), vec![GuiEvent::Idle(Duration::from_secs(30))]);
+    // only reported once
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(gui.settle(), vec![]);
+
+    // waking up doesn't move the focus
+    gui.push_event(HidEvent::Down);
+    assert_eq!(gui.settle(), vec![GuiEvent::Active]);
+    assert_eq!(gui.focused_cell(), (0, 0));
+}
+
+#[test]
+fn renderer_goes_by_the_gui_clock() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .text("Text")
+        .build();
+    let (mut gui, recorder) = gui(layout, &clock);
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(gui.step(), None);
+    assert_eq!(recorder.recording().ticks.last(), Some(&Duration::from_secs(5)));
+}
+
+#[test]
+fn osd_hides_on_the_gui_clock() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .text("Text")
+        .build();
+    let (mut gui, _) = gui(layout, &clock);
+    let osd = Osd::new(Box::new(NullRenderer), ColorPalette::default()).timeout(Duration::from_secs(2));
+    gui.set_osd(osd);
+    gui.osd().unwrap().show("Volume", 0.5).unwrap();
+
+    clock.advance(Duration::from_millis(1999));
+    gui.settle();
+    assert!(gui.osd().unwrap().visible());
+    clock.advance(Duration::from_millis(1));
+    gui.settle();
+    assert!(!gui.osd().unwrap().visible());
+}
+
+#[test]
+fn countdown_confirms_the_default_choice() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Update")
+            .line()
+                .text("Rebooting in")
+                .countdown(Duration::from_secs(10), 9)
+            .line()
+                .button_stateless("Cancel", 1)
+                .button_stateless("Reboot", 2)
+        .build();
+    let (mut gui, _) = gui(layout, &clock);
+    gui.push_focus_scope(FocusScope::new(5, 1..2).auto_confirm(9, (1, 1)));
+    assert_eq!(gui.focused_cell(), (1, 0));
+
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(gui.settle(), vec![GuiEvent::StatelessButtonPress("Reboot".to_string(), 2)]);
+}
+
+#[test]
+fn unattended_countdown_still_confirms() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Update")
+            .line()
+                .countdown(Duration::from_secs(10), 9)
+            .line()
+                .button_stateless("Cancel", 1)
+                .button_stateless("Reboot", 2)
+        .build();
+    let mut gui = Gui::builder(layout)
+        .renderer(Box::new(NullRenderer))
+        .no_default_inputs()
+        .clock(clock.clone())
+        .idle(IdleConfig::dim(Duration::from_secs(5)))
+        .idle_events(Duration::from_secs(5))
+        .build();
+    gui.push_focus_scope(FocusScope::new(5, 1..2).auto_confirm(9, (1, 1)));
+
+    clock.advance(Duration::from_secs(6));
+    assert_eq!(gui.settle(), vec![GuiEvent::Idle(Duration::from_secs(6))]);
+    clock.advance(Duration::from_secs(4));
+    assert_eq!(gui.settle(), vec![GuiEvent::StatelessButtonPress("Reboot".to_string(), 2)]);
+
+    // nobody touched anything, the next press still wakes it up
+    gui.push_event(HidEvent::Left);
+    assert_eq!(gui.settle(), vec![GuiEvent::Active]);
+}
+
+#[test]
+fn countdown_just_expires_without_its_scope() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Update")
+            .line()
+                .countdown(Duration::from_secs(10), 9)
+            .line()
+                .button_stateless("Cancel", 1)
+                .button_stateless("Reboot", 2)
+        .build();
+    let (mut gui, _) = gui(layout, &clock);
+    gui.push_focus_scope(FocusScope::new(5, 1..2).auto_confirm(9, (1, 1)));
+    gui.pop_focus_scope();
+
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(gui.settle(), vec![GuiEvent::TimerExpired(9)]);
+}
+
+#[test]
+fn presses_during_the_cooldown_count_once() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Pad")
+            .line()
+                .button_stateless("Fire", 1)
+                .button_stateless("Jump", 2)
+        .build();
+    let (mut gui, _) = gui(layout, &clock);
+    gui.set_activation_cooldown(Duration::from_millis(200));
+    gui.set_item_cooldown(2, Some(Duration::ZERO));
+    let fire = GuiEvent::StatelessButtonPress("Fire".into(), 1);
+    let jump = GuiEvent::StatelessButtonPress("Jump".into(), 2);
+
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.settle(), vec![fire.clone()]);
+    clock.advance(Duration::from_millis(200));
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.settle(), vec![fire]);
+
+    // the cooldown is turned off for this one
+    gui.push_event(HidEvent::Right);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.settle(), vec![jump.clone(), jump]);
+}
+
+// ticks the renderer got while get_ev waited about 300ms for the window to close
+fn ticks_while_waiting(gui: &mut Gui, recorder: &RecordingRenderer) -> usize {
+    let before = recorder.recording().ticks.len();
+    let closer = recorder.clone();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        closer.send(RendererEvent::WindowClosed);
+    });
+    assert_eq!(gui.get_ev(), GuiEvent::Quit);
+    thread.join().unwrap();
+    let after = recorder.recording().ticks.len();
+    after - before
+}
+
+#[test]
+fn marquee_keeps_the_renderer_ticking() {
+    let clock = ManualClock::new();
+    let layout = Layout::builder()
+        .tab("Tab")
+            .line()
+                .button_stateless("A label far too long to fit into any cell", 1)
+        .build();
+    let (mut gui, recorder) = gui(layout, &clock);
+    gui.settle();
+
+    gui.set_marquee(true);
+    assert!(recorder.recording().marquee);
+    // a frame every 50ms
+    assert!(ticks_while_waiting(&mut gui, &recorder) >= 3);
+
+    gui.set_marquee(false);
+    assert!(!recorder.recording().marquee);
+    assert_eq!(ticks_while_waiting(&mut gui, &recorder), 0);
+}
@@ -0,0 +1,82 @@
+#![cfg(all(feature = "wifi", feature = "test-util"))]
+
+// a file of its own, nmcli is swapped for a script through PATH
+
+use std::{fs, os::unix::fs::PermissionsExt, thread, time::Duration};
+
+use sgui::{
+    layout::{Item, Layout},
+    renderer_null::RecordingRenderer,
+    Gui, GuiEvent, HidEvent,
+};
+
+const NMCLI: &str = r#"#!/bin/sh
+case "$*" in
+    *"connection show"*) printf 'Home:802-11-wireless\nWired:802-3-ethernet\n' ;;
+    *"wifi list"*) printf '*:Home:70:WPA2\n:Cafe:40:\n:Office:90:WPA2\n:Cafe:60:\n:Back\\:Room:10:--\n:::\n' ;;
+    *"wifi connect Cafe"*) echo "Device 'wlan0' successfully activated." ;;
+    *) echo "Error: no such network" >&2; exit 10 ;;
+esac
+"#;
+
+// labels of the buttons on the first tab once the scan is in
+fn networks(gui: &mut Gui, recorder: &RecordingRenderer) -> Vec<String> {
+    for _ in 0..500 {
+        gui.settle();
+        if !matches!(recorder.item(0, 0), Item::Text(_)) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let items = recorder.recording().items.clone();
+    items.into_iter()
+        .flatten()
+        .filter_map(|v| match v {
+            Item::StatelessButton(text, _) => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn picker_lists_and_connects_through_nmcli() {
+    let dir = std::env::temp_dir().join(format!("sgui-nmcli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("nmcli");
+    fs::write(&script, NMCLI).unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    std::env::set_var("PATH", format!("{}:{}", dir.display(), std::env::var("PATH").unwrap_or_default()));
+
+    let recorder = RecordingRenderer::new();
+    let mut gui = Gui::builder(Layout::builder().tab("Wi-Fi").line().text("Loading").build())
+        .renderer(Box::new(recorder.clone()))
+        .no_default_inputs()
+        .build();
+    gui.show_wifi_picker(0, 100);
+    gui.settle();
+    assert_eq!(recorder.item(0, 0), Item::Text("Scanning…".to_string()));
+
+    // connected first, then by signal, one entry per network
+    assert_eq!(networks(&mut gui, &recorder), vec![
+        "✓ ▂▄▆  Home 🔒",
+        "▂▄▆█ Office 🔒",
+        "▂▄▆  Cafe",
+        "▂    Back:Room",
+    ]);
+
+    // secured networks that aren't known yet ask for the password first
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::ButtonPress);
+    gui.settle();
+    assert!(matches!(recorder.item(2, 0), Item::TextInput(_, _, true, 100)));
+    assert!(recorder.recording().keyboard.is_some());
+
+    // the password input stays below Office after the keyboard is closed
+    gui.push_event(HidEvent::Back);
+    gui.push_event(HidEvent::Down);
+    gui.push_event(HidEvent::ButtonPress);
+    assert_eq!(gui.settle(), vec![]);
+    assert_eq!(gui.get_ev(), GuiEvent::WifiConnected("Cafe".to_string()));
+    assert_eq!(networks(&mut gui, &recorder)[..3], ["▂▄▆  Home 🔒", "▂▄▆█ Office 🔒", "✓ ▂▄▆  Cafe"]);
+    fs::remove_dir_all(&dir).unwrap();
+}